@@ -73,6 +73,26 @@ mod schema;
 ///
 /// The `group` parameter joins the prefix with the path at compile time,
 /// so the handler is registered at the full path during auto-discovery.
+///
+/// Middleware for this route alone goes in `#[middleware(...)]` below the
+/// route macro. It runs after routing, in the order listed:
+///
+/// ```ignore
+/// #[post("/auth/login")]
+/// #[middleware(RateLimitMiddleware::new(RateLimitConfig::per_minute(5)))]
+/// async fn login(body: Json<LoginRequest>) -> Result<Json<TokenResponse>> { /* ... */ }
+/// ```
+///
+/// `#[requires_role("...")]` and `#[requires_scope("...")]` below the route
+/// macro restrict it to users whose token grants the role or scope, and
+/// answer 403 otherwise. Repeat them to require several:
+///
+/// ```ignore
+/// #[post("/orders")]
+/// #[requires_role("staff")]
+/// #[requires_scope("orders:write")]
+/// async fn create_order(body: Json<NewOrder>) -> Result<Json<Order>> { /* ... */ }
+/// ```
 #[proc_macro_attribute]
 pub fn get(attr: TokenStream, item: TokenStream) -> TokenStream {
     route_macro("GET", attr, item)
@@ -158,6 +178,49 @@ pub fn public(_attr: TokenStream, item: TokenStream) -> TokenStream {
     .into()
 }
 
+/// Applies a named rate limit policy to a route.
+///
+/// The policy is defined with `RateLimitConfig::route_policy` and is checked
+/// on top of the policies every request gets. Works above or below the route
+/// macro and is picked up by `Rapina::discover()`.
+///
+/// # Example
+///
+/// ```ignore
+/// use rapina::prelude::*;
+///
+/// #[post("/login")]
+/// #[rate_limit("login")]
+/// async fn login(body: Json<LoginRequest>) -> Result<Json<TokenResponse>> {
+///     // ...
+/// }
+///
+/// Rapina::new()
+///     .with_rate_limit(
+///         RateLimitConfig::per_minute(100)
+///             .route_policy("login", RateLimitPolicy::per_minute(5)),
+///     )
+///     .discover()
+/// ```
+#[proc_macro_attribute]
+pub fn rate_limit(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let policy: syn::LitStr = syn::parse(attr).expect("expected #[rate_limit(\"policy\")]");
+    let func: ItemFn =
+        syn::parse(item.clone()).expect("#[rate_limit] must be applied to a function");
+    let func_name_str = func.sig.ident.to_string();
+    let item2: proc_macro2::TokenStream = item.into();
+    quote! {
+        #item2
+        rapina::inventory::submit! {
+            rapina::discovery::RateLimitMarker {
+                handler_name: #func_name_str,
+                policy: #policy,
+            }
+        }
+    }
+    .into()
+}
+
 fn route_macro_core(
     method: &str,
     attr: proc_macro2::TokenStream,
@@ -183,11 +246,24 @@ fn route_macro_core(
     // Extract #[public] attribute if present (when #[public] is below the route macro)
     let is_public = extract_public_attr(&mut func.attrs);
 
+    // Extract #[rate_limit("policy")] attribute if present (below the route macro)
+    let rate_limit = match extract_rate_limit_attr(&mut func.attrs) {
+        Some(policy) => quote! { Some(#policy) },
+        None => quote! { None },
+    };
+
+    // Extract #[requires_role("...")] and #[requires_scope("...")] attributes
+    let required_roles = extract_requirement_attrs(&mut func.attrs, "requires_role");
+    let required_scopes = extract_requirement_attrs(&mut func.attrs, "requires_scope");
+
     // Extract #[errors(ErrorType)] attribute if present
     let error_type = extract_errors_attr(&mut func.attrs);
 
-    // Extract #[cache(ttl = N)] attribute if present
-    let cache_ttl = extract_cache_attr(&mut func.attrs);
+    // Extract #[cache(ttl = N, ...)] attribute if present
+    let cache_attr = extract_cache_attr(&mut func.attrs);
+
+    // Extract #[middleware(...)] attributes if present
+    let middlewares = extract_middleware_attrs(&mut func.attrs);
 
     let error_responses_impl = if let Some(err_type) = &error_type {
         quote! {
@@ -199,6 +275,26 @@ fn route_macro_core(
         quote! {}
     };
 
+    let required_roles_impl = if required_roles.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            fn required_roles() -> &'static [&'static str] {
+                &[#(#required_roles),*]
+            }
+        }
+    };
+
+    let required_scopes_impl = if required_scopes.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            fn required_scopes() -> &'static [&'static str] {
+                &[#(#required_scopes),*]
+            }
+        }
+    };
+
     // Extract return type for schema generation
     let response_schema_impl = if let syn::ReturnType::Type(_, return_type) = &func.sig.output {
         if let Some(inner_type) = extract_json_inner_type(return_type) {
@@ -214,6 +310,15 @@ fn route_macro_core(
         quote! {}
     };
 
+    let response_content_type_impl = match &func.sig.output {
+        syn::ReturnType::Type(_, return_type) if returns_sse(return_type) => quote! {
+            fn response_content_type() -> Option<&'static str> {
+                Some(rapina::sse::TEXT_EVENT_STREAM)
+            }
+        },
+        _ => quote! {},
+    };
+
     // Extract request body type and content type for schema generation.
     // Only generate requestBody for POST, PUT, and PATCH methods per OpenAPI spec.
     let (request_schema_impl, request_content_type_impl, request_body_required_impl) =
@@ -254,15 +359,63 @@ fn route_macro_core(
         syn::ReturnType::Default => quote! {},
     };
 
-    // Optional cache TTL header injection
-    let cache_header_injection = if let Some(ttl) = cache_ttl {
-        let ttl_str = ttl.to_string();
+    // Optional cache TTL and vary header injection
+    let cache_header_injection = if let Some(cache) = cache_attr {
+        let ttl_str = cache.ttl.to_string();
+        let vary_injection = if cache.vary.is_empty() {
+            quote! {}
+        } else {
+            let vary_str = cache.vary.join(", ");
+            quote! {
+                __rapina_response.headers_mut().insert(
+                    "x-rapina-cache-vary",
+                    rapina::http::HeaderValue::from_static(#vary_str),
+                );
+            }
+        };
+        let stale_injection = match cache.stale {
+            Some(stale) => {
+                let stale_str = stale.to_string();
+                quote! {
+                    __rapina_response.headers_mut().insert(
+                        "x-rapina-cache-stale",
+                        rapina::http::HeaderValue::from_static(#stale_str),
+                    );
+                }
+            }
+            None => quote! {},
+        };
+        let tags_injection = if cache.tags.is_empty() {
+            quote! {}
+        } else {
+            let tags_str = cache.tags.join(", ");
+            quote! {
+                __rapina_response.headers_mut().insert(
+                    "x-rapina-cache-tags",
+                    rapina::http::HeaderValue::from_static(#tags_str),
+                );
+            }
+        };
+        let vary_user_injection = if cache.vary_user {
+            quote! {
+                __rapina_response.headers_mut().insert(
+                    "x-rapina-cache-vary-user",
+                    rapina::http::HeaderValue::from_static("1"),
+                );
+            }
+        } else {
+            quote! {}
+        };
         quote! {
             let mut __rapina_response = __rapina_response;
             __rapina_response.headers_mut().insert(
                 "x-rapina-cache-ttl",
                 rapina::http::HeaderValue::from_static(#ttl_str),
             );
+            #stale_injection
+            #tags_injection
+            #vary_injection
+            #vary_user_injection
         }
     } else {
         quote! {}
@@ -363,6 +516,20 @@ fn route_macro_core(
         proc_macro2::Span::call_site(),
     );
 
+    // Route middleware is scoped by giving the route its own group
+    let register_body = if middlewares.is_empty() {
+        quote! { __rapina_router.#router_method(#path_str, #func_name) }
+    } else {
+        quote! {
+            __rapina_router.group(
+                "/",
+                rapina::router::Router::new()
+                    .#router_method(#path_str, #func_name)
+                    #(.middleware(#middlewares))*,
+            )
+        }
+    };
+
     // Generate the struct, Handler impl, and inventory submission
     quote! {
         #[derive(Clone, Copy)]
@@ -373,10 +540,13 @@ fn route_macro_core(
             const NAME: &'static str = #func_name_str;
 
             #response_schema_impl
+            #response_content_type_impl
             #request_schema_impl
             #request_content_type_impl
             #request_body_required_impl
             #error_responses_impl
+            #required_roles_impl
+            #required_scopes_impl
 
             fn call(
                 &self,
@@ -392,7 +562,7 @@ fn route_macro_core(
 
         #[doc(hidden)]
         fn #register_fn_name(__rapina_router: rapina::router::Router) -> rapina::router::Router {
-            __rapina_router.#router_method(#path_str, #func_name)
+            #register_body
         }
 
         rapina::inventory::submit! {
@@ -401,7 +571,11 @@ fn route_macro_core(
                 path: #path_str,
                 handler_name: #func_name_str,
                 is_public: #is_public,
+                rate_limit: #rate_limit,
+                required_roles: &[#(#required_roles),*],
+                required_scopes: &[#(#required_scopes),*],
                 response_schema: <#func_name as rapina::handler::Handler>::response_schema,
+                response_content_type: <#func_name as rapina::handler::Handler>::response_content_type,
                 request_schema: <#func_name as rapina::handler::Handler>::request_schema,
                 request_content_type: <#func_name as rapina::handler::Handler>::request_content_type,
                 request_body_required: <#func_name as rapina::handler::Handler>::request_body_required,
@@ -425,8 +599,8 @@ fn extract_json_inner_type(return_type: &syn::Type) -> Option<proc_macro2::Token
             return Some(quote!(#inner_type));
         }
 
-        // Result<Json<T>> or Result<Json<T>, E>
-        if last_segment.ident == "Result"
+        // Result<Json<T>> or Result<Json<T>, E>, and Conditional<Json<T>>
+        if (last_segment.ident == "Result" || last_segment.ident == "Conditional")
             && let syn::PathArguments::AngleBracketed(args) = &last_segment.arguments
             && let Some(syn::GenericArgument::Type(ok_type)) = args.args.first()
         {
@@ -436,6 +610,25 @@ fn extract_json_inner_type(return_type: &syn::Type) -> Option<proc_macro2::Token
     None
 }
 
+/// Whether the handler returns `Sse<S>` or `Result<Sse<S>>`.
+fn returns_sse(return_type: &syn::Type) -> bool {
+    if let syn::Type::Path(type_path) = return_type
+        && let Some(last_segment) = type_path.path.segments.last()
+    {
+        if last_segment.ident == "Sse" {
+            return true;
+        }
+
+        if last_segment.ident == "Result"
+            && let syn::PathArguments::AngleBracketed(args) = &last_segment.arguments
+            && let Some(syn::GenericArgument::Type(ok_type)) = args.args.first()
+        {
+            return returns_sse(ok_type);
+        }
+    }
+    false
+}
+
 /// Extracts the request body metadata from handler function arguments.
 /// Supports Json<T>, Form<T>, Validated<Json<T>>, and Validated<Form<T>>.
 fn extract_request_body_meta(
@@ -517,27 +710,105 @@ fn extract_errors_attr(attrs: &mut Vec<syn::Attribute>) -> Option<syn::Type> {
     Some(err_type)
 }
 
-/// Extract #[cache(ttl = N)] attribute from function attributes, removing it if found.
-fn extract_cache_attr(attrs: &mut Vec<syn::Attribute>) -> Option<u64> {
+/// Options parsed from a `#[cache(...)]` attribute.
+struct CacheAttr {
+    ttl: u64,
+    /// Seconds an expired entry may still be served while it is refreshed
+    stale: Option<u64>,
+    /// Tags the response can be invalidated by
+    tags: Vec<String>,
+    /// Lowercased request header names the cache key varies by
+    vary: Vec<String>,
+    /// Whether the cache key varies by the authenticated user
+    vary_user: bool,
+}
+
+/// Extract #[cache(ttl = N, stale = M, tags = ["tag", ...], vary = ["Header", ...], vary_user)]
+/// attribute from function attributes, removing it if found.
+fn extract_cache_attr(attrs: &mut Vec<syn::Attribute>) -> Option<CacheAttr> {
     let idx = attrs
         .iter()
         .position(|attr| attr.path().is_ident("cache"))?;
     let attr = attrs.remove(idx);
 
     let mut ttl: Option<u64> = None;
+    let mut stale: Option<u64> = None;
+    let mut tags = Vec::new();
+    let mut vary = Vec::new();
+    let mut vary_user = false;
     attr.parse_nested_meta(|meta| {
         if meta.path.is_ident("ttl") {
             let value = meta.value()?;
             let lit: syn::LitInt = value.parse()?;
             ttl = Some(lit.base10_parse()?);
             Ok(())
+        } else if meta.path.is_ident("stale") {
+            let value = meta.value()?;
+            let lit: syn::LitInt = value.parse()?;
+            stale = Some(lit.base10_parse()?);
+            Ok(())
+        } else if meta.path.is_ident("tags") {
+            for tag in parse_string_list(&meta)? {
+                let value = tag.value();
+                if value.is_empty() || value.contains(',') || !value.is_ascii() {
+                    return Err(syn::Error::new(tag.span(), "expected a tag name"));
+                }
+                tags.push(value);
+            }
+            Ok(())
+        } else if meta.path.is_ident("vary") {
+            for name in parse_string_list(&meta)? {
+                let value = name.value();
+                if value.is_empty() || value.contains(',') {
+                    return Err(syn::Error::new(name.span(), "expected a header name"));
+                }
+                vary.push(value.to_ascii_lowercase());
+            }
+            Ok(())
+        } else if meta.path.is_ident("vary_user") {
+            vary_user = true;
+            Ok(())
         } else {
-            Err(meta.error("expected `ttl`"))
+            Err(meta.error("expected `ttl`, `stale`, `tags`, `vary` or `vary_user`"))
         }
     })
     .expect("expected #[cache(ttl = N)]");
 
-    ttl
+    Some(CacheAttr {
+        ttl: ttl.expect("expected #[cache(ttl = N)]"),
+        stale,
+        tags,
+        vary,
+        vary_user,
+    })
+}
+
+/// Parses the `["a", "b"]` value of a nested meta item.
+fn parse_string_list(
+    meta: &syn::meta::ParseNestedMeta,
+) -> syn::Result<syn::punctuated::Punctuated<syn::LitStr, syn::Token![,]>> {
+    let value = meta.value()?;
+    let content;
+    syn::bracketed!(content in value);
+    syn::punctuated::Punctuated::parse_terminated(&content)
+}
+
+/// Extract all #[middleware(expr, ...)] attributes from function attributes, removing them.
+fn extract_middleware_attrs(attrs: &mut Vec<syn::Attribute>) -> Vec<syn::Expr> {
+    let mut middlewares = Vec::new();
+    attrs.retain(|attr| {
+        if !attr.path().is_ident("middleware") {
+            return true;
+        }
+        let exprs = attr
+            .parse_args_with(
+                syn::punctuated::Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated,
+            )
+            .expect("expected #[middleware(MiddlewareExpr, ...)]");
+        middlewares.extend(exprs);
+        false
+    });
+    middlewares
 }
 
 /// Extract #[public] attribute from function attributes, removing it if found.
@@ -550,6 +821,39 @@ fn extract_public_attr(attrs: &mut Vec<syn::Attribute>) -> bool {
     }
 }
 
+/// Extract the policy name from a `#[rate_limit("name")]` attribute, removing it.
+fn extract_rate_limit_attr(attrs: &mut Vec<syn::Attribute>) -> Option<syn::LitStr> {
+    let idx = attrs
+        .iter()
+        .position(|attr| attr.path().is_ident("rate_limit"))?;
+    let attr = attrs.remove(idx);
+    Some(
+        attr.parse_args()
+            .expect("expected #[rate_limit(\"policy\")]"),
+    )
+}
+
+/// Extract every `#[requires_role("...")]` or `#[requires_scope("...")]`
+/// attribute named `name`, removing them.
+fn extract_requirement_attrs(attrs: &mut Vec<syn::Attribute>, name: &str) -> Vec<syn::LitStr> {
+    let mut values = Vec::new();
+    attrs.retain(|attr| {
+        if !attr.path().is_ident(name) {
+            return true;
+        }
+        let value: syn::LitStr = attr
+            .parse_args()
+            .unwrap_or_else(|_| panic!("expected #[{name}(\"...\")]"));
+        assert!(
+            !value.value().trim().is_empty(),
+            "#[{name}] needs a non-empty value"
+        );
+        values.push(value);
+        false
+    });
+    values
+}
+
 /// Registers a channel handler for the relay system.
 ///
 /// Channel handlers receive [`RelayEvent`](rapina::relay::RelayEvent) events
@@ -1520,6 +1824,38 @@ mod tests {
         assert!(!output_str.contains("fn request_content_type"));
     }
 
+    #[test]
+    fn test_sse_return_type_generates_response_content_type() {
+        let path = quote!("/events");
+        let input = quote! {
+            async fn events() -> Result<Sse<EventStream>> {
+                Ok(Sse::new(EventStream::new()))
+            }
+        };
+
+        let output = route_macro_core("GET", path, input);
+        let output_str = output.to_string();
+
+        assert!(output_str.contains("fn response_content_type"));
+        assert!(output_str.contains("TEXT_EVENT_STREAM"));
+        assert!(!output_str.contains("fn response_schema"));
+    }
+
+    #[test]
+    fn test_json_return_type_no_response_content_type() {
+        let path = quote!("/users");
+        let input = quote! {
+            async fn list_users() -> Json<Vec<UserResponse>> {
+                Json(vec![])
+            }
+        };
+
+        let output = route_macro_core("GET", path, input);
+        let output_str = output.to_string();
+
+        assert!(!output_str.contains("fn response_content_type"));
+    }
+
     #[test]
     fn test_non_json_return_type_no_response_schema() {
         let path = quote!("/health");
@@ -1631,6 +1967,85 @@ mod tests {
         assert!(output_str.contains("is_public : true"));
     }
 
+    #[test]
+    fn test_rate_limit_attr_below_route_sets_policy() {
+        let path = quote!("/login");
+        let input = quote! {
+            #[rate_limit("login")]
+            async fn login() -> &'static str {
+                "ok"
+            }
+        };
+
+        let output = route_macro_core("POST", path, input);
+        let output_str = output.to_string();
+
+        assert!(output_str.contains("rate_limit : Some (\"login\")"));
+        assert!(!output_str.contains("# [rate_limit"));
+    }
+
+    #[test]
+    fn test_requirement_attrs_set_roles_and_scopes() {
+        let path = quote!("/orders");
+        let input = quote! {
+            #[requires_role("staff")]
+            #[requires_scope("orders:write")]
+            #[requires_scope("orders:read")]
+            async fn create_order() -> &'static str {
+                "created"
+            }
+        };
+
+        let output_str = route_macro_core("POST", path, input).to_string();
+        assert!(output_str.contains("fn required_roles"));
+        assert!(output_str.contains("required_roles : & [\"staff\"]"));
+        assert!(output_str.contains("required_scopes : & [\"orders:write\" , \"orders:read\"]"));
+        assert!(!output_str.contains("# [requires_role"));
+        assert!(!output_str.contains("# [requires_scope"));
+    }
+
+    #[test]
+    fn test_route_without_requirement_attrs() {
+        let path = quote!("/orders");
+        let input = quote! {
+            async fn list_orders() -> &'static str {
+                "orders"
+            }
+        };
+
+        let output_str = route_macro_core("GET", path, input).to_string();
+        assert!(!output_str.contains("fn required_roles"));
+        assert!(!output_str.contains("fn required_scopes"));
+        assert!(output_str.contains("required_roles : & []"));
+    }
+
+    #[test]
+    #[should_panic(expected = "#[requires_role] needs a non-empty value")]
+    fn test_requires_role_rejects_empty_value() {
+        let path = quote!("/admin");
+        let input = quote! {
+            #[requires_role("")]
+            async fn admin() -> &'static str {
+                "admin"
+            }
+        };
+
+        route_macro_core("GET", path, input);
+    }
+
+    #[test]
+    fn test_route_without_rate_limit_attr() {
+        let path = quote!("/users");
+        let input = quote! {
+            async fn list_users() -> &'static str {
+                "ok"
+            }
+        };
+
+        let output = route_macro_core("GET", path, input);
+        assert!(output.to_string().contains("rate_limit : None"));
+    }
+
     #[test]
     fn test_cache_attr_injects_ttl_header() {
         let path = quote!("/products");
@@ -1648,6 +2063,98 @@ mod tests {
         assert!(output_str.contains("60"));
     }
 
+    #[test]
+    fn test_conditional_json_return_generates_response_schema() {
+        let path = quote!("/orders/:id");
+        let input = quote! {
+            async fn get_order() -> Result<Conditional<Json<Order>>> {
+                todo!()
+            }
+        };
+
+        let output_str = route_macro_core("GET", path, input).to_string();
+        assert!(output_str.contains("fn response_schema"));
+        assert!(output_str.contains("Order"));
+    }
+
+    #[test]
+    fn test_cache_attr_injects_vary_headers() {
+        let path = quote!("/me/orders");
+        let input = quote! {
+            #[cache(ttl = 60, vary = ["Accept-Language", "X-Tenant"], vary_user)]
+            async fn my_orders() -> &'static str {
+                "orders"
+            }
+        };
+
+        let output = route_macro_core("GET", path, input);
+        let output_str = output.to_string();
+
+        assert!(output_str.contains("x-rapina-cache-vary"));
+        assert!(output_str.contains("\"accept-language, x-tenant\""));
+        assert!(output_str.contains("x-rapina-cache-vary-user"));
+    }
+
+    #[test]
+    fn test_cache_attr_injects_stale_header() {
+        let path = quote!("/products");
+        let input = quote! {
+            #[cache(ttl = 60, stale = 30)]
+            async fn list_products() -> &'static str {
+                "products"
+            }
+        };
+
+        let output_str = route_macro_core("GET", path, input).to_string();
+        assert!(output_str.contains("x-rapina-cache-stale"));
+        assert!(output_str.contains("\"30\""));
+    }
+
+    #[test]
+    fn test_cache_attr_injects_tags_header() {
+        let path = quote!("/orders");
+        let input = quote! {
+            #[cache(ttl = 60, tags = ["orders", "customers"])]
+            async fn list_orders() -> &'static str {
+                "orders"
+            }
+        };
+
+        let output_str = route_macro_core("GET", path, input).to_string();
+        assert!(output_str.contains("x-rapina-cache-tags"));
+        assert!(output_str.contains("\"orders, customers\""));
+    }
+
+    #[test]
+    fn test_cache_attr_without_vary_injects_ttl_only() {
+        let path = quote!("/products");
+        let input = quote! {
+            #[cache(ttl = 60)]
+            async fn list_products() -> &'static str {
+                "products"
+            }
+        };
+
+        let output_str = route_macro_core("GET", path, input).to_string();
+        assert!(!output_str.contains("x-rapina-cache-vary"));
+        assert!(!output_str.contains("x-rapina-cache-stale"));
+        assert!(!output_str.contains("x-rapina-cache-tags"));
+    }
+
+    #[test]
+    #[should_panic(expected = "expected #[cache(ttl = N)]")]
+    fn test_cache_attr_rejects_unknown_option() {
+        let path = quote!("/products");
+        let input = quote! {
+            #[cache(ttl = 60, vary_by = "user")]
+            async fn list_products() -> &'static str {
+                "products"
+            }
+        };
+
+        route_macro_core("GET", path, input);
+    }
+
     #[test]
     fn test_relay_macro_generates_wrapper_and_inventory() {
         let attr = quote!("room:*");
@@ -1726,6 +2233,46 @@ mod tests {
         assert!(!output_str.contains("x-rapina-cache-ttl"));
     }
 
+    #[test]
+    fn test_middleware_attr_registers_route_in_own_group() {
+        let path = quote!("/auth/login");
+        let input = quote! {
+            #[middleware(TimeoutMiddleware::default(), RequestLogMiddleware::new())]
+            #[middleware(TraceIdMiddleware::new())]
+            async fn login() -> &'static str {
+                "ok"
+            }
+        };
+
+        let output = route_macro_core("POST", path, input);
+        let output_str = output.to_string();
+
+        assert!(!output_str.contains("# [middleware"));
+        assert!(output_str.contains("__rapina_router . group (\"/\""));
+        let timeout = output_str.find(". middleware (TimeoutMiddleware").unwrap();
+        let log = output_str
+            .find(". middleware (RequestLogMiddleware")
+            .unwrap();
+        let trace = output_str.find(". middleware (TraceIdMiddleware").unwrap();
+        assert!(timeout < log && log < trace);
+    }
+
+    #[test]
+    fn test_no_middleware_attr_registers_route_directly() {
+        let path = quote!("/auth/login");
+        let input = quote! {
+            async fn login() -> &'static str {
+                "ok"
+            }
+        };
+
+        let output = route_macro_core("POST", path, input);
+        let output_str = output.to_string();
+
+        assert!(!output_str.contains("group"));
+        assert!(output_str.contains("__rapina_router . post (\"/auth/login\" , login)"));
+    }
+
     #[test]
     fn test_cache_attr_with_extractors() {
         let path = quote!("/users/:id");
@@ -1,14 +1,19 @@
 //! The main application builder for Rapina.
 
-use crate::auth::{AuthConfig, AuthMiddleware, PublicRoutes};
+use crate::auth::{AuthClaims, AuthConfig, AuthScheme, PublicRoutes};
+use crate::conditional::{ConditionalConfig, ConditionalMiddleware};
 #[cfg(feature = "cron-scheduler")]
 use crate::cron_scheduler::CronScheduler;
+use crate::handler::Handler;
 use crate::health::{HealthRegistry, health_check, liveness_check, readiness_check};
 use crate::introspection::{RouteRegistry, list_routes};
+use crate::listener::IntoListener;
 #[cfg(feature = "metrics")]
 use crate::metrics::{MetricsMiddleware, MetricsRegistry, metrics_handler};
 #[cfg(feature = "compression")]
-use crate::middleware::{CompressionConfig, CompressionMiddleware};
+use crate::middleware::{
+    CompressionConfig, CompressionMiddleware, DecompressionConfig, DecompressionMiddleware,
+};
 use crate::middleware::{
     CorsConfig, CorsMiddleware, Middleware, MiddlewareStack, RequestLogConfig, RequestLogMiddleware,
 };
@@ -18,11 +23,15 @@ use crate::observability::TracingConfig;
 use crate::openapi::{OpenApiRegistry, build_openapi_spec, openapi_spec};
 #[cfg(feature = "jwks")]
 use crate::prelude::JwksClient;
+use crate::proxy::TrustedProxies;
 use crate::router::Router;
-use crate::server::{ShutdownHook, serve};
+use crate::server::{ShutdownHook, serve, serve_on};
+#[cfg(feature = "session")]
+use crate::session::{SessionConfig, SessionMiddleware};
 use crate::state::AppState;
 use std::future::Future;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use std::time::Duration;
 
 /// The main application type for building Rapina servers.
@@ -71,10 +80,15 @@ pub struct Rapina {
     pub(crate) openapi: bool,
     pub(crate) openapi_title: String,
     pub(crate) openapi_version: String,
-    /// Authentication configuration (if enabled)
-    pub(crate) auth_config: Option<AuthConfig>,
+    /// Authentication schemes, tried in order (empty if auth is disabled)
+    pub(crate) auth_schemes: Vec<Arc<dyn AuthScheme>>,
+    /// Public keys of the JWT configs, served on the JWKS endpoint
+    pub(crate) auth_keys: Vec<jsonwebtoken::jwk::Jwk>,
     /// Public routes registry
     pub(crate) public_routes: PublicRoutes,
+    /// Routes that opt into a named rate limit policy
+    #[cfg(feature = "rate-limit")]
+    pub(crate) rate_limit_routes: crate::middleware::RateLimitRoutes,
     /// Whether auto-discovery is enabled
     pub(crate) auto_discover: bool,
     /// Graceful shutdown timeout (default 30s)
@@ -122,8 +136,11 @@ impl Rapina {
             openapi: false,
             openapi_title: "API".to_string(),
             openapi_version: "1.0.0".to_string(),
-            auth_config: None,
+            auth_schemes: Vec::new(),
+            auth_keys: Vec::new(),
             public_routes: PublicRoutes::new(),
+            #[cfg(feature = "rate-limit")]
+            rate_limit_routes: Default::default(),
             auto_discover: false,
             shutdown_timeout: Duration::from_secs(30),
             shutdown_hooks: Vec::new(),
@@ -139,11 +156,41 @@ impl Rapina {
     }
 
     /// Sets the router for the application.
-    pub fn router(mut self, router: Router) -> Self {
+    ///
+    /// A fallback set earlier via [`fallback`](Self::fallback) is kept unless
+    /// the new router defines its own.
+    pub fn router(mut self, mut router: Router) -> Self {
+        if router.fallback.is_none() {
+            router.fallback = self.router.fallback.take();
+        }
         self.router = router;
         self
     }
 
+    /// Sets the handler for requests that match no route.
+    ///
+    /// Equivalent to [`Router::fallback`]. Without a fallback, unmatched
+    /// requests receive a 404 in the configured error format.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// #[get("/")]
+    /// async fn spa_index() -> (StatusCode, &'static str) {
+    ///     (StatusCode::OK, include_str!("../dist/index.html"))
+    /// }
+    ///
+    /// Rapina::new()
+    ///     .discover()
+    ///     .fallback(spa_index)
+    ///     .listen("127.0.0.1:3000")
+    ///     .await
+    /// ```
+    pub fn fallback<H: Handler>(mut self, handler: H) -> Self {
+        self.router = std::mem::take(&mut self.router).fallback(handler);
+        self
+    }
+
     /// Enables route auto-discovery.
     ///
     /// When enabled, handlers annotated with `#[get]`, `#[post]`, `#[put]`,
@@ -277,6 +324,27 @@ impl Rapina {
         self
     }
 
+    /// Sets the reverse proxies whose forwarding headers are trusted.
+    ///
+    /// Requests from these proxies get their [`ClientIp`](crate::extract::ClientIp)
+    /// from the header set with [`TrustedProxies::header`], which also keys rate
+    /// limiting and request logs. Without this, the client IP is the socket peer.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// Rapina::new()
+    ///     .with_trusted_proxies(TrustedProxies::new(["10.0.0.0/8"]))
+    ///     .with_rate_limit(RateLimitConfig::per_minute(100))
+    ///     .router(router)
+    ///     .listen("0.0.0.0:3000")
+    ///     .await
+    /// ```
+    pub fn with_trusted_proxies(mut self, proxies: TrustedProxies) -> Self {
+        self.state = self.state.with(proxies);
+        self
+    }
+
     /// Enables rate limiting for the application.
     ///
     /// Uses a token bucket algorithm to limit requests per client.
@@ -297,6 +365,32 @@ impl Rapina {
         self
     }
 
+    /// Applies a named rate limit policy to a route, on top of the policies
+    /// every request is checked against.
+    ///
+    /// The policy is defined with
+    /// [`RateLimitConfig::route_policy`]. Discovered routes can use
+    /// `#[rate_limit("name")]` instead.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// Rapina::new()
+    ///     .with_rate_limit(
+    ///         RateLimitConfig::per_minute(100)
+    ///             .route_policy("login", RateLimitPolicy::per_minute(5)),
+    ///     )
+    ///     .rate_limit_route("POST", "/login", "login")
+    ///     .router(router)
+    ///     .listen("127.0.0.1:3000")
+    ///     .await
+    /// ```
+    #[cfg(feature = "rate-limit")]
+    pub fn rate_limit_route(mut self, method: &str, path: &str, policy: &str) -> Self {
+        self.rate_limit_routes.add(method, path, policy);
+        self
+    }
+
     /// Enables configurable request/response logging.
     ///
     /// Use `RequestLogConfig::verbose()` for full logging with default
@@ -317,13 +411,67 @@ impl Rapina {
         self
     }
 
-    /// Enables response compression (gzip, deflate).
+    /// Enables response compression (gzip, deflate, and brotli or zstd with
+    /// their feature flags).
     #[cfg(feature = "compression")]
     pub fn with_compression(mut self, config: CompressionConfig) -> Self {
         self.middlewares.add(CompressionMiddleware::new(config));
         self
     }
 
+    /// Enables request body decompression.
+    ///
+    /// Bodies sent with a `Content-Encoding` the server can decode are
+    /// inflated before `Json`, `Form` or `Multipart` read them, capped at
+    /// [`DecompressionConfig::max_size`]. Unsupported encodings get 415.
+    #[cfg(feature = "compression")]
+    pub fn with_decompression(mut self, config: DecompressionConfig) -> Self {
+        self.middlewares.add(DecompressionMiddleware::new(config));
+        self
+    }
+
+    /// Enables conditional `GET` handling: `ETag`s hashed from response
+    /// bodies and `304 Not Modified` for clients with a current copy.
+    ///
+    /// Register it before [`with_compression`](Self::with_compression) so
+    /// the tags describe the bytes actually sent.
+    pub fn with_conditional(mut self, config: ConditionalConfig) -> Self {
+        self.middlewares.add(ConditionalMiddleware::new(config));
+        self
+    }
+
+    /// Enables cookie sessions, read and changed through the
+    /// [`Session`](crate::session::Session) extractor.
+    ///
+    /// Also schedules a cron job that deletes expired sessions from the
+    /// store, on the config's cleanup schedule.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use rapina::session::{InMemorySessionStore, SessionConfig};
+    ///
+    /// Rapina::new()
+    ///     .with_sessions(SessionConfig::from_env(InMemorySessionStore::new())?)
+    ///     .discover()
+    ///     .listen("127.0.0.1:3000")
+    ///     .await
+    /// ```
+    #[cfg(feature = "session")]
+    pub fn with_sessions(mut self, config: SessionConfig) -> Self {
+        let store = config.store().clone();
+        let schedule = config.cleanup_schedule().to_string();
+        self.middlewares.add(SessionMiddleware::new(config));
+        self.cron(&schedule, move || {
+            let store = store.clone();
+            async move {
+                let removed = store.cleanup_expired().await?;
+                tracing::debug!(removed, "deleted expired sessions");
+                Ok::<(), crate::error::Error>(())
+            }
+        })
+    }
+
     /// Enables the Relay system for real-time push over WebSocket.
     ///
     /// Registers a WebSocket endpoint (default `/ws`) through the normal
@@ -355,6 +503,12 @@ impl Rapina {
     ///
     /// When enabled, all routes require a valid `Authorization: Bearer <token>` header
     /// unless marked with `#[public]` or registered via [`public_route`](Self::public_route).
+    /// Tokens are decoded into the config's claims type, see
+    /// [`AuthConfig::with_claims`].
+    ///
+    /// This adds the config as an [`AuthScheme`], like
+    /// [`with_auth_scheme`](Self::with_auth_scheme), and publishes its
+    /// asymmetric keys on [`JWKS_PATH`](crate::auth::JWKS_PATH).
     ///
     /// # Example
     ///
@@ -367,8 +521,31 @@ impl Rapina {
     ///     .listen("127.0.0.1:3000")
     ///     .await
     /// ```
-    pub fn with_auth(mut self, config: AuthConfig) -> Self {
-        self.auth_config = Some(config);
+    pub fn with_auth<C: AuthClaims>(mut self, config: AuthConfig<C>) -> Self {
+        self.auth_keys.extend(config.jwks().keys);
+        self.with_auth_scheme(config)
+    }
+
+    /// Enables authentication with `scheme`, such as
+    /// [`ApiKeyAuth`](crate::auth::ApiKeyAuth).
+    ///
+    /// Several schemes can be enabled and are tried in the order they were
+    /// added: the first to accept a request's credentials authenticates it,
+    /// and a request none of them accepts gets the first error one returned.
+    /// Every scheme is listed in the OpenAPI `securitySchemes`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// Rapina::new()
+    ///     .with_auth(AuthConfig::from_env()?)
+    ///     .with_auth_scheme(ApiKeyAuth::header("X-API-Key", ApiKeys(conn)))
+    ///     .router(router)
+    ///     .listen("127.0.0.1:3000")
+    ///     .await
+    /// ```
+    pub fn with_auth_scheme(mut self, scheme: impl AuthScheme) -> Self {
+        self.auth_schemes.push(Arc::new(scheme));
         self
     }
 
@@ -530,19 +707,21 @@ impl Rapina {
 
     /// Warms up the JWKS cache immediately without waiting for the next cronjob tick
     #[cfg(feature = "jwks")]
-    async fn warmup_jwks_cache(&self) -> () {
+    async fn warmup_jwks_cache(self) -> Self {
         let Some(jwks_client) = self.state.get::<JwksClient>().cloned() else {
             tracing::error!(
                 "Skipped warmup of JWKS cache because the Rapina state for JwksClient is empty. Did you forget to call .state(jwks_client)?"
             );
 
-            return;
+            return self;
         };
 
         match jwks_client.refresh_jwks_cache().await {
             Ok(_) => tracing::info!("Successfully warmed up JWKS cache"),
             Err(e) => tracing::error!("Failed warmup of JWKS cache: {}", e),
         }
+
+        self
     }
 
     /// Schedules the JWKS refresh cronjob
@@ -826,6 +1005,13 @@ impl Rapina {
                     .map(|m| m.handler_name)
                     .collect();
 
+            #[cfg(feature = "rate-limit")]
+            let rate_limit_names: std::collections::HashMap<&str, &str> =
+                inventory::iter::<crate::discovery::RateLimitMarker>
+                    .into_iter()
+                    .map(|m| (m.handler_name, m.policy))
+                    .collect();
+
             let mut discovered_public = 0usize;
             for descriptor in inventory::iter::<crate::discovery::RouteDescriptor> {
                 self.router = (descriptor.register)(self.router);
@@ -833,6 +1019,14 @@ impl Rapina {
                     self.public_routes.add(descriptor.method, descriptor.path);
                     discovered_public += 1;
                 }
+                #[cfg(feature = "rate-limit")]
+                if let Some(policy) = descriptor
+                    .rate_limit
+                    .or_else(|| rate_limit_names.get(descriptor.handler_name).copied())
+                {
+                    self.rate_limit_routes
+                        .add(descriptor.method, descriptor.path, policy);
+                }
             }
 
             let discovered_count = self.router.routes.len() - manual_count;
@@ -871,11 +1065,36 @@ impl Rapina {
                     .get_named(&path, "relay_ws", crate::relay::RelayHub::ws_handler);
         }
 
+        #[cfg(feature = "rate-limit")]
+        if !self.rate_limit_routes.is_empty() {
+            let routes = std::mem::take(&mut self.rate_limit_routes);
+            self.state = self.state.with(routes);
+        }
+
         // Add auth middleware if configured
-        if let Some(auth_config) = self.auth_config.take() {
-            let auth_middleware =
-                AuthMiddleware::with_public_routes(auth_config, self.public_routes.clone());
-            self.middlewares.add(auth_middleware);
+        let auth_schemes: Arc<[Arc<dyn AuthScheme>]> =
+            std::mem::take(&mut self.auth_schemes).into();
+        if !auth_schemes.is_empty() {
+            if !self.auth_keys.is_empty() {
+                let keys = std::mem::take(&mut self.auth_keys);
+                self.public_routes.add("GET", crate::auth::JWKS_PATH);
+                self.state =
+                    self.state
+                        .with(crate::auth::PublishedKeys(jsonwebtoken::jwk::JwkSet {
+                            keys,
+                        }));
+                self.router =
+                    self.router
+                        .get_named(crate::auth::JWKS_PATH, "jwks", crate::auth::jwks);
+            }
+            self.state = self
+                .state
+                .with(crate::auth::UserIdResolver::new(auth_schemes.clone()));
+            self.middlewares
+                .push(Arc::new(crate::auth::AuthMiddleware::from_schemes(
+                    auth_schemes.clone(),
+                    self.public_routes.clone(),
+                )));
         }
 
         if self.introspection {
@@ -915,7 +1134,14 @@ impl Rapina {
 
         if self.openapi {
             let routes = self.router.routes();
-            let spec = build_openapi_spec(&self.openapi_title, &self.openapi_version, &routes);
+            let mut spec = build_openapi_spec(&self.openapi_title, &self.openapi_version, &routes);
+            spec.with_security(
+                auth_schemes
+                    .iter()
+                    .map(|scheme| (scheme.name().to_string(), scheme.security_scheme())),
+                &routes,
+                |method, path| self.public_routes.is_public(method, path),
+            );
             self.state = self.state.with(OpenApiRegistry::new(spec));
             self.router =
                 self.router
@@ -943,6 +1169,128 @@ impl Rapina {
     /// in dev mode and the banner is correct by construction.
     pub async fn listen(self, addr: &str) -> std::io::Result<()> {
         let addr: SocketAddr = resolve_listen_addr(addr);
+        let app = self.start().await;
+
+        serve(
+            app.router,
+            app.state,
+            app.middlewares,
+            addr,
+            app.shutdown_timeout,
+            app.shutdown_hooks,
+        )
+        .await
+    }
+
+    /// Starts the HTTP server on an already-bound listener.
+    ///
+    /// Works like [`listen`](Self::listen), including graceful shutdown and
+    /// shutdown hooks, for sockets created elsewhere: a
+    /// [`std::net::TcpListener`] inherited from systemd or a supervisor, a
+    /// Tokio listener, or any type implementing [`Listener`](crate::listener::Listener).
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    /// println!("listening on {}", listener.local_addr()?);
+    ///
+    /// Rapina::new().discover().listen_on(listener).await
+    /// ```
+    pub async fn listen_on<L: IntoListener>(self, listener: L) -> std::io::Result<()> {
+        let listener = listener.into_listener()?;
+        let app = self.start().await;
+
+        serve_on(
+            app.router,
+            app.state,
+            app.middlewares,
+            listener,
+            app.shutdown_timeout,
+            app.shutdown_hooks,
+        )
+        .await
+    }
+
+    /// Starts the HTTP server on a Unix domain socket at `path`.
+    ///
+    /// An existing socket file at `path` is replaced only when nothing accepts
+    /// connections on it. A socket another server is listening on fails with
+    /// [`AddrInUse`](std::io::ErrorKind::AddrInUse), and any other existing
+    /// file is an error. The socket file is removed again once the server has
+    /// shut down.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// Rapina::new()
+    ///     .discover()
+    ///     .listen_unix("/run/myapp/http.sock")
+    ///     .await
+    /// ```
+    #[cfg(unix)]
+    pub async fn listen_unix(self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        use std::os::unix::fs::FileTypeExt;
+
+        let path = path.as_ref();
+        let stale_socket = std::fs::symlink_metadata(path)
+            .map(|metadata| metadata.file_type().is_socket())
+            .unwrap_or(false);
+        if stale_socket {
+            if std::os::unix::net::UnixStream::connect(path).is_ok() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::AddrInUse,
+                    format!("{} is in use by a running server", path.display()),
+                ));
+            }
+            std::fs::remove_file(path)?;
+        }
+
+        let listener = tokio::net::UnixListener::bind(path)?;
+        let result = self.listen_on(listener).await;
+        let _ = std::fs::remove_file(path);
+        result
+    }
+
+    /// Starts the HTTPS server on the given address.
+    ///
+    /// Works like [`listen`](Self::listen), including graceful shutdown and
+    /// shutdown hooks, but terminates TLS in-process using the certificate and
+    /// key from `tls`. Returns an error before binding if they cannot be loaded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the address is invalid.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use rapina::tls::TlsConfig;
+    ///
+    /// Rapina::new()
+    ///     .discover()
+    ///     .listen_tls("0.0.0.0:8443", TlsConfig::new("server.crt", "server.key"))
+    ///     .await
+    /// ```
+    #[cfg(feature = "tls")]
+    pub async fn listen_tls(self, addr: &str, tls: crate::tls::TlsConfig) -> std::io::Result<()> {
+        let addr: SocketAddr = resolve_listen_addr(addr);
+        let app = self.start().await;
+
+        crate::server::serve_tls(
+            app.router,
+            app.state,
+            app.middlewares,
+            addr,
+            tls,
+            app.shutdown_timeout,
+            app.shutdown_hooks,
+        )
+        .await
+    }
+
+    /// Prepares the app and spawns its background workers, right before serving.
+    async fn start(self) -> Self {
         let app = self.prepare();
 
         // Spawn the background job worker if configured.  The worker receives
@@ -958,23 +1306,12 @@ impl Rapina {
         #[cfg(feature = "jwks")]
         // Warms up the JWKS cache so it is available immediately when the webserver starts up
         // and registers the JWKS refresh cronjob
-        let app = {
-            app.warmup_jwks_cache().await;
-            app.schedule_jwks_cronjob()
-        };
+        let app = app.warmup_jwks_cache().await.schedule_jwks_cronjob();
 
         #[cfg(feature = "cron-scheduler")]
         let app = app.start_cronjob_scheduler().await;
 
-        serve(
-            app.router,
-            app.state,
-            app.middlewares,
-            addr,
-            app.shutdown_timeout,
-            app.shutdown_hooks,
-        )
-        .await
+        app
     }
 }
 
@@ -1322,6 +1659,19 @@ mod tests {
         assert_eq!(app.cron_scheduler.unwrap().len(), 1);
     }
 
+    #[cfg(feature = "session")]
+    #[test]
+    fn test_with_sessions_schedules_cleanup() {
+        use crate::session::{InMemorySessionStore, SessionConfig};
+
+        let config = SessionConfig::new(
+            "0123456789abcdef0123456789abcdef",
+            InMemorySessionStore::new(),
+        );
+        let app = Rapina::new().with_sessions(config);
+        assert_eq!(app.cron_scheduler.unwrap().len(), 1);
+    }
+
     #[cfg(feature = "cron-scheduler")]
     #[test]
     fn test_rapina_cron_adds_multiple_cronjobs() {
@@ -1344,4 +1694,72 @@ mod tests {
         let broken_schedule = "";
         Rapina::new().cron(broken_schedule, || async { Ok::<(), Error>(()) });
     }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_listen_unix_replaces_stale_socket_and_cleans_up() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.sock");
+        // Leaves the socket file behind, like a crashed previous run.
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+
+        let app = Rapina::new()
+            .with_introspection(false)
+            .router(Router::new().route(http::Method::GET, "/", |_, _, _| async { "ok" }));
+        let handle = tokio::spawn(app.listen_unix(path.clone()));
+
+        let mut stream = loop {
+            if let Ok(stream) = tokio::net::UnixStream::connect(&path).await {
+                break stream;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf).await.unwrap();
+        assert!(String::from_utf8_lossy(&buf).starts_with("HTTP/1.1 200"));
+
+        nix::sys::signal::kill(nix::unistd::getpid(), nix::sys::signal::Signal::SIGTERM).unwrap();
+        let result = tokio::time::timeout(Duration::from_secs(5), handle).await;
+        assert!(result.unwrap().unwrap().is_ok());
+        assert!(!path.exists(), "socket file should be removed on shutdown");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_listen_unix_refuses_to_replace_regular_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("not-a-socket");
+        std::fs::write(&path, "data").unwrap();
+
+        let result = Rapina::new()
+            .with_introspection(false)
+            .listen_unix(&path)
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "data");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_listen_unix_refuses_to_replace_live_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.sock");
+        let _running = std::os::unix::net::UnixListener::bind(&path).unwrap();
+
+        let result = Rapina::new()
+            .with_introspection(false)
+            .listen_unix(&path)
+            .await;
+
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::AddrInUse);
+        assert!(std::os::unix::net::UnixStream::connect(&path).is_ok());
+    }
 }
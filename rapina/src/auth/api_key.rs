@@ -0,0 +1,259 @@
+//! API key authentication.
+//!
+//! Keys are never stored: the app keeps the SHA-256 hash of each key it
+//! hands out ([`hash_api_key`]) and [`ApiKeyAuth`] looks up the hash of the
+//! key a request presents through an [`ApiKeyStore`].
+
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use http::{HeaderMap, HeaderName, Uri};
+use sha2::{Digest, Sha256};
+
+use crate::error::Error;
+use crate::middleware::BoxFuture;
+use crate::openapi::{ParameterLocation, SecurityScheme};
+
+use super::{AuthClaims, AuthScheme, Claims, Identity};
+
+/// A boxed future for trait object compatibility.
+pub type ApiKeyFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Looks up the owner of an API key by the key's hash.
+///
+/// # Example
+///
+/// ```ignore
+/// struct ApiKeys(DatabaseConnection);
+///
+/// impl ApiKeyStore for ApiKeys {
+///     fn lookup(&self, key_hash: &str) -> ApiKeyFuture<'_, Result<Option<Claims>, Error>> {
+///         let key_hash = key_hash.to_string();
+///         Box::pin(async move {
+///             let key = api_key::Entity::find()
+///                 .filter(api_key::Column::Hash.eq(key_hash))
+///                 .filter(api_key::Column::RevokedAt.is_null())
+///                 .one(&self.0)
+///                 .await
+///                 .map_err(|e| DbError(e).into_api_error())?;
+///             Ok(key.map(|key| Claims::new(key.owner_id, 0)))
+///         })
+///     }
+/// }
+/// ```
+pub trait ApiKeyStore<C = Claims>: Send + Sync + 'static {
+    /// Returns the claims of the key whose [`hash_api_key`] is `key_hash`,
+    /// or `None` if there is no such key or it has been revoked.
+    fn lookup(&self, key_hash: &str) -> ApiKeyFuture<'_, Result<Option<C>, Error>>;
+}
+
+/// The hex SHA-256 hash of `key`, which is what to store for it.
+///
+/// A fast hash is enough because keys are long random strings, not
+/// passwords that could be guessed.
+pub fn hash_api_key(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Where a request carries its API key.
+#[derive(Debug, Clone)]
+enum KeySource {
+    /// The header, and its name as given, for the docs
+    Header(HeaderName, String),
+    Query(String),
+}
+
+/// Authenticates requests by an API key in a header or query parameter.
+///
+/// The key's owner is handed to handlers as [`CurrentUser<C>`](super::CurrentUser),
+/// just like a JWT's, so the same handlers, roles and scopes work for both.
+/// Add it next to JWT authentication with
+/// [`Rapina::with_auth_scheme`](crate::app::Rapina::with_auth_scheme).
+///
+/// # Example
+///
+/// ```ignore
+/// Rapina::new()
+///     .with_auth(AuthConfig::from_env()?)
+///     .with_auth_scheme(ApiKeyAuth::header("X-API-Key", ApiKeys(conn)))
+///     .discover()
+///     .listen("127.0.0.1:3000")
+///     .await
+/// ```
+pub struct ApiKeyAuth<C = Claims> {
+    source: KeySource,
+    name: String,
+    store: Arc<dyn ApiKeyStore<C>>,
+    claims: PhantomData<fn() -> C>,
+}
+
+impl<C> Clone for ApiKeyAuth<C> {
+    fn clone(&self) -> Self {
+        Self {
+            source: self.source.clone(),
+            name: self.name.clone(),
+            store: self.store.clone(),
+            claims: PhantomData,
+        }
+    }
+}
+
+impl<C: AuthClaims> ApiKeyAuth<C> {
+    /// Reads keys from the `header` request header.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `header` isn't a valid header name.
+    pub fn header(header: &str, store: impl ApiKeyStore<C>) -> Self {
+        let name = HeaderName::try_from(header)
+            .unwrap_or_else(|_| panic!("invalid API key header name: {:?}", header));
+        Self::from_source(KeySource::Header(name, header.to_string()), store)
+    }
+
+    /// Reads keys from the `param` query parameter.
+    ///
+    /// Query strings end up in access logs and browser history, so prefer
+    /// a header where clients allow it.
+    pub fn query(param: impl Into<String>, store: impl ApiKeyStore<C>) -> Self {
+        Self::from_source(KeySource::Query(param.into()), store)
+    }
+
+    fn from_source(source: KeySource, store: impl ApiKeyStore<C>) -> Self {
+        Self {
+            source,
+            name: "apiKeyAuth".to_string(),
+            store: Arc::new(store),
+            claims: PhantomData,
+        }
+    }
+
+    /// Sets the scheme's key in the OpenAPI `securitySchemes`
+    /// (default: `apiKeyAuth`), to tell several key schemes apart.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// The key the request carries, if any.
+    fn key(&self, headers: &HeaderMap, uri: &Uri) -> Option<String> {
+        let key = match &self.source {
+            KeySource::Header(header, _) => headers.get(header)?.to_str().ok()?.to_string(),
+            KeySource::Query(param) => {
+                serde_urlencoded::from_str::<Vec<(String, String)>>(uri.query()?)
+                    .ok()?
+                    .into_iter()
+                    .find_map(|(name, value)| (name == *param).then_some(value))?
+            }
+        };
+        Some(key).filter(|key| !key.is_empty())
+    }
+}
+
+impl<C: AuthClaims> AuthScheme for ApiKeyAuth<C> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn security_scheme(&self) -> SecurityScheme {
+        let (name, location) = match &self.source {
+            KeySource::Header(_, name) => (name.clone(), ParameterLocation::Header),
+            KeySource::Query(param) => (param.clone(), ParameterLocation::Query),
+        };
+        SecurityScheme::ApiKey { name, location }
+    }
+
+    fn authenticate<'a>(
+        &'a self,
+        headers: &'a HeaderMap,
+        uri: &'a Uri,
+    ) -> BoxFuture<'a, Result<Option<Identity>, Error>> {
+        Box::pin(async move {
+            let Some(key) = self.key(headers, uri) else {
+                return Ok(None);
+            };
+            match self.store.lookup(&hash_api_key(&key)).await? {
+                Some(claims) => Ok(Some(Identity::new(claims))),
+                None => Err(Error::unauthorized("invalid API key")),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Keys;
+
+    impl ApiKeyStore for Keys {
+        fn lookup(&self, key_hash: &str) -> ApiKeyFuture<'_, Result<Option<Claims>, Error>> {
+            let claims =
+                (key_hash == hash_api_key("sk_live_1")).then(|| Claims::new("service-1", 0));
+            Box::pin(async move { Ok(claims) })
+        }
+    }
+
+    #[test]
+    fn test_hash_api_key() {
+        // echo -n "abc" | sha256sum
+        assert_eq!(
+            hash_api_key("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_header_key() {
+        let auth = ApiKeyAuth::header("X-API-Key", Keys);
+        let uri = Uri::from_static("/");
+        let mut headers = HeaderMap::new();
+
+        assert!(auth.authenticate(&headers, &uri).await.unwrap().is_none());
+
+        headers.insert("x-api-key", "sk_live_1".parse().unwrap());
+        let identity = auth.authenticate(&headers, &uri).await.unwrap().unwrap();
+        assert_eq!(identity.id(), "service-1");
+
+        headers.insert("x-api-key", "sk_live_2".parse().unwrap());
+        let err = auth.authenticate(&headers, &uri).await.unwrap_err();
+        assert_eq!(err.status(), 401);
+        assert_eq!(err.message(), "invalid API key");
+    }
+
+    #[tokio::test]
+    async fn test_query_key() {
+        let auth = ApiKeyAuth::query("api_key", Keys);
+        let headers = HeaderMap::new();
+
+        let uri = Uri::from_static("/items?page=2&api_key=sk_live_1");
+        let identity = auth.authenticate(&headers, &uri).await.unwrap().unwrap();
+        assert_eq!(identity.id(), "service-1");
+
+        for uri in ["/items", "/items?page=2", "/items?api_key="] {
+            let uri = Uri::try_from(uri).unwrap();
+            assert!(auth.authenticate(&headers, &uri).await.unwrap().is_none());
+        }
+    }
+
+    #[test]
+    fn test_security_scheme() {
+        let header = ApiKeyAuth::header("X-API-Key", Keys);
+        assert_eq!(header.name(), "apiKeyAuth");
+        assert_eq!(
+            serde_json::to_value(header.security_scheme()).unwrap(),
+            serde_json::json!({"type": "apiKey", "name": "X-API-Key", "in": "header"})
+        );
+
+        let query = ApiKeyAuth::query("api_key", Keys).with_name("queryKey");
+        assert_eq!(query.name(), "queryKey");
+        assert_eq!(
+            serde_json::to_value(query.security_scheme()).unwrap(),
+            serde_json::json!({"type": "apiKey", "name": "api_key", "in": "query"})
+        );
+    }
+}
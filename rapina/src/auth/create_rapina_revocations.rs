@@ -0,0 +1,138 @@
+//! SeaORM migration for the token revocation tables.
+//!
+//! This is a framework-provided migration for
+//! [`SeaOrmRevocationStore`](super::SeaOrmRevocationStore). Register it in
+//! the project's migration list so it runs alongside application migrations:
+//!
+//! ```rust,ignore
+//! use rapina::auth::create_rapina_revocations;
+//!
+//! rapina::migrations! {
+//!     create_rapina_revocations,   // framework table — sorts first
+//!     m20260315_000001_create_users,
+//! }
+//! ```
+//!
+//! Unlike `rapina_jobs`, the tables use only portable column types, so the
+//! store works on PostgreSQL, MySQL and SQLite.
+
+use crate::migration::prelude::*;
+
+/// Migration that creates the `rapina_revoked_tokens` and
+/// `rapina_revoked_subjects` tables.
+///
+/// Implements [`MigrationName`](sea_orm_migration::MigrationName) manually
+/// (instead of `DeriveMigrationName`) to use a zero-timestamp prefix that
+/// sorts before all user migrations.
+pub struct Migration;
+
+impl sea_orm_migration::MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m00000000_000001_create_rapina_revocations"
+    }
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RapinaRevokedTokens::Table)
+                    .col(
+                        ColumnDef::new(RapinaRevokedTokens::Id)
+                            .string_len(255)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(RapinaRevokedTokens::ExpiresAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(RapinaRevokedSubjects::Table)
+                    .col(
+                        ColumnDef::new(RapinaRevokedSubjects::Subject)
+                            .string_len(255)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(RapinaRevokedSubjects::IssuedBefore)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RapinaRevokedSubjects::ExpiresAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RapinaRevokedSubjects::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(RapinaRevokedTokens::Table).to_owned())
+            .await
+    }
+}
+
+/// Column identifiers for the `rapina_revoked_tokens` table.
+///
+/// Holds revoked token ids (`jti`) and session ids (`sid`), each with the
+/// Unix timestamp after which the revocation can be purged.
+#[derive(DeriveIden)]
+pub(crate) enum RapinaRevokedTokens {
+    Table,
+    Id,
+    ExpiresAt,
+}
+
+/// Column identifiers for the `rapina_revoked_subjects` table.
+///
+/// Holds one cutoff per user: tokens issued before `issued_before` are
+/// revoked.
+#[derive(DeriveIden)]
+pub(crate) enum RapinaRevokedSubjects {
+    Table,
+    Subject,
+    IssuedBefore,
+    ExpiresAt,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migration_name_sorts_after_jobs_and_before_user_migrations() {
+        let name = sea_orm_migration::MigrationName::name(&Migration);
+        assert_eq!(name, "m00000000_000001_create_rapina_revocations");
+        assert!("m00000000_000000_create_rapina_jobs" < name);
+        assert!(name < "m20260315_000001_create_users");
+    }
+
+    #[test]
+    fn iden_table_names() {
+        assert_eq!(
+            RapinaRevokedTokens::Table.to_string(),
+            "rapina_revoked_tokens"
+        );
+        assert_eq!(
+            RapinaRevokedSubjects::Table.to_string(),
+            "rapina_revoked_subjects"
+        );
+    }
+}
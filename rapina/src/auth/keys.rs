@@ -0,0 +1,481 @@
+//! Keys for signing and verifying tokens.
+//!
+//! A [`SigningKey`] issues tokens and a [`VerifyingKey`] accepts them. Both
+//! carry a key id (`kid`) that is written into the header of every token, so
+//! several verification keys can be active at once while a signing key is
+//! rotated. The public half of RSA, ECDSA and EdDSA keys is published as a
+//! JSON Web Key; HMAC secrets never are.
+
+use std::sync::Arc;
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use http::header::{CACHE_CONTROL, CONTENT_TYPE};
+use http::{Request, Response, StatusCode};
+use hyper::body::Incoming;
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, EllipticCurveKeyParameters, Jwk, JwkSet,
+    KeyAlgorithm, OctetKeyPairParameters, PublicKeyUse, RSAKeyParameters,
+};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+
+use crate::extract::PathParams;
+use crate::response::{APPLICATION_JSON, BoxBody, IntoResponse};
+use crate::state::AppState;
+
+/// Where the public keys of an app's verification keys are published.
+pub const JWKS_PATH: &str = "/.well-known/jwks.json";
+
+/// Error loading a signing or verifying key.
+#[derive(Debug)]
+pub enum KeyError {
+    /// The PEM couldn't be read as a key for the algorithm.
+    Invalid { kid: String, reason: String },
+    /// The private key doesn't belong to the public key it was paired with.
+    Mismatch { kid: String },
+}
+
+impl std::fmt::Display for KeyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeyError::Invalid { kid, reason } => write!(f, "invalid key '{}': {}", kid, reason),
+            KeyError::Mismatch { kid } => {
+                write!(f, "private key '{}' does not match its public key", kid)
+            }
+        }
+    }
+}
+
+impl std::error::Error for KeyError {}
+
+fn invalid(kid: &str, reason: impl std::fmt::Display) -> KeyError {
+    KeyError::Invalid {
+        kid: kid.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+/// A key that accepts tokens.
+///
+/// # Example
+///
+/// ```ignore
+/// let previous = VerifyingKey::es256("2026-01", std::fs::read("keys/2026-01.pub.pem")?)?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct VerifyingKey {
+    kid: Option<String>,
+    algorithm: Algorithm,
+    decoding: DecodingKey,
+    jwk: Option<Jwk>,
+}
+
+impl VerifyingKey {
+    /// An HMAC-SHA256 shared secret.
+    pub fn hs256(kid: impl Into<String>, secret: impl AsRef<[u8]>) -> Self {
+        Self::secret(Some(kid.into()), secret.as_ref())
+    }
+
+    pub(crate) fn secret(kid: Option<String>, secret: &[u8]) -> Self {
+        Self {
+            kid,
+            algorithm: Algorithm::HS256,
+            decoding: DecodingKey::from_secret(secret),
+            jwk: None,
+        }
+    }
+
+    /// An RSA public key in PEM format, for RS256.
+    pub fn rs256(kid: impl Into<String>, public_pem: impl AsRef<[u8]>) -> Result<Self, KeyError> {
+        let kid = kid.into();
+        let decoding =
+            DecodingKey::from_rsa_pem(public_pem.as_ref()).map_err(|e| invalid(&kid, e))?;
+        let (n, e) = rsa_components(decoding.as_bytes())
+            .ok_or_else(|| invalid(&kid, "expected an RSA public key"))?;
+        let params = AlgorithmParameters::RSA(RSAKeyParameters {
+            n: URL_SAFE_NO_PAD.encode(n),
+            e: URL_SAFE_NO_PAD.encode(e),
+            ..Default::default()
+        });
+        Ok(Self::public(kid, Algorithm::RS256, decoding, params))
+    }
+
+    /// An ECDSA P-256 public key in PEM format, for ES256.
+    pub fn es256(kid: impl Into<String>, public_pem: impl AsRef<[u8]>) -> Result<Self, KeyError> {
+        let kid = kid.into();
+        let decoding =
+            DecodingKey::from_ec_pem(public_pem.as_ref()).map_err(|e| invalid(&kid, e))?;
+        // An uncompressed P-256 point: 0x04, then 32 bytes each of x and y
+        let point = decoding.as_bytes();
+        if point.len() != 65 || point[0] != 0x04 {
+            return Err(invalid(&kid, "expected a P-256 public key"));
+        }
+        let params = AlgorithmParameters::EllipticCurve(EllipticCurveKeyParameters {
+            curve: EllipticCurve::P256,
+            x: URL_SAFE_NO_PAD.encode(&point[1..33]),
+            y: URL_SAFE_NO_PAD.encode(&point[33..]),
+            ..Default::default()
+        });
+        Ok(Self::public(kid, Algorithm::ES256, decoding, params))
+    }
+
+    /// An Ed25519 public key in PEM format, for EdDSA.
+    pub fn eddsa(kid: impl Into<String>, public_pem: impl AsRef<[u8]>) -> Result<Self, KeyError> {
+        let kid = kid.into();
+        let decoding =
+            DecodingKey::from_ed_pem(public_pem.as_ref()).map_err(|e| invalid(&kid, e))?;
+        if decoding.as_bytes().len() != 32 {
+            return Err(invalid(&kid, "expected an Ed25519 public key"));
+        }
+        let params = AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+            curve: EllipticCurve::Ed25519,
+            x: URL_SAFE_NO_PAD.encode(decoding.as_bytes()),
+            ..Default::default()
+        });
+        Ok(Self::public(kid, Algorithm::EdDSA, decoding, params))
+    }
+
+    fn public(
+        kid: String,
+        algorithm: Algorithm,
+        decoding: DecodingKey,
+        params: AlgorithmParameters,
+    ) -> Self {
+        let key_algorithm = match algorithm {
+            Algorithm::RS256 => KeyAlgorithm::RS256,
+            Algorithm::ES256 => KeyAlgorithm::ES256,
+            _ => KeyAlgorithm::EdDSA,
+        };
+        let jwk = Jwk {
+            common: CommonParameters {
+                public_key_use: Some(PublicKeyUse::Signature),
+                key_algorithm: Some(key_algorithm),
+                key_id: Some(kid.clone()),
+                ..Default::default()
+            },
+            algorithm: params,
+        };
+        Self {
+            kid: Some(kid),
+            algorithm,
+            decoding,
+            jwk: Some(jwk),
+        }
+    }
+
+    /// The key id tokens checked by this key carry in their header.
+    pub fn kid(&self) -> Option<&str> {
+        self.kid.as_deref()
+    }
+
+    /// The algorithm tokens checked by this key are signed with.
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// The public key as a JSON Web Key, or `None` for an HMAC secret.
+    pub fn jwk(&self) -> Option<&Jwk> {
+        self.jwk.as_ref()
+    }
+
+    pub(crate) fn decoding_key(&self) -> &DecodingKey {
+        &self.decoding
+    }
+}
+
+/// A key that issues tokens.
+///
+/// Asymmetric keys take the private key and the public key as separate PEMs,
+/// the way `openssl` writes them out. The public key verifies tokens and is
+/// published on `/.well-known/jwks.json`.
+///
+/// # Example
+///
+/// ```ignore
+/// let key = SigningKey::rs256(
+///     "2026-10",
+///     std::fs::read("keys/2026-10.pem")?,
+///     std::fs::read("keys/2026-10.pub.pem")?,
+/// )?;
+/// ```
+#[derive(Clone)]
+pub struct SigningKey {
+    encoding: EncodingKey,
+    verifying: VerifyingKey,
+}
+
+impl std::fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SigningKey")
+            .field("kid", &self.kid())
+            .field("algorithm", &self.algorithm())
+            .finish_non_exhaustive()
+    }
+}
+
+impl SigningKey {
+    /// An HMAC-SHA256 shared secret.
+    pub fn hs256(kid: impl Into<String>, secret: impl AsRef<[u8]>) -> Self {
+        Self::secret(Some(kid.into()), secret.as_ref())
+    }
+
+    pub(crate) fn secret(kid: Option<String>, secret: &[u8]) -> Self {
+        Self {
+            encoding: EncodingKey::from_secret(secret),
+            verifying: VerifyingKey::secret(kid, secret),
+        }
+    }
+
+    /// An RSA key pair in PEM format, for RS256.
+    pub fn rs256(
+        kid: impl Into<String>,
+        private_pem: impl AsRef<[u8]>,
+        public_pem: impl AsRef<[u8]>,
+    ) -> Result<Self, KeyError> {
+        let verifying = VerifyingKey::rs256(kid, public_pem)?;
+        let encoding = EncodingKey::from_rsa_pem(private_pem.as_ref())
+            .map_err(|e| invalid(verifying.kid().unwrap_or_default(), e))?;
+        Self::pair(encoding, verifying)
+    }
+
+    /// An ECDSA P-256 key pair in PEM format, for ES256.
+    pub fn es256(
+        kid: impl Into<String>,
+        private_pem: impl AsRef<[u8]>,
+        public_pem: impl AsRef<[u8]>,
+    ) -> Result<Self, KeyError> {
+        let verifying = VerifyingKey::es256(kid, public_pem)?;
+        let encoding = EncodingKey::from_ec_pem(private_pem.as_ref())
+            .map_err(|e| invalid(verifying.kid().unwrap_or_default(), e))?;
+        Self::pair(encoding, verifying)
+    }
+
+    /// An Ed25519 key pair in PEM format, for EdDSA.
+    pub fn eddsa(
+        kid: impl Into<String>,
+        private_pem: impl AsRef<[u8]>,
+        public_pem: impl AsRef<[u8]>,
+    ) -> Result<Self, KeyError> {
+        let verifying = VerifyingKey::eddsa(kid, public_pem)?;
+        let encoding = EncodingKey::from_ed_pem(private_pem.as_ref())
+            .map_err(|e| invalid(verifying.kid().unwrap_or_default(), e))?;
+        Self::pair(encoding, verifying)
+    }
+
+    /// Pairs the keys after checking a token signed by one verifies with the other.
+    fn pair(encoding: EncodingKey, verifying: VerifyingKey) -> Result<Self, KeyError> {
+        let kid = verifying.kid().unwrap_or_default().to_string();
+        let claims = serde_json::json!({ "sub": "rapina-key-check" });
+        let token = jsonwebtoken::encode(&Header::new(verifying.algorithm), &claims, &encoding)
+            .map_err(|e| invalid(&kid, e))?;
+        let mut validation = Validation::new(verifying.algorithm);
+        validation.required_spec_claims.clear();
+        validation.validate_exp = false;
+        jsonwebtoken::decode::<serde_json::Value>(&token, &verifying.decoding, &validation)
+            .map_err(|_| KeyError::Mismatch { kid })?;
+        Ok(Self {
+            encoding,
+            verifying,
+        })
+    }
+
+    /// The key id written into the header of tokens this key signs.
+    pub fn kid(&self) -> Option<&str> {
+        self.verifying.kid()
+    }
+
+    /// The algorithm this key signs with.
+    pub fn algorithm(&self) -> Algorithm {
+        self.verifying.algorithm
+    }
+
+    /// The key that verifies tokens signed by this one.
+    pub fn verifying_key(&self) -> &VerifyingKey {
+        &self.verifying
+    }
+
+    pub(crate) fn encoding_key(&self) -> &EncodingKey {
+        &self.encoding
+    }
+}
+
+/// The key set served on [`JWKS_PATH`].
+pub(crate) struct PublishedKeys(pub(crate) JwkSet);
+
+/// Handler for the JWKS endpoint.
+///
+/// Serves the public verification keys as a JSON Web Key Set. Clients may
+/// cache it for a few minutes, so a new key should be added with
+/// [`AuthConfig::verify_with`](super::AuthConfig::verify_with) a little
+/// before it starts signing.
+pub(crate) async fn jwks(
+    _req: Request<Incoming>,
+    _params: PathParams,
+    state: Arc<AppState>,
+) -> Response<BoxBody> {
+    match state.get::<PublishedKeys>() {
+        Some(keys) => {
+            let json = serde_json::to_vec(&keys.0).unwrap_or_default();
+            Response::builder()
+                .status(StatusCode::OK)
+                .header(CONTENT_TYPE, APPLICATION_JSON)
+                .header(CACHE_CONTROL, "public, max-age=300")
+                .body(BoxBody::from(bytes::Bytes::from(json)))
+                .unwrap()
+        }
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Reads the modulus and exponent from a DER-encoded PKCS#1 `RSAPublicKey`.
+fn rsa_components(der: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (sequence, rest) = der_element(der, 0x30)?;
+    if !rest.is_empty() {
+        return None;
+    }
+    let (n, rest) = der_element(sequence, 0x02)?;
+    let (e, rest) = der_element(rest, 0x02)?;
+    // A private key carries more integers after the first two
+    if !rest.is_empty() {
+        return None;
+    }
+    Some((strip_leading_zeros(n), strip_leading_zeros(e)))
+}
+
+/// Splits one DER element with the given tag off the front of `input`,
+/// returning its contents and whatever follows it.
+fn der_element(input: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    let (&first, input) = input.split_first()?;
+    if first != tag {
+        return None;
+    }
+    let (&len, mut input) = input.split_first()?;
+    let len = if len & 0x80 == 0 {
+        len as usize
+    } else {
+        let octets = (len & 0x7f) as usize;
+        if octets == 0 || octets > std::mem::size_of::<usize>() || input.len() < octets {
+            return None;
+        }
+        let (bytes, rest) = input.split_at(octets);
+        input = rest;
+        bytes.iter().fold(0usize, |acc, &b| (acc << 8) | b as usize)
+    };
+    (input.len() >= len).then(|| input.split_at(len))
+}
+
+fn strip_leading_zeros(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .position(|&b| b != 0)
+        .unwrap_or(bytes.len().saturating_sub(1));
+    &bytes[start..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RSA_PRIVATE: &str = include_str!("../../tests/fixtures/jwt/rsa_private.pem");
+    const RSA_PUBLIC: &str = include_str!("../../tests/fixtures/jwt/rsa_public.pem");
+    const EC_PRIVATE: &str = include_str!("../../tests/fixtures/jwt/ec_private.pem");
+    const EC_PUBLIC: &str = include_str!("../../tests/fixtures/jwt/ec_public.pem");
+    const ED_PRIVATE: &str = include_str!("../../tests/fixtures/jwt/ed_private.pem");
+    const ED_PUBLIC: &str = include_str!("../../tests/fixtures/jwt/ed_public.pem");
+
+    fn round_trips(jwk: &Jwk, key: &VerifyingKey) {
+        let decoding = DecodingKey::from_jwk(jwk).unwrap();
+        let signing = match key.algorithm() {
+            Algorithm::RS256 => SigningKey::rs256("k", RSA_PRIVATE, RSA_PUBLIC),
+            Algorithm::ES256 => SigningKey::es256("k", EC_PRIVATE, EC_PUBLIC),
+            _ => SigningKey::eddsa("k", ED_PRIVATE, ED_PUBLIC),
+        }
+        .unwrap();
+        let token = jsonwebtoken::encode(
+            &Header::new(key.algorithm()),
+            &serde_json::json!({ "sub": "user" }),
+            signing.encoding_key(),
+        )
+        .unwrap();
+        let mut validation = Validation::new(key.algorithm());
+        validation.required_spec_claims.clear();
+        validation.validate_exp = false;
+        assert!(jsonwebtoken::decode::<serde_json::Value>(&token, &decoding, &validation).is_ok());
+    }
+
+    #[test]
+    fn test_rs256_jwk() {
+        let key = VerifyingKey::rs256("rsa-1", RSA_PUBLIC).unwrap();
+        let jwk = key.jwk().unwrap();
+        let json = serde_json::to_value(jwk).unwrap();
+        assert_eq!(json["kty"], "RSA");
+        assert_eq!(json["kid"], "rsa-1");
+        assert_eq!(json["alg"], "RS256");
+        assert_eq!(json["use"], "sig");
+        assert_eq!(json["e"], "AQAB");
+        round_trips(jwk, &key);
+    }
+
+    #[test]
+    fn test_es256_jwk() {
+        let key = VerifyingKey::es256("ec-1", EC_PUBLIC).unwrap();
+        let jwk = key.jwk().unwrap();
+        let json = serde_json::to_value(jwk).unwrap();
+        assert_eq!(json["kty"], "EC");
+        assert_eq!(json["crv"], "P-256");
+        assert_eq!(json["alg"], "ES256");
+        round_trips(jwk, &key);
+    }
+
+    #[test]
+    fn test_eddsa_jwk() {
+        let key = VerifyingKey::eddsa("ed-1", ED_PUBLIC).unwrap();
+        let jwk = key.jwk().unwrap();
+        let json = serde_json::to_value(jwk).unwrap();
+        assert_eq!(json["kty"], "OKP");
+        assert_eq!(json["crv"], "Ed25519");
+        assert_eq!(json["alg"], "EdDSA");
+        round_trips(jwk, &key);
+    }
+
+    #[test]
+    fn test_hmac_key_has_no_jwk() {
+        let key = SigningKey::hs256("hmac-1", "secret");
+        assert_eq!(key.kid(), Some("hmac-1"));
+        assert_eq!(key.algorithm(), Algorithm::HS256);
+        assert!(key.verifying_key().jwk().is_none());
+    }
+
+    #[test]
+    fn test_rejects_wrong_key_type() {
+        assert!(matches!(
+            VerifyingKey::rs256("k", EC_PUBLIC),
+            Err(KeyError::Invalid { .. })
+        ));
+        assert!(matches!(
+            VerifyingKey::es256("k", "not a pem"),
+            Err(KeyError::Invalid { .. })
+        ));
+    }
+
+    #[test]
+    fn test_rejects_mismatched_pair() {
+        let other = VerifyingKey::rs256("k", RSA_PUBLIC).unwrap();
+        let encoding = EncodingKey::from_ec_pem(EC_PRIVATE.as_bytes()).unwrap();
+        assert!(SigningKey::pair(encoding, other).is_err());
+
+        // A second P-256 key: the same algorithm, but not this pair
+        let err = SigningKey::es256("k", EC_PRIVATE, OTHER_EC_PUBLIC).unwrap_err();
+        assert!(matches!(err, KeyError::Mismatch { .. }));
+        assert_eq!(
+            err.to_string(),
+            "private key 'k' does not match its public key"
+        );
+    }
+
+    const OTHER_EC_PUBLIC: &str = "-----BEGIN PUBLIC KEY-----
+MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEWIcOmzCTUoOfHmyS3k8aTpcb8PqU
+F0r/ywgOxeoTmhBHzjyboYG+U5SXcjpbf4N2noo+sg5hDLhOndHziHvvhA==
+-----END PUBLIC KEY-----
+";
+}
@@ -1,18 +1,29 @@
 //! Authentication middleware for Rapina.
 
+use std::marker::PhantomData;
+use std::sync::Arc;
+
 use hyper::body::Incoming;
 use hyper::{Request, Response};
 
-use crate::auth::{AuthConfig, CurrentUser, PublicRoutes};
+use crate::auth::{AuthClaims, AuthConfig, AuthScheme, Claims, PublicRoutes, scheme};
 use crate::context::RequestContext;
 use crate::error::Error;
 use crate::middleware::{BoxFuture, Middleware, Next};
 use crate::response::{BoxBody, IntoResponse};
 
-/// Middleware that enforces JWT authentication on all routes.
+/// Middleware that enforces authentication on all routes.
 ///
 /// Routes marked with `#[public]` or starting with `/__rapina` bypass authentication.
 /// All other routes require a valid `Authorization: Bearer <token>` header.
+/// The token's claims are decoded as `C`, the claims type of the
+/// [`AuthConfig`], and handed to handlers as [`CurrentUser<C>`](crate::auth::CurrentUser).
+/// When the config has a revocation store, revoked tokens are rejected.
+///
+/// Further [`AuthScheme`]s added with [`or_scheme`](Self::or_scheme), such
+/// as [`ApiKeyAuth`](crate::auth::ApiKeyAuth), are tried in order after the
+/// JWT. The first to accept the request's credentials authenticates it; if
+/// none does, the first rejection is returned.
 ///
 /// # Example
 ///
@@ -28,38 +39,52 @@ use crate::response::{BoxBody, IntoResponse};
 ///     .listen("127.0.0.1:3000")
 ///     .await
 /// ```
-pub struct AuthMiddleware {
-    config: AuthConfig,
+pub struct AuthMiddleware<C = Claims> {
+    schemes: Arc<[Arc<dyn AuthScheme>]>,
     public_routes: PublicRoutes,
+    claims: PhantomData<fn() -> C>,
 }
 
-impl AuthMiddleware {
+impl<C: AuthClaims> AuthMiddleware<C> {
     /// Creates a new auth middleware with the given configuration.
-    pub fn new(config: AuthConfig) -> Self {
-        Self {
-            config,
-            public_routes: PublicRoutes::new(),
-        }
+    pub fn new(config: AuthConfig<C>) -> Self {
+        Self::with_public_routes(config, PublicRoutes::new())
     }
 
     /// Creates a new auth middleware with explicit public routes.
-    pub fn with_public_routes(config: AuthConfig, public_routes: PublicRoutes) -> Self {
+    pub fn with_public_routes(config: AuthConfig<C>, public_routes: PublicRoutes) -> Self {
         Self {
-            config,
+            schemes: Arc::new([Arc::new(config) as Arc<dyn AuthScheme>]),
             public_routes,
+            claims: PhantomData,
         }
     }
 
-    /// Extracts the bearer token from the Authorization header.
-    fn extract_bearer_token(req: &Request<Incoming>) -> Option<&str> {
-        req.headers()
-            .get(http::header::AUTHORIZATION)
-            .and_then(|v| v.to_str().ok())
-            .and_then(|v| v.strip_prefix("Bearer "))
+    /// Also authenticates requests with `scheme`, tried after the schemes
+    /// already added.
+    pub fn or_scheme(mut self, scheme: impl AuthScheme) -> Self {
+        let mut schemes = self.schemes.to_vec();
+        schemes.push(Arc::new(scheme));
+        self.schemes = schemes.into();
+        self
+    }
+}
+
+impl AuthMiddleware {
+    /// Creates a middleware trying `schemes` in order.
+    pub(crate) fn from_schemes(
+        schemes: Arc<[Arc<dyn AuthScheme>]>,
+        public_routes: PublicRoutes,
+    ) -> Self {
+        Self {
+            schemes,
+            public_routes,
+            claims: PhantomData,
+        }
     }
 }
 
-impl Middleware for AuthMiddleware {
+impl<C: AuthClaims> Middleware for AuthMiddleware<C> {
     fn handle<'a>(
         &'a self,
         mut req: Request<Incoming>,
@@ -75,29 +100,16 @@ impl Middleware for AuthMiddleware {
                 return next.run(req).await;
             }
 
-            // Extract and validate the bearer token
-            let token = match Self::extract_bearer_token(&req) {
-                Some(t) => t,
-                None => {
-                    return Error::unauthorized("missing authorization header").into_response();
-                }
-            };
-
-            // Decode and validate the JWT
-            let claims = match self.config.decode(token) {
-                Ok(c) => c,
-                Err(e) => {
-                    return e.into_response();
-                }
-            };
-
-            // Create CurrentUser and inject it into request extensions
-            let current_user = CurrentUser {
-                id: claims.sub.clone(),
-                claims,
+            // Try each scheme until one accepts the request's credentials
+            let identity = match scheme::authenticate(&self.schemes, req.headers(), req.uri()).await
+            {
+                Ok(Some(identity)) => identity,
+                Ok(None) => return Error::unauthorized("missing credentials").into_response(),
+                Err(e) => return e.into_response(),
             };
 
-            req.extensions_mut().insert(current_user);
+            // Hand the user to handlers as CurrentUser
+            identity.insert_into(req.extensions_mut());
 
             next.run(req).await
         })
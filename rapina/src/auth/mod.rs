@@ -31,18 +31,179 @@
 //!         .await
 //! }
 //! ```
+//!
+//! # Custom Claims
+//!
+//! Tokens carry [`Claims`] unless the app declares its own claims type.
+//! Implement [`AuthClaims`] for it and pass it to
+//! [`AuthConfig::with_claims`]; handlers then take `CurrentUser<MyClaims>`.
+//! Roles and scopes reported by [`AuthClaims`] are what
+//! `#[requires_role("...")]` and `#[requires_scope("...")]` check.
+//!
+//! ```ignore
+//! #[derive(Clone, Serialize, Deserialize)]
+//! struct TenantClaims {
+//!     sub: String,
+//!     exp: u64,
+//!     tenant: String,
+//!     roles: Vec<String>,
+//! }
+//!
+//! impl AuthClaims for TenantClaims {
+//!     fn subject(&self) -> &str {
+//!         &self.sub
+//!     }
+//!
+//!     fn has_role(&self, role: &str) -> bool {
+//!         self.roles.iter().any(|r| r == role)
+//!     }
+//! }
+//!
+//! #[delete("/users/:id")]
+//! #[requires_role("admin")]
+//! async fn delete_user(user: CurrentUser<TenantClaims>, id: Path<u64>) -> StatusCode {
+//!     // only admins get here
+//! }
+//!
+//! let auth = AuthConfig::from_env()?.with_claims::<TenantClaims>();
+//! ```
+//!
+//! # Signing Keys
+//!
+//! [`AuthConfig::new`] signs with an HMAC secret. For RS256, ES256 or EdDSA,
+//! load a [`SigningKey`] from PEM files and pass it to
+//! [`AuthConfig::from_key`]. Every token then names its key in the `kid`
+//! header, the public keys are served on `/.well-known/jwks.json`, and older
+//! keys added with [`AuthConfig::verify_with`] keep verifying the tokens
+//! they signed while the new key takes over.
+//!
+//! ```ignore
+//! let auth = AuthConfig::from_key(
+//!     SigningKey::es256("2026-10", std::fs::read("keys/2026-10.pem")?, std::fs::read("keys/2026-10.pub.pem")?)?,
+//!     3600,
+//! )
+//! .verify_with(VerifyingKey::es256("2026-07", std::fs::read("keys/2026-07.pub.pem")?)?);
+//! ```
+//!
+//! # Refresh Tokens and Revocation
+//!
+//! With a [`RevocationStore`] configured, [`AuthConfig::create_token_pair`]
+//! starts a session with a short-lived access token and a refresh token.
+//! Each refresh token works once: [`AuthConfig::refresh`] swaps it for a new
+//! pair, and presenting it a second time revokes the whole session. The
+//! middleware rejects revoked tokens, and [`AuthConfig::logout`] and
+//! [`AuthConfig::logout_all`] end one session or every session of a user.
+//!
+//! ```ignore
+//! let auth = AuthConfig::from_env()?.with_revocation_store(InMemoryRevocationStore::new());
+//!
+//! #[public]
+//! #[post("/token/refresh")]
+//! async fn refresh(auth: State<AuthConfig>, body: Json<RefreshRequest>) -> Result<Json<TokenPair>> {
+//!     Ok(Json(auth.refresh(&body.refresh_token).await?))
+//! }
+//! ```
+//!
+//! # API Keys
+//!
+//! [`ApiKeyAuth`] authenticates machine clients by a key in a header or
+//! query parameter, looking up the key's hash through an [`ApiKeyStore`].
+//! Add it with `Rapina::with_auth_scheme`; it sets [`CurrentUser`] like
+//! JWTs do, and the app's [`AuthScheme`]s are tried in the order they were
+//! added.
+//!
+//! ```ignore
+//! Rapina::new()
+//!     .with_auth(AuthConfig::from_env()?)
+//!     .with_auth_scheme(ApiKeyAuth::header("X-API-Key", ApiKeys(conn)))
+//! ```
 
+mod api_key;
+#[cfg(feature = "database")]
+pub mod create_rapina_revocations;
+mod keys;
 mod middleware;
-
+mod refresh;
+mod revocation;
+#[cfg(feature = "database")]
+mod revocation_db;
+mod scheme;
+
+pub use api_key::{ApiKeyAuth, ApiKeyFuture, ApiKeyStore, hash_api_key};
+pub use keys::{JWKS_PATH, KeyError, SigningKey, VerifyingKey};
 pub use middleware::AuthMiddleware;
+pub use refresh::{RefreshToken, TokenPair};
+pub use revocation::{InMemoryRevocationStore, RevocationCheck, RevocationFuture, RevocationStore};
+#[cfg(feature = "database")]
+pub use revocation_db::SeaOrmRevocationStore;
+pub use scheme::{AuthScheme, Identity};
+
+pub(crate) use keys::{PublishedKeys, jwks};
 
 use crate::error::Error;
 use crate::extract::{FromRequestParts, PathParams};
 use crate::state::AppState;
-use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Header, Validation, decode, decode_header, encode};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
 use std::sync::Arc;
 
+/// Claims a token can carry, whether the built-in [`Claims`] or an app's own.
+///
+/// The claims are deserialized from every valid token, so the type must
+/// have an `exp` field holding the expiration as a Unix timestamp. Roles
+/// and scopes default to none; override [`has_role`](Self::has_role) and
+/// [`has_scope`](Self::has_scope) to back `#[requires_role]` and
+/// `#[requires_scope]`.
+pub trait AuthClaims: Serialize + DeserializeOwned + Clone + Send + Sync + 'static {
+    /// The user the token was issued to, exposed as [`CurrentUser::id`].
+    fn subject(&self) -> &str;
+
+    /// Whether the token grants `role`.
+    fn has_role(&self, _role: &str) -> bool {
+        false
+    }
+
+    /// Whether the token grants `scope`.
+    fn has_scope(&self, _scope: &str) -> bool {
+        false
+    }
+
+    /// The token's own id (`jti`), which lets it be revoked on its own.
+    fn token_id(&self) -> Option<&str> {
+        None
+    }
+
+    /// The login session the token belongs to (`sid`), which lets
+    /// [`AuthConfig::logout`] end the session.
+    fn session_id(&self) -> Option<&str> {
+        None
+    }
+
+    /// When the token was issued (`iat`), as a Unix timestamp. Without it
+    /// a token can't outlive [`AuthConfig::logout_all`].
+    fn issued_at(&self) -> Option<u64> {
+        None
+    }
+
+    /// When the token expires (`exp`), as a Unix timestamp, so a revocation
+    /// is kept exactly as long as the token would be accepted.
+    fn expires_at(&self) -> Option<u64> {
+        None
+    }
+}
+
+/// The current Unix time in seconds.
+pub(crate) fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
 /// JWT claims structure.
 ///
 /// Contains the standard JWT claims plus any custom data.
@@ -54,30 +215,53 @@ pub struct Claims {
     pub exp: u64,
     /// Issued at time (Unix timestamp)
     pub iat: u64,
+    /// Token ID, unique to every token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+    /// Session ID, shared by the tokens of one login (see [`TokenPair`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
 }
 
 impl Claims {
     /// Creates new claims for the given subject with specified expiration.
     pub fn new(sub: impl Into<String>, expires_in_secs: u64) -> Self {
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let now = now();
 
         Self {
             sub: sub.into(),
             exp: now + expires_in_secs,
             iat: now,
+            jti: Some(uuid::Uuid::new_v4().to_string()),
+            sid: None,
         }
     }
 
     /// Checks if the token has expired.
     pub fn is_expired(&self) -> bool {
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        self.exp < now
+        self.exp < now()
+    }
+}
+
+impl AuthClaims for Claims {
+    fn subject(&self) -> &str {
+        &self.sub
+    }
+
+    fn token_id(&self) -> Option<&str> {
+        self.jti.as_deref()
+    }
+
+    fn session_id(&self) -> Option<&str> {
+        self.sid.as_deref()
+    }
+
+    fn issued_at(&self) -> Option<u64> {
+        Some(self.iat)
+    }
+
+    fn expires_at(&self) -> Option<u64> {
+        Some(self.exp)
     }
 }
 
@@ -113,6 +297,7 @@ impl TokenResponse {
 ///
 /// This extractor is automatically populated by the auth middleware
 /// for protected routes. Use it to access the current user's information.
+/// With custom claims, name the claims type: `CurrentUser<MyClaims>`.
 ///
 /// # Example
 ///
@@ -126,14 +311,26 @@ impl TokenResponse {
 /// }
 /// ```
 #[derive(Debug, Clone)]
-pub struct CurrentUser {
-    /// The user ID (from JWT `sub` claim)
+pub struct CurrentUser<C = Claims> {
+    /// The user ID (from the token's subject)
     pub id: String,
     /// The full JWT claims
-    pub claims: Claims,
+    pub claims: C,
+}
+
+impl<C: AuthClaims> CurrentUser<C> {
+    /// Whether the user's token grants `role`.
+    pub fn has_role(&self, role: &str) -> bool {
+        self.claims.has_role(role)
+    }
+
+    /// Whether the user's token grants `scope`.
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.claims.has_scope(scope)
+    }
 }
 
-impl FromRequestParts for CurrentUser {
+impl<C: AuthClaims> FromRequestParts for CurrentUser<C> {
     async fn from_request_parts(
         parts: &http::request::Parts,
         _params: &PathParams,
@@ -141,18 +338,114 @@ impl FromRequestParts for CurrentUser {
     ) -> Result<Self, Error> {
         parts
             .extensions
-            .get::<CurrentUser>()
+            .get::<CurrentUser<C>>()
             .cloned()
             .ok_or_else(|| Error::unauthorized("authentication required"))
     }
 }
 
+/// Roles and scopes of a token, for code that doesn't know its claims type.
+trait Grants: Send + Sync {
+    fn grants_role(&self, role: &str) -> bool;
+    fn grants_scope(&self, scope: &str) -> bool;
+}
+
+impl<C: AuthClaims> Grants for C {
+    fn grants_role(&self, role: &str) -> bool {
+        self.has_role(role)
+    }
+
+    fn grants_scope(&self, scope: &str) -> bool {
+        self.has_scope(scope)
+    }
+}
+
+/// The authenticated user with the claims type erased.
+///
+/// Set next to [`CurrentUser`] by [`AuthMiddleware`], so the router and
+/// other middleware can identify and authorize a user whatever claims the
+/// app uses.
+#[derive(Clone)]
+pub(crate) struct Authenticated {
+    pub(crate) id: String,
+    grants: Arc<dyn Grants>,
+}
+
+impl Authenticated {
+    pub(crate) fn new<C: AuthClaims>(claims: &C) -> Self {
+        Self {
+            id: claims.subject().to_string(),
+            grants: Arc::new(claims.clone()),
+        }
+    }
+}
+
+/// Checks a request against the roles and scopes its route requires.
+///
+/// Every role and scope must be granted. Without an authenticated user
+/// the request is unauthorized; with one that lacks a grant it is forbidden.
+pub(crate) fn authorize(
+    extensions: &http::Extensions,
+    roles: &[String],
+    scopes: &[String],
+) -> Result<(), Error> {
+    if roles.is_empty() && scopes.is_empty() {
+        return Ok(());
+    }
+    let user = extensions
+        .get::<Authenticated>()
+        .ok_or_else(|| Error::unauthorized("authentication required"))?;
+    if let Some(role) = roles.iter().find(|role| !user.grants.grants_role(role)) {
+        return Err(Error::forbidden(format!("requires role `{}`", role)));
+    }
+    if let Some(scope) = scopes.iter().find(|scope| !user.grants.grants_scope(scope)) {
+        return Err(Error::forbidden(format!("requires scope `{}`", scope)));
+    }
+    Ok(())
+}
+
+/// Works out the user a request belongs to.
+///
+/// [`AuthMiddleware`] sets [`CurrentUser`] once it runs, but middleware in
+/// front of it, such as the response cache, needs the user id earlier.
+/// The app stores one of these in its state when authentication is enabled.
+#[derive(Clone)]
+pub(crate) struct UserIdResolver(Arc<[Arc<dyn AuthScheme>]>);
+
+impl UserIdResolver {
+    /// Resolves the user the first accepting scheme authenticates.
+    pub(crate) fn new(schemes: Arc<[Arc<dyn AuthScheme>]>) -> Self {
+        Self(schemes)
+    }
+
+    pub(crate) async fn resolve(
+        &self,
+        headers: &http::HeaderMap,
+        uri: &http::Uri,
+    ) -> Option<String> {
+        let identity = scheme::authenticate(&self.0, headers, uri).await.ok()??;
+        Some(identity.id().to_string())
+    }
+}
+
+/// Refresh token lifetime unless configured: 30 days.
+const DEFAULT_REFRESH_EXPIRATION: u64 = 30 * 24 * 60 * 60;
+
 /// Configuration for JWT authentication.
 ///
 /// Use environment variables to configure:
 /// - `JWT_SECRET` - The secret key for signing/verifying tokens (required)
 /// - `JWT_EXPIRATION` - Token expiration in seconds (default: 3600)
 ///
+/// Tokens carry [`Claims`] by default; [`with_claims`](Self::with_claims)
+/// switches to an app-defined [`AuthClaims`] type.
+///
+/// Tokens are signed with HS256 by default; [`from_key`](Self::from_key)
+/// takes an RS256, ES256 or EdDSA [`SigningKey`] instead.
+///
+/// Refresh tokens, logout and revocation need a [`RevocationStore`], set
+/// with [`with_revocation_store`](Self::with_revocation_store). Clones share it.
+///
 /// # Example
 ///
 /// ```ignore
@@ -160,31 +453,141 @@ impl FromRequestParts for CurrentUser {
 /// // or with explicit values:
 /// let config = AuthConfig::new("my-secret-key", 7200);
 /// ```
-#[derive(Clone)]
-pub struct AuthConfig {
-    /// The secret key for signing and verifying JWT tokens
-    secret: String,
+pub struct AuthConfig<C = Claims> {
+    /// The key new tokens are signed with
+    signing: SigningKey,
+    /// Keys tokens are accepted from, starting with the signing key's
+    verifying: Vec<VerifyingKey>,
     /// Token expiration time in seconds
     expiration: u64,
+    /// Refresh token expiration time in seconds
+    refresh_expiration: u64,
+    /// Revoked tokens, sessions and users, if revocation is enabled
+    revocations: Option<Arc<dyn RevocationStore>>,
+    claims: PhantomData<fn() -> C>,
+}
+
+impl<C> Clone for AuthConfig<C> {
+    fn clone(&self) -> Self {
+        Self {
+            signing: self.signing.clone(),
+            verifying: self.verifying.clone(),
+            expiration: self.expiration,
+            refresh_expiration: self.refresh_expiration,
+            revocations: self.revocations.clone(),
+            claims: PhantomData,
+        }
+    }
 }
 
 impl AuthConfig {
     /// Creates a new auth configuration with the given secret and expiration.
+    ///
+    /// Tokens are signed with HS256 and carry no `kid`.
     pub fn new(secret: impl Into<String>, expiration: u64) -> Self {
+        Self::from_key(
+            SigningKey::secret(None, secret.into().as_bytes()),
+            expiration,
+        )
+    }
+
+    /// Creates an auth configuration that signs tokens with `key`.
+    ///
+    /// ```ignore
+    /// let key = SigningKey::rs256(
+    ///     "2026-10",
+    ///     std::fs::read("keys/2026-10.pem")?,
+    ///     std::fs::read("keys/2026-10.pub.pem")?,
+    /// )?;
+    /// let config = AuthConfig::from_key(key, 3600);
+    /// ```
+    pub fn from_key(key: SigningKey, expiration: u64) -> Self {
         Self {
-            secret: secret.into(),
+            verifying: vec![key.verifying_key().clone()],
+            signing: key,
             expiration,
+            refresh_expiration: DEFAULT_REFRESH_EXPIRATION,
+            revocations: None,
+            claims: PhantomData,
         }
     }
 
     /// Loads configuration from environment variables.
     ///
     /// Required: `JWT_SECRET`
-    /// Optional: `JWT_EXPIRATION` (default: 3600 seconds)
+    /// Optional: `JWT_EXPIRATION` (default: 3600 seconds),
+    /// `JWT_REFRESH_EXPIRATION` (default: 2592000 seconds, 30 days)
     pub fn from_env() -> Result<Self, crate::config::ConfigError> {
         let secret = crate::config::get_env("JWT_SECRET")?;
         let expiration = crate::config::get_env_parsed_or("JWT_EXPIRATION", 3600);
-        Ok(Self { secret, expiration })
+        let refresh_expiration =
+            crate::config::get_env_parsed_or("JWT_REFRESH_EXPIRATION", DEFAULT_REFRESH_EXPIRATION);
+        Ok(Self::new(secret, expiration).with_refresh_expiration(refresh_expiration))
+    }
+
+    /// Creates a new token for the given user ID.
+    pub fn create_token(&self, user_id: impl Into<String>) -> Result<String, Error> {
+        let claims = Claims::new(user_id, self.expiration);
+        self.encode(&claims)
+    }
+}
+
+impl<C: AuthClaims> AuthConfig<C> {
+    /// Uses `D` as the claims type for encoding and decoding tokens.
+    ///
+    /// ```ignore
+    /// let auth = AuthConfig::from_env()?.with_claims::<TenantClaims>();
+    /// ```
+    pub fn with_claims<D: AuthClaims>(self) -> AuthConfig<D> {
+        AuthConfig {
+            signing: self.signing,
+            verifying: self.verifying,
+            expiration: self.expiration,
+            refresh_expiration: self.refresh_expiration,
+            revocations: self.revocations,
+            claims: PhantomData,
+        }
+    }
+
+    /// Sets how long refresh tokens last, in seconds (default: 30 days).
+    ///
+    /// Every refresh issues a new refresh token with the full lifetime, so a
+    /// session ends after this long without a refresh.
+    pub fn with_refresh_expiration(mut self, secs: u64) -> Self {
+        self.refresh_expiration = secs;
+        self
+    }
+
+    /// Enables refresh tokens, logout and revocation, keeping revocations
+    /// in `store`.
+    ///
+    /// [`AuthMiddleware`] then checks every token against the store.
+    pub fn with_revocation_store(mut self, store: impl RevocationStore) -> Self {
+        self.revocations = Some(Arc::new(store));
+        self
+    }
+
+    /// Also accepts tokens signed by `key`.
+    ///
+    /// Keep the previous key here after rotating so tokens it signed stay
+    /// valid until they expire, or add the next key ahead of time so clients
+    /// caching the JWKS already know it when it starts signing.
+    pub fn verify_with(mut self, key: VerifyingKey) -> Self {
+        self.verifying.push(key);
+        self
+    }
+
+    /// The public keys of the verification keys, as served on [`JWKS_PATH`].
+    ///
+    /// HMAC secrets are never included.
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet {
+            keys: self
+                .verifying
+                .iter()
+                .filter_map(|key| key.jwk().cloned())
+                .collect(),
+        }
     }
 
     /// Returns the configured expiration time in seconds.
@@ -192,38 +595,96 @@ impl AuthConfig {
         self.expiration
     }
 
-    /// Encodes claims into a JWT token.
-    pub fn encode(&self, claims: &Claims) -> Result<String, Error> {
-        encode(
-            &Header::default(),
-            claims,
-            &EncodingKey::from_secret(self.secret.as_bytes()),
-        )
-        .map_err(|e| Error::internal(format!("failed to encode token: {}", e)))
+    /// Returns the configured refresh token expiration time in seconds.
+    pub fn refresh_expiration(&self) -> u64 {
+        self.refresh_expiration
+    }
+
+    /// The `exp` for a token issued now: the current Unix time plus the
+    /// configured expiration.
+    pub fn expires_at(&self) -> u64 {
+        now() + self.expiration
+    }
+
+    /// Encodes claims into a JWT token, signed with the signing key and
+    /// naming it in the `kid` header.
+    pub fn encode(&self, claims: &C) -> Result<String, Error> {
+        self.encode_token(claims, false)
+    }
+
+    fn encode_token<T: Serialize>(&self, claims: &T, refresh: bool) -> Result<String, Error> {
+        let mut header = Header::new(self.signing.algorithm());
+        header.kid = self.signing.kid().map(str::to_string);
+        if refresh {
+            header.typ = Some(refresh::REFRESH_TOKEN_TYPE.to_string());
+        }
+        encode(&header, claims, self.signing.encoding_key())
+            .map_err(|e| Error::internal(format!("failed to encode token: {}", e)))
     }
 
     /// Decodes and validates a JWT token.
-    pub fn decode(&self, token: &str) -> Result<Claims, Error> {
-        let token_data = decode::<Claims>(
-            token,
-            &DecodingKey::from_secret(self.secret.as_bytes()),
-            &Validation::default(),
-        )
-        .map_err(|e| match e.kind() {
-            jsonwebtoken::errors::ErrorKind::ExpiredSignature => {
-                Error::unauthorized("token expired")
+    ///
+    /// A token with a `kid` is checked against the verification key with
+    /// that id; one without is checked against every key for its algorithm.
+    /// Refresh tokens are rejected. This doesn't consult the revocation
+    /// store; [`verify`](Self::verify) does.
+    pub fn decode(&self, token: &str) -> Result<C, Error> {
+        self.decode_token(token, false)
+    }
+
+    /// Decodes and validates a JWT token, then rejects it if it has been
+    /// revoked. This is what [`AuthMiddleware`] runs.
+    pub async fn verify(&self, token: &str) -> Result<C, Error> {
+        let claims = self.decode(token)?;
+        if let Some(store) = &self.revocations {
+            let check = RevocationCheck {
+                token_id: claims.token_id().map(str::to_string),
+                session_id: claims.session_id().map(str::to_string),
+                subject: claims.subject().to_string(),
+                issued_at: claims.issued_at(),
+            };
+            if store.is_revoked(&check).await? {
+                return Err(Error::unauthorized("token revoked"));
             }
-            jsonwebtoken::errors::ErrorKind::InvalidToken => Error::unauthorized("invalid token"),
-            _ => Error::unauthorized(format!("token validation failed: {}", e)),
-        })?;
+        }
+        Ok(claims)
+    }
 
-        Ok(token_data.claims)
+    /// Decodes a refresh token when `refresh` is set, or any other token
+    /// when it isn't, so neither kind passes for the other.
+    fn decode_token<T: DeserializeOwned>(&self, token: &str, refresh: bool) -> Result<T, Error> {
+        let header = decode_header(token).map_err(|_| Error::unauthorized("invalid token"))?;
+        if (header.typ.as_deref() == Some(refresh::REFRESH_TOKEN_TYPE)) != refresh {
+            return Err(Error::unauthorized("invalid token type"));
+        }
+        let mut keys = self
+            .verifying
+            .iter()
+            .filter(|key| key.algorithm() == header.alg)
+            .filter(|key| header.kid.is_none() || key.kid() == header.kid.as_deref())
+            .peekable();
+        if keys.peek().is_none() {
+            return Err(Error::unauthorized("unknown signing key"));
+        }
+
+        let mut last_error = None;
+        for key in keys {
+            match decode::<T>(token, key.decoding_key(), &Validation::new(key.algorithm())) {
+                Ok(token_data) => return Ok(token_data.claims),
+                // Try the next key; any other failure means the key matched
+                Err(e) if *e.kind() == ErrorKind::InvalidSignature => last_error = Some(e),
+                Err(e) => return Err(token_error(e)),
+            }
+        }
+        Err(last_error.map_or_else(|| Error::unauthorized("invalid token"), token_error))
     }
+}
 
-    /// Creates a new token for the given user ID.
-    pub fn create_token(&self, user_id: impl Into<String>) -> Result<String, Error> {
-        let claims = Claims::new(user_id, self.expiration);
-        self.encode(&claims)
+fn token_error(e: jsonwebtoken::errors::Error) -> Error {
+    match e.kind() {
+        ErrorKind::ExpiredSignature => Error::unauthorized("token expired"),
+        ErrorKind::InvalidToken => Error::unauthorized("invalid token"),
+        _ => Error::unauthorized(format!("token validation failed: {}", e)),
     }
 }
 
@@ -261,19 +722,7 @@ impl PublicRoutes {
 
     /// Matches a route pattern against a path.
     fn matches_pattern(pattern: &str, path: &str) -> bool {
-        let pattern_parts: Vec<&str> = pattern.split('/').collect();
-        let path_parts: Vec<&str> = path.split('/').collect();
-
-        if pattern_parts.len() != path_parts.len() {
-            return false;
-        }
-
-        pattern_parts
-            .iter()
-            .zip(path_parts.iter())
-            .all(|(pattern_part, path_part)| {
-                pattern_part.starts_with(':') || pattern_part == path_part
-            })
+        crate::extract::extract_path_params(pattern, path).is_some()
     }
 }
 
@@ -302,6 +751,89 @@ mod tests {
         assert!(claims.is_expired());
     }
 
+    #[tokio::test]
+    async fn test_user_id_resolver_reads_bearer_token() {
+        let config = AuthConfig::new("secret", 3600);
+        let token = config.create_token("user-7").unwrap();
+        let resolver = UserIdResolver::new(Arc::new([Arc::new(config) as Arc<dyn AuthScheme>]));
+        let uri = http::Uri::from_static("/");
+
+        let mut headers = http::HeaderMap::new();
+        assert_eq!(resolver.resolve(&headers, &uri).await, None);
+
+        headers.insert(
+            http::header::AUTHORIZATION,
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        assert_eq!(
+            resolver.resolve(&headers, &uri).await.as_deref(),
+            Some("user-7")
+        );
+
+        headers.insert(
+            http::header::AUTHORIZATION,
+            "Bearer not-a-token".parse().unwrap(),
+        );
+        assert_eq!(resolver.resolve(&headers, &uri).await, None);
+    }
+
+    #[derive(Clone, Serialize, Deserialize)]
+    struct RoleClaims {
+        sub: String,
+        exp: u64,
+        roles: Vec<String>,
+    }
+
+    impl AuthClaims for RoleClaims {
+        fn subject(&self) -> &str {
+            &self.sub
+        }
+
+        fn has_role(&self, role: &str) -> bool {
+            self.roles.iter().any(|r| r == role)
+        }
+    }
+
+    #[test]
+    fn test_auth_config_with_custom_claims() {
+        let config = AuthConfig::new("secret", 3600).with_claims::<RoleClaims>();
+        let token = config
+            .encode(&RoleClaims {
+                sub: "user-3".to_string(),
+                exp: config.expires_at(),
+                roles: vec!["admin".to_string()],
+            })
+            .unwrap();
+
+        let claims = config.decode(&token).unwrap();
+        assert_eq!(claims.subject(), "user-3");
+        assert!(claims.has_role("admin"));
+        assert!(!claims.has_scope("admin"));
+    }
+
+    #[test]
+    fn test_authorize() {
+        let roles = vec!["admin".to_string()];
+        let scopes = vec!["orders:write".to_string()];
+        let mut extensions = http::Extensions::new();
+
+        assert!(authorize(&extensions, &[], &[]).is_ok());
+        assert_eq!(
+            authorize(&extensions, &roles, &[]).unwrap_err().status(),
+            401
+        );
+
+        extensions.insert(Authenticated::new(&RoleClaims {
+            sub: "user-3".to_string(),
+            exp: 0,
+            roles: vec!["admin".to_string()],
+        }));
+        assert!(authorize(&extensions, &roles, &[]).is_ok());
+        let err = authorize(&extensions, &roles, &scopes).unwrap_err();
+        assert_eq!(err.status(), 403);
+        assert_eq!(err.message(), "requires scope `orders:write`");
+    }
+
     #[test]
     fn test_auth_config_new() {
         let config = AuthConfig::new("secret", 7200);
@@ -347,6 +879,95 @@ mod tests {
         assert!(result.is_err());
     }
 
+    const RSA_PRIVATE: &str = include_str!("../../tests/fixtures/jwt/rsa_private.pem");
+    const RSA_PUBLIC: &str = include_str!("../../tests/fixtures/jwt/rsa_public.pem");
+    const EC_PRIVATE: &str = include_str!("../../tests/fixtures/jwt/ec_private.pem");
+    const EC_PUBLIC: &str = include_str!("../../tests/fixtures/jwt/ec_public.pem");
+
+    fn rsa_config() -> AuthConfig {
+        AuthConfig::from_key(
+            SigningKey::rs256("rsa-1", RSA_PRIVATE, RSA_PUBLIC).unwrap(),
+            3600,
+        )
+    }
+
+    fn ec_config() -> AuthConfig {
+        AuthConfig::from_key(
+            SigningKey::es256("ec-1", EC_PRIVATE, EC_PUBLIC).unwrap(),
+            3600,
+        )
+    }
+
+    #[test]
+    fn test_auth_config_from_key_sets_kid() {
+        let config = ec_config();
+        let token = config.create_token("user-1").unwrap();
+
+        let header = decode_header(&token).unwrap();
+        assert_eq!(header.alg, jsonwebtoken::Algorithm::ES256);
+        assert_eq!(header.kid.as_deref(), Some("ec-1"));
+        assert_eq!(config.decode(&token).unwrap().sub, "user-1");
+    }
+
+    #[test]
+    fn test_auth_config_new_sets_no_kid() {
+        let token = AuthConfig::new("secret", 3600)
+            .create_token("user-1")
+            .unwrap();
+
+        let header = decode_header(&token).unwrap();
+        assert_eq!(header.alg, jsonwebtoken::Algorithm::HS256);
+        assert_eq!(header.kid, None);
+    }
+
+    #[test]
+    fn test_auth_config_key_rotation() {
+        let old_token = rsa_config().create_token("user-1").unwrap();
+
+        let rotated = ec_config().verify_with(VerifyingKey::rs256("rsa-1", RSA_PUBLIC).unwrap());
+        assert_eq!(rotated.decode(&old_token).unwrap().sub, "user-1");
+        let new_token = rotated.create_token("user-2").unwrap();
+        assert_eq!(rotated.decode(&new_token).unwrap().sub, "user-2");
+
+        let err = ec_config().decode(&old_token).unwrap_err();
+        assert_eq!(err.status(), 401);
+        assert_eq!(err.message(), "unknown signing key");
+    }
+
+    #[test]
+    fn test_auth_config_matches_kid_and_algorithm() {
+        // An HMAC token claiming the RSA key's id must not be checked with it
+        let forged = AuthConfig::from_key(SigningKey::hs256("rsa-1", RSA_PUBLIC), 3600)
+            .create_token("user-1")
+            .unwrap();
+        let err = rsa_config().decode(&forged).unwrap_err();
+        assert_eq!(err.message(), "unknown signing key");
+
+        // A token without a kid is tried against every key for its algorithm
+        let config = AuthConfig::new("current", 3600)
+            .verify_with(VerifyingKey::hs256("previous", "previous"));
+        let token = AuthConfig::new("previous", 3600)
+            .create_token("user-1")
+            .unwrap();
+        assert_eq!(config.decode(&token).unwrap().sub, "user-1");
+    }
+
+    #[test]
+    fn test_auth_config_jwks() {
+        assert!(AuthConfig::new("secret", 3600).jwks().keys.is_empty());
+
+        let config = ec_config()
+            .verify_with(VerifyingKey::rs256("rsa-1", RSA_PUBLIC).unwrap())
+            .verify_with(VerifyingKey::hs256("hmac-1", "secret"));
+        let kids: Vec<_> = config
+            .jwks()
+            .keys
+            .iter()
+            .map(|jwk| jwk.common.key_id.clone().unwrap())
+            .collect();
+        assert_eq!(kids, ["ec-1", "rsa-1"]);
+    }
+
     #[test]
     fn test_public_routes_empty() {
         let routes = PublicRoutes::new();
@@ -375,6 +996,17 @@ mod tests {
         assert!(!routes.is_public("GET", "/users/123/private"));
     }
 
+    #[test]
+    fn test_public_routes_with_catch_all() {
+        let mut routes = PublicRoutes::new();
+        routes.add("GET", "/assets/*path");
+
+        assert!(routes.is_public("GET", "/assets/app.js"));
+        assert!(routes.is_public("GET", "/assets/img/logo.png"));
+        assert!(!routes.is_public("GET", "/assets"));
+        assert!(!routes.is_public("GET", "/private/app.js"));
+    }
+
     #[test]
     fn test_public_routes_introspection_always_public() {
         let routes = PublicRoutes::new();
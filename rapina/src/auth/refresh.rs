@@ -0,0 +1,304 @@
+//! Refresh tokens, logout and revocation for [`AuthConfig`].
+//!
+//! A refresh token belongs to a session (`sid`) that every access token
+//! issued from it shares. Refresh tokens are single use: spending one
+//! revokes its `jti`, and presenting a spent one again means it was copied,
+//! so the whole session is revoked.
+
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+
+use super::{AuthClaims, AuthConfig, Claims, CurrentUser, RevocationCheck, RevocationStore, now};
+
+/// The `typ` header of refresh tokens, which keeps them from being
+/// accepted as access tokens and the other way round.
+pub(super) const REFRESH_TOKEN_TYPE: &str = "refresh+jwt";
+
+#[derive(Serialize, Deserialize)]
+struct RefreshClaims {
+    sub: String,
+    sid: String,
+    jti: String,
+    iat: u64,
+    exp: u64,
+}
+
+/// A refresh token and the session it continues.
+///
+/// Returned by [`AuthConfig::issue_refresh_token`] and
+/// [`AuthConfig::rotate_refresh_token`]. With custom claims, put
+/// `session_id` in the access token's `sid` so
+/// [`AuthConfig::logout`] can end the session.
+#[derive(Debug, Clone)]
+pub struct RefreshToken {
+    /// The refresh token to hand to the client
+    pub token: String,
+    /// The user the session belongs to
+    pub subject: String,
+    /// The session the token continues
+    pub session_id: String,
+    /// Refresh token expiration time in seconds
+    pub expires_in: u64,
+}
+
+/// Access and refresh token response for login and refresh endpoints.
+///
+/// # Example
+///
+/// ```ignore
+/// #[public]
+/// #[post("/login")]
+/// async fn login(auth: State<AuthConfig>, body: Json<LoginRequest>) -> Result<Json<TokenPair>> {
+///     // ... check the credentials ...
+///     Ok(Json(auth.create_token_pair(&body.username)?))
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct TokenPair {
+    /// The JWT access token
+    pub token: String,
+    /// Access token expiration time in seconds
+    pub expires_in: u64,
+    /// The refresh token, good for one call to the refresh endpoint
+    pub refresh_token: String,
+    /// Refresh token expiration time in seconds
+    pub refresh_expires_in: u64,
+}
+
+impl AuthConfig {
+    /// Starts a session for `user_id`, returning its first access and
+    /// refresh tokens.
+    pub fn create_token_pair(&self, user_id: impl Into<String>) -> Result<TokenPair, Error> {
+        let refresh = self.issue_refresh_token(user_id)?;
+        self.token_pair(refresh)
+    }
+
+    /// Exchanges a refresh token for a new access and refresh token in the
+    /// same session. See [`rotate_refresh_token`](Self::rotate_refresh_token).
+    pub async fn refresh(&self, refresh_token: &str) -> Result<TokenPair, Error> {
+        let refresh = self.rotate_refresh_token(refresh_token).await?;
+        self.token_pair(refresh)
+    }
+
+    fn token_pair(&self, refresh: RefreshToken) -> Result<TokenPair, Error> {
+        let mut claims = Claims::new(refresh.subject, self.expiration);
+        claims.sid = Some(refresh.session_id);
+        Ok(TokenPair {
+            token: self.encode(&claims)?,
+            expires_in: self.expiration,
+            refresh_token: refresh.token,
+            refresh_expires_in: refresh.expires_in,
+        })
+    }
+}
+
+impl<C: AuthClaims> AuthConfig<C> {
+    /// The configured revocation store, if any.
+    pub fn revocations(&self) -> Option<&dyn RevocationStore> {
+        self.revocations.as_deref()
+    }
+
+    fn store(&self) -> Result<&Arc<dyn RevocationStore>, Error> {
+        self.revocations.as_ref().ok_or_else(|| {
+            Error::internal("refresh tokens and logout need AuthConfig::with_revocation_store")
+        })
+    }
+
+    /// How long a session or user revocation has to be kept: until the
+    /// longest-lived token issued now would expire.
+    fn revocation_horizon(&self) -> u64 {
+        now() + self.expiration.max(self.refresh_expiration)
+    }
+
+    /// Starts a session for `subject`, returning its first refresh token.
+    pub fn issue_refresh_token(&self, subject: impl Into<String>) -> Result<RefreshToken, Error> {
+        self.store()?;
+        self.refresh_token_for(subject.into(), uuid::Uuid::new_v4().to_string())
+    }
+
+    fn refresh_token_for(
+        &self,
+        subject: String,
+        session_id: String,
+    ) -> Result<RefreshToken, Error> {
+        let now = now();
+        let claims = RefreshClaims {
+            sub: subject.clone(),
+            sid: session_id.clone(),
+            jti: uuid::Uuid::new_v4().to_string(),
+            iat: now,
+            exp: now + self.refresh_expiration,
+        };
+        Ok(RefreshToken {
+            token: self.encode_token(&claims, true)?,
+            subject,
+            session_id,
+            expires_in: self.refresh_expiration,
+        })
+    }
+
+    /// Spends a refresh token and returns the next one in its session.
+    ///
+    /// Each refresh token works once. Presenting one that was already spent
+    /// revokes its session, since either the client or whoever copied the
+    /// token is replaying it; both then have to log in again.
+    pub async fn rotate_refresh_token(&self, token: &str) -> Result<RefreshToken, Error> {
+        let store = self.store()?;
+        let claims: RefreshClaims = self.decode_token(token, true)?;
+
+        if !store.revoke(&claims.jti, claims.exp).await? {
+            tracing::warn!(
+                subject = %claims.sub,
+                session_id = %claims.sid,
+                "refresh token reused, revoking its session"
+            );
+            store.revoke(&claims.sid, self.revocation_horizon()).await?;
+            return Err(Error::unauthorized("refresh token reused"));
+        }
+
+        let check = RevocationCheck {
+            token_id: None,
+            session_id: Some(claims.sid.clone()),
+            subject: claims.sub.clone(),
+            issued_at: Some(claims.iat),
+        };
+        if store.is_revoked(&check).await? {
+            return Err(Error::unauthorized("token revoked"));
+        }
+
+        self.refresh_token_for(claims.sub, claims.sid)
+    }
+
+    /// Logs out `user`: revokes the access token they authenticated with
+    /// and, when it carries a session id, every token of that session.
+    pub async fn logout(&self, user: &CurrentUser<C>) -> Result<(), Error> {
+        let store = self.store()?;
+        let claims = &user.claims;
+        if claims.token_id().is_none() && claims.session_id().is_none() {
+            return Err(Error::internal(
+                "can't revoke a token without a `jti` or `sid` claim",
+            ));
+        }
+        if let Some(jti) = claims.token_id() {
+            let expires_at = claims
+                .expires_at()
+                .unwrap_or_else(|| self.revocation_horizon());
+            store.revoke(jti, expires_at).await?;
+        }
+        if let Some(sid) = claims.session_id() {
+            store.revoke(sid, self.revocation_horizon()).await?;
+        }
+        Ok(())
+    }
+
+    /// Signs `subject` out of every session by revoking all tokens issued
+    /// to them before now.
+    pub async fn logout_all(&self, subject: &str) -> Result<(), Error> {
+        self.store()?
+            .revoke_subject(subject, now(), self.revocation_horizon())
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::InMemoryRevocationStore;
+
+    fn config() -> AuthConfig {
+        AuthConfig::new("refresh-secret", 900).with_revocation_store(InMemoryRevocationStore::new())
+    }
+
+    fn user(config: &AuthConfig, token: &str) -> CurrentUser {
+        let claims = config.decode(token).unwrap();
+        CurrentUser {
+            id: claims.sub.clone(),
+            claims,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_token_pair_shares_session() {
+        let config = config();
+        let pair = config.create_token_pair("user-1").unwrap();
+        assert_eq!(pair.expires_in, 900);
+        assert_eq!(pair.refresh_expires_in, 30 * 24 * 60 * 60);
+
+        let claims = config.verify(&pair.token).await.unwrap();
+        assert_eq!(claims.sub, "user-1");
+        assert!(claims.sid.is_some());
+        assert!(claims.jti.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_access_and_refresh_tokens_are_not_interchangeable() {
+        let config = config();
+        let pair = config.create_token_pair("user-1").unwrap();
+
+        let err = config.decode(&pair.refresh_token).unwrap_err();
+        assert_eq!(err.message(), "invalid token type");
+        let err = config.refresh(&pair.token).await.unwrap_err();
+        assert_eq!(err.message(), "invalid token type");
+    }
+
+    #[tokio::test]
+    async fn test_refresh_rotates_and_detects_reuse() {
+        let config = config();
+        let first = config.create_token_pair("user-1").unwrap();
+
+        let second = config.refresh(&first.refresh_token).await.unwrap();
+        assert_ne!(second.refresh_token, first.refresh_token);
+        assert_eq!(
+            config.decode(&second.token).unwrap().sid,
+            config.decode(&first.token).unwrap().sid
+        );
+
+        // Replaying the spent token revokes the session, including the
+        // tokens issued by the legitimate refresh
+        let err = config.refresh(&first.refresh_token).await.unwrap_err();
+        assert_eq!(err.status(), 401);
+        assert_eq!(err.message(), "refresh token reused");
+        assert!(config.verify(&second.token).await.is_err());
+        let err = config.refresh(&second.refresh_token).await.unwrap_err();
+        assert_eq!(err.message(), "token revoked");
+    }
+
+    #[tokio::test]
+    async fn test_logout_ends_the_session_only() {
+        let config = config();
+        let phone = config.create_token_pair("user-1").unwrap();
+        let laptop = config.create_token_pair("user-1").unwrap();
+
+        config.logout(&user(&config, &phone.token)).await.unwrap();
+
+        let err = config.verify(&phone.token).await.unwrap_err();
+        assert_eq!(err.message(), "token revoked");
+        assert!(config.refresh(&phone.refresh_token).await.is_err());
+        assert!(config.verify(&laptop.token).await.is_ok());
+        assert!(config.refresh(&laptop.refresh_token).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_logout_all_ends_every_session() {
+        let config = config();
+        let mut claims = Claims::new("user-1", 900);
+        claims.iat -= 10;
+        let older = config.encode(&claims).unwrap();
+        let other_user = config.create_token("user-2").unwrap();
+
+        config.logout_all("user-1").await.unwrap();
+
+        assert!(config.verify(&older).await.is_err());
+        assert!(config.verify(&other_user).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_without_store_is_an_error() {
+        let config = AuthConfig::new("refresh-secret", 900);
+        let err = config.create_token_pair("user-1").unwrap_err();
+        assert_eq!(err.status(), 500);
+    }
+}
@@ -0,0 +1,262 @@
+//! Token revocation.
+//!
+//! A [`RevocationStore`] remembers three kinds of revocation, each until the
+//! tokens it covers would have expired anyway:
+//!
+//! - a token id (`jti`), for one leaked access or refresh token
+//! - a session id (`sid`), for every token issued in one login session
+//! - a subject cutoff, for every token issued to a user before a point in time
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use dashmap::DashMap;
+use dashmap::mapref::entry::Entry;
+
+use crate::error::Error;
+
+use super::now;
+
+/// A boxed future for trait object compatibility.
+pub type RevocationFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// How often to drop expired entries (every N revocations)
+const CLEANUP_INTERVAL: u64 = 1000;
+
+/// The identifiers of a token that revocation can apply to.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RevocationCheck {
+    /// The token's own id (`jti`)
+    pub token_id: Option<String>,
+    /// The login session the token belongs to (`sid`)
+    pub session_id: Option<String>,
+    /// The user the token was issued to (`sub`)
+    pub subject: String,
+    /// When the token was issued (`iat`), as a Unix timestamp
+    pub issued_at: Option<u64>,
+}
+
+/// Trait for token revocation storage backends.
+///
+/// Uses `BoxFuture` returns for `dyn RevocationStore` compatibility, like
+/// [`RateLimitStore`](crate::middleware::RateLimitStore). Token and session
+/// ids share one namespace; [`AuthConfig`](super::AuthConfig) generates both
+/// as random UUIDs.
+pub trait RevocationStore: Send + Sync + 'static {
+    /// Revokes a token or session id until `expires_at` (a Unix timestamp).
+    ///
+    /// Returns `false` if the id was already revoked. The check and the
+    /// insert must be atomic: refresh-token reuse detection relies on only
+    /// one of two concurrent calls for the same id returning `true`.
+    fn revoke(&self, id: &str, expires_at: u64) -> RevocationFuture<'_, Result<bool, Error>>;
+
+    /// Revokes every token issued to `subject` before `issued_before`,
+    /// remembering the cutoff until `expires_at`.
+    ///
+    /// Issue times have a resolution of one second, so a token issued in
+    /// the same second as the cutoff stays valid. That lets the app sign a
+    /// user out everywhere and log the current device straight back in.
+    fn revoke_subject(
+        &self,
+        subject: &str,
+        issued_before: u64,
+        expires_at: u64,
+    ) -> RevocationFuture<'_, Result<(), Error>>;
+
+    /// Whether the token, its session or its subject has been revoked.
+    ///
+    /// A token without `issued_at` counts as revoked once its subject has a
+    /// cutoff.
+    fn is_revoked(&self, token: &RevocationCheck) -> RevocationFuture<'_, Result<bool, Error>>;
+}
+
+/// In-process revocations in a `DashMap`.
+///
+/// Each instance of the app keeps its own list, so behind a load balancer a
+/// token revoked on one replica is still accepted by the others. Use a
+/// shared store, such as the SeaORM one, in that case.
+#[derive(Debug, Default)]
+pub struct InMemoryRevocationStore {
+    /// Revoked token and session ids, with when they can be forgotten
+    ids: DashMap<String, u64>,
+    /// Subject cutoffs: (issued before, expires at)
+    subjects: DashMap<String, (u64, u64)>,
+    revocation_count: AtomicU64,
+}
+
+impl InMemoryRevocationStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drops revocations whose tokens have expired.
+    fn cleanup_expired(&self) {
+        let now = now();
+        self.ids.retain(|_, expires_at| *expires_at > now);
+        self.subjects.retain(|_, (_, expires_at)| *expires_at > now);
+    }
+
+    fn maybe_cleanup(&self) {
+        let count = self.revocation_count.fetch_add(1, Ordering::Relaxed);
+        if count > 0 && count % CLEANUP_INTERVAL == 0 {
+            self.cleanup_expired();
+        }
+    }
+}
+
+impl RevocationStore for InMemoryRevocationStore {
+    fn revoke(&self, id: &str, expires_at: u64) -> RevocationFuture<'_, Result<bool, Error>> {
+        self.maybe_cleanup();
+        let revoked = match self.ids.entry(id.to_string()) {
+            Entry::Occupied(mut entry) => {
+                let existing = entry.get_mut();
+                *existing = (*existing).max(expires_at);
+                false
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(expires_at);
+                true
+            }
+        };
+        Box::pin(async move { Ok(revoked) })
+    }
+
+    fn revoke_subject(
+        &self,
+        subject: &str,
+        issued_before: u64,
+        expires_at: u64,
+    ) -> RevocationFuture<'_, Result<(), Error>> {
+        self.maybe_cleanup();
+        self.subjects
+            .entry(subject.to_string())
+            .and_modify(|(cutoff, until)| {
+                *cutoff = (*cutoff).max(issued_before);
+                *until = (*until).max(expires_at);
+            })
+            .or_insert((issued_before, expires_at));
+        Box::pin(async { Ok(()) })
+    }
+
+    fn is_revoked(&self, token: &RevocationCheck) -> RevocationFuture<'_, Result<bool, Error>> {
+        let now = now();
+        let id_revoked = |id: &Option<String>| {
+            id.as_ref()
+                .and_then(|id| self.ids.get(id))
+                .is_some_and(|expires_at| *expires_at > now)
+        };
+        let subject_revoked = self
+            .subjects
+            .get(&token.subject)
+            .filter(|entry| entry.1 > now)
+            .is_some_and(|entry| token.issued_at.is_none_or(|iat| iat < entry.0));
+        let revoked =
+            id_revoked(&token.token_id) || id_revoked(&token.session_id) || subject_revoked;
+        Box::pin(async move { Ok(revoked) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(token_id: &str, session_id: &str, issued_at: u64) -> RevocationCheck {
+        RevocationCheck {
+            token_id: Some(token_id.to_string()),
+            session_id: Some(session_id.to_string()),
+            subject: "user-1".to_string(),
+            issued_at: Some(issued_at),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_revoke_is_atomic_once() {
+        let store = InMemoryRevocationStore::new();
+        let later = now() + 60;
+
+        assert!(store.revoke("jti-1", later).await.unwrap());
+        assert!(!store.revoke("jti-1", later).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_revoked_ids() {
+        let store = InMemoryRevocationStore::new();
+        let now = now();
+
+        assert!(
+            !store
+                .is_revoked(&check("jti-1", "sid-1", now))
+                .await
+                .unwrap()
+        );
+
+        store.revoke("jti-1", now + 60).await.unwrap();
+        assert!(
+            store
+                .is_revoked(&check("jti-1", "sid-1", now))
+                .await
+                .unwrap()
+        );
+        assert!(
+            !store
+                .is_revoked(&check("jti-2", "sid-1", now))
+                .await
+                .unwrap()
+        );
+
+        store.revoke("sid-1", now + 60).await.unwrap();
+        assert!(
+            store
+                .is_revoked(&check("jti-2", "sid-1", now))
+                .await
+                .unwrap()
+        );
+
+        // Expired revocations no longer apply
+        store.revoke("sid-2", now - 1).await.unwrap();
+        assert!(
+            !store
+                .is_revoked(&check("jti-3", "sid-2", now))
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_revoked_subject() {
+        let store = InMemoryRevocationStore::new();
+        let now = now();
+
+        store.revoke_subject("user-1", now, now + 60).await.unwrap();
+        assert!(store.is_revoked(&check("a", "b", now - 10)).await.unwrap());
+        assert!(store.is_revoked(&check("a", "b", now - 1)).await.unwrap());
+        assert!(!store.is_revoked(&check("a", "b", now)).await.unwrap());
+
+        let without_iat = RevocationCheck {
+            subject: "user-1".to_string(),
+            ..Default::default()
+        };
+        assert!(store.is_revoked(&without_iat).await.unwrap());
+
+        let other = RevocationCheck {
+            subject: "user-2".to_string(),
+            ..Default::default()
+        };
+        assert!(!store.is_revoked(&other).await.unwrap());
+    }
+
+    #[test]
+    fn test_in_memory_cleanup_expired() {
+        let store = InMemoryRevocationStore::new();
+        let now = now();
+        store.ids.insert("old".to_string(), now - 1);
+        store.ids.insert("new".to_string(), now + 60);
+        store.subjects.insert("user-1".to_string(), (now, now - 1));
+
+        store.cleanup_expired();
+        assert!(!store.ids.contains_key("old"));
+        assert!(store.ids.contains_key("new"));
+        assert!(store.subjects.is_empty());
+    }
+}
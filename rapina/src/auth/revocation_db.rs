@@ -0,0 +1,174 @@
+//! SeaORM-backed token revocation store.
+
+use sea_orm::sea_query::{Expr, OnConflict, Query};
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr};
+
+use crate::database::DbError;
+use crate::error::{Error, IntoApiError};
+
+use super::create_rapina_revocations::{RapinaRevokedSubjects, RapinaRevokedTokens};
+use super::now;
+use super::revocation::{RevocationCheck, RevocationFuture, RevocationStore};
+
+/// Revocations kept in the app's database, shared by every replica.
+///
+/// The tables come from the
+/// [`create_rapina_revocations`](super::create_rapina_revocations) migration.
+/// Expired rows are ignored but not deleted; call
+/// [`purge_expired`](Self::purge_expired) on a schedule to remove them.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let conn = DatabaseConfig::from_env()?.connect().await?;
+/// let auth = AuthConfig::from_env()?
+///     .with_revocation_store(SeaOrmRevocationStore::new(conn));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SeaOrmRevocationStore {
+    conn: DatabaseConnection,
+}
+
+impl SeaOrmRevocationStore {
+    /// Creates a store on the given connection pool.
+    pub fn new(conn: DatabaseConnection) -> Self {
+        Self { conn }
+    }
+
+    /// Deletes revocations whose tokens have expired, returning how many
+    /// rows were removed.
+    pub async fn purge_expired(&self) -> Result<u64, DbErr> {
+        let backend = self.conn.get_database_backend();
+        let now = now() as i64;
+
+        let tokens = Query::delete()
+            .from_table(RapinaRevokedTokens::Table)
+            .and_where(Expr::col(RapinaRevokedTokens::ExpiresAt).lte(now))
+            .to_owned();
+        let subjects = Query::delete()
+            .from_table(RapinaRevokedSubjects::Table)
+            .and_where(Expr::col(RapinaRevokedSubjects::ExpiresAt).lte(now))
+            .to_owned();
+
+        let tokens = self.conn.execute(backend.build(&tokens)).await?;
+        let subjects = self.conn.execute(backend.build(&subjects)).await?;
+        Ok(tokens.rows_affected() + subjects.rows_affected())
+    }
+
+    async fn insert_id(&self, id: String, expires_at: u64) -> Result<bool, DbErr> {
+        let insert = Query::insert()
+            .into_table(RapinaRevokedTokens::Table)
+            .columns([RapinaRevokedTokens::Id, RapinaRevokedTokens::ExpiresAt])
+            .values_panic([id.into(), (expires_at as i64).into()])
+            .on_conflict(
+                OnConflict::column(RapinaRevokedTokens::Id)
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .to_owned();
+        let result = self
+            .conn
+            .execute(self.conn.get_database_backend().build(&insert))
+            .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn upsert_subject(
+        &self,
+        subject: String,
+        issued_before: u64,
+        expires_at: u64,
+    ) -> Result<(), DbErr> {
+        let upsert = Query::insert()
+            .into_table(RapinaRevokedSubjects::Table)
+            .columns([
+                RapinaRevokedSubjects::Subject,
+                RapinaRevokedSubjects::IssuedBefore,
+                RapinaRevokedSubjects::ExpiresAt,
+            ])
+            .values_panic([
+                subject.into(),
+                (issued_before as i64).into(),
+                (expires_at as i64).into(),
+            ])
+            .on_conflict(
+                OnConflict::column(RapinaRevokedSubjects::Subject)
+                    .update_columns([
+                        RapinaRevokedSubjects::IssuedBefore,
+                        RapinaRevokedSubjects::ExpiresAt,
+                    ])
+                    .to_owned(),
+            )
+            .to_owned();
+        self.conn
+            .execute(self.conn.get_database_backend().build(&upsert))
+            .await?;
+        Ok(())
+    }
+
+    async fn check(&self, token: RevocationCheck) -> Result<bool, DbErr> {
+        let backend = self.conn.get_database_backend();
+        let now = now() as i64;
+
+        let ids: Vec<String> = [token.token_id, token.session_id]
+            .into_iter()
+            .flatten()
+            .collect();
+        if !ids.is_empty() {
+            let select = Query::select()
+                .column(RapinaRevokedTokens::Id)
+                .from(RapinaRevokedTokens::Table)
+                .and_where(Expr::col(RapinaRevokedTokens::Id).is_in(ids))
+                .and_where(Expr::col(RapinaRevokedTokens::ExpiresAt).gt(now))
+                .limit(1)
+                .to_owned();
+            if self.conn.query_one(backend.build(&select)).await?.is_some() {
+                return Ok(true);
+            }
+        }
+
+        let select = Query::select()
+            .column(RapinaRevokedSubjects::IssuedBefore)
+            .from(RapinaRevokedSubjects::Table)
+            .and_where(Expr::col(RapinaRevokedSubjects::Subject).eq(token.subject))
+            .and_where(Expr::col(RapinaRevokedSubjects::ExpiresAt).gt(now))
+            .to_owned();
+        let Some(row) = self.conn.query_one(backend.build(&select)).await? else {
+            return Ok(false);
+        };
+        let issued_before: i64 = row.try_get("", "issued_before")?;
+        Ok(token
+            .issued_at
+            .is_none_or(|iat| (iat as i64) < issued_before))
+    }
+}
+
+fn store_error(e: DbErr) -> Error {
+    DbError(e).into_api_error()
+}
+
+impl RevocationStore for SeaOrmRevocationStore {
+    fn revoke(&self, id: &str, expires_at: u64) -> RevocationFuture<'_, Result<bool, Error>> {
+        let id = id.to_string();
+        Box::pin(async move { self.insert_id(id, expires_at).await.map_err(store_error) })
+    }
+
+    fn revoke_subject(
+        &self,
+        subject: &str,
+        issued_before: u64,
+        expires_at: u64,
+    ) -> RevocationFuture<'_, Result<(), Error>> {
+        let subject = subject.to_string();
+        Box::pin(async move {
+            self.upsert_subject(subject, issued_before, expires_at)
+                .await
+                .map_err(store_error)
+        })
+    }
+
+    fn is_revoked(&self, token: &RevocationCheck) -> RevocationFuture<'_, Result<bool, Error>> {
+        let token = token.clone();
+        Box::pin(async move { self.check(token).await.map_err(store_error) })
+    }
+}
@@ -0,0 +1,225 @@
+//! Authentication schemes.
+//!
+//! An [`AuthScheme`] turns the credentials a request carries into an
+//! [`Identity`]. [`AuthConfig`] is the bearer JWT scheme and
+//! [`ApiKeyAuth`](super::ApiKeyAuth) the API key one; an app can accept
+//! several, which [`AuthMiddleware`](super::AuthMiddleware) tries in order.
+
+use std::sync::Arc;
+
+use http::{HeaderMap, Uri};
+
+use crate::error::Error;
+use crate::middleware::BoxFuture;
+use crate::openapi::SecurityScheme;
+
+use super::{AuthClaims, AuthConfig, Authenticated, CurrentUser};
+
+/// A way of authenticating requests.
+///
+/// # Example
+///
+/// ```ignore
+/// struct InternalToken;
+///
+/// impl AuthScheme for InternalToken {
+///     fn name(&self) -> &str {
+///         "internalToken"
+///     }
+///
+///     fn security_scheme(&self) -> SecurityScheme {
+///         SecurityScheme::ApiKey {
+///             name: "X-Internal-Token".to_string(),
+///             location: ParameterLocation::Header,
+///         }
+///     }
+///
+///     fn authenticate<'a>(
+///         &'a self,
+///         headers: &'a HeaderMap,
+///         _uri: &'a Uri,
+///     ) -> BoxFuture<'a, Result<Option<Identity>, Error>> {
+///         Box::pin(async move {
+///             let Some(token) = headers.get("x-internal-token") else {
+///                 return Ok(None);
+///             };
+///             // ... check the token ...
+///             Ok(Some(Identity::new(Claims::new("internal", 0))))
+///         })
+///     }
+/// }
+/// ```
+pub trait AuthScheme: Send + Sync + 'static {
+    /// The scheme's key in the OpenAPI `securitySchemes`.
+    fn name(&self) -> &str;
+
+    /// How the scheme is documented in OpenAPI.
+    fn security_scheme(&self) -> SecurityScheme;
+
+    /// Authenticates a request from its headers and URI.
+    ///
+    /// Returns `Ok(None)` when the request carries no credentials for this
+    /// scheme, so the next one gets a turn, and an error when it carries
+    /// credentials that aren't valid.
+    fn authenticate<'a>(
+        &'a self,
+        headers: &'a HeaderMap,
+        uri: &'a Uri,
+    ) -> BoxFuture<'a, Result<Option<Identity>, Error>>;
+}
+
+/// Who a request was authenticated as.
+///
+/// Created from the user's claims; handlers then receive them as
+/// [`CurrentUser<C>`](CurrentUser), whichever scheme authenticated the
+/// request.
+pub struct Identity {
+    authenticated: Authenticated,
+    current_user: Box<dyn FnOnce(&mut http::Extensions) + Send>,
+}
+
+impl Identity {
+    /// An identity with the given claims.
+    pub fn new<C: AuthClaims>(claims: C) -> Self {
+        let authenticated = Authenticated::new(&claims);
+        let current_user = CurrentUser {
+            id: authenticated.id.clone(),
+            claims,
+        };
+        Self {
+            authenticated,
+            current_user: Box::new(move |extensions| {
+                extensions.insert(current_user);
+            }),
+        }
+    }
+
+    /// The user's id (their claims' subject).
+    pub fn id(&self) -> &str {
+        &self.authenticated.id
+    }
+
+    /// Makes the user available to the rest of the request.
+    pub(crate) fn insert_into(self, extensions: &mut http::Extensions) {
+        (self.current_user)(extensions);
+        extensions.insert(self.authenticated);
+    }
+}
+
+impl std::fmt::Debug for Identity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Identity")
+            .field("id", &self.authenticated.id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Tries `schemes` in order, returning the first identity one of them
+/// accepts, or else the first rejection.
+pub(crate) async fn authenticate(
+    schemes: &[Arc<dyn AuthScheme>],
+    headers: &HeaderMap,
+    uri: &Uri,
+) -> Result<Option<Identity>, Error> {
+    let mut rejection = None;
+    for scheme in schemes {
+        match scheme.authenticate(headers, uri).await {
+            Ok(Some(identity)) => return Ok(Some(identity)),
+            Ok(None) => {}
+            Err(e) => {
+                rejection.get_or_insert(e);
+            }
+        }
+    }
+    rejection.map_or(Ok(None), Err)
+}
+
+/// Bearer JWTs in the `Authorization` header.
+impl<C: AuthClaims> AuthScheme for AuthConfig<C> {
+    fn name(&self) -> &str {
+        "bearerAuth"
+    }
+
+    fn security_scheme(&self) -> SecurityScheme {
+        SecurityScheme::Http {
+            scheme: "bearer".to_string(),
+            bearer_format: Some("JWT".to_string()),
+        }
+    }
+
+    fn authenticate<'a>(
+        &'a self,
+        headers: &'a HeaderMap,
+        _uri: &'a Uri,
+    ) -> BoxFuture<'a, Result<Option<Identity>, Error>> {
+        Box::pin(async move {
+            let Some(token) = bearer_token(headers) else {
+                return Ok(None);
+            };
+            let claims = self.verify(token).await?;
+            Ok(Some(Identity::new(claims)))
+        })
+    }
+}
+
+/// The token of an `Authorization: Bearer <token>` header.
+pub(crate) fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::Claims;
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            http::header::AUTHORIZATION,
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        headers
+    }
+
+    #[tokio::test]
+    async fn test_schemes_are_tried_in_order() {
+        let first = AuthConfig::new("first-secret", 3600);
+        let second = AuthConfig::new("second-secret", 3600);
+        let schemes: Vec<Arc<dyn AuthScheme>> =
+            vec![Arc::new(first.clone()), Arc::new(second.clone())];
+        let uri = Uri::from_static("/");
+
+        // A token for the second scheme is rejected by the first
+        let token = second.create_token("user-2").unwrap();
+        let identity = authenticate(&schemes, &bearer(&token), &uri)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(identity.id(), "user-2");
+
+        let none = authenticate(&schemes, &HeaderMap::new(), &uri)
+            .await
+            .unwrap();
+        assert!(none.is_none());
+
+        // With no scheme accepting it, the first rejection is returned
+        let err = authenticate(&schemes, &bearer("not-a-token"), &uri)
+            .await
+            .unwrap_err();
+        assert_eq!(err.message(), "invalid token");
+    }
+
+    #[test]
+    fn test_identity_sets_current_user() {
+        let identity = Identity::new(Claims::new("user-1", 3600));
+        let mut extensions = http::Extensions::new();
+        identity.insert_into(&mut extensions);
+
+        let user = extensions.get::<CurrentUser>().unwrap();
+        assert_eq!(user.id, "user-1");
+        assert!(extensions.get::<Authenticated>().is_some());
+    }
+}
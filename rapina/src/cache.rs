@@ -16,28 +16,83 @@
 //!     .listen("127.0.0.1:3000")
 //!     .await
 //! ```
+//!
+//! # Personalised responses
+//!
+//! Cache keys are built from the path and query string. A request carrying
+//! an `Authorization` header, or a response marked `Cache-Control: private`
+//! or setting a cookie, is personalised and is never cached under that key.
+//! Name what the response depends on instead, and it is cached per value:
+//!
+//! ```ignore
+//! #[get("/me/orders")]
+//! #[cache(ttl = 60, vary_user)]
+//! async fn my_orders(user: CurrentUser) -> Json<Vec<Order>> { /* ... */ }
+//!
+//! #[get("/catalog")]
+//! #[cache(ttl = 300, vary = ["Accept-Language", "X-Tenant"])]
+//! async fn catalog() -> Json<Catalog> { /* ... */ }
+//! ```
+//!
+//! # Expiry
+//!
+//! Concurrent misses for the same key run the handler once. With a `stale`
+//! window, an expired entry keeps being served while one background request
+//! refreshes it:
+//!
+//! ```ignore
+//! #[get("/leaderboard")]
+//! #[cache(ttl = 60, stale = 300)]
+//! async fn leaderboard() -> Json<Vec<Score>> { /* ... */ }
+//! ```
 
+use std::collections::HashSet;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use bytes::Bytes;
 use dashmap::DashMap;
-use http::{Response, header};
-use http_body_util::{BodyExt, Full};
+use http::{HeaderMap, HeaderName, HeaderValue, Response, header};
+use http_body_util::BodyExt;
 use hyper::Request;
 use hyper::body::Incoming;
+use tokio::sync::watch;
 
+use crate::auth::{Authenticated, UserIdResolver};
 use crate::context::RequestContext;
+use crate::error::Error;
+use crate::extract::{FromRequestParts, PathParams};
 use crate::middleware::{BoxFuture, Middleware, Next};
 use crate::response::BoxBody;
+use crate::state::AppState;
 
 /// Internal header injected by the `#[cache(ttl = N)]` macro.
 /// The middleware reads this to determine caching behavior, then strips it.
 pub(crate) const CACHE_TTL_HEADER: &str = "x-rapina-cache-ttl";
 
+/// Internal header injected by `#[cache(vary = [...])]`: the request headers,
+/// comma separated, whose values are part of the cache key.
+pub(crate) const CACHE_VARY_HEADER: &str = "x-rapina-cache-vary";
+
+/// Internal header injected by `#[cache(vary_user)]`: the cache key includes
+/// the id of the authenticated user.
+pub(crate) const CACHE_VARY_USER_HEADER: &str = "x-rapina-cache-vary-user";
+
+/// Internal header injected by `#[cache(tags = [...])]`: the tags, comma
+/// separated, that [`Cache::invalidate_tag`] purges the response by.
+pub(crate) const CACHE_TAGS_HEADER: &str = "x-rapina-cache-tags";
+
+/// Internal header injected by `#[cache(ttl = N, stale = M)]`: how many
+/// seconds past its TTL an entry may still be served while it is refreshed.
+pub(crate) const CACHE_STALE_HEADER: &str = "x-rapina-cache-stale";
+
+/// Stored with entries that have a stale window: when, in milliseconds since
+/// the Unix epoch, the entry stops being fresh. Never sent to clients.
+const CACHE_FRESH_UNTIL_HEADER: &str = "x-rapina-cache-fresh-until";
+
 /// Header added to responses indicating cache status.
 pub const CACHE_STATUS_HEADER: &str = "x-cache";
 
@@ -68,6 +123,16 @@ pub trait CacheBackend: Send + Sync + 'static {
 
     /// Invalidates all entries whose key starts with the given prefix.
     fn invalidate_prefix(&self, prefix: &str) -> CacheFuture<'_, ()>;
+
+    /// Invalidates the entry stored under exactly `key`.
+    fn invalidate(&self, key: &str) -> CacheFuture<'_, ()>;
+
+    /// Records that the entry under `key`, stored for `ttl`, carries each of
+    /// `tags`, so [`invalidate_tag`](CacheBackend::invalidate_tag) removes it.
+    fn tag(&self, key: &str, tags: &[String], ttl: Duration) -> CacheFuture<'_, ()>;
+
+    /// Invalidates every entry tagged with `tag`.
+    fn invalidate_tag(&self, tag: &str) -> CacheFuture<'_, ()>;
 }
 
 struct CacheEntry {
@@ -79,6 +144,8 @@ struct CacheEntry {
 /// In-memory cache using DashMap with TTL-based expiry and capacity limits.
 pub struct InMemoryCache {
     entries: Arc<DashMap<String, CacheEntry>>,
+    /// Keys carrying each tag
+    tags: Arc<DashMap<String, HashSet<String>>>,
     max_entries: usize,
     op_count: Arc<AtomicU64>,
 }
@@ -87,6 +154,7 @@ impl InMemoryCache {
     pub fn new(max_entries: usize) -> Self {
         Self {
             entries: Arc::new(DashMap::new()),
+            tags: Arc::new(DashMap::new()),
             max_entries,
             op_count: Arc::new(AtomicU64::new(0)),
         }
@@ -102,6 +170,10 @@ impl InMemoryCache {
     fn cleanup_expired(&self) {
         let now = Instant::now();
         self.entries.retain(|_, entry| entry.expires_at > now);
+        self.tags.retain(|_, keys| {
+            keys.retain(|key| self.entries.contains_key(key));
+            !keys.is_empty()
+        });
     }
 
     fn evict_if_full(&self) {
@@ -172,6 +244,101 @@ impl CacheBackend for InMemoryCache {
 
         Box::pin(std::future::ready(()))
     }
+
+    fn invalidate(&self, key: &str) -> CacheFuture<'_, ()> {
+        self.entries.remove(key);
+
+        Box::pin(std::future::ready(()))
+    }
+
+    fn tag(&self, key: &str, tags: &[String], _ttl: Duration) -> CacheFuture<'_, ()> {
+        for tag in tags {
+            self.tags
+                .entry(tag.clone())
+                .or_default()
+                .insert(key.to_string());
+        }
+
+        Box::pin(std::future::ready(()))
+    }
+
+    fn invalidate_tag(&self, tag: &str) -> CacheFuture<'_, ()> {
+        if let Some((_, keys)) = self.tags.remove(tag) {
+            for key in keys {
+                self.entries.remove(&key);
+            }
+        }
+
+        Box::pin(std::future::ready(()))
+    }
+}
+
+/// Extractor for invalidating cached responses from a handler.
+///
+/// Mutations already purge their own path prefix; use this when a write
+/// affects responses cached elsewhere. Available whenever the cache
+/// middleware is installed.
+///
+/// ```ignore
+/// #[post("/orders")]
+/// async fn create_order(cache: Cache, body: Json<NewOrder>) -> Result<Json<Order>> {
+///     let order = save(body.into_inner()).await?;
+///     cache.invalidate(&format!("/customers/{}/orders", order.customer_id)).await;
+///     cache.invalidate_tag("order-stats").await;
+///     Ok(Json(order))
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Cache {
+    backend: Arc<dyn CacheBackend>,
+}
+
+impl Cache {
+    /// Invalidates the cached response for a request path, such as
+    /// `/customers/3/orders` or `/search?q=rust`, including every variant
+    /// cached for it with `vary` or `vary_user`.
+    pub async fn invalidate(&self, path: &str) {
+        let (path, query) = path.split_once('?').unwrap_or((path, ""));
+        let key = build_cache_key(path, query);
+        self.backend.invalidate(&key).await;
+        self.backend.invalidate_prefix(&format!("{}|", key)).await;
+    }
+
+    /// Invalidates every cached response whose path starts with `prefix`.
+    pub async fn invalidate_prefix(&self, prefix: &str) {
+        self.backend
+            .invalidate_prefix(&format!("GET:{}", prefix))
+            .await;
+    }
+
+    /// Invalidates every cached response tagged with `tag` through
+    /// `#[cache(tags = [...])]`.
+    pub async fn invalidate_tag(&self, tag: &str) {
+        self.backend.invalidate_tag(tag).await;
+    }
+
+    /// Invalidates every cached response carrying any of `tags`.
+    pub async fn invalidate_tags<I>(&self, tags: I)
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        for tag in tags {
+            self.backend.invalidate_tag(tag.as_ref()).await;
+        }
+    }
+}
+
+impl FromRequestParts for Cache {
+    async fn from_request_parts(
+        parts: &http::request::Parts,
+        _params: &PathParams,
+        _state: &Arc<AppState>,
+    ) -> Result<Self, Error> {
+        parts.extensions.get::<Cache>().cloned().ok_or_else(|| {
+            Error::internal("cache is not configured; enable it with Rapina::with_cache")
+        })
+    }
 }
 
 /// Configuration for the cache layer.
@@ -214,27 +381,317 @@ impl CacheConfig {
     }
 }
 
+/// What a cached route's responses depend on besides the path and query.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct VarySpec {
+    /// Request headers whose values are part of the key, sorted
+    headers: Vec<HeaderName>,
+    /// Whether the authenticated user's id is part of the key
+    user: bool,
+}
+
+impl VarySpec {
+    /// Reads the spec the `#[cache]` macro put on a response.
+    fn from_response(response: &Response<BoxBody>) -> Self {
+        let mut headers: Vec<HeaderName> = response
+            .headers()
+            .get(CACHE_VARY_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| {
+                v.split(',')
+                    .filter_map(|name| HeaderName::from_bytes(name.trim().as_bytes()).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        headers.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        headers.dedup();
+
+        Self {
+            headers,
+            user: response.headers().contains_key(CACHE_VARY_USER_HEADER),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.headers.is_empty() && !self.user
+    }
+
+    /// Extends `base` with the varying values, or returns `None` when the key
+    /// needs a user and the request has none.
+    fn key(&self, base: &str, headers: &HeaderMap, user_id: Option<&str>) -> Option<String> {
+        use percent_encoding::{NON_ALPHANUMERIC, utf8_percent_encode};
+
+        let mut key = base.to_string();
+        for name in &self.headers {
+            let values: Vec<&[u8]> = headers.get_all(name).iter().map(|v| v.as_bytes()).collect();
+            let value = String::from_utf8_lossy(&values.join(&b", "[..])).into_owned();
+            key.push_str(&format!(
+                "|{}={}",
+                name,
+                utf8_percent_encode(&value, NON_ALPHANUMERIC)
+            ));
+        }
+        if self.user {
+            let id = user_id?;
+            key.push_str(&format!(
+                "|user={}",
+                utf8_percent_encode(id, NON_ALPHANUMERIC)
+            ));
+        }
+        Some(key)
+    }
+
+    /// The `Vary` header values matching this spec.
+    fn vary_values(&self) -> Vec<HeaderValue> {
+        let mut values: Vec<HeaderValue> = self
+            .headers
+            .iter()
+            .map(|name| HeaderValue::from_str(name.as_str()).unwrap())
+            .collect();
+        if self.user {
+            values.push(HeaderValue::from_static("authorization"));
+        }
+        values
+    }
+}
+
 /// Cache middleware that intercepts requests and serves cached responses.
 ///
 /// On GET requests: checks cache, returns hit if found, caches miss if
 /// handler sets `x-rapina-cache-ttl` header (via `#[cache(ttl = N)]`).
 ///
+/// Responses that depend on more than the path and query, as declared with
+/// `#[cache(vary = [...])]` or `#[cache(vary_user)]`, are cached per value
+/// and get a matching `Vary` header. Personalised requests and responses
+/// without such a declaration are not cached at all.
+///
+/// Concurrent misses for the same key are coalesced: one request runs the
+/// handler and the others wait for its response. Routes with
+/// `#[cache(ttl = N, stale = M)]` keep serving an expired entry for up to
+/// `M` more seconds, marked `x-cache: STALE`, while a single background
+/// request refreshes it.
+///
 /// On POST/PUT/DELETE with 2xx: auto-invalidates cached GET responses
 /// matching the resource path prefix.
 pub struct CacheMiddleware {
-    backend: Arc<dyn CacheBackend>,
+    shared: Arc<CacheShared>,
 }
 
 impl CacheMiddleware {
     pub fn new(backend: Arc<dyn CacheBackend>) -> Self {
-        Self { backend }
+        Self {
+            shared: Arc::new(CacheShared {
+                backend,
+                vary_specs: DashMap::new(),
+                in_flight: DashMap::new(),
+            }),
+        }
+    }
+}
+
+/// State shared with background refreshes, which outlive the request that
+/// started them.
+struct CacheShared {
+    backend: Arc<dyn CacheBackend>,
+    /// Vary specs learned from each path's responses, so later requests can
+    /// build the full key before running the handler.
+    vary_specs: DashMap<String, VarySpec>,
+    /// Keys whose handler is running, with the channel its cached response
+    /// is published on.
+    in_flight: DashMap<String, watch::Sender<Option<CachedResponse>>>,
+}
+
+/// What storing a GET's response needs to know about the request.
+struct CacheRequest {
+    path: String,
+    base_key: String,
+    headers: HeaderMap,
+    user_id: Option<String>,
+    personalised: bool,
+}
+
+/// Whether a request runs the handler for a key or waits for the one that is.
+enum Join {
+    Lead(Flight),
+    Wait(watch::Receiver<Option<CachedResponse>>),
+}
+
+/// The right to run the handler for a key.
+///
+/// Dropping it without [`finish`](Flight::finish), e.g. because the request
+/// was cancelled, lets the waiting requests run the handler themselves.
+struct Flight {
+    shared: Arc<CacheShared>,
+    key: Option<String>,
+}
+
+impl Flight {
+    /// Hands the cached response, if there is one, to the waiting requests.
+    fn finish(mut self, cached: Option<CachedResponse>) {
+        if let Some(key) = self.key.take()
+            && let Some((_, sender)) = self.shared.in_flight.remove(&key)
+        {
+            sender.send_replace(cached);
+        }
+    }
+}
+
+impl Drop for Flight {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            self.shared.in_flight.remove(&key);
+        }
+    }
+}
+
+impl CacheShared {
+    fn join(self: &Arc<Self>, key: &str) -> Join {
+        match self.in_flight.entry(key.to_string()) {
+            dashmap::Entry::Occupied(entry) => Join::Wait(entry.get().subscribe()),
+            dashmap::Entry::Vacant(entry) => {
+                entry.insert(watch::channel(None).0);
+                Join::Lead(Flight {
+                    shared: self.clone(),
+                    key: Some(key.to_string()),
+                })
+            }
+        }
+    }
+
+    /// Runs the handler as the leader for `key`, sharing the result with
+    /// every request waiting on it.
+    async fn lead(
+        &self,
+        flight: Flight,
+        key: &str,
+        request: &CacheRequest,
+        response: impl Future<Output = Response<BoxBody>>,
+    ) -> Response<BoxBody> {
+        let (response, stored) = self.store(request, response.await).await;
+        // Waiters looked up `key`; a response stored under another key
+        // varies by something they may not share.
+        flight
+            .finish(stored.and_then(|(stored_key, cached)| (stored_key == key).then_some(cached)));
+        response
+    }
+
+    /// Caches the handler's response if its route asked for it.
+    ///
+    /// Returns the response for the client, and the key and entry stored.
+    async fn store(
+        &self,
+        request: &CacheRequest,
+        mut response: Response<BoxBody>,
+    ) -> (Response<BoxBody>, Option<(String, CachedResponse)>) {
+        let ttl = extract_seconds_header(&response, CACHE_TTL_HEADER);
+        let stale = extract_seconds_header(&response, CACHE_STALE_HEADER);
+        let spec = VarySpec::from_response(&response);
+        let tags: Vec<String> = response
+            .headers()
+            .get(CACHE_TAGS_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| {
+                v.split(',')
+                    .map(str::trim)
+                    .filter(|tag| !tag.is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+        for internal in [
+            CACHE_TTL_HEADER,
+            CACHE_STALE_HEADER,
+            CACHE_TAGS_HEADER,
+            CACHE_VARY_HEADER,
+            CACHE_VARY_USER_HEADER,
+        ] {
+            response.headers_mut().remove(internal);
+        }
+
+        // Check if handler wants caching
+        let Some(ttl) = ttl else {
+            return (response, None);
+        };
+
+        if !spec.is_empty() {
+            for value in spec.vary_values() {
+                response.headers_mut().append(header::VARY, value);
+            }
+        }
+
+        if spec.is_empty() && (request.personalised || is_personalised_response(&response)) {
+            tracing::debug!(
+                path = %request.path,
+                "not caching a personalised response; add `vary` or `vary_user` to #[cache]"
+            );
+            return (response, None);
+        }
+
+        if !spec.is_empty() {
+            self.vary_specs.insert(request.path.clone(), spec.clone());
+        }
+        let Some(cache_key) = spec.key(
+            &request.base_key,
+            &request.headers,
+            request.user_id.as_deref(),
+        ) else {
+            return (response, None);
+        };
+
+        let (parts, body) = response.into_parts();
+        let body_bytes = match body.collect().await {
+            Ok(collected) => collected.to_bytes(),
+            Err(_) => {
+                let response = Response::builder()
+                    .status(http::StatusCode::INTERNAL_SERVER_ERROR)
+                    .body(BoxBody::empty())
+                    .unwrap();
+                return (response, None);
+            }
+        };
+
+        // Build CachedResponse
+        let mut cached = CachedResponse {
+            status: parts.status.as_u16(),
+            headers: parts
+                .headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_str().unwrap_or("").to_string()))
+                .collect(),
+            body: body_bytes.clone(),
+        };
+
+        // A stale window keeps the entry past its TTL, remembering when it
+        // stopped being fresh.
+        let mut keep_for = Duration::from_secs(ttl);
+        if let Some(stale) = stale {
+            let fresh_until = unix_millis() + ttl * 1000;
+            cached.headers.push((
+                CACHE_FRESH_UNTIL_HEADER.to_string(),
+                fresh_until.to_string(),
+            ));
+            keep_for += Duration::from_secs(stale);
+        }
+
+        // Store in cache
+        self.backend.set(&cache_key, cached.clone(), keep_for).await;
+        if !tags.is_empty() {
+            self.backend.tag(&cache_key, &tags, keep_for).await;
+        }
+
+        // Return response with MISS marker
+        let mut response = Response::from_parts(parts, BoxBody::from(body_bytes));
+        response
+            .headers_mut()
+            .insert(CACHE_STATUS_HEADER, http::HeaderValue::from_static("MISS"));
+        (response, Some((cache_key, cached)))
     }
 }
 
 impl Middleware for CacheMiddleware {
     fn handle<'a>(
         &'a self,
-        req: Request<Incoming>,
+        mut req: Request<Incoming>,
         _ctx: &'a RequestContext,
         next: Next<'a>,
     ) -> BoxFuture<'a, Response<BoxBody>> {
@@ -242,61 +699,91 @@ impl Middleware for CacheMiddleware {
             let method = req.method().clone();
             let path = req.uri().path().to_string();
             let query = req.uri().query().unwrap_or("").to_string();
+            req.extensions_mut().insert(Cache {
+                backend: self.shared.backend.clone(),
+            });
 
             // Only cache GET requests
             if method == http::Method::GET {
-                let cache_key = build_cache_key(&path, &query);
+                let headers = req.headers().clone();
+                let mut user_id = req
+                    .extensions()
+                    .get::<Authenticated>()
+                    .map(|user| user.id.clone());
+                if user_id.is_none()
+                    && let Some(resolver) = next.state().get::<UserIdResolver>()
+                {
+                    user_id = resolver.resolve(&headers, req.uri()).await;
+                }
+                let request = CacheRequest {
+                    base_key: build_cache_key(&path, &query),
+                    // An API key authenticates without an Authorization header
+                    personalised: is_personalised_request(&req) || user_id.is_some(),
+                    path,
+                    headers,
+                    user_id,
+                };
+
+                // Personalised requests only use the cache once the route
+                // is known to vary by what personalises them.
+                let known_spec = self
+                    .shared
+                    .vary_specs
+                    .get(&request.path)
+                    .map(|spec| spec.clone());
+                let lookup_key = match &known_spec {
+                    Some(spec) => spec.key(
+                        &request.base_key,
+                        &request.headers,
+                        request.user_id.as_deref(),
+                    ),
+                    None if request.personalised => None,
+                    None => Some(request.base_key.clone()),
+                };
+
+                let Some(key) = lookup_key else {
+                    let response = next.run(req).await;
+                    return self.shared.store(&request, response).await.0;
+                };
 
                 // Check cache
-                if let Some(cached) = self.backend.get(&cache_key).await {
-                    return build_response_from_cache(cached, "HIT");
+                if let Some(cached) = self.shared.backend.get(&key).await {
+                    if !is_stale(&cached) {
+                        return build_response_from_cache(cached, "HIT");
+                    }
+
+                    // Serve the stale entry; the first request to see it
+                    // also refreshes it once the response is on its way.
+                    if let Join::Lead(flight) = self.shared.join(&key) {
+                        let shared = self.shared.clone();
+                        let next = next.detach();
+                        tokio::spawn(async move {
+                            shared.lead(flight, &key, &request, next.run(req)).await;
+                        });
+                    }
+                    return build_response_from_cache(cached, "STALE");
                 }
 
-                // Cache miss — run handler
-                let response = next.run(req).await;
-
-                // Check if handler wants caching
-                if let Some(ttl) = extract_ttl_header(&response) {
-                    let (parts, body) = response.into_parts();
-                    let body_bytes = match body.collect().await {
-                        Ok(collected) => collected.to_bytes(),
-                        Err(_) => {
-                            return Response::builder()
-                                .status(http::StatusCode::INTERNAL_SERVER_ERROR)
-                                .body(Full::new(Bytes::new()))
-                                .unwrap();
+                // Cache miss — run handler, unless another request already is
+                match self.shared.join(&key) {
+                    Join::Lead(flight) => {
+                        return self
+                            .shared
+                            .lead(flight, &key, &request, next.run(req))
+                            .await;
+                    }
+                    Join::Wait(mut receiver) => {
+                        if receiver.changed().await.is_ok() {
+                            let cached = receiver.borrow().clone();
+                            if let Some(cached) = cached {
+                                return build_response_from_cache(cached, "HIT");
+                            }
                         }
-                    };
-
-                    // Build CachedResponse
-                    let cached = CachedResponse {
-                        status: parts.status.as_u16(),
-                        headers: parts
-                            .headers
-                            .iter()
-                            .filter(|(name, _)| name.as_str() != CACHE_TTL_HEADER)
-                            .map(|(name, value)| {
-                                (name.to_string(), value.to_str().unwrap_or("").to_string())
-                            })
-                            .collect(),
-                        body: body_bytes.clone(),
-                    };
-
-                    // Store in cache
-                    self.backend
-                        .set(&cache_key, cached, Duration::from_secs(ttl))
-                        .await;
-
-                    // Return response without the internal header, with MISS marker
-                    let mut response = Response::from_parts(parts, Full::new(body_bytes));
-                    response.headers_mut().remove(CACHE_TTL_HEADER);
-                    response
-                        .headers_mut()
-                        .insert(CACHE_STATUS_HEADER, http::HeaderValue::from_static("MISS"));
-                    return response;
+                        // The leader's response couldn't be shared
+                        let response = next.run(req).await;
+                        return self.shared.store(&request, response).await.0;
+                    }
                 }
-
-                return response;
             }
 
             // Mutations: run handler first
@@ -305,7 +792,7 @@ impl Middleware for CacheMiddleware {
             // Auto-invalidate on successful mutations
             if is_mutation(&method) && response.status().is_success() {
                 let prefix = build_invalidation_prefix(&path);
-                self.backend.invalidate_prefix(&prefix).await;
+                self.shared.backend.invalidate_prefix(&prefix).await;
             }
 
             response
@@ -313,6 +800,27 @@ impl Middleware for CacheMiddleware {
     }
 }
 
+/// Whether the request identifies a user, so the response may be theirs alone.
+fn is_personalised_request(req: &Request<Incoming>) -> bool {
+    req.headers().contains_key(header::AUTHORIZATION)
+        || req.extensions().get::<Authenticated>().is_some()
+}
+
+/// Whether the response says it is meant for one client only.
+fn is_personalised_response(response: &Response<BoxBody>) -> bool {
+    let private = response
+        .headers()
+        .get_all(header::CACHE_CONTROL)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|directive| {
+            let directive = directive.trim();
+            directive.eq_ignore_ascii_case("private") || directive.eq_ignore_ascii_case("no-store")
+        });
+    private || response.headers().contains_key(header::SET_COOKIE)
+}
+
 fn build_cache_key(path: &str, query: &str) -> String {
     if query.is_empty() {
         format!("GET:{}", path)
@@ -342,18 +850,38 @@ fn is_mutation(method: &http::Method) -> bool {
     )
 }
 
-fn extract_ttl_header(response: &Response<BoxBody>) -> Option<u64> {
+fn extract_seconds_header(response: &Response<BoxBody>, name: &str) -> Option<u64> {
     response
         .headers()
-        .get(CACHE_TTL_HEADER)
+        .get(name)
         .and_then(|v| v.to_str().ok())
         .and_then(|v| v.parse().ok())
 }
 
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Whether an entry with a stale window is past its TTL.
+fn is_stale(cached: &CachedResponse) -> bool {
+    cached
+        .headers
+        .iter()
+        .find(|(name, _)| name == CACHE_FRESH_UNTIL_HEADER)
+        .and_then(|(_, value)| value.parse::<u64>().ok())
+        .is_some_and(|fresh_until| unix_millis() >= fresh_until)
+}
+
 fn build_response_from_cache(cached: CachedResponse, status: &'static str) -> Response<BoxBody> {
     let mut builder = Response::builder().status(cached.status);
 
     for (name, value) in &cached.headers {
+        if name == CACHE_FRESH_UNTIL_HEADER {
+            continue;
+        }
         if let (Ok(name), Ok(value)) = (
             header::HeaderName::from_bytes(name.as_bytes()),
             header::HeaderValue::from_str(value),
@@ -362,7 +890,7 @@ fn build_response_from_cache(cached: CachedResponse, status: &'static str) -> Re
         }
     }
 
-    let mut response = builder.body(Full::new(cached.body)).unwrap();
+    let mut response = builder.body(BoxBody::from(cached.body)).unwrap();
 
     response
         .headers_mut()
@@ -516,6 +1044,195 @@ mod tests {
         assert_eq!(key1, key2);
     }
 
+    fn response_with(headers: &[(&'static str, &'static str)]) -> Response<BoxBody> {
+        let mut response = Response::new(BoxBody::empty());
+        for (name, value) in headers {
+            response
+                .headers_mut()
+                .append(*name, HeaderValue::from_static(value));
+        }
+        response
+    }
+
+    #[test]
+    fn test_vary_spec_from_response() {
+        let response = response_with(&[
+            (CACHE_VARY_HEADER, "x-tenant, Accept-Language"),
+            (CACHE_VARY_USER_HEADER, "1"),
+        ]);
+        let spec = VarySpec::from_response(&response);
+        assert_eq!(spec.headers, ["accept-language", "x-tenant"]);
+        assert!(spec.user);
+
+        assert!(VarySpec::from_response(&response_with(&[])).is_empty());
+    }
+
+    #[test]
+    fn test_vary_spec_key() {
+        let spec = VarySpec {
+            headers: vec![header::ACCEPT_LANGUAGE],
+            user: true,
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT_LANGUAGE, HeaderValue::from_static("pt-BR"));
+
+        assert_eq!(
+            spec.key("GET:/me", &headers, Some("42")).as_deref(),
+            Some("GET:/me|accept-language=pt%2DBR|user=42")
+        );
+        // No user: nothing to key on
+        assert_eq!(spec.key("GET:/me", &headers, None), None);
+    }
+
+    #[test]
+    fn test_vary_spec_key_escapes_values() {
+        let spec = VarySpec {
+            headers: vec![HeaderName::from_static("x-tenant")],
+            user: true,
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert("x-tenant", HeaderValue::from_static("a|user=1"));
+
+        let key = spec.key("GET:/x", &headers, Some("2")).unwrap();
+        assert_eq!(key, "GET:/x|x-tenant=a%7Cuser%3D1|user=2");
+    }
+
+    #[test]
+    fn test_personalised_response() {
+        assert!(is_personalised_response(&response_with(&[(
+            "cache-control",
+            "max-age=60, private"
+        )])));
+        assert!(is_personalised_response(&response_with(&[(
+            "set-cookie",
+            "sid=1"
+        )])));
+        assert!(!is_personalised_response(&response_with(&[(
+            "cache-control",
+            "public, max-age=60"
+        )])));
+    }
+
+    fn cached_with(headers: Vec<(String, String)>) -> CachedResponse {
+        CachedResponse {
+            status: 200,
+            headers,
+            body: Bytes::from("body"),
+        }
+    }
+
+    #[test]
+    fn test_is_stale() {
+        let past = (unix_millis() - 1).to_string();
+        let future = (unix_millis() + 60_000).to_string();
+        let fresh_until = CACHE_FRESH_UNTIL_HEADER.to_string();
+
+        assert!(is_stale(&cached_with(vec![(fresh_until.clone(), past)])));
+        assert!(!is_stale(&cached_with(vec![(fresh_until, future)])));
+        // Entries without a stale window are fresh until the backend drops them
+        assert!(!is_stale(&cached_with(vec![])));
+    }
+
+    #[test]
+    fn test_response_from_cache_hides_freshness() {
+        let cached = cached_with(vec![
+            (CACHE_FRESH_UNTIL_HEADER.to_string(), "1".to_string()),
+            ("content-type".to_string(), "text/plain".to_string()),
+        ]);
+        let response = build_response_from_cache(cached, "STALE");
+        assert!(response.headers().get(CACHE_FRESH_UNTIL_HEADER).is_none());
+        assert_eq!(
+            response.headers().get("content-type").unwrap(),
+            "text/plain"
+        );
+        assert_eq!(
+            response.headers().get(CACHE_STATUS_HEADER).unwrap(),
+            "STALE"
+        );
+    }
+
+    fn shared() -> Arc<CacheShared> {
+        CacheMiddleware::new(Arc::new(InMemoryCache::new(10))).shared
+    }
+
+    #[tokio::test]
+    async fn test_flight_finish_publishes_to_waiters() {
+        let shared = shared();
+        let Join::Lead(flight) = shared.join("GET:/a") else {
+            panic!("first request should lead");
+        };
+        let Join::Wait(mut receiver) = shared.join("GET:/a") else {
+            panic!("second request should wait");
+        };
+
+        flight.finish(Some(cached_with(vec![])));
+        receiver.changed().await.unwrap();
+        assert_eq!(receiver.borrow().as_ref().unwrap().body, "body");
+        assert!(shared.in_flight.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dropped_flight_releases_waiters() {
+        let shared = shared();
+        let Join::Lead(flight) = shared.join("GET:/a") else {
+            panic!("first request should lead");
+        };
+        let Join::Wait(mut receiver) = shared.join("GET:/a") else {
+            panic!("second request should wait");
+        };
+
+        drop(flight);
+        assert!(receiver.changed().await.is_err());
+        assert!(matches!(shared.join("GET:/a"), Join::Lead(_)));
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_cache_invalidate_key() {
+        let cache = InMemoryCache::new(100);
+        let ttl = Duration::from_secs(60);
+        cache.set("GET:/a", cached_with(vec![]), ttl).await;
+        cache.set("GET:/ab", cached_with(vec![]), ttl).await;
+
+        cache.invalidate("GET:/a").await;
+        assert!(cache.get("GET:/a").await.is_none());
+        assert!(cache.get("GET:/ab").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_cache_invalidate_tag() {
+        let cache = InMemoryCache::new(100);
+        let ttl = Duration::from_secs(60);
+        let orders = ["orders".to_string()];
+        cache.set("GET:/orders", cached_with(vec![]), ttl).await;
+        cache
+            .set("GET:/customers/3/orders", cached_with(vec![]), ttl)
+            .await;
+        cache.set("GET:/products", cached_with(vec![]), ttl).await;
+        cache.tag("GET:/orders", &orders, ttl).await;
+        cache.tag("GET:/customers/3/orders", &orders, ttl).await;
+
+        cache.invalidate_tag("orders").await;
+        assert!(cache.get("GET:/orders").await.is_none());
+        assert!(cache.get("GET:/customers/3/orders").await.is_none());
+        assert!(cache.get("GET:/products").await.is_some());
+        assert!(cache.tags.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_cache_cleanup_prunes_tags() {
+        let cache = InMemoryCache::new(100);
+        cache
+            .set("GET:/a", cached_with(vec![]), Duration::from_millis(1))
+            .await;
+        cache
+            .tag("GET:/a", &["t".to_string()], Duration::from_millis(1))
+            .await;
+
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        cache.cleanup_expired();
+        assert!(cache.tags.is_empty());
+    }
+
     #[test]
     fn test_build_invalidation_prefix() {
         assert_eq!(build_invalidation_prefix("/users/123"), "GET:/users");
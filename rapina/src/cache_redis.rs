@@ -44,10 +44,36 @@ impl From<StoredResponse> for CachedResponse {
     }
 }
 
+/// Adds the entry key in `ARGV[1]` to every tag set in `KEYS`, keeping
+/// each set at least as long as the entry (`ARGV[2]` seconds).
+const TAG_SCRIPT: &str = r#"
+for _, tag in ipairs(KEYS) do
+  redis.call('SADD', tag, ARGV[1])
+  if redis.call('TTL', tag) < tonumber(ARGV[2]) then
+    redis.call('EXPIRE', tag, ARGV[2])
+  end
+end
+return 0
+"#;
+
+/// Deletes every entry in the tag set `KEYS[1]`, then the set itself.
+const INVALIDATE_TAG_SCRIPT: &str = r#"
+for _, key in ipairs(redis.call('SMEMBERS', KEYS[1])) do
+  redis.call('DEL', key)
+end
+redis.call('DEL', KEYS[1])
+return 0
+"#;
+
 /// Redis cache backend using multiplexed async connections.
+///
+/// Entries are stored as JSON under `{prefix}{key}`. Tags are sets of entry
+/// keys under `{prefix}tag:{tag}`, expiring with their longest-lived entry.
 pub struct RedisCache {
     conn: redis::aio::MultiplexedConnection,
     prefix: String,
+    tag_script: redis::Script,
+    invalidate_tag_script: redis::Script,
 }
 
 impl RedisCache {
@@ -58,6 +84,8 @@ impl RedisCache {
         Ok(Self {
             conn,
             prefix: "rapina:".to_string(),
+            tag_script: redis::Script::new(TAG_SCRIPT),
+            invalidate_tag_script: redis::Script::new(INVALIDATE_TAG_SCRIPT),
         })
     }
 
@@ -70,6 +98,10 @@ impl RedisCache {
     fn prefixed_key(&self, key: &str) -> String {
         format!("{}{}", self.prefix, key)
     }
+
+    fn tag_key(&self, tag: &str) -> String {
+        format!("{}tag:{}", self.prefix, tag)
+    }
 }
 
 impl CacheBackend for RedisCache {
@@ -100,46 +132,88 @@ impl CacheBackend for RedisCache {
     }
 
     fn invalidate_prefix(&self, prefix: &str) -> CacheFuture<'_, ()> {
-        let pattern = format!("{}{}*", self.prefix, prefix);
+        let pattern = format!("{}*", escape_glob(&self.prefixed_key(prefix)));
         let mut conn = self.conn.clone();
 
         Box::pin(async move {
-            let keys: Vec<String> = match redis::cmd("SCAN")
-                .arg(0)
-                .arg("MATCH")
-                .arg(&pattern)
-                .arg("COUNT")
-                .arg(100)
-                .query_async::<Vec<redis::Value>>(&mut conn)
-                .await
-            {
-                Ok(result) => {
-                    if result.len() >= 2 {
-                        if let Some(redis::Value::Array(keys)) = result.into_iter().nth(1) {
-                            keys.into_iter()
-                                .filter_map(|v| {
-                                    if let redis::Value::BulkString(s) = v {
-                                        String::from_utf8(s).ok()
-                                    } else {
-                                        None
-                                    }
-                                })
-                                .collect()
-                        } else {
-                            return;
-                        }
-                    } else {
-                        return;
-                    }
+            // Walk the whole keyspace; a single SCAN only returns one batch.
+            let mut cursor: u64 = 0;
+            loop {
+                let Ok((next, keys)) = redis::cmd("SCAN")
+                    .arg(cursor)
+                    .arg("MATCH")
+                    .arg(&pattern)
+                    .arg("COUNT")
+                    .arg(100)
+                    .query_async::<(u64, Vec<String>)>(&mut conn)
+                    .await
+                else {
+                    return;
+                };
+
+                if !keys.is_empty() {
+                    let _: Result<(), _> =
+                        redis::cmd("DEL").arg(&keys).query_async(&mut conn).await;
                 }
-                Err(_) => return,
-            };
+                if next == 0 {
+                    return;
+                }
+                cursor = next;
+            }
+        })
+    }
 
-            if !keys.is_empty() {
-                let _: Result<(), _> = redis::cmd("DEL").arg(&keys).query_async(&mut conn).await;
+    fn invalidate(&self, key: &str) -> CacheFuture<'_, ()> {
+        let full_key = self.prefixed_key(key);
+        let mut conn = self.conn.clone();
+
+        Box::pin(async move {
+            let _: Result<(), _> = conn.del(&full_key).await;
+        })
+    }
+
+    fn tag(&self, key: &str, tags: &[String], ttl: Duration) -> CacheFuture<'_, ()> {
+        let full_key = self.prefixed_key(key);
+        let tag_keys: Vec<String> = tags.iter().map(|tag| self.tag_key(tag)).collect();
+        let mut conn = self.conn.clone();
+
+        Box::pin(async move {
+            let mut invocation = self.tag_script.prepare_invoke();
+            for tag_key in &tag_keys {
+                invocation.key(tag_key);
             }
+            let _: Result<(), _> = invocation
+                .arg(&full_key)
+                .arg(ttl.as_secs().max(1))
+                .invoke_async(&mut conn)
+                .await;
         })
     }
+
+    fn invalidate_tag(&self, tag: &str) -> CacheFuture<'_, ()> {
+        let tag_key = self.tag_key(tag);
+        let mut conn = self.conn.clone();
+
+        Box::pin(async move {
+            let _: Result<(), _> = self
+                .invalidate_tag_script
+                .key(&tag_key)
+                .invoke_async(&mut conn)
+                .await;
+        })
+    }
+}
+
+/// Escapes the characters `SCAN MATCH` treats as glob syntax.
+fn escape_glob(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
 }
 
 #[cfg(test)]
@@ -164,6 +238,15 @@ mod tests {
         assert_eq!(result.body, Bytes::from(r#"{"ok":true}"#));
     }
 
+    #[test]
+    fn test_escape_glob() {
+        assert_eq!(escape_glob("rapina:GET:/a"), "rapina:GET:/a");
+        assert_eq!(
+            escape_glob("GET:/search?q=[x]*"),
+            "GET:/search\\?q=\\[x\\]\\*"
+        );
+    }
+
     // Integration tests require a running Redis instance.
     // Run with: cargo test --features cache-redis -- --ignored
     #[ignore]
@@ -187,4 +270,33 @@ mod tests {
         assert!(result.is_some());
         assert_eq!(result.unwrap().body, Bytes::from("test data"));
     }
+
+    #[ignore]
+    #[tokio::test]
+    async fn test_redis_cache_invalidate_and_tags() {
+        let cache = RedisCache::connect("redis://127.0.0.1:6379")
+            .await
+            .expect("Redis connection failed")
+            .with_prefix(&format!("rapina:test:{}:", std::process::id()));
+        let response = CachedResponse {
+            status: 200,
+            headers: vec![],
+            body: Bytes::from("test data"),
+        };
+        let ttl = Duration::from_secs(10);
+
+        cache.set("GET:/a", response.clone(), ttl).await;
+        cache.set("GET:/b", response.clone(), ttl).await;
+        cache.set("GET:/c", response, ttl).await;
+        cache.tag("GET:/b", &["orders".to_string()], ttl).await;
+        cache.tag("GET:/c", &["orders".to_string()], ttl).await;
+
+        cache.invalidate("GET:/a").await;
+        assert!(cache.get("GET:/a").await.is_none());
+        assert!(cache.get("GET:/b").await.is_some());
+
+        cache.invalidate_tag("orders").await;
+        assert!(cache.get("GET:/b").await.is_none());
+        assert!(cache.get("GET:/c").await.is_none());
+    }
 }
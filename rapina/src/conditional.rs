@@ -0,0 +1,624 @@
+//! HTTP conditional requests.
+//!
+//! [`ConditionalMiddleware`] gives `GET` responses a strong [`ETag`] hashed
+//! from their body and answers `If-None-Match` and `If-Modified-Since` with
+//! `304 Not Modified`, so polling clients stop re-downloading unchanged
+//! data. Handlers that already know a resource's version return it wrapped
+//! in [`Conditional`] instead, which skips the hashing.
+//!
+//! For optimistic concurrency, the [`IfMatch`] extractor checks a
+//! mutation's `If-Match` header against the resource's current tag and
+//! fails with `412 Precondition Failed` when the client's copy is stale.
+//!
+//! ```rust,ignore
+//! use rapina::conditional::{Conditional, ConditionalConfig, ETag, IfMatch};
+//! use rapina::prelude::*;
+//!
+//! #[get("/orders/:id")]
+//! async fn get_order(id: Path<u64>, db: Db) -> Result<Conditional<Json<Order>>> {
+//!     let order = Order::load(db.conn(), *id).await?;
+//!     let etag = ETag::strong(order.version.to_string());
+//!     Ok(Conditional::new(Json(order)).etag(etag))
+//! }
+//!
+//! #[put("/orders/:id")]
+//! async fn update_order(id: Path<u64>, if_match: IfMatch, db: Db, body: Json<OrderUpdate>) -> Result<Json<Order>> {
+//!     let order = Order::load(db.conn(), *id).await?;
+//!     if_match.check(&ETag::strong(order.version.to_string()))?;
+//!     Ok(Json(order.update(db.conn(), body.into_inner()).await?))
+//! }
+//!
+//! Rapina::new()
+//!     .with_conditional(ConditionalConfig::default())
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use http::header::{
+    CONTENT_LENGTH, CONTENT_TYPE, ETAG, IF_MATCH, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use http::{HeaderMap, HeaderValue, Method, Request, Response, StatusCode};
+use http_body_util::BodyExt;
+use httpdate::HttpDate;
+use hyper::body::{Body, Incoming};
+use sha2::{Digest, Sha256};
+
+use crate::context::RequestContext;
+use crate::error::Error;
+use crate::extract::{FromRequestParts, PathParams};
+use crate::middleware::{BoxFuture, Middleware, Next};
+use crate::response::{BoxBody, IntoResponse};
+use crate::state::AppState;
+
+const DEFAULT_MAX_SIZE: usize = 1024 * 1024; // 1MB
+
+/// An entity tag: an opaque validator identifying one version of a
+/// resource.
+///
+/// Strong tags promise byte-for-byte identical representations and are the
+/// only ones `If-Match` accepts. Weak tags (`W/"..."`) only promise
+/// equivalent content.
+///
+/// # Examples
+///
+/// ```
+/// use rapina::conditional::ETag;
+///
+/// assert_eq!(ETag::strong("v7").to_string(), "\"v7\"");
+/// assert_eq!(ETag::weak("v7").to_string(), "W/\"v7\"");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ETag {
+    tag: String,
+    weak: bool,
+}
+
+impl ETag {
+    /// Creates a strong tag.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tag` contains a double quote or a character not allowed in
+    /// a header value.
+    pub fn strong(tag: impl Into<String>) -> Self {
+        Self::new(tag.into(), false)
+    }
+
+    /// Creates a weak tag.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tag` contains a double quote or a character not allowed in
+    /// a header value.
+    pub fn weak(tag: impl Into<String>) -> Self {
+        Self::new(tag.into(), true)
+    }
+
+    /// Creates a strong tag from a hash of `data`.
+    pub fn from_bytes(data: &[u8]) -> Self {
+        let digest = Sha256::digest(data);
+        let tag = digest[..16].iter().map(|b| format!("{:02x}", b)).collect();
+        Self { tag, weak: false }
+    }
+
+    fn new(tag: String, weak: bool) -> Self {
+        assert!(
+            tag.bytes().all(is_etag_char),
+            "invalid entity tag: {:?}",
+            tag
+        );
+        Self { tag, weak }
+    }
+
+    /// The opaque tag, without quotes or the `W/` prefix.
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Whether this is a weak tag.
+    pub fn is_weak(&self) -> bool {
+        self.weak
+    }
+
+    /// Strong comparison: both tags are strong and identical.
+    pub fn strong_eq(&self, other: &ETag) -> bool {
+        !self.weak && !other.weak && self.tag == other.tag
+    }
+
+    /// Weak comparison: the opaque tags are identical.
+    pub fn weak_eq(&self, other: &ETag) -> bool {
+        self.tag == other.tag
+    }
+
+    /// Parses a single `"tag"` or `W/"tag"`.
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        match parse_list(value)?.as_slice() {
+            [etag] => Some(etag.clone()),
+            _ => None,
+        }
+    }
+
+    fn to_header_value(&self) -> HeaderValue {
+        HeaderValue::from_str(&self.to_string()).expect("entity tag is a valid header value")
+    }
+}
+
+impl fmt::Display for ETag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.weak {
+            write!(f, "W/\"{}\"", self.tag)
+        } else {
+            write!(f, "\"{}\"", self.tag)
+        }
+    }
+}
+
+/// `etagc` from RFC 9110: visible ASCII except the double quote, or obs-text.
+fn is_etag_char(b: u8) -> bool {
+    b == 0x21 || (0x23..=0x7e).contains(&b) || b >= 0x80
+}
+
+/// Parses a comma separated list of entity tags, or `None` if it is
+/// malformed. A lone `*` is handled by the callers.
+fn parse_list(value: &str) -> Option<Vec<ETag>> {
+    let mut etags = Vec::new();
+    let mut rest = value;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c == ',' || c.is_ascii_whitespace());
+        if rest.is_empty() {
+            return Some(etags);
+        }
+        let weak = match rest.strip_prefix("W/") {
+            Some(stripped) => {
+                rest = stripped;
+                true
+            }
+            None => false,
+        };
+        let (tag, after) = rest.strip_prefix('"')?.split_once('"')?;
+        if !tag.bytes().all(is_etag_char) {
+            return None;
+        }
+        etags.push(ETag {
+            tag: tag.to_string(),
+            weak,
+        });
+        rest = after;
+        if !rest.is_empty() && !rest.starts_with([',', ' ', '\t']) {
+            return None;
+        }
+    }
+}
+
+/// Whether `header` is `*` or lists a tag matching `etag` under `eq`.
+fn list_matches(header: &str, etag: &ETag, eq: fn(&ETag, &ETag) -> bool) -> bool {
+    if header.trim() == "*" {
+        return true;
+    }
+    parse_list(header).is_some_and(|list| list.iter().any(|candidate| eq(candidate, etag)))
+}
+
+/// Whether the client's cached copy is still current.
+///
+/// `If-None-Match` uses weak comparison and wins over `If-Modified-Since`
+/// when both are sent.
+pub(crate) fn is_not_modified(
+    headers: &HeaderMap,
+    etag: Option<&ETag>,
+    last_modified: Option<HttpDate>,
+) -> bool {
+    if let Some(if_none_match) = headers.get(IF_NONE_MATCH).and_then(|v| v.to_str().ok()) {
+        return etag.is_some_and(|etag| list_matches(if_none_match, etag, ETag::weak_eq));
+    }
+    let if_modified_since = headers
+        .get(IF_MODIFIED_SINCE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<HttpDate>().ok());
+    match (if_modified_since, last_modified) {
+        (Some(since), Some(modified)) => modified <= since,
+        _ => false,
+    }
+}
+
+/// Turns a `200 OK` into the `304 Not Modified` answering it.
+///
+/// Validators and caching headers such as `Cache-Control` and `Vary` stay;
+/// the body and the headers describing it go.
+pub(crate) fn not_modified(mut response: Response<BoxBody>) -> Response<BoxBody> {
+    *response.status_mut() = StatusCode::NOT_MODIFIED;
+    response.headers_mut().remove(CONTENT_TYPE);
+    response.headers_mut().remove(CONTENT_LENGTH);
+    *response.body_mut() = BoxBody::empty();
+    response
+}
+
+/// A response with explicit validators.
+///
+/// Sets `ETag` and `Last-Modified` on a successful response, so the
+/// [`ConditionalMiddleware`] can answer `If-None-Match` and
+/// `If-Modified-Since` without hashing the body. Use it when the handler
+/// already knows the resource's version, e.g. from a revision column.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// Conditional::new(Json(order))
+///     .etag(ETag::strong(order.version.to_string()))
+///     .last_modified(order.updated_at.into())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Conditional<T> {
+    inner: T,
+    etag: Option<ETag>,
+    last_modified: Option<SystemTime>,
+}
+
+impl<T> Conditional<T> {
+    /// Wraps a response without any validators yet.
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            etag: None,
+            last_modified: None,
+        }
+    }
+
+    /// Sets the `ETag` header.
+    pub fn etag(mut self, etag: ETag) -> Self {
+        self.etag = Some(etag);
+        self
+    }
+
+    /// Sets the `Last-Modified` header.
+    pub fn last_modified(mut self, time: SystemTime) -> Self {
+        self.last_modified = Some(time);
+        self
+    }
+}
+
+impl<T: IntoResponse> IntoResponse for Conditional<T> {
+    fn into_response(self) -> Response<BoxBody> {
+        let mut response = self.inner.into_response();
+        if !response.status().is_success() {
+            return response;
+        }
+        if let Some(etag) = &self.etag {
+            response.headers_mut().insert(ETAG, etag.to_header_value());
+        }
+        if let Some(time) = self.last_modified
+            && let Ok(value) = HeaderValue::from_str(&HttpDate::from(time).to_string())
+        {
+            response.headers_mut().insert(LAST_MODIFIED, value);
+        }
+        response
+    }
+}
+
+/// Extracts the `If-Match` precondition of a request.
+///
+/// Call [`check`](IfMatch::check) with the resource's current tag before
+/// changing it: if another client updated the resource since this one read
+/// it, the tags differ and the request fails with `412 Precondition
+/// Failed` instead of overwriting their change. A request without
+/// `If-Match` always passes.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// #[delete("/orders/:id")]
+/// async fn delete_order(id: Path<u64>, if_match: IfMatch, db: Db) -> Result<StatusCode> {
+///     let order = Order::load(db.conn(), *id).await?;
+///     if_match.check(&ETag::strong(order.version.to_string()))?;
+///     order.delete(db.conn()).await?;
+///     Ok(StatusCode::NO_CONTENT)
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct IfMatch(Option<String>);
+
+impl IfMatch {
+    /// Whether the request sent an `If-Match` header.
+    pub fn is_present(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Whether the precondition holds for a resource whose current tag is
+    /// `current`.
+    ///
+    /// Uses strong comparison, so a weak tag never matches; `*` matches any
+    /// current tag.
+    pub fn matches(&self, current: &ETag) -> bool {
+        match &self.0 {
+            None => true,
+            Some(header) => list_matches(header, current, ETag::strong_eq),
+        }
+    }
+
+    /// Fails with `412 Precondition Failed` unless the precondition holds.
+    pub fn check(&self, current: &ETag) -> Result<(), Error> {
+        if self.matches(current) {
+            Ok(())
+        } else {
+            Err(Error::precondition_failed(
+                "resource has changed since it was read",
+            ))
+        }
+    }
+}
+
+impl FromRequestParts for IfMatch {
+    async fn from_request_parts(
+        parts: &http::request::Parts,
+        _params: &PathParams,
+        _state: &Arc<AppState>,
+    ) -> Result<Self, Error> {
+        let header = parts
+            .headers
+            .get(IF_MATCH)
+            .map(|v| String::from_utf8_lossy(v.as_bytes()).into_owned());
+        Ok(IfMatch(header))
+    }
+}
+
+/// Configuration for [`ConditionalMiddleware`].
+#[derive(Debug, Clone)]
+pub struct ConditionalConfig {
+    /// Largest response body, in bytes, that is buffered and hashed into an
+    /// `ETag`. Larger and streaming bodies are sent without one. Defaults to
+    /// 1 MB.
+    pub max_size: usize,
+}
+
+impl ConditionalConfig {
+    pub fn new(max_size: usize) -> Self {
+        Self { max_size }
+    }
+}
+
+impl Default for ConditionalConfig {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_SIZE)
+    }
+}
+
+/// Middleware that answers conditional `GET` and `HEAD` requests.
+///
+/// A `200 OK` to a `GET` without an `ETag` gets a strong one hashed from
+/// its body, as long as the body has a known size of at most
+/// [`ConditionalConfig::max_size`]. Responses that carry their own
+/// validators, set through [`Conditional`], are used as they are. When the
+/// request's `If-None-Match` or `If-Modified-Since` shows the client already
+/// has the current version, the body is replaced by `304 Not Modified`.
+///
+/// The handler still runs; a 304 saves the transfer, not the work. Register
+/// the middleware before compression so the tag describes the bytes
+/// actually sent.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// Rapina::new()
+///     .with_conditional(ConditionalConfig::default())
+///     .with_compression(CompressionConfig::default())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ConditionalMiddleware {
+    config: ConditionalConfig,
+}
+
+impl ConditionalMiddleware {
+    pub fn new(config: ConditionalConfig) -> Self {
+        Self { config }
+    }
+
+    /// Buffers the body and tags the response with its hash, or returns it
+    /// untouched if the body is streaming or too large.
+    async fn tag(&self, response: Response<BoxBody>) -> Response<BoxBody> {
+        let small = response
+            .body()
+            .size_hint()
+            .exact()
+            .is_some_and(|len| len <= self.config.max_size as u64);
+        if !small {
+            return response;
+        }
+
+        let (mut parts, body) = response.into_parts();
+        let bytes = match body.collect().await {
+            Ok(collected) => collected.to_bytes(),
+            Err(_) => return Error::internal("failed to read response body").into_response(),
+        };
+        parts
+            .headers
+            .insert(ETAG, ETag::from_bytes(&bytes).to_header_value());
+        Response::from_parts(parts, BoxBody::from(bytes))
+    }
+}
+
+impl Middleware for ConditionalMiddleware {
+    fn handle<'a>(
+        &'a self,
+        req: Request<Incoming>,
+        _ctx: &'a RequestContext,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, Response<BoxBody>> {
+        Box::pin(async move {
+            let method = req.method().clone();
+            if method != Method::GET && method != Method::HEAD {
+                return next.run(req).await;
+            }
+
+            let headers = req.headers().clone();
+            let mut response = next.run(req).await;
+            if response.status() != StatusCode::OK {
+                return response;
+            }
+
+            // HEAD bodies are already gone, so only explicit tags count.
+            if method == Method::GET && !response.headers().contains_key(ETAG) {
+                response = self.tag(response).await;
+            }
+
+            let etag = response
+                .headers()
+                .get(ETAG)
+                .and_then(|v| v.to_str().ok())
+                .and_then(ETag::parse);
+            let last_modified = response
+                .headers()
+                .get(LAST_MODIFIED)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<HttpDate>().ok());
+
+            if is_not_modified(&headers, etag.as_ref(), last_modified) {
+                return not_modified(response);
+            }
+            response
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(name: http::HeaderName, value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn test_etag_display() {
+        assert_eq!(ETag::strong("abc").to_string(), "\"abc\"");
+        assert_eq!(ETag::weak("abc").to_string(), "W/\"abc\"");
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid entity tag")]
+    fn test_etag_rejects_quote() {
+        ETag::strong("a\"b");
+    }
+
+    #[test]
+    fn test_etag_from_bytes_is_stable() {
+        let etag = ETag::from_bytes(b"{\"id\":1}");
+        assert_eq!(etag, ETag::from_bytes(b"{\"id\":1}"));
+        assert_ne!(etag, ETag::from_bytes(b"{\"id\":2}"));
+        assert_eq!(etag.tag().len(), 32);
+        assert!(!etag.is_weak());
+    }
+
+    #[test]
+    fn test_etag_parse() {
+        assert_eq!(ETag::parse("\"a\""), Some(ETag::strong("a")));
+        assert_eq!(ETag::parse(" W/\"a\" "), Some(ETag::weak("a")));
+        assert_eq!(ETag::parse("a"), None);
+        assert_eq!(ETag::parse("\"a\", \"b\""), None);
+    }
+
+    #[test]
+    fn test_parse_list() {
+        assert_eq!(
+            parse_list("\"a\", W/\"b\",\"c,d\""),
+            Some(vec![
+                ETag::strong("a"),
+                ETag::weak("b"),
+                ETag::strong("c,d")
+            ])
+        );
+        assert_eq!(parse_list("\"a\"x"), None);
+        assert_eq!(parse_list("\"unterminated"), None);
+    }
+
+    #[test]
+    fn test_comparisons() {
+        assert!(ETag::strong("a").strong_eq(&ETag::strong("a")));
+        assert!(!ETag::weak("a").strong_eq(&ETag::strong("a")));
+        assert!(ETag::weak("a").weak_eq(&ETag::strong("a")));
+        assert!(!ETag::strong("a").weak_eq(&ETag::strong("b")));
+    }
+
+    #[test]
+    fn test_is_not_modified_etag() {
+        let etag = ETag::strong("a");
+        let matching = headers(IF_NONE_MATCH, "\"x\", W/\"a\"");
+        assert!(is_not_modified(&matching, Some(&etag), None));
+        assert!(is_not_modified(
+            &headers(IF_NONE_MATCH, "*"),
+            Some(&etag),
+            None
+        ));
+        assert!(!is_not_modified(
+            &headers(IF_NONE_MATCH, "\"b\""),
+            Some(&etag),
+            None
+        ));
+        assert!(!is_not_modified(&matching, None, None));
+    }
+
+    #[test]
+    fn test_is_not_modified_date() {
+        let modified: HttpDate = "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap();
+        let same = headers(IF_MODIFIED_SINCE, "Wed, 21 Oct 2015 07:28:00 GMT");
+        let earlier = headers(IF_MODIFIED_SINCE, "Tue, 20 Oct 2015 07:28:00 GMT");
+        assert!(is_not_modified(&same, None, Some(modified)));
+        assert!(!is_not_modified(&earlier, None, Some(modified)));
+    }
+
+    #[test]
+    fn test_if_none_match_wins_over_date() {
+        let modified: HttpDate = "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap();
+        let mut both = headers(IF_NONE_MATCH, "\"old\"");
+        both.insert(
+            IF_MODIFIED_SINCE,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert!(!is_not_modified(
+            &both,
+            Some(&ETag::strong("new")),
+            Some(modified)
+        ));
+    }
+
+    #[test]
+    fn test_if_match() {
+        let current = ETag::strong("v2");
+        assert!(IfMatch(None).matches(&current));
+        assert!(IfMatch(Some("\"v1\", \"v2\"".into())).matches(&current));
+        assert!(IfMatch(Some("*".into())).matches(&current));
+        assert!(!IfMatch(Some("\"v1\"".into())).matches(&current));
+        // Weak tags never satisfy If-Match
+        assert!(!IfMatch(Some("W/\"v2\"".into())).matches(&current));
+        assert!(!IfMatch(Some("garbage".into())).matches(&current));
+
+        let error = IfMatch(Some("\"v1\"".into())).check(&current).unwrap_err();
+        assert_eq!(error.status(), 412);
+        assert_eq!(error.code(), "PRECONDITION_FAILED");
+    }
+
+    #[test]
+    fn test_conditional_sets_validators_on_success_only() {
+        let response = Conditional::new("ok")
+            .etag(ETag::strong("v1"))
+            .last_modified(SystemTime::UNIX_EPOCH)
+            .into_response();
+        assert_eq!(response.headers().get(ETAG).unwrap(), "\"v1\"");
+        assert_eq!(
+            response.headers().get(LAST_MODIFIED).unwrap(),
+            "Thu, 01 Jan 1970 00:00:00 GMT"
+        );
+
+        let response = Conditional::new(StatusCode::NOT_FOUND)
+            .etag(ETag::strong("v1"))
+            .into_response();
+        assert!(response.headers().get(ETAG).is_none());
+    }
+
+    #[test]
+    fn test_config_default() {
+        assert_eq!(ConditionalConfig::default().max_size, 1024 * 1024);
+    }
+}
@@ -30,7 +30,10 @@ impl DateHeaderCache {
             let mut interval = tokio::time::interval(Duration::from_secs(1));
             loop {
                 interval.tick().await;
-                let _ = tx.send(format_date_header());
+                if tx.send(format_date_header()).is_err() {
+                    // Every cache handle is gone.
+                    break;
+                }
             }
         });
 
@@ -15,6 +15,7 @@
 //!
 //! The `#[public]` attribute emits a [`PublicMarker`] so the discovery loop
 //! can mark routes as public without manual `.public_route()` calls.
+//! `#[rate_limit("name")]` works the same way through a [`RateLimitMarker`].
 
 use crate::error::ErrorVariant;
 use crate::router::Router;
@@ -31,8 +32,16 @@ pub struct RouteDescriptor {
     pub handler_name: &'static str,
     /// Whether `#[public]` was found below the route macro
     pub is_public: bool,
+    /// The rate limit policy named by `#[rate_limit]` below the route macro
+    pub rate_limit: Option<&'static str>,
+    /// Roles named by `#[requires_role]`, all of which the user must hold
+    pub required_roles: &'static [&'static str],
+    /// Scopes named by `#[requires_scope]`, all of which the token must grant
+    pub required_scopes: &'static [&'static str],
     /// Returns the JSON Schema for the response type, if available
     pub response_schema: fn() -> Option<serde_json::Value>,
+    /// Returns the content type for the response when it isn't JSON (e.g., "text/event-stream")
+    pub response_content_type: fn() -> Option<&'static str>,
     /// Returns the JSON Schema for the request body, if available
     pub request_schema: fn() -> Option<serde_json::Value>,
     /// Returns the content type for the request body (e.g., "application/json")
@@ -58,3 +67,18 @@ pub struct PublicMarker {
 }
 
 inventory::collect!(PublicMarker);
+
+/// Marker naming the rate limit policy for a handler.
+///
+/// Emitted by `#[rate_limit("name")]` when placed above a route macro. When
+/// it is below the route macro, the route macro sets
+/// [`RouteDescriptor::rate_limit`] directly instead.
+pub struct RateLimitMarker {
+    /// Function name of the handler this marker applies to
+    pub handler_name: &'static str,
+    /// Name of a policy registered with
+    /// [`RateLimitConfig::route_policy`](crate::middleware::RateLimitConfig::route_policy)
+    pub policy: &'static str,
+}
+
+inventory::collect!(RateLimitMarker);
@@ -31,7 +31,6 @@ use std::fmt;
 use crate::response::{APPLICATION_JSON, APPLICATION_PROBLEM_JSON, BoxBody, IntoResponse};
 use bytes::Bytes;
 use http::header::CONTENT_TYPE;
-use http_body_util::Full;
 
 /// Configuration for error response format.
 ///
@@ -256,11 +255,21 @@ impl Error {
         Self::new(404, "NOT_FOUND", message)
     }
 
+    /// Creates a 405 Method Not Allowed error.
+    pub fn method_not_allowed(message: impl Into<String>) -> Self {
+        Self::new(405, "METHOD_NOT_ALLOWED", message)
+    }
+
     /// Creates a 409 Conflict error.
     pub fn conflict(message: impl Into<String>) -> Self {
         Self::new(409, "CONFLICT", message)
     }
 
+    /// Creates a 412 Precondition Failed error.
+    pub fn precondition_failed(message: impl Into<String>) -> Self {
+        Self::new(412, "PRECONDITION_FAILED", message)
+    }
+
     /// Creates a 422 Validation Error.
     pub fn validation(message: impl Into<String>) -> Self {
         Self::new(422, "VALIDATION_ERROR", message)
@@ -431,7 +440,7 @@ impl IntoResponse for Error {
             http::Response::builder()
                 .status(self.0.status)
                 .header(CONTENT_TYPE, APPLICATION_PROBLEM_JSON)
-                .body(Full::new(Bytes::from(body)))
+                .body(BoxBody::from(Bytes::from(body)))
                 .unwrap()
         } else {
             let response = standard::ErrorResponse {
@@ -447,7 +456,7 @@ impl IntoResponse for Error {
             http::Response::builder()
                 .status(self.0.status)
                 .header(CONTENT_TYPE, APPLICATION_JSON)
-                .body(Full::new(Bytes::from(body)))
+                .body(BoxBody::from(Bytes::from(body)))
                 .unwrap()
         }
     }
@@ -577,6 +586,20 @@ mod tests {
         assert_eq!(err.code(), "NOT_FOUND");
     }
 
+    #[test]
+    fn test_error_method_not_allowed() {
+        let err = Error::method_not_allowed("method POST not allowed");
+        assert_eq!(err.status(), 405);
+        assert_eq!(err.code(), "METHOD_NOT_ALLOWED");
+    }
+
+    #[test]
+    fn test_error_precondition_failed() {
+        let err = Error::precondition_failed("resource has changed");
+        assert_eq!(err.status(), 412);
+        assert_eq!(err.code(), "PRECONDITION_FAILED");
+    }
+
     #[test]
     fn test_error_conflict() {
         let err = Error::conflict("already exists");
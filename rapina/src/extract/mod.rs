@@ -12,6 +12,7 @@ use smallvec::SmallVec;
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fmt;
+use std::net::{IpAddr, SocketAddr};
 use std::ops::Deref;
 use std::sync::Arc;
 use validator::Validate;
@@ -219,6 +220,96 @@ pub struct State<T>(pub Arc<T>);
 #[derive(Debug)]
 pub struct Context(pub RequestContext);
 
+/// Information about the connection a request arrived on.
+///
+/// Inserted by the server for every request, so handlers and middleware can
+/// see the real peer address instead of trusting headers the client controls.
+/// Addresses are `None` on transports without IP addresses, such as Unix
+/// domain sockets.
+///
+/// # Examples
+///
+/// ```ignore
+/// use rapina::prelude::*;
+///
+/// #[get("/whoami")]
+/// async fn whoami(conn: ConnectInfo) -> String {
+///     match conn.remote_addr() {
+///         Some(addr) => format!("{} over {:?}", addr.ip(), conn.version()),
+///         None => "local socket".to_string(),
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectInfo {
+    remote_addr: Option<SocketAddr>,
+    local_addr: Option<SocketAddr>,
+    version: http::Version,
+    secure: bool,
+}
+
+impl ConnectInfo {
+    pub(crate) fn new(
+        remote_addr: Option<SocketAddr>,
+        local_addr: Option<SocketAddr>,
+        secure: bool,
+    ) -> Self {
+        Self {
+            remote_addr,
+            local_addr,
+            version: http::Version::default(),
+            secure,
+        }
+    }
+
+    pub(crate) fn with_version(mut self, version: http::Version) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Returns the address of the peer that opened the connection.
+    ///
+    /// Behind a reverse proxy this is the proxy's address.
+    pub fn remote_addr(&self) -> Option<SocketAddr> {
+        self.remote_addr
+    }
+
+    /// Returns the local address the connection was accepted on.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    /// Returns the HTTP version negotiated for the request.
+    pub fn version(&self) -> http::Version {
+        self.version
+    }
+
+    /// Returns `true` if the connection is encrypted with TLS.
+    pub fn is_secure(&self) -> bool {
+        self.secure
+    }
+}
+
+/// The IP address of the client that sent the request.
+///
+/// This is the socket peer address, unless the peer is one of the app's
+/// [`TrustedProxies`](crate::proxy::TrustedProxies), in which case it is read
+/// from the forwarding headers the proxies added. Fails with 500 when no IP
+/// is known, e.g. for a Unix socket client when no proxies are configured.
+///
+/// # Examples
+///
+/// ```ignore
+/// use rapina::prelude::*;
+///
+/// #[get("/ip")]
+/// async fn my_ip(ip: ClientIp) -> String {
+///     ip.to_string()
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ClientIp(pub IpAddr);
+
 /// Wraps an extractor and validates the extracted value.
 ///
 /// Uses the `validator` crate to run validation rules on the inner value.
@@ -423,6 +514,13 @@ impl Context {
     }
 }
 
+impl ClientIp {
+    /// Consumes the extractor and returns the inner IP address.
+    pub fn into_inner(self) -> IpAddr {
+        self.0
+    }
+}
+
 impl<T> Validated<T> {
     /// Consumes the extractor and returns the validated inner value.
     pub fn into_inner(self) -> T {
@@ -430,18 +528,37 @@ impl<T> Validated<T> {
     }
 }
 
+/// Reads the whole request body, decoding it first when
+/// [`DecompressionMiddleware`](crate::middleware::DecompressionMiddleware)
+/// marked it as compressed.
+pub(crate) async fn read_body(
+    req: Request<Incoming>,
+    read_error: &'static str,
+) -> Result<Bytes, Error> {
+    #[cfg(feature = "compression")]
+    if let Some(decode) = req
+        .extensions()
+        .get::<crate::middleware::decompression::DecodeBody>()
+        .cloned()
+    {
+        return decode.read(req.into_body()).await;
+    }
+
+    Ok(req
+        .into_body()
+        .collect()
+        .await
+        .map_err(|_| Error::bad_request(read_error))?
+        .to_bytes())
+}
+
 impl<T: DeserializeOwned + Send> FromRequest for Json<T> {
     async fn from_request(
         req: Request<Incoming>,
         _params: &PathParams,
         _state: &Arc<AppState>,
     ) -> Result<Self, Error> {
-        let body = req.into_body();
-        let bytes = body
-            .collect()
-            .await
-            .map_err(|_| Error::bad_request("Failed to read request body"))?
-            .to_bytes();
+        let bytes = read_body(req, "Failed to read request body").await?;
 
         let value: T = serde_json::from_slice(&bytes)
             .map_err(|e| Error::bad_request(format!("Invalid JSON in request body: {}", e)))?;
@@ -456,7 +573,7 @@ impl<T: serde::Serialize> IntoResponse for (http::StatusCode, Json<T>) {
         http::Response::builder()
             .status(self.0)
             .header(CONTENT_TYPE, APPLICATION_JSON)
-            .body(http_body_util::Full::new(Bytes::from(body)))
+            .body(BoxBody::from(Bytes::from(body)))
             .unwrap()
     }
 }
@@ -489,12 +606,7 @@ impl<T: DeserializeOwned + Send> FromRequest for Form<T> {
             )));
         }
 
-        let body = req.into_body();
-        let bytes = body
-            .collect()
-            .await
-            .map_err(|_| Error::bad_request("Failed to read form data from request body"))?
-            .to_bytes();
+        let bytes = read_body(req, "Failed to read form data from request body").await?;
 
         let value: T = serde_urlencoded::from_bytes(&bytes)
             .map_err(|e| Error::bad_request(format!("Invalid URL-encoded form data: {}", e)))?;
@@ -569,6 +681,39 @@ impl FromRequestParts for Context {
     }
 }
 
+impl FromRequestParts for ClientIp {
+    async fn from_request_parts(
+        parts: &http::request::Parts,
+        _params: &PathParams,
+        _state: &Arc<AppState>,
+    ) -> Result<Self, Error> {
+        parts
+            .extensions
+            .get::<ClientIp>()
+            .copied()
+            .ok_or_else(|| Error::internal("client IP address is unknown"))
+    }
+}
+
+impl FromRequestParts for ConnectInfo {
+    async fn from_request_parts(
+        parts: &http::request::Parts,
+        _params: &PathParams,
+        _state: &Arc<AppState>,
+    ) -> Result<Self, Error> {
+        parts
+            .extensions
+            .get::<ConnectInfo>()
+            .copied()
+            .ok_or_else(|| {
+                Error::internal(
+                    "ConnectInfo missing from request extensions. \
+                 The request did not come through the Rapina server.",
+                )
+            })
+    }
+}
+
 impl<T: DeserializeOwned + Send> FromRequestParts for Query<T> {
     async fn from_request_parts(
         parts: &http::request::Parts,
@@ -892,14 +1037,26 @@ pub fn extract_path_params(pattern: &str, path: &str) -> Option<PathParams> {
     let pattern_parts: Vec<&str> = pattern.split('/').collect();
     let path_parts: Vec<&str> = path.split('/').collect();
 
-    if pattern_parts.len() != path_parts.len() {
+    let catch_all = pattern_parts.last().and_then(|last| last.strip_prefix('*'));
+
+    if catch_all.is_some() {
+        if path_parts.len() < pattern_parts.len() {
+            return None;
+        }
+    } else if pattern_parts.len() != path_parts.len() {
         return None;
     }
 
     let mut params = PathParams::new();
 
-    for (pattern_part, path_part) in pattern_parts.iter().zip(path_parts.iter()) {
-        if let Some(param_name) = pattern_part.strip_prefix(':') {
+    for (i, (pattern_part, path_part)) in pattern_parts.iter().zip(path_parts.iter()).enumerate() {
+        if let (Some(name), true) = (catch_all, i == pattern_parts.len() - 1) {
+            let rest = path_parts[i..].join("/");
+            if rest.is_empty() {
+                return None;
+            }
+            params.insert(name.to_string(), rest);
+        } else if let Some(param_name) = pattern_part.strip_prefix(':') {
             params.insert(param_name.to_string(), path_part.to_string());
         } else if pattern_part != path_part {
             return None;
@@ -948,6 +1105,13 @@ impl Deref for Headers {
     }
 }
 
+impl Deref for ClientIp {
+    type Target = IpAddr;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
 // Database extractor (requires "database" feature)
 #[cfg(feature = "database")]
 impl FromRequestParts for crate::database::Db {
@@ -1042,6 +1206,16 @@ mod tests {
         assert!(result.is_none());
     }
 
+    #[test]
+    fn test_extract_path_params_catch_all() {
+        let params = extract_path_params("/files/*path", "/files/a/b/c.txt").unwrap();
+        assert_eq!(params.get("path"), Some(&"a/b/c.txt".to_string()));
+
+        assert!(extract_path_params("/files/*path", "/files/").is_none());
+        assert!(extract_path_params("/files/*path", "/files").is_none());
+        assert!(extract_path_params("/files/*path", "/docs/a").is_none());
+    }
+
     #[test]
     fn test_extract_path_params_root() {
         let result = extract_path_params("/", "/");
@@ -1261,6 +1435,34 @@ mod tests {
         assert_eq!(result.unwrap().trace_id(), "custom-123");
     }
 
+    #[tokio::test]
+    async fn test_connect_info_extractor() {
+        let info = ConnectInfo::new(
+            Some("192.0.2.1:50000".parse().unwrap()),
+            Some("127.0.0.1:3000".parse().unwrap()),
+            true,
+        )
+        .with_version(http::Version::HTTP_2);
+        let (mut parts, _) = TestRequest::get("/").into_parts();
+        parts.extensions.insert(info);
+
+        let conn = ConnectInfo::from_request_parts(&parts, &empty_params(), &empty_state())
+            .await
+            .unwrap();
+        assert_eq!(conn.remote_addr(), Some("192.0.2.1:50000".parse().unwrap()));
+        assert_eq!(conn.local_addr(), Some("127.0.0.1:3000".parse().unwrap()));
+        assert_eq!(conn.version(), http::Version::HTTP_2);
+        assert!(conn.is_secure());
+    }
+
+    #[tokio::test]
+    async fn test_connect_info_extractor_missing() {
+        let (parts, _) = TestRequest::get("/").into_parts();
+        let result = ConnectInfo::from_request_parts(&parts, &empty_params(), &empty_state()).await;
+
+        assert_eq!(result.unwrap_err().status(), 500);
+    }
+
     // State extractor tests
     #[tokio::test]
     async fn test_state_extractor_success() {
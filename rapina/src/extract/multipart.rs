@@ -52,6 +52,17 @@ impl FromRequest for Multipart {
             .and_then(|v| multer::parse_boundary(v).ok())
             .ok_or_else(|| Error::bad_request("invalid or missing multipart boundary"))?;
 
+        #[cfg(feature = "compression")]
+        if req
+            .extensions()
+            .get::<crate::middleware::decompression::DecodeBody>()
+            .is_some()
+        {
+            let bytes = crate::extract::read_body(req, "Failed to read request body").await?;
+            let stream = futures_util::stream::once(async move { Ok::<_, multer::Error>(bytes) });
+            return Ok(Self::new_with_stream(stream, boundary));
+        }
+
         let stream =
             http_body_util::BodyStream::new(req.into_body()).filter_map(|result| async move {
                 match result {
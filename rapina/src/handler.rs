@@ -27,6 +27,12 @@ pub trait Handler: Clone + Send + Sync + 'static {
         None
     }
 
+    /// Content type for the success response, when it isn't JSON
+    /// (e.g., "text/event-stream").
+    fn response_content_type() -> Option<&'static str> {
+        None
+    }
+
     /// JSON Schema for the request body (if available).
     fn request_schema() -> Option<serde_json::Value> {
         None
@@ -48,6 +54,16 @@ pub trait Handler: Clone + Send + Sync + 'static {
         Vec::new()
     }
 
+    /// Roles the authenticated user must all hold, from `#[requires_role]`.
+    fn required_roles() -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Scopes the user's token must all grant, from `#[requires_scope]`.
+    fn required_scopes() -> &'static [&'static str] {
+        &[]
+    }
+
     /// Handle the request.
     fn call(&self, req: Request<Incoming>, params: PathParams, state: Arc<AppState>) -> BoxFuture;
 }
@@ -36,7 +36,7 @@ pub async fn liveness_check(
     Response::builder()
         .status(StatusCode::OK)
         .header(CONTENT_TYPE, APPLICATION_JSON)
-        .body(http_body_util::Full::new(bytes::Bytes::from(
+        .body(BoxBody::from(bytes::Bytes::from(
             serde_json::to_vec(&body).unwrap_or_default(),
         )))
         .unwrap()
@@ -109,7 +109,7 @@ pub async fn readiness_check(
     Response::builder()
         .status(status)
         .header(CONTENT_TYPE, APPLICATION_JSON)
-        .body(http_body_util::Full::new(bytes::Bytes::from(
+        .body(BoxBody::from(bytes::Bytes::from(
             serde_json::to_vec(&body).unwrap_or_default(),
         )))
         .unwrap()
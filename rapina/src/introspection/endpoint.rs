@@ -52,7 +52,7 @@ pub async fn list_routes(
             Response::builder()
                 .status(StatusCode::OK)
                 .header(CONTENT_TYPE, APPLICATION_JSON)
-                .body(http_body_util::Full::new(bytes::Bytes::from(json)))
+                .body(BoxBody::from(bytes::Bytes::from(json)))
                 .unwrap()
         }
         None => StatusCode::NOT_FOUND.into_response(),
@@ -24,11 +24,17 @@ pub struct RouteInfo {
     pub method: String,
     /// The path pattern with parameters (e.g., "/users/:id").
     pub path: String,
+    /// Name of the trailing `*name` catch-all segment, if the path has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wildcard: Option<String>,
     /// The name of the handler function.
     pub handler_name: String,
     /// JSON Schema for the success response.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub response_schema: Option<serde_json::Value>,
+    /// Content type for the success response, when it isn't JSON (e.g., "text/event-stream").
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_content_type: Option<String>,
     /// JSON Schema for the request body.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub request_schema: Option<serde_json::Value>,
@@ -41,6 +47,12 @@ pub struct RouteInfo {
     /// Error variants for OpenAPI documentation.
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub error_responses: Vec<ErrorVariant>,
+    /// Roles the authenticated user must all hold.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub required_roles: Vec<String>,
+    /// Scopes the user's token must all grant.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub required_scopes: Vec<String>,
 }
 
 impl RouteInfo {
@@ -56,17 +68,45 @@ impl RouteInfo {
         request_body_required: Option<bool>,
         error_responses: Vec<ErrorVariant>,
     ) -> Self {
+        let path = path.into();
+        let wildcard = path
+            .rsplit('/')
+            .next()
+            .and_then(|last| last.strip_prefix('*'))
+            .map(str::to_string);
         Self {
             method: method.into(),
-            path: path.into(),
+            path,
+            wildcard,
             handler_name: handler_name.into(),
             response_schema,
+            response_content_type: None,
             request_schema,
             request_content_type: request_content_type.map(|s| s.into()),
             request_body_required,
             error_responses,
+            required_roles: Vec::new(),
+            required_scopes: Vec::new(),
         }
     }
+
+    /// Sets the content type of the success response.
+    pub fn with_response_content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.response_content_type = Some(content_type.into());
+        self
+    }
+
+    /// Sets the roles the route requires.
+    pub fn with_required_roles(mut self, roles: Vec<String>) -> Self {
+        self.required_roles = roles;
+        self
+    }
+
+    /// Sets the scopes the route requires.
+    pub fn with_required_scopes(mut self, scopes: Vec<String>) -> Self {
+        self.required_scopes = scopes;
+        self
+    }
 }
 
 #[cfg(test)]
@@ -103,6 +143,25 @@ mod tests {
             Vec::new(),
         );
         assert_eq!(info.path, "/users/:id");
+        assert!(info.wildcard.is_none());
+    }
+
+    #[test]
+    fn test_route_info_with_wildcard() {
+        let info = RouteInfo::new(
+            "GET",
+            "/files/*path",
+            "serve_file",
+            None,
+            None,
+            None::<String>,
+            None,
+            Vec::new(),
+        );
+        assert_eq!(info.wildcard.as_deref(), Some("path"));
+
+        let json = serde_json::to_string(&info).unwrap();
+        assert!(json.contains("\"wildcard\":\"path\""));
     }
 
     #[test]
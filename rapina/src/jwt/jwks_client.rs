@@ -135,9 +135,12 @@ pub fn default_validation() -> Validation {
 }
 
 /// Creates a HTTP client in Release mode, with strict enforcement for HTTPS connections
+///
+/// The crypto provider is passed explicitly rather than taken from the process
+/// default, which rustls cannot pick when another dependency enables `ring`.
 fn build_https_client() -> HttpsClient {
     let builder = HttpsConnectorBuilder::new()
-        .with_native_roots()
+        .with_provider_and_native_roots(rustls::crypto::aws_lc_rs::default_provider())
         .expect("no native root CA certificates found")
         .https_only()
         .enable_all_versions()
@@ -203,7 +206,7 @@ mod tests {
     /// Builds a client that accepts plain HTTP (test servers only).
     fn build_test_http_client() -> HttpsClient {
         let connector = HttpsConnectorBuilder::new()
-            .with_native_roots()
+            .with_provider_and_native_roots(rustls::crypto::aws_lc_rs::default_provider())
             .expect("no native root CA certificates found")
             .https_or_http()
             .enable_all_versions()
@@ -311,6 +314,21 @@ mod tests {
         JwksClient::direct(jwks_url.to_string(), refresh_schedule.to_string());
     }
 
+    #[cfg(feature = "tls")]
+    #[tokio::test]
+    async fn test_https_client_builds_next_to_tls_listener() {
+        let dir = tempfile::tempdir().unwrap();
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let cert_path = dir.path().join("server.crt");
+        let key_path = dir.path().join("server.key");
+        std::fs::write(&cert_path, cert.cert.pem()).unwrap();
+        std::fs::write(&key_path, cert.key_pair.serialize_pem()).unwrap();
+
+        let tls = crate::tls::TlsConfig::new(cert_path, key_path);
+        assert!(tls.build_acceptor().is_ok());
+        let _ = build_https_client();
+    }
+
     #[tokio::test]
     async fn test_cache_empty_by_default_direct() {
         let client = JwksClient::direct(
@@ -55,8 +55,11 @@
 //! - [`Cookie`](extract::Cookie) - Extract and deserialize cookies
 //! - [`State`](extract::State) - Access application state
 //! - [`Context`](extract::Context) - Access request context with trace_id
+//! - [`ConnectInfo`](extract::ConnectInfo) - Peer address, TLS, and HTTP version of the connection
+//! - [`ClientIp`](extract::ClientIp) - Client IP address, resolved through trusted proxies
 //! - [`Validated`](extract::Validated) - Validate extracted data
 //! - [`Multipart`](extract::Multipart) - Parse multipart form data (e.g. file uploads)
+//! - [`LastEventId`](sse::LastEventId) - Id of the last Server-Sent Event a reconnecting client saw
 //! - [`JsonWebToken`](jwt::JsonWebToken) - Parse and validate Json Web Tokens with configurable JWKS
 
 //!
@@ -87,6 +90,7 @@ pub mod auth;
 pub mod cache;
 #[cfg(feature = "cache-redis")]
 pub mod cache_redis;
+pub mod conditional;
 pub mod config;
 pub mod context;
 #[cfg(feature = "cron-scheduler")]
@@ -104,6 +108,7 @@ pub mod introspection;
 pub mod jobs;
 #[cfg(feature = "jwks")]
 pub mod jwt;
+pub mod listener;
 #[cfg(feature = "metrics")]
 pub mod metrics;
 pub mod middleware;
@@ -113,14 +118,23 @@ pub mod observability;
 pub mod openapi;
 #[cfg(feature = "database")]
 pub mod pagination;
+pub mod proxy;
+#[cfg(feature = "rate-limit-redis")]
+pub mod rate_limit_redis;
 #[cfg(feature = "websocket")]
 pub mod relay;
 pub mod response;
 pub mod router;
 pub mod server;
+#[cfg(feature = "session")]
+pub mod session;
+pub mod sse;
 pub mod state;
+pub mod static_files;
 pub mod test;
 pub mod testing;
+#[cfg(feature = "tls")]
+pub mod tls;
 #[cfg(feature = "websocket")]
 pub mod websocket;
 
@@ -134,14 +148,20 @@ pub mod websocket;
 /// ```
 pub mod prelude {
     pub use crate::app::Rapina;
-    pub use crate::auth::{AuthConfig, CurrentUser, TokenResponse};
-    pub use crate::cache::{CacheConfig, CacheMiddleware};
+    pub use crate::auth::{
+        ApiKeyAuth, ApiKeyFuture, ApiKeyStore, AuthClaims, AuthConfig, CurrentUser,
+        InMemoryRevocationStore, SigningKey, TokenPair, TokenResponse, VerifyingKey,
+    };
+    pub use crate::cache::{Cache, CacheConfig, CacheMiddleware};
+    pub use crate::conditional::{Conditional, ConditionalConfig, ETag, IfMatch};
     pub use crate::config::{
         ConfigError, get_env, get_env_or, get_env_parsed, get_env_parsed_or, load_dotenv,
     };
     pub use crate::context::RequestContext;
     pub use crate::error::{DocumentedError, Error, ErrorVariant, IntoApiError, Result};
-    pub use crate::extract::{Context, Cookie, Form, Headers, Json, Path, Query, State, Validated};
+    pub use crate::extract::{
+        ClientIp, ConnectInfo, Context, Cookie, Form, Headers, Json, Path, Query, State, Validated,
+    };
     #[cfg(feature = "multipart")]
     pub use crate::extract::{Field, Multipart};
     pub use crate::introspection::RouteInfo;
@@ -150,17 +170,20 @@ pub mod prelude {
     #[cfg(feature = "jwks")]
     pub use crate::jwt::{JsonWebToken, JwksClient};
     #[cfg(feature = "rate-limit")]
-    pub use crate::middleware::{KeyExtractor, RateLimitConfig};
+    pub use crate::middleware::{KeyExtractor, RateLimitConfig, RateLimitPolicy};
     pub use crate::middleware::{Middleware, Next, RequestLogConfig};
     #[cfg(feature = "tower")]
     pub use crate::middleware::{RapinaService, TowerLayerMiddleware};
     pub use crate::observability::TracingConfig;
     #[cfg(feature = "database")]
     pub use crate::pagination::{Paginate, Paginated, PaginationConfig};
+    pub use crate::proxy::{ForwardedHeader, TrustedProxies};
     #[cfg(feature = "websocket")]
     pub use crate::relay::{Relay, RelayConfig, RelayEvent};
-    pub use crate::response::{IntoResponse, StaticStr};
+    pub use crate::response::{IntoResponse, ReaderBody, StaticStr, StreamBody};
     pub use crate::router::Router;
+    #[cfg(feature = "session")]
+    pub use crate::session::{Session, SessionConfig};
 
     pub use http::{Method, StatusCode};
     pub use schemars::JsonSchema;
@@ -168,11 +191,15 @@ pub mod prelude {
     pub use tracing;
     pub use validator::Validate;
 
-    pub use rapina_macros::{Config, delete, get, job, patch, post, public, put, relay, schema};
+    pub use rapina_macros::{
+        Config, delete, get, job, patch, post, public, put, rate_limit, relay, schema,
+    };
 }
 
 // Re-export proc macros at crate root so they work as rapina::schema!, rapina::get!, etc.
-pub use rapina_macros::{Config, delete, get, job, patch, post, public, put, relay, schema};
+pub use rapina_macros::{
+    Config, delete, get, job, patch, post, public, put, rate_limit, relay, schema,
+};
 
 // Re-export dependencies so users don't need to add them to their Cargo.toml
 pub use http;
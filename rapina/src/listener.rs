@@ -0,0 +1,187 @@
+//! Sources of incoming connections for the server.
+//!
+//! [`Rapina::listen`](crate::app::Rapina::listen) binds a TCP socket from a
+//! `host:port` string. When the socket comes from somewhere else, hand it to
+//! [`Rapina::listen_on`](crate::app::Rapina::listen_on) instead:
+//!
+//! - an already-bound [`std::net::TcpListener`], e.g. from systemd socket
+//!   activation or a zero-downtime handoff
+//! - a [`tokio::net::TcpListener`] or [`tokio::net::UnixListener`]
+//! - any custom type implementing [`Listener`]
+//!
+//! [`Rapina::listen_unix`](crate::app::Rapina::listen_unix) binds a Unix
+//! domain socket at a path.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use rapina::prelude::*;
+//!
+//! #[tokio::main]
+//! async fn main() -> std::io::Result<()> {
+//!     // Socket passed in by systemd as file descriptor 3.
+//!     use std::os::fd::FromRawFd;
+//!     let listener = unsafe { std::net::TcpListener::from_raw_fd(3) };
+//!
+//!     Rapina::new()
+//!         .discover()
+//!         .listen_on(listener)
+//!         .await
+//! }
+//! ```
+
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// A source of incoming connections.
+///
+/// Implemented for [`tokio::net::TcpListener`] and, on Unix,
+/// [`tokio::net::UnixListener`].
+pub trait Listener: Send + 'static {
+    /// The connection stream produced by [`accept`](Self::accept).
+    type Io: AsyncRead + AsyncWrite + Unpin + Send + 'static;
+    /// The address type of the listener and its peers.
+    type Addr: fmt::Debug + Clone + Send + Sync + 'static;
+
+    /// Waits for the next connection.
+    fn accept(&mut self) -> impl Future<Output = io::Result<(Self::Io, Self::Addr)>> + Send;
+
+    /// Returns the address the listener is bound to.
+    fn local_addr(&self) -> io::Result<Self::Addr>;
+
+    /// Returns the remote and local IP addresses of an accepted connection,
+    /// as reported by [`ConnectInfo`](crate::extract::ConnectInfo).
+    ///
+    /// The default reports neither, which suits transports without IP
+    /// addresses.
+    fn socket_addrs(io: &Self::Io) -> (Option<SocketAddr>, Option<SocketAddr>) {
+        let _ = io;
+        (None, None)
+    }
+}
+
+impl Listener for tokio::net::TcpListener {
+    type Io = tokio::net::TcpStream;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> io::Result<(Self::Io, Self::Addr)> {
+        tokio::net::TcpListener::accept(self).await
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        tokio::net::TcpListener::local_addr(self)
+    }
+
+    fn socket_addrs(io: &Self::Io) -> (Option<SocketAddr>, Option<SocketAddr>) {
+        (io.peer_addr().ok(), io.local_addr().ok())
+    }
+}
+
+#[cfg(unix)]
+impl Listener for tokio::net::UnixListener {
+    type Io = tokio::net::UnixStream;
+    type Addr = tokio::net::unix::SocketAddr;
+
+    async fn accept(&mut self) -> io::Result<(Self::Io, Self::Addr)> {
+        tokio::net::UnixListener::accept(self).await
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        tokio::net::UnixListener::local_addr(self)
+    }
+}
+
+/// Conversion into a [`Listener`].
+///
+/// Lets [`Rapina::listen_on`](crate::app::Rapina::listen_on) accept blocking
+/// standard library listeners as well as async ones. The conversion switches
+/// standard listeners to non-blocking mode and must run inside a Tokio runtime.
+pub trait IntoListener {
+    /// The listener this converts into.
+    type Listener: Listener;
+
+    /// Performs the conversion.
+    fn into_listener(self) -> io::Result<Self::Listener>;
+}
+
+impl<L: Listener> IntoListener for L {
+    type Listener = L;
+
+    fn into_listener(self) -> io::Result<Self::Listener> {
+        Ok(self)
+    }
+}
+
+impl IntoListener for std::net::TcpListener {
+    type Listener = tokio::net::TcpListener;
+
+    fn into_listener(self) -> io::Result<Self::Listener> {
+        self.set_nonblocking(true)?;
+        tokio::net::TcpListener::from_std(self)
+    }
+}
+
+#[cfg(unix)]
+impl IntoListener for std::os::unix::net::UnixListener {
+    type Listener = tokio::net::UnixListener;
+
+    fn into_listener(self) -> io::Result<Self::Listener> {
+        self.set_nonblocking(true)?;
+        tokio::net::UnixListener::from_std(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn test_std_tcp_listener_into_listener() {
+        let std_listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = std_listener.local_addr().unwrap();
+
+        let mut listener = std_listener.into_listener().unwrap();
+        assert_eq!(Listener::local_addr(&listener).unwrap(), addr);
+
+        let client = tokio::spawn(async move {
+            let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+            stream.write_all(b"ping").await.unwrap();
+        });
+
+        let (mut stream, peer) = Listener::accept(&mut listener).await.unwrap();
+        let mut buf = [0u8; 4];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+        assert!(peer.ip().is_loopback());
+        client.await.unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_unix_listener_accepts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rapina.sock");
+        let mut listener = tokio::net::UnixListener::bind(&path).unwrap();
+
+        let client_path = path.clone();
+        let client = tokio::spawn(async move {
+            let mut stream = tokio::net::UnixStream::connect(client_path).await.unwrap();
+            stream.write_all(b"ping").await.unwrap();
+        });
+
+        let (mut stream, _) = Listener::accept(&mut listener).await.unwrap();
+        let mut buf = [0u8; 4];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+        assert_eq!(
+            Listener::local_addr(&listener).unwrap().as_pathname(),
+            Some(path.as_path())
+        );
+        client.await.unwrap();
+    }
+}
@@ -0,0 +1,122 @@
+//! `Accept-Encoding` parsing (RFC 9110, section 12.5.3).
+
+/// The codings a client accepts, with their weights.
+///
+/// Weights are kept in thousandths, the precision RFC 9110 allows, so they
+/// compare exactly.
+#[derive(Debug, Default)]
+pub(crate) struct AcceptEncoding<'a> {
+    entries: Vec<(&'a str, u16)>,
+}
+
+impl<'a> AcceptEncoding<'a> {
+    /// Parses a header value. Entries with a malformed weight are skipped.
+    pub(crate) fn parse(header: &'a str) -> Self {
+        let entries = header
+            .split(',')
+            .filter_map(|item| {
+                let mut parts = item.split(';');
+                let coding = parts.next()?.trim();
+                if coding.is_empty() {
+                    return None;
+                }
+                let mut weight = 1000;
+                for param in parts {
+                    let (name, value) = param.split_once('=')?;
+                    if name.trim().eq_ignore_ascii_case("q") {
+                        weight = parse_weight(value.trim())?;
+                    }
+                }
+                Some((coding, weight))
+            })
+            .collect();
+        Self { entries }
+    }
+
+    /// The weight the client gives `coding`, either by name or through `*`.
+    ///
+    /// Returns `None` when the header doesn't mention it at all.
+    pub(crate) fn weight(&self, coding: &str) -> Option<u16> {
+        self.explicit_weight(coding).or_else(|| {
+            self.entries
+                .iter()
+                .find(|(name, _)| *name == "*")
+                .map(|&(_, weight)| weight)
+        })
+    }
+
+    /// The weight the client gives `coding` by name, ignoring `*`.
+    pub(crate) fn explicit_weight(&self, coding: &str) -> Option<u16> {
+        self.entries
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(coding))
+            .map(|&(_, weight)| weight)
+    }
+
+    /// Whether the client accepts `coding`.
+    pub(crate) fn accepts(&self, coding: &str) -> bool {
+        self.weight(coding).is_some_and(|weight| weight > 0)
+    }
+}
+
+/// Parses a `qvalue` (`0`, `0.5`, `1.000`, ...) into thousandths.
+fn parse_weight(value: &str) -> Option<u16> {
+    let (int, frac) = value.split_once('.').unwrap_or((value, ""));
+    if frac.len() > 3 || !frac.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let frac_thousandths = format!("{:0<3}", frac).parse::<u16>().ok()?;
+    match int {
+        "0" => Some(frac_thousandths),
+        "1" if frac_thousandths == 0 => Some(1000),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_weight() {
+        assert_eq!(parse_weight("1"), Some(1000));
+        assert_eq!(parse_weight("1.000"), Some(1000));
+        assert_eq!(parse_weight("0"), Some(0));
+        assert_eq!(parse_weight("0.5"), Some(500));
+        assert_eq!(parse_weight("0.125"), Some(125));
+        assert_eq!(parse_weight("1.5"), None);
+        assert_eq!(parse_weight("0.1234"), None);
+        assert_eq!(parse_weight("abc"), None);
+    }
+
+    #[test]
+    fn test_weights() {
+        let accept = AcceptEncoding::parse("gzip;q=0.8, br, identity;q=0");
+        assert_eq!(accept.weight("gzip"), Some(800));
+        assert_eq!(accept.weight("BR"), Some(1000));
+        assert_eq!(accept.weight("identity"), Some(0));
+        assert_eq!(accept.weight("zstd"), None);
+    }
+
+    #[test]
+    fn test_zero_weight_is_not_accepted() {
+        let accept = AcceptEncoding::parse("gzip;q=0, deflate");
+        assert!(!accept.accepts("gzip"));
+        assert!(accept.accepts("deflate"));
+    }
+
+    #[test]
+    fn test_wildcard() {
+        let accept = AcceptEncoding::parse("*;q=0.5, gzip;q=0");
+        assert_eq!(accept.weight("br"), Some(500));
+        assert!(!accept.accepts("gzip"));
+        assert_eq!(accept.explicit_weight("br"), None);
+    }
+
+    #[test]
+    fn test_malformed_entries_skipped() {
+        let accept = AcceptEncoding::parse("gzip;q=2, , br ; q = 0.3");
+        assert_eq!(accept.weight("gzip"), None);
+        assert_eq!(accept.weight("br"), Some(300));
+    }
+}
@@ -4,50 +4,100 @@ use bytes::Bytes;
 use flate2::Compression;
 use flate2::write::{DeflateEncoder, GzEncoder};
 use http::{HeaderValue, Response, header};
-use http_body_util::{BodyExt, Full};
+use http_body_util::BodyExt;
 use hyper::Request;
-use hyper::body::Incoming;
+use hyper::body::{Body, Incoming};
 
 use crate::context::RequestContext;
 use crate::response::{APPLICATION_JSON, BoxBody};
 
+use super::accept_encoding::AcceptEncoding;
 use super::{BoxFuture, Middleware, Next};
 
 const DEFAULT_MIN_SIZE: usize = 1024;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum Algorithm {
+/// A content coding [`CompressionMiddleware`] can produce.
+///
+/// Brotli and zstd need the `compression-br` and `compression-zstd` feature
+/// flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// Brotli (`br`).
+    #[cfg(feature = "compression-br")]
+    Brotli,
+    /// Zstandard (`zstd`).
+    #[cfg(feature = "compression-zstd")]
+    Zstd,
+    /// Gzip (`gzip`).
     Gzip,
+    /// Deflate (`deflate`).
     Deflate,
 }
 
-impl Algorithm {
-    fn from_accept_encoding(header: &str) -> Option<Self> {
-        if header.contains("gzip") {
-            Some(Algorithm::Gzip)
-        } else if header.contains("deflate") {
-            Some(Algorithm::Deflate)
-        } else {
-            None
+impl Encoding {
+    /// Every encoding compiled in, in the default order of preference.
+    fn all() -> Vec<Self> {
+        vec![
+            #[cfg(feature = "compression-br")]
+            Encoding::Brotli,
+            #[cfg(feature = "compression-zstd")]
+            Encoding::Zstd,
+            Encoding::Gzip,
+            Encoding::Deflate,
+        ]
+    }
+
+    /// Picks the encoding for a request's `Accept-Encoding` header.
+    ///
+    /// The client's weights decide; `preference` breaks ties. Codings with
+    /// `q=0`, or excluded through `*;q=0`, are never picked, and an explicit
+    /// `identity` that outweighs every coding leaves the response as it is.
+    fn negotiate(header: &str, preference: &[Encoding]) -> Option<Self> {
+        let accept = AcceptEncoding::parse(header);
+
+        let mut best: Option<(Encoding, u16)> = None;
+        for &encoding in preference {
+            let weight = accept.weight(encoding.content_encoding()).unwrap_or(0);
+            if weight > 0 && best.is_none_or(|(_, best_weight)| weight > best_weight) {
+                best = Some((encoding, weight));
+            }
+        }
+
+        let (encoding, weight) = best?;
+        match accept.explicit_weight("identity") {
+            Some(identity) if identity > weight => None,
+            _ => Some(encoding),
         }
     }
 
     fn content_encoding(&self) -> &'static str {
         match self {
-            Algorithm::Gzip => "gzip",
-            Algorithm::Deflate => "deflate",
+            #[cfg(feature = "compression-br")]
+            Encoding::Brotli => "br",
+            #[cfg(feature = "compression-zstd")]
+            Encoding::Zstd => "zstd",
+            Encoding::Gzip => "gzip",
+            Encoding::Deflate => "deflate",
         }
     }
 
-    fn compress(&self, data: &[u8], level: Compression) -> std::io::Result<Vec<u8>> {
+    fn compress(&self, data: &[u8], level: u32) -> std::io::Result<Vec<u8>> {
         match self {
-            Algorithm::Gzip => {
-                let mut encoder = GzEncoder::new(Vec::new(), level);
+            #[cfg(feature = "compression-br")]
+            Encoding::Brotli => {
+                let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, level, 22);
+                encoder.write_all(data)?;
+                Ok(encoder.into_inner())
+            }
+            #[cfg(feature = "compression-zstd")]
+            Encoding::Zstd => zstd::stream::encode_all(data, level as i32),
+            Encoding::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::new(level));
                 encoder.write_all(data)?;
                 encoder.finish()
             }
-            Algorithm::Deflate => {
-                let mut encoder = DeflateEncoder::new(Vec::new(), level);
+            Encoding::Deflate => {
+                let mut encoder = DeflateEncoder::new(Vec::new(), Compression::new(level));
                 encoder.write_all(data)?;
                 encoder.finish()
             }
@@ -62,8 +112,13 @@ pub struct CompressionConfig {
     /// Responses smaller than this value are sent uncompressed. Defaults to 1024 bytes.
     pub min_size: usize,
     /// Compression level from 0 (none) to 9 (best). Values above 9 are
-    /// clamped to 9. Defaults to 6.
+    /// clamped to 9. Defaults to 6. Brotli uses it as its quality and zstd
+    /// as its level.
     pub level: u32,
+    /// Encodings the server offers, most preferred first. Used to break
+    /// ties between encodings the client weighs equally. Defaults to
+    /// brotli, zstd, gzip, then deflate, for those compiled in.
+    pub preference: Vec<Encoding>,
 }
 
 impl CompressionConfig {
@@ -71,28 +126,43 @@ impl CompressionConfig {
         Self {
             min_size,
             level: level.min(9),
+            preference: Encoding::all(),
         }
     }
+
+    /// Sets the encodings to offer, most preferred first. Encodings left out
+    /// are never used.
+    ///
+    /// ```rust,ignore
+    /// CompressionConfig::default().preference([Encoding::Gzip, Encoding::Brotli])
+    /// ```
+    pub fn preference(mut self, preference: impl IntoIterator<Item = Encoding>) -> Self {
+        self.preference = preference.into_iter().collect();
+        self
+    }
 }
 
 impl Default for CompressionConfig {
     fn default() -> Self {
-        Self {
-            min_size: DEFAULT_MIN_SIZE,
-            level: 6,
-        }
+        Self::new(DEFAULT_MIN_SIZE, 6)
     }
 }
 
-/// Middleware that compresses response bodies using gzip or deflate.
+/// Middleware that compresses response bodies using gzip, deflate, and
+/// optionally brotli or zstd.
 ///
-/// Negotiates the encoding via the `Accept-Encoding` request header (gzip is
-/// preferred over deflate). Only text-based content types such as
+/// Negotiates the encoding via the `Accept-Encoding` request header,
+/// following the client's q-values and falling back to
+/// [`CompressionConfig::preference`] on ties. Only text-based content types such as
 /// `application/json` and `text/*` are compressed; binary responses are passed
 /// through unchanged. Responses smaller than [`CompressionConfig::min_size`]
 /// are also left uncompressed. If compression does not reduce the payload size
 /// the original body is returned.
 ///
+/// Streaming bodies, whose size isn't known up front, are passed through as
+/// they are: buffering them to compress would hold back every chunk until
+/// the stream ends.
+///
 /// # Example
 ///
 /// ```rust,ignore
@@ -127,6 +197,10 @@ impl CompressionMiddleware {
     fn is_already_encoded(response: &Response<BoxBody>) -> bool {
         response.headers().contains_key(header::CONTENT_ENCODING)
     }
+
+    fn is_streaming(response: &Response<BoxBody>) -> bool {
+        response.body().size_hint().exact().is_none()
+    }
 }
 
 impl Default for CompressionMiddleware {
@@ -149,13 +223,14 @@ impl Middleware for CompressionMiddleware {
                 .and_then(|v| v.to_str().ok())
                 .unwrap_or("");
 
-            let algorithm = Algorithm::from_accept_encoding(accept_encoding);
+            let encoding = Encoding::negotiate(accept_encoding, &self.config.preference);
 
             let response = next.run(req).await;
 
-            let algorithm = match algorithm {
+            let encoding = match encoding {
                 Some(alg)
                     if !Self::is_already_encoded(&response)
+                        && !Self::is_streaming(&response)
                         && Self::is_compressible_content_type(
                             response.headers().get(header::CONTENT_TYPE),
                         ) =>
@@ -168,28 +243,27 @@ impl Middleware for CompressionMiddleware {
             let (parts, body) = response.into_parts();
             let body_bytes = match body.collect().await {
                 Ok(collected) => collected.to_bytes(),
-                Err(_) => return Response::from_parts(parts, Full::new(Bytes::new())),
+                Err(_) => return Response::from_parts(parts, BoxBody::empty()),
             };
 
             if body_bytes.len() < self.config.min_size {
-                return Response::from_parts(parts, Full::new(body_bytes));
+                return Response::from_parts(parts, BoxBody::from(body_bytes));
             }
 
-            let level = Compression::new(self.config.level);
-            let compressed = match algorithm.compress(&body_bytes, level) {
+            let compressed = match encoding.compress(&body_bytes, self.config.level) {
                 Ok(data) => data,
-                Err(_) => return Response::from_parts(parts, Full::new(body_bytes)),
+                Err(_) => return Response::from_parts(parts, BoxBody::from(body_bytes)),
             };
 
             // not worth it
             if compressed.len() >= body_bytes.len() {
-                return Response::from_parts(parts, Full::new(body_bytes));
+                return Response::from_parts(parts, BoxBody::from(body_bytes));
             }
 
-            let mut response = Response::from_parts(parts, Full::new(Bytes::from(compressed)));
+            let mut response = Response::from_parts(parts, BoxBody::from(Bytes::from(compressed)));
             response.headers_mut().insert(
                 header::CONTENT_ENCODING,
-                HeaderValue::from_static(algorithm.content_encoding()),
+                HeaderValue::from_static(encoding.content_encoding()),
             );
             response.headers_mut().remove(header::CONTENT_LENGTH);
             response
@@ -219,34 +293,109 @@ mod tests {
     }
 
     #[test]
-    fn test_algorithm_from_accept_encoding() {
+    fn test_config_default_preference() {
+        let config = CompressionConfig::default();
+        assert_eq!(config.preference, Encoding::all());
+        assert_eq!(
+            &config.preference[config.preference.len() - 2..],
+            &[Encoding::Gzip, Encoding::Deflate]
+        );
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let preference = [Encoding::Gzip, Encoding::Deflate];
         assert_eq!(
-            Algorithm::from_accept_encoding("gzip, deflate"),
-            Some(Algorithm::Gzip)
+            Encoding::negotiate("gzip, deflate", &preference),
+            Some(Encoding::Gzip)
         );
         assert_eq!(
-            Algorithm::from_accept_encoding("deflate"),
-            Some(Algorithm::Deflate)
+            Encoding::negotiate("deflate", &preference),
+            Some(Encoding::Deflate)
         );
-        assert_eq!(Algorithm::from_accept_encoding("br"), None);
+        assert_eq!(Encoding::negotiate("compress", &preference), None);
+        assert_eq!(Encoding::negotiate("", &preference), None);
     }
 
     #[test]
-    fn test_gzip_compression() {
+    fn test_negotiate_honors_zero_weight() {
+        let preference = [Encoding::Gzip, Encoding::Deflate];
+        assert_eq!(Encoding::negotiate("gzip;q=0", &preference), None);
+        assert_eq!(
+            Encoding::negotiate("gzip;q=0, deflate", &preference),
+            Some(Encoding::Deflate)
+        );
+        assert_eq!(Encoding::negotiate("*;q=0", &preference), None);
+    }
+
+    #[test]
+    fn test_negotiate_follows_client_weights() {
+        let preference = [Encoding::Gzip, Encoding::Deflate];
+        assert_eq!(
+            Encoding::negotiate("gzip;q=0.5, deflate;q=0.9", &preference),
+            Some(Encoding::Deflate)
+        );
+    }
+
+    #[test]
+    fn test_negotiate_breaks_ties_by_preference() {
+        assert_eq!(
+            Encoding::negotiate("gzip, deflate", &[Encoding::Deflate, Encoding::Gzip]),
+            Some(Encoding::Deflate)
+        );
+        assert_eq!(
+            Encoding::negotiate("gzip, deflate", &[Encoding::Gzip]),
+            Some(Encoding::Gzip)
+        );
+        assert_eq!(Encoding::negotiate("deflate", &[Encoding::Gzip]), None);
+    }
+
+    #[test]
+    fn test_negotiate_wildcard() {
+        let preference = [Encoding::Gzip, Encoding::Deflate];
+        assert_eq!(Encoding::negotiate("*", &preference), Some(Encoding::Gzip));
+        assert_eq!(
+            Encoding::negotiate("*, gzip;q=0", &preference),
+            Some(Encoding::Deflate)
+        );
+    }
+
+    #[test]
+    fn test_negotiate_identity() {
+        let preference = [Encoding::Gzip, Encoding::Deflate];
+        assert_eq!(
+            Encoding::negotiate("identity, gzip;q=0.5", &preference),
+            None
+        );
+        assert_eq!(
+            Encoding::negotiate("identity;q=0, gzip", &preference),
+            Some(Encoding::Gzip)
+        );
+        assert_eq!(Encoding::negotiate("identity", &preference), None);
+    }
+
+    #[cfg(feature = "compression-br")]
+    #[test]
+    fn test_brotli_compression() {
         let data = "hello from rapina ".repeat(100);
-        let compressed = Algorithm::Gzip
-            .compress(data.as_bytes(), Compression::default())
-            .unwrap();
+        let compressed = Encoding::Brotli.compress(data.as_bytes(), 6).unwrap();
         assert!(compressed.len() < data.len());
+
+        let mut decompressed = Vec::new();
+        brotli::BrotliDecompress(&mut compressed.as_slice(), &mut decompressed).unwrap();
+        assert_eq!(decompressed, data.as_bytes());
     }
 
+    #[cfg(feature = "compression-zstd")]
     #[test]
-    fn test_deflate_compression() {
+    fn test_zstd_compression() {
         let data = "hello from rapina ".repeat(100);
-        let compressed = Algorithm::Deflate
-            .compress(data.as_bytes(), Compression::default())
-            .unwrap();
+        let compressed = Encoding::Zstd.compress(data.as_bytes(), 6).unwrap();
         assert!(compressed.len() < data.len());
+        assert_eq!(
+            zstd::decode_all(compressed.as_slice()).unwrap(),
+            data.as_bytes()
+        );
     }
 
     #[test]
@@ -0,0 +1,348 @@
+use std::io::Read;
+
+use bytes::Bytes;
+use http::header::{ACCEPT_ENCODING, CONTENT_ENCODING};
+use http::{HeaderValue, Request, Response};
+use http_body_util::{BodyExt, LengthLimitError, Limited};
+use hyper::body::Incoming;
+
+use crate::context::RequestContext;
+use crate::error::Error;
+use crate::response::{BoxBody, IntoResponse};
+
+use super::{BoxFuture, Middleware, Next};
+
+const DEFAULT_MAX_SIZE: usize = 1024 * 1024; // 1MB
+
+/// A content coding the middleware can decode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Coding {
+    #[cfg(feature = "compression-br")]
+    Brotli,
+    #[cfg(feature = "compression-zstd")]
+    Zstd,
+    Gzip,
+    Deflate,
+}
+
+impl Coding {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            #[cfg(feature = "compression-br")]
+            "br" => Some(Coding::Brotli),
+            #[cfg(feature = "compression-zstd")]
+            "zstd" => Some(Coding::Zstd),
+            "gzip" | "x-gzip" => Some(Coding::Gzip),
+            "deflate" => Some(Coding::Deflate),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "compression-br")]
+            Coding::Brotli => "br",
+            #[cfg(feature = "compression-zstd")]
+            Coding::Zstd => "zstd",
+            Coding::Gzip => "gzip",
+            Coding::Deflate => "deflate",
+        }
+    }
+
+    /// Every coding compiled in, as listed in a 415's `Accept-Encoding`.
+    fn supported() -> &'static str {
+        match (
+            cfg!(feature = "compression-br"),
+            cfg!(feature = "compression-zstd"),
+        ) {
+            (true, true) => "br, zstd, gzip, deflate",
+            (true, false) => "br, gzip, deflate",
+            (false, true) => "zstd, gzip, deflate",
+            (false, false) => "gzip, deflate",
+        }
+    }
+
+    /// Decodes `data`, reading at most `max_size + 1` bytes of output so an
+    /// oversized body is detected without inflating all of it.
+    fn decode(&self, data: &[u8], max_size: usize) -> Result<Vec<u8>, Error> {
+        let limit = max_size as u64 + 1;
+        let mut out = Vec::new();
+        let result = match self {
+            #[cfg(feature = "compression-br")]
+            Coding::Brotli => brotli::Decompressor::new(data, 4096)
+                .take(limit)
+                .read_to_end(&mut out),
+            #[cfg(feature = "compression-zstd")]
+            Coding::Zstd => zstd::stream::read::Decoder::with_buffer(data)
+                .and_then(|decoder| decoder.take(limit).read_to_end(&mut out)),
+            Coding::Gzip => flate2::read::MultiGzDecoder::new(data)
+                .take(limit)
+                .read_to_end(&mut out),
+            Coding::Deflate => flate2::read::ZlibDecoder::new(data)
+                .take(limit)
+                .read_to_end(&mut out),
+        };
+
+        result.map_err(|_| Error::bad_request(format!("invalid {} request body", self.name())))?;
+        if out.len() > max_size {
+            return Err(too_large(max_size));
+        }
+        Ok(out)
+    }
+}
+
+fn too_large(max_size: usize) -> Error {
+    Error::new(
+        413,
+        "PAYLOAD_TOO_LARGE",
+        format!("decompressed request body exceeds {} bytes", max_size),
+    )
+}
+
+/// Tells the body extractors how to decode a compressed request body.
+///
+/// Inserted into the request extensions by [`DecompressionMiddleware`] and
+/// read back by [`read_body`](crate::extract) when `Json`, `Form` or
+/// `Multipart` consume the body.
+#[derive(Debug, Clone)]
+pub(crate) struct DecodeBody {
+    /// The codings from `Content-Encoding`, in the order they were applied.
+    codings: Vec<Coding>,
+    max_size: usize,
+}
+
+impl DecodeBody {
+    /// Reads and decodes the body, failing with 413 once either the
+    /// compressed or the decoded body grows past the limit.
+    pub(crate) async fn read(&self, body: Incoming) -> Result<Bytes, Error> {
+        let compressed = Limited::new(body, self.max_size)
+            .collect()
+            .await
+            .map_err(|e| {
+                if e.is::<LengthLimitError>() {
+                    too_large(self.max_size)
+                } else {
+                    Error::bad_request("Failed to read request body")
+                }
+            })?
+            .to_bytes();
+
+        let mut data = compressed.to_vec();
+        for coding in self.codings.iter().rev() {
+            data = coding.decode(&data, self.max_size)?;
+        }
+        Ok(Bytes::from(data))
+    }
+}
+
+/// Configuration for [`DecompressionMiddleware`].
+#[derive(Debug, Clone)]
+pub struct DecompressionConfig {
+    /// Largest decoded body, in bytes, that is accepted. Larger bodies are
+    /// rejected with 413 before they are fully inflated. Defaults to 1 MB.
+    pub max_size: usize,
+}
+
+impl DecompressionConfig {
+    pub fn new(max_size: usize) -> Self {
+        Self { max_size }
+    }
+}
+
+impl Default for DecompressionConfig {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_SIZE)
+    }
+}
+
+/// Middleware that decodes compressed request bodies.
+///
+/// Requests with a `Content-Encoding` of gzip or deflate, and brotli or zstd
+/// with the `compression-br` and `compression-zstd` features, are decoded
+/// when the `Json`, `Form` or `Multipart` extractor reads the body, so
+/// handlers see the plain payload. Stacked codings such as `gzip, zstd` are
+/// undone in reverse order.
+///
+/// [`BodyLimitMiddleware`](super::BodyLimitMiddleware) only sees the
+/// compressed size, so the decoded body is capped separately at
+/// [`DecompressionConfig::max_size`]; a body that inflates past it gets
+/// `413 Payload Too Large`. Requests using an encoding that can't be decoded
+/// get `415 Unsupported Media Type` with an `Accept-Encoding` header listing
+/// the supported ones.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// Rapina::new()
+///     .with_decompression(DecompressionConfig::new(8 * 1024 * 1024))
+/// ```
+#[derive(Debug, Clone)]
+pub struct DecompressionMiddleware {
+    config: DecompressionConfig,
+}
+
+impl DecompressionMiddleware {
+    pub fn new(config: DecompressionConfig) -> Self {
+        Self { config }
+    }
+
+    /// Parses `Content-Encoding`, skipping `identity`. Returns the name of
+    /// the first coding that can't be decoded on failure.
+    fn parse_codings(header: &str) -> Result<Vec<Coding>, String> {
+        header
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty() && !name.eq_ignore_ascii_case("identity"))
+            .map(|name| Coding::from_name(name).ok_or_else(|| name.to_string()))
+            .collect()
+    }
+}
+
+impl Default for DecompressionMiddleware {
+    fn default() -> Self {
+        Self::new(DecompressionConfig::default())
+    }
+}
+
+impl Middleware for DecompressionMiddleware {
+    fn handle<'a>(
+        &'a self,
+        mut req: Request<Incoming>,
+        _ctx: &'a RequestContext,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, Response<BoxBody>> {
+        Box::pin(async move {
+            let Some(header) = req.headers().get(CONTENT_ENCODING) else {
+                return next.run(req).await;
+            };
+
+            let codings = match header.to_str() {
+                Ok(header) => Self::parse_codings(header),
+                Err(_) => Err("invalid".to_string()),
+            };
+            let codings = match codings {
+                Ok(codings) => codings,
+                Err(name) => {
+                    let mut response = Error::new(
+                        415,
+                        "UNSUPPORTED_MEDIA_TYPE",
+                        format!("unsupported content encoding: {}", name),
+                    )
+                    .into_response();
+                    response.headers_mut().insert(
+                        ACCEPT_ENCODING,
+                        HeaderValue::from_static(Coding::supported()),
+                    );
+                    return response;
+                }
+            };
+
+            if !codings.is_empty() {
+                req.extensions_mut().insert(DecodeBody {
+                    codings,
+                    max_size: self.config.max_size,
+                });
+            }
+
+            next.run(req).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::Compression;
+    use flate2::write::{GzEncoder, ZlibEncoder};
+    use std::io::Write;
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_config_default() {
+        assert_eq!(DecompressionConfig::default().max_size, 1024 * 1024);
+    }
+
+    #[test]
+    fn test_parse_codings() {
+        assert_eq!(
+            DecompressionMiddleware::parse_codings("gzip"),
+            Ok(vec![Coding::Gzip])
+        );
+        assert_eq!(
+            DecompressionMiddleware::parse_codings("X-Gzip, identity, deflate"),
+            Ok(vec![Coding::Gzip, Coding::Deflate])
+        );
+        assert_eq!(
+            DecompressionMiddleware::parse_codings("identity"),
+            Ok(vec![])
+        );
+        assert_eq!(
+            DecompressionMiddleware::parse_codings("gzip, compress"),
+            Err("compress".to_string())
+        );
+    }
+
+    #[test]
+    fn test_decode_gzip() {
+        let decoded = Coding::Gzip.decode(&gzip(b"{\"a\":1}"), 1024).unwrap();
+        assert_eq!(decoded, b"{\"a\":1}");
+    }
+
+    #[test]
+    fn test_decode_deflate() {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"hello").unwrap();
+        let decoded = Coding::Deflate
+            .decode(&encoder.finish().unwrap(), 1024)
+            .unwrap();
+        assert_eq!(decoded, b"hello");
+    }
+
+    #[test]
+    fn test_decode_stops_at_limit() {
+        let bomb = gzip(&vec![0; 10 * 1024 * 1024]);
+        assert!(bomb.len() < 64 * 1024);
+
+        let error = Coding::Gzip.decode(&bomb, 1024).unwrap_err();
+        assert_eq!(error.status(), 413);
+    }
+
+    #[test]
+    fn test_decode_exactly_at_limit() {
+        let decoded = Coding::Gzip.decode(&gzip(&[7; 1024]), 1024).unwrap();
+        assert_eq!(decoded.len(), 1024);
+    }
+
+    #[test]
+    fn test_decode_invalid_data() {
+        let error = Coding::Gzip.decode(b"not gzip", 1024).unwrap_err();
+        assert_eq!(error.status(), 400);
+    }
+
+    #[cfg(feature = "compression-zstd")]
+    #[test]
+    fn test_decode_zstd() {
+        let encoded = zstd::encode_all(&b"hello zstd"[..], 3).unwrap();
+        assert_eq!(Coding::Zstd.decode(&encoded, 1024).unwrap(), b"hello zstd");
+
+        let bomb = zstd::encode_all(&vec![0; 1024 * 1024][..], 3).unwrap();
+        assert_eq!(Coding::Zstd.decode(&bomb, 1024).unwrap_err().status(), 413);
+    }
+
+    #[cfg(feature = "compression-br")]
+    #[test]
+    fn test_decode_brotli() {
+        let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, 5, 22);
+        encoder.write_all(b"hello brotli").unwrap();
+        let encoded = encoder.into_inner();
+        assert_eq!(
+            Coding::Brotli.decode(&encoded, 1024).unwrap(),
+            b"hello brotli"
+        );
+    }
+}
@@ -9,11 +9,21 @@
 //! - [`BodyLimitMiddleware`] - Limit request body size
 //! - [`TraceIdMiddleware`] - Add trace IDs to requests/responses
 //! - [`RequestLogMiddleware`] - Structured request logging
+//!
+//! # Scope
+//!
+//! Middleware added with [`Rapina::middleware`](crate::app::Rapina::middleware)
+//! runs for every request, before routing. Middleware added with
+//! [`Router::middleware`] only runs for requests that match one of that
+//! router's routes, after routing and right before the handler.
 
+pub(crate) mod accept_encoding;
 mod body_limit;
 #[cfg(feature = "compression")]
 mod compression;
 mod cors;
+#[cfg(feature = "compression")]
+pub(crate) mod decompression;
 #[cfg(feature = "rate-limit")]
 mod rate_limit;
 mod request_log;
@@ -24,10 +34,17 @@ mod trace_id;
 
 pub use body_limit::BodyLimitMiddleware;
 #[cfg(feature = "compression")]
-pub use compression::{CompressionConfig, CompressionMiddleware};
+pub use compression::{CompressionConfig, CompressionMiddleware, Encoding};
 pub use cors::{AllowedHeaders, AllowedMethods, AllowedOrigins, CorsConfig, CorsMiddleware};
+#[cfg(feature = "compression")]
+pub use decompression::{DecompressionConfig, DecompressionMiddleware};
+#[cfg(feature = "rate-limit")]
+pub(crate) use rate_limit::RateLimitRoutes;
 #[cfg(feature = "rate-limit")]
-pub use rate_limit::{KeyExtractor, RateLimitConfig, RateLimitMiddleware};
+pub use rate_limit::{
+    DEFAULT_POLICY, InMemoryRateLimitStore, KeyExtractor, RateLimitBackend, RateLimitConfig,
+    RateLimitDecision, RateLimitFuture, RateLimitMiddleware, RateLimitPolicy, RateLimitStore,
+};
 pub use request_log::{RequestLogConfig, RequestLogMiddleware};
 pub use timeout::TimeoutMiddleware;
 #[cfg(feature = "tower")]
@@ -46,8 +63,9 @@ use hyper::body::Incoming;
 use hyper::{Request, Response};
 
 use crate::context::RequestContext;
-use crate::response::BoxBody;
-use crate::router::Router;
+use crate::extract::PathParams;
+use crate::response::{BoxBody, IntoResponse};
+use crate::router::{HandlerFn, Router};
 use crate::state::AppState;
 
 /// A boxed future type used by middleware.
@@ -89,11 +107,43 @@ pub trait Middleware: Send + Sync + 'static {
     ) -> BoxFuture<'a, Response<BoxBody>>;
 }
 
+/// What a middleware chain ends in once every middleware has run.
+#[derive(Clone)]
+pub(crate) enum Endpoint {
+    /// The global chain: route the request.
+    Router(Arc<Router>),
+    /// A route's own chain: call the handler the router matched.
+    Route,
+}
+
+/// The handler and parameters a route chain ends in.
+///
+/// Carried in the request extensions rather than in [`Endpoint`] so a
+/// middleware shared by several routes (e.g. through a group) never holds
+/// on to one particular route.
+#[derive(Clone)]
+pub(crate) struct RouteTarget {
+    pub(crate) handler: HandlerFn,
+    pub(crate) params: PathParams,
+}
+
+impl Endpoint {
+    async fn call(&self, mut req: Request<Incoming>, state: &Arc<AppState>) -> Response<BoxBody> {
+        match self {
+            Endpoint::Router(router) => router.handle(req, state).await,
+            Endpoint::Route => match req.extensions_mut().remove::<RouteTarget>() {
+                Some(target) => (target.handler)(req, target.params, state.clone()).await,
+                None => crate::error::Error::internal("route handler missing").into_response(),
+            },
+        }
+    }
+}
+
 /// Represents the next middleware or handler in the chain.
 #[derive(Clone)]
 pub struct Next<'a> {
     middlewares: &'a [Arc<dyn Middleware>],
-    router: Arc<Router>,
+    endpoint: Endpoint,
     state: Arc<AppState>,
     ctx: &'a RequestContext,
 }
@@ -104,31 +154,85 @@ impl<'a> Next<'a> {
         router: Arc<Router>,
         state: Arc<AppState>,
         ctx: &'a RequestContext,
+    ) -> Self {
+        Self::with_endpoint(middlewares, Endpoint::Router(router), state, ctx)
+    }
+
+    pub(crate) fn with_endpoint(
+        middlewares: &'a [Arc<dyn Middleware>],
+        endpoint: Endpoint,
+        state: Arc<AppState>,
+        ctx: &'a RequestContext,
     ) -> Self {
         Self {
             middlewares,
-            router,
+            endpoint,
             state,
             ctx,
         }
     }
 
+    /// The application state, for middleware that reads settings stored there.
+    pub(crate) fn state(&self) -> &Arc<AppState> {
+        &self.state
+    }
+
+    /// Takes ownership of the rest of the chain, so it can run on a spawned
+    /// task after this request's response has gone out.
+    pub(crate) fn detach(&self) -> DetachedNext {
+        DetachedNext {
+            middlewares: self.middlewares.to_vec(),
+            endpoint: self.endpoint.clone(),
+            state: self.state.clone(),
+            ctx: self.ctx.clone(),
+        }
+    }
+
     /// Runs the next middleware or handler in the chain.
     pub async fn run(self, req: Request<Incoming>) -> Response<BoxBody> {
         if let Some((current, rest)) = self.middlewares.split_first() {
             let next = Next {
                 middlewares: rest,
-                router: self.router.clone(),
+                endpoint: self.endpoint.clone(),
                 state: self.state.clone(),
                 ctx: self.ctx,
             };
             current.handle(req, self.ctx, next).await
         } else {
-            self.router.handle(req, &self.state).await
+            self.endpoint.call(req, &self.state).await
         }
     }
 }
 
+/// The rest of a middleware chain, detached from the request that reached it.
+pub(crate) struct DetachedNext {
+    middlewares: Vec<Arc<dyn Middleware>>,
+    endpoint: Endpoint,
+    state: Arc<AppState>,
+    ctx: RequestContext,
+}
+
+impl DetachedNext {
+    /// Runs the rest of the chain, with the app's error format.
+    pub(crate) async fn run(self, req: Request<Incoming>) -> Response<BoxBody> {
+        let config = self
+            .state
+            .get::<crate::error::ErrorConfig>()
+            .cloned()
+            .unwrap_or_default();
+
+        let next = Next::with_endpoint(
+            &self.middlewares,
+            self.endpoint.clone(),
+            self.state.clone(),
+            &self.ctx,
+        );
+        crate::error::ERROR_CONFIG
+            .scope(config, next.run(req))
+            .await
+    }
+}
+
 /// A stack of middleware to be executed in order.
 pub struct MiddlewareStack {
     middlewares: Vec<Arc<dyn Middleware>>,
@@ -1,13 +1,18 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, Instant};
 
 use dashmap::DashMap;
+use http::HeaderValue;
 use hyper::body::Incoming;
 use hyper::{Request, Response};
 
 use crate::context::RequestContext;
 use crate::error::Error;
+use crate::extract::ClientIp;
 use crate::response::{BoxBody, IntoResponse};
 
 use super::{BoxFuture, Middleware, Next};
@@ -15,6 +20,9 @@ use super::{BoxFuture, Middleware, Next};
 /// Type alias for custom key extractor functions
 type KeyExtractorFn = Arc<dyn Fn(&Request<Incoming>) -> String + Send + Sync>;
 
+/// A boxed future for trait object compatibility.
+pub type RateLimitFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
 /// How often to run cleanup (every N requests)
 const CLEANUP_INTERVAL: u64 = 1000;
 
@@ -28,10 +36,162 @@ struct TokenBucket {
     last_refill: Instant,
 }
 
+/// The outcome of taking a token from a bucket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitDecision {
+    /// Whether the request may proceed
+    pub allowed: bool,
+    /// Whole tokens left in the bucket after this request
+    pub remaining: u32,
+    /// Seconds until the next token is available, 0 if the request was allowed
+    pub retry_after: u64,
+    /// Seconds until the bucket is full again
+    pub reset: u64,
+}
+
+/// Trait for rate limit storage backends.
+///
+/// Uses `BoxFuture` returns for `dyn RateLimitStore` compatibility, like
+/// [`CacheBackend`](crate::cache::CacheBackend). A store keeps one token bucket
+/// per key; the refill rate and capacity are passed on every call, so a single
+/// store can serve several limits.
+pub trait RateLimitStore: Send + Sync + 'static {
+    /// Refills the bucket for `key` at `rate` tokens per second up to `burst`,
+    /// then tries to take one token from it.
+    ///
+    /// A key seen for the first time starts with a full bucket. The check and
+    /// the update must be atomic, so concurrent requests can't both take the
+    /// last token.
+    fn take(&self, key: &str, rate: f64, burst: u32) -> RateLimitFuture<'_, RateLimitDecision>;
+}
+
+/// In-process token buckets in a `DashMap`.
+///
+/// Each instance of the app counts separately, so behind a load balancer with
+/// N replicas clients get N times the configured limit. Use a shared store,
+/// such as the Redis one, in that case.
+#[derive(Debug, Default)]
+pub struct InMemoryRateLimitStore {
+    buckets: DashMap<String, TokenBucket>,
+    request_count: AtomicU64,
+}
+
+impl InMemoryRateLimitStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remove buckets that haven't been accessed recently
+    fn cleanup_stale_buckets(&self) {
+        let now = Instant::now();
+        self.buckets
+            .retain(|_, bucket| now.duration_since(bucket.last_refill) < STALE_AFTER);
+    }
+
+    fn take_now(&self, key: &str, rate: f64, burst: u32) -> RateLimitDecision {
+        // Periodic cleanup: every CLEANUP_INTERVAL requests, prune stale buckets
+        let count = self.request_count.fetch_add(1, Ordering::Relaxed);
+        if count > 0 && count % CLEANUP_INTERVAL == 0 {
+            self.cleanup_stale_buckets();
+        }
+
+        let now = Instant::now();
+        let mut bucket = self
+            .buckets
+            .entry(key.to_string())
+            .or_insert_with(|| TokenBucket {
+                tokens: burst as f64,
+                last_refill: now,
+            });
+
+        // Refill tokens based on elapsed time
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(burst as f64);
+        bucket.last_refill = now;
+
+        // Try to consume one token
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+        let reset = ((burst as f64 - bucket.tokens) / rate).ceil() as u64;
+
+        if allowed {
+            RateLimitDecision {
+                allowed: true,
+                remaining: bucket.tokens as u32,
+                retry_after: 0,
+                reset,
+            }
+        } else {
+            // Calculate when bucket will have 1 token
+            let tokens_needed = 1.0 - bucket.tokens;
+            RateLimitDecision {
+                allowed: false,
+                remaining: 0,
+                retry_after: (tokens_needed / rate).ceil() as u64,
+                reset,
+            }
+        }
+    }
+}
+
+impl RateLimitStore for InMemoryRateLimitStore {
+    fn take(&self, key: &str, rate: f64, burst: u32) -> RateLimitFuture<'_, RateLimitDecision> {
+        Box::pin(std::future::ready(self.take_now(key, rate, burst)))
+    }
+}
+
+/// Where token buckets are stored
+#[derive(Clone, Default)]
+pub enum RateLimitBackend {
+    /// A new [`InMemoryRateLimitStore`] per middleware
+    #[default]
+    InMemory,
+    /// A Redis server shared by all instances (requires `rate-limit-redis` feature)
+    #[cfg(feature = "rate-limit-redis")]
+    Redis { url: String },
+    /// A user-provided store
+    Custom(Arc<dyn RateLimitStore>),
+}
+
+impl std::fmt::Debug for RateLimitBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RateLimitBackend::InMemory => write!(f, "RateLimitBackend::InMemory"),
+            #[cfg(feature = "rate-limit-redis")]
+            RateLimitBackend::Redis { url } => f
+                .debug_struct("RateLimitBackend::Redis")
+                .field("url", url)
+                .finish(),
+            RateLimitBackend::Custom(_) => write!(f, "RateLimitBackend::Custom(...)"),
+        }
+    }
+}
+
+impl RateLimitBackend {
+    /// Builds the store for this backend.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a Redis URL is invalid.
+    fn build(self) -> Arc<dyn RateLimitStore> {
+        match self {
+            RateLimitBackend::InMemory => Arc::new(InMemoryRateLimitStore::new()),
+            #[cfg(feature = "rate-limit-redis")]
+            RateLimitBackend::Redis { url } => Arc::new(
+                crate::rate_limit_redis::RedisRateLimitStore::new(&url)
+                    .unwrap_or_else(|e| panic!("invalid rate limit Redis URL {:?}: {}", url, e)),
+            ),
+            RateLimitBackend::Custom(store) => store,
+        }
+    }
+}
+
 /// How to identify clients for rate limiting
 #[derive(Clone)]
 pub enum KeyExtractor {
-    /// Extract from X-Forwarded-For, X-Real-IP, or fallback to "unknown"
+    /// The request's [`ClientIp`], or "unknown" if there is none
     Ip,
     /// Custom extraction function
     Custom(KeyExtractorFn),
@@ -56,24 +216,60 @@ impl KeyExtractor {
     }
 
     fn extract_ip(req: &Request<Incoming>) -> String {
-        // X-Forwarded-For can have multiple IPs: "client, proxy1, proxy2"
-        // We want the leftmost (original client)
-        if let Some(ip) = req
-            .headers()
-            .get("x-forwarded-for")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|v| v.split(',').next())
-        {
-            return ip.trim().to_string();
-        }
+        req.extensions()
+            .get::<ClientIp>()
+            .map(|ip| ip.to_string())
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+}
+
+/// Name of the policy built from [`RateLimitConfig::new`]'s rate and burst.
+pub const DEFAULT_POLICY: &str = "default";
+
+/// A token bucket limit: a refill rate and a capacity.
+///
+/// A client can spend `burst` requests at once, then gets one more every
+/// `1 / requests_per_second` seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitPolicy {
+    /// Tokens added to the bucket per second
+    pub requests_per_second: f64,
+    /// Bucket capacity
+    pub burst: u32,
+}
 
-        // Fallback to X-Real-IP (common with nginx)
-        if let Some(ip) = req.headers().get("x-real-ip").and_then(|v| v.to_str().ok()) {
-            return ip.trim().to_string();
+impl RateLimitPolicy {
+    /// Create a policy with requests per second and burst capacity
+    pub fn new(requests_per_second: f64, burst: u32) -> Self {
+        Self {
+            requests_per_second,
+            burst,
         }
+    }
 
-        // No proxy headers found
-        "unknown".to_string()
+    /// `requests` per second, all of which may be spent at once
+    pub fn per_second(requests: u32) -> Self {
+        Self::new(requests as f64, requests)
+    }
+
+    /// `requests` per minute, all of which may be spent at once
+    pub fn per_minute(requests: u32) -> Self {
+        Self::new(requests as f64 / 60.0, requests)
+    }
+
+    /// `requests` per hour, all of which may be spent at once
+    pub fn per_hour(requests: u32) -> Self {
+        Self::new(requests as f64 / 3600.0, requests)
+    }
+
+    /// `requests` per day, all of which may be spent at once
+    pub fn per_day(requests: u32) -> Self {
+        Self::new(requests as f64 / 86400.0, requests)
+    }
+
+    /// Seconds an empty bucket takes to fill up, the `w` of `RateLimit-Policy`
+    pub fn window(&self) -> u64 {
+        (self.burst as f64 / self.requests_per_second).ceil() as u64
     }
 }
 
@@ -86,6 +282,13 @@ pub struct RateLimitConfig {
     pub burst: u32,
     /// How to identify clients
     pub key_extractor: KeyExtractor,
+    /// Where token buckets are stored
+    pub backend: RateLimitBackend,
+    /// More policies checked on every request, next to the default one
+    pub policies: Vec<(String, RateLimitPolicy)>,
+    /// Policies only checked on routes that name them with `#[rate_limit]`
+    /// or [`Rapina::rate_limit_route`](crate::app::Rapina::rate_limit_route)
+    pub route_policies: HashMap<String, RateLimitPolicy>,
 }
 
 impl RateLimitConfig {
@@ -95,9 +298,17 @@ impl RateLimitConfig {
             requests_per_second,
             burst,
             key_extractor: KeyExtractor::Ip,
+            backend: RateLimitBackend::InMemory,
+            policies: Vec::new(),
+            route_policies: HashMap::new(),
         }
     }
 
+    /// Create config from a policy
+    pub fn from_policy(policy: RateLimitPolicy) -> Self {
+        Self::new(policy.requests_per_second, policy.burst)
+    }
+
     /// Convenience: configure as requests per minute
     pub fn per_minute(requests: u32) -> Self {
         Self::new(requests as f64 / 60.0, requests)
@@ -108,75 +319,190 @@ impl RateLimitConfig {
         self.key_extractor = extractor;
         self
     }
+
+    /// Keep token buckets in Redis, so all instances share one limit
+    /// (requires `rate-limit-redis` feature)
+    #[cfg(feature = "rate-limit-redis")]
+    pub fn redis(mut self, url: &str) -> Self {
+        self.backend = RateLimitBackend::Redis {
+            url: url.to_string(),
+        };
+        self
+    }
+
+    /// Keep token buckets in a custom store
+    pub fn with_store(mut self, store: impl RateLimitStore) -> Self {
+        self.backend = RateLimitBackend::Custom(Arc::new(store));
+        self
+    }
+
+    /// Check another policy on every request, e.g. a daily quota next to a
+    /// per-second burst limit. A request must pass all of them.
+    pub fn with_policy(mut self, name: &str, policy: RateLimitPolicy) -> Self {
+        self.policies.push((name.to_string(), policy));
+        self
+    }
+
+    /// Define a policy that only applies to routes that ask for it by name,
+    /// on top of the policies every request is checked against.
+    pub fn route_policy(mut self, name: &str, policy: RateLimitPolicy) -> Self {
+        self.route_policies.insert(name.to_string(), policy);
+        self
+    }
+
+    /// The policies a request is checked against, in order: the default
+    /// one, those from [`with_policy`](Self::with_policy), then the route's.
+    fn policies_for<'a>(
+        &'a self,
+        route_policy: Option<&'a str>,
+    ) -> impl Iterator<Item = (&'a str, RateLimitPolicy)> {
+        let default = (
+            DEFAULT_POLICY,
+            RateLimitPolicy::new(self.requests_per_second, self.burst),
+        );
+        let route = route_policy.and_then(|name| match self.route_policies.get(name) {
+            Some(policy) => Some((name, *policy)),
+            None => {
+                tracing::warn!(policy = name, "route names an unknown rate limit policy");
+                None
+            }
+        });
+
+        std::iter::once(default)
+            .chain(
+                self.policies
+                    .iter()
+                    .map(|(name, policy)| (name.as_str(), *policy)),
+            )
+            .chain(route)
+    }
 }
 
-/// Rate limiting middleware using token bucket algorithm
+/// Which routes use which [`RateLimitConfig::route_policy`].
+///
+/// Filled from `#[rate_limit("name")]` during discovery and from
+/// [`Rapina::rate_limit_route`](crate::app::Rapina::rate_limit_route), then
+/// stored in the app state where [`RateLimitMiddleware`] looks it up.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RateLimitRoutes {
+    routes: Vec<(String, String, String)>, // (method, path, policy)
+}
+
+impl RateLimitRoutes {
+    pub(crate) fn add(&mut self, method: &str, path: &str, policy: &str) {
+        self.routes
+            .push((method.to_string(), path.to_string(), policy.to_string()));
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    /// The policy for a request, if its route names one. `HEAD` requests use
+    /// the policy of the `GET` route that serves them.
+    fn policy_for(&self, method: &str, path: &str) -> Option<&str> {
+        self.routes
+            .iter()
+            .find(|(m, p, _)| {
+                (m == method || (method == "HEAD" && m == "GET"))
+                    && crate::extract::extract_path_params(p, path).is_some()
+            })
+            .map(|(_, _, policy)| policy.as_str())
+    }
+}
+
+/// The outcome of checking every policy that applies to a request.
 #[derive(Debug)]
+struct RateLimitOutcome {
+    /// The policy that denied the request, or the one closest to running out
+    policy: RateLimitPolicy,
+    decision: RateLimitDecision,
+    /// `RateLimit-Policy` entries for every policy checked
+    policies: Vec<String>,
+}
+
+impl RateLimitOutcome {
+    /// Sets `RateLimit-Limit`, `RateLimit-Remaining`, `RateLimit-Reset` and
+    /// `RateLimit-Policy`, plus `Retry-After` when the request was denied.
+    fn apply(&self, response: &mut Response<BoxBody>) {
+        let headers = response.headers_mut();
+        headers.insert("ratelimit-limit", HeaderValue::from(self.policy.burst));
+        headers.insert(
+            "ratelimit-remaining",
+            HeaderValue::from(self.decision.remaining),
+        );
+        headers.insert("ratelimit-reset", HeaderValue::from(self.decision.reset));
+        if let Ok(policy) = HeaderValue::from_str(&self.policies.join(", ")) {
+            headers.insert("ratelimit-policy", policy);
+        }
+        if !self.decision.allowed {
+            headers.insert("retry-after", HeaderValue::from(self.decision.retry_after));
+        }
+    }
+}
+
+/// Rate limiting middleware using token bucket algorithm
+#[derive(Clone)]
 pub struct RateLimitMiddleware {
     config: RateLimitConfig,
-    buckets: Arc<DashMap<String, TokenBucket>>,
-    request_count: Arc<AtomicU64>,
+    store: Arc<dyn RateLimitStore>,
 }
 
-impl Clone for RateLimitMiddleware {
-    fn clone(&self) -> Self {
-        Self {
-            config: self.config.clone(),
-            buckets: Arc::clone(&self.buckets),
-            request_count: Arc::clone(&self.request_count),
-        }
+impl std::fmt::Debug for RateLimitMiddleware {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RateLimitMiddleware")
+            .field("config", &self.config)
+            .finish_non_exhaustive()
     }
 }
 
 impl RateLimitMiddleware {
+    /// Creates the middleware and its store.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the config selects Redis with an invalid URL.
     pub fn new(config: RateLimitConfig) -> Self {
-        Self {
-            config,
-            buckets: Arc::new(DashMap::new()),
-            request_count: Arc::new(AtomicU64::new(0)),
-        }
+        let store = config.backend.clone().build();
+        Self { config, store }
     }
 
-    /// Remove buckets that haven't been accessed recently
-    fn cleanup_stale_buckets(&self) {
-        let now = Instant::now();
-        self.buckets
-            .retain(|_, bucket| now.duration_since(bucket.last_refill) < STALE_AFTER);
-    }
-
-    /// Check if request is allowed, returns Some(retry_after_secs) if rate limited
-    fn check_rate_limit(&self, key: &str) -> Option<u64> {
-        // Periodic cleanup: every CLEANUP_INTERVAL requests, prune stale buckets
-        let count = self.request_count.fetch_add(1, Ordering::Relaxed);
-        if count > 0 && count % CLEANUP_INTERVAL == 0 {
-            self.cleanup_stale_buckets();
+    /// Takes a token from each policy's bucket for `key`, stopping at the
+    /// first policy that denies the request.
+    async fn check_rate_limit(&self, key: &str, route_policy: Option<&str>) -> RateLimitOutcome {
+        let mut outcome: Option<RateLimitOutcome> = None;
+        let mut policies = Vec::new();
+
+        for (name, policy) in self.config.policies_for(route_policy) {
+            let decision = self
+                .store
+                .take(
+                    &format!("{}:{}", name, key),
+                    policy.requests_per_second,
+                    policy.burst,
+                )
+                .await;
+            policies.push(format!("{};w={}", policy.burst, policy.window()));
+
+            let tighter = outcome
+                .as_ref()
+                .is_none_or(|o| !decision.allowed || decision.remaining < o.decision.remaining);
+            if tighter {
+                outcome = Some(RateLimitOutcome {
+                    policy,
+                    decision,
+                    policies: Vec::new(),
+                });
+            }
+            if !decision.allowed {
+                break;
+            }
         }
 
-        let now = Instant::now();
-        let mut bucket = self
-            .buckets
-            .entry(key.to_string())
-            .or_insert_with(|| TokenBucket {
-                tokens: self.config.burst as f64,
-                last_refill: now,
-            });
-
-        // Refill tokens based on elapsed time
-        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
-        let refill = elapsed * self.config.requests_per_second;
-        bucket.tokens = (bucket.tokens + refill).min(self.config.burst as f64);
-        bucket.last_refill = now;
-
-        // Try to consume one token
-        if bucket.tokens >= 1.0 {
-            bucket.tokens -= 1.0;
-            None // Request allowed
-        } else {
-            // Calculate when bucket will have 1 token
-            let tokens_needed = 1.0 - bucket.tokens;
-            let seconds_until_ready = tokens_needed / self.config.requests_per_second;
-            Some(seconds_until_ready.ceil() as u64)
-        }
+        // The default policy is always checked, so there is an outcome
+        let mut outcome = outcome.expect("no rate limit policies");
+        outcome.policies = policies;
+        outcome
     }
 }
 
@@ -189,20 +515,23 @@ impl Middleware for RateLimitMiddleware {
     ) -> BoxFuture<'a, Response<BoxBody>> {
         Box::pin(async move {
             let key = self.config.key_extractor.extract(&req);
-
-            if let Some(retry_after) = self.check_rate_limit(&key) {
-                let mut response = Error::rate_limited("too many requests")
+            let route_policy = next
+                .state()
+                .get::<RateLimitRoutes>()
+                .and_then(|routes| routes.policy_for(req.method().as_str(), req.uri().path()))
+                .map(str::to_string);
+
+            let outcome = self.check_rate_limit(&key, route_policy.as_deref()).await;
+
+            let mut response = if outcome.decision.allowed {
+                next.run(req).await
+            } else {
+                Error::rate_limited("too many requests")
                     .with_trace_id(ctx.trace_id())
-                    .into_response();
-
-                response
-                    .headers_mut()
-                    .insert("retry-after", retry_after.to_string().parse().unwrap());
-
-                return response;
-            }
-
-            next.run(req).await
+                    .into_response()
+            };
+            outcome.apply(&mut response);
+            response
         })
     }
 }
@@ -240,108 +569,218 @@ mod tests {
     }
 
     #[test]
-    fn test_middleware_allows_burst() {
-        let config = RateLimitConfig::new(1.0, 5); // 1 req/sec, burst of 5
-        let middleware = RateLimitMiddleware::new(config);
+    fn test_default_backend_is_in_memory() {
+        let config = RateLimitConfig::per_minute(100);
+        assert!(matches!(config.backend, RateLimitBackend::InMemory));
+    }
+
+    #[test]
+    fn test_store_allows_burst() {
+        let store = InMemoryRateLimitStore::new(); // 1 req/sec, burst of 5
 
         // Should allow 5 requests (burst capacity)
-        for _ in 0..5 {
-            assert!(middleware.check_rate_limit("test-key").is_none());
+        for remaining in (0..5).rev() {
+            let decision = store.take_now("test-key", 1.0, 5);
+            assert!(decision.allowed);
+            assert_eq!(decision.remaining, remaining);
         }
 
         // 6th request should be rate limited
-        assert!(middleware.check_rate_limit("test-key").is_some());
+        assert!(!store.take_now("test-key", 1.0, 5).allowed);
     }
 
     #[test]
-    fn test_middleware_returns_retry_after() {
-        let config = RateLimitConfig::new(1.0, 1); // 1 req/sec, burst of 1
-        let middleware = RateLimitMiddleware::new(config);
+    fn test_store_returns_retry_after() {
+        let store = InMemoryRateLimitStore::new(); // 1 req/sec, burst of 1
 
         // First request allowed
-        assert!(middleware.check_rate_limit("test-key").is_none());
+        assert!(store.take_now("test-key", 1.0, 1).allowed);
 
         // Second request blocked with retry_after
-        let retry_after = middleware.check_rate_limit("test-key");
-        assert!(retry_after.is_some());
-        assert_eq!(retry_after.unwrap(), 1); // Should wait ~1 second
+        let decision = store.take_now("test-key", 1.0, 1);
+        assert!(!decision.allowed);
+        assert_eq!(decision.retry_after, 1); // Should wait ~1 second
     }
 
     #[test]
-    fn test_middleware_separate_keys() {
-        let config = RateLimitConfig::new(1.0, 1);
-        let middleware = RateLimitMiddleware::new(config);
+    fn test_store_separate_keys() {
+        let store = InMemoryRateLimitStore::new();
 
         // Each key gets its own bucket
-        assert!(middleware.check_rate_limit("user-1").is_none());
-        assert!(middleware.check_rate_limit("user-2").is_none());
-        assert!(middleware.check_rate_limit("user-3").is_none());
+        assert!(store.take_now("user-1", 1.0, 1).allowed);
+        assert!(store.take_now("user-2", 1.0, 1).allowed);
+        assert!(store.take_now("user-3", 1.0, 1).allowed);
 
         // But same key is limited
-        assert!(middleware.check_rate_limit("user-1").is_some());
+        assert!(!store.take_now("user-1", 1.0, 1).allowed);
     }
 
-    #[test]
-    fn test_middleware_clone_shares_state() {
+    async fn allowed(middleware: &RateLimitMiddleware, key: &str) -> bool {
+        middleware
+            .check_rate_limit(key, None)
+            .await
+            .decision
+            .allowed
+    }
+
+    #[tokio::test]
+    async fn test_middleware_clone_shares_state() {
         let config = RateLimitConfig::new(1.0, 2);
         let middleware1 = RateLimitMiddleware::new(config);
         let middleware2 = middleware1.clone();
 
         // Use one token via middleware1
-        assert!(middleware1.check_rate_limit("shared-key").is_none());
+        assert!(allowed(&middleware1, "shared-key").await);
 
         // Use second token via middleware2 (same shared bucket)
-        assert!(middleware2.check_rate_limit("shared-key").is_none());
+        assert!(allowed(&middleware2, "shared-key").await);
 
         // Both should now see the bucket as empty
-        assert!(middleware1.check_rate_limit("shared-key").is_some());
-        assert!(middleware2.check_rate_limit("shared-key").is_some());
+        assert!(!allowed(&middleware1, "shared-key").await);
+        assert!(!allowed(&middleware2, "shared-key").await);
+    }
+
+    #[tokio::test]
+    async fn test_middleware_uses_custom_store() {
+        let store = Arc::new(InMemoryRateLimitStore::new());
+        let config = RateLimitConfig::new(1.0, 1).with_store(SharedStore(Arc::clone(&store)));
+        let middleware1 = RateLimitMiddleware::new(config.clone());
+        let middleware2 = RateLimitMiddleware::new(config);
+
+        // Separate middlewares built from the same config share the custom store
+        assert!(allowed(&middleware1, "key").await);
+        assert!(!allowed(&middleware2, "key").await);
+        assert_eq!(store.buckets.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_all_policies_must_allow() {
+        let config =
+            RateLimitConfig::new(100.0, 100).with_policy("daily", RateLimitPolicy::per_day(2));
+        let middleware = RateLimitMiddleware::new(config);
+
+        let outcome = middleware.check_rate_limit("key", None).await;
+        assert!(outcome.decision.allowed);
+        // The daily quota is the one closest to running out
+        assert_eq!(outcome.policy, RateLimitPolicy::per_day(2));
+        assert_eq!(outcome.decision.remaining, 1);
+        assert_eq!(outcome.policies, vec!["100;w=1", "2;w=86400"]);
+
+        assert!(allowed(&middleware, "key").await);
+        let outcome = middleware.check_rate_limit("key", None).await;
+        assert!(!outcome.decision.allowed);
+        assert_eq!(outcome.decision.retry_after, 43200);
+    }
+
+    #[tokio::test]
+    async fn test_route_policy_only_applies_when_named() {
+        let config =
+            RateLimitConfig::new(100.0, 100).route_policy("login", RateLimitPolicy::per_minute(1));
+        let middleware = RateLimitMiddleware::new(config);
+
+        assert!(allowed(&middleware, "key").await);
+        assert!(allowed(&middleware, "key").await);
+
+        let outcome = middleware.check_rate_limit("key", Some("login")).await;
+        assert!(outcome.decision.allowed);
+        assert_eq!(outcome.policies, vec!["100;w=1", "1;w=60"]);
+        assert!(
+            !middleware
+                .check_rate_limit("key", Some("login"))
+                .await
+                .decision
+                .allowed
+        );
+
+        // An unknown name falls back to the policies every route gets
+        assert!(
+            middleware
+                .check_rate_limit("key", Some("missing"))
+                .await
+                .decision
+                .allowed
+        );
+    }
+
+    #[test]
+    fn test_policy_window() {
+        assert_eq!(RateLimitPolicy::per_second(10).window(), 1);
+        assert_eq!(RateLimitPolicy::per_minute(100).window(), 60);
+        assert_eq!(RateLimitPolicy::per_hour(5).window(), 3600);
+        assert_eq!(RateLimitPolicy::per_day(1000).window(), 86400);
+        assert_eq!(RateLimitPolicy::new(2.0, 10).window(), 5);
+    }
+
+    #[test]
+    fn test_store_reports_reset() {
+        let store = InMemoryRateLimitStore::new();
+
+        assert_eq!(store.take_now("key", 1.0, 5).reset, 1);
+        assert_eq!(store.take_now("key", 1.0, 5).reset, 2);
+    }
+
+    #[test]
+    fn test_route_registry_matches_patterns() {
+        let mut routes = RateLimitRoutes::default();
+        routes.add("POST", "/login", "login");
+        routes.add("GET", "/reports/:id", "reports");
+
+        assert_eq!(routes.policy_for("POST", "/login"), Some("login"));
+        assert_eq!(routes.policy_for("GET", "/login"), None);
+        assert_eq!(routes.policy_for("GET", "/reports/7"), Some("reports"));
+        assert_eq!(routes.policy_for("HEAD", "/reports/7"), Some("reports"));
+    }
+
+    struct SharedStore(Arc<InMemoryRateLimitStore>);
+
+    impl RateLimitStore for SharedStore {
+        fn take(&self, key: &str, rate: f64, burst: u32) -> RateLimitFuture<'_, RateLimitDecision> {
+            self.0.take(key, rate, burst)
+        }
     }
 
     #[test]
     fn test_cleanup_removes_stale_buckets() {
-        let config = RateLimitConfig::new(1.0, 5);
-        let middleware = RateLimitMiddleware::new(config);
+        let store = InMemoryRateLimitStore::new();
 
         // Create some buckets
-        middleware.check_rate_limit("key-1");
-        middleware.check_rate_limit("key-2");
-        middleware.check_rate_limit("key-3");
+        store.take_now("key-1", 1.0, 5);
+        store.take_now("key-2", 1.0, 5);
+        store.take_now("key-3", 1.0, 5);
 
-        assert_eq!(middleware.buckets.len(), 3);
+        assert_eq!(store.buckets.len(), 3);
 
         // Manually age one bucket by setting last_refill to the past
-        if let Some(mut bucket) = middleware.buckets.get_mut("key-1") {
+        if let Some(mut bucket) = store.buckets.get_mut("key-1") {
             bucket.last_refill = Instant::now() - Duration::from_secs(700); // older than STALE_AFTER
         }
 
         // Run cleanup
-        middleware.cleanup_stale_buckets();
+        store.cleanup_stale_buckets();
 
         // key-1 should be removed, key-2 and key-3 should remain
-        assert_eq!(middleware.buckets.len(), 2);
-        assert!(middleware.buckets.get("key-1").is_none());
-        assert!(middleware.buckets.get("key-2").is_some());
-        assert!(middleware.buckets.get("key-3").is_some());
+        assert_eq!(store.buckets.len(), 2);
+        assert!(store.buckets.get("key-1").is_none());
+        assert!(store.buckets.get("key-2").is_some());
+        assert!(store.buckets.get("key-3").is_some());
     }
 
     #[test]
     fn test_cleanup_triggered_periodically() {
-        let config = RateLimitConfig::new(1000.0, 1000); // high burst to not get limited
-        let middleware = RateLimitMiddleware::new(config);
+        let store = InMemoryRateLimitStore::new(); // high burst to not get limited
 
         // Make requests and manually age a bucket
-        middleware.check_rate_limit("stale-key");
-        if let Some(mut bucket) = middleware.buckets.get_mut("stale-key") {
+        store.take_now("stale-key", 1000.0, 1000);
+        if let Some(mut bucket) = store.buckets.get_mut("stale-key") {
             bucket.last_refill = Instant::now() - Duration::from_secs(700);
         }
 
         // Make CLEANUP_INTERVAL requests to trigger cleanup
         for i in 0..super::CLEANUP_INTERVAL {
-            middleware.check_rate_limit(&format!("key-{}", i));
+            store.take_now(&format!("key-{}", i), 1000.0, 1000);
         }
 
         // The stale bucket should have been cleaned up
-        assert!(middleware.buckets.get("stale-key").is_none());
+        assert!(store.buckets.get("stale-key").is_none());
     }
 }
@@ -3,6 +3,7 @@ use hyper::{Request, Response};
 use tracing::{Instrument, info, info_span};
 
 use crate::context::RequestContext;
+use crate::extract::{ClientIp, ConnectInfo};
 use crate::response::BoxBody;
 
 use super::{BoxFuture, Middleware, Next};
@@ -87,7 +88,9 @@ fn format_headers(headers: &hyper::HeaderMap, redacted: &[String]) -> String {
 /// Structured request/response logging middleware.
 ///
 /// With default configuration this logs method, path, status, and duration
-/// at INFO level — identical to the previous zero-config behavior.
+/// at INFO level — identical to the previous zero-config behavior. The span
+/// also carries the HTTP version, the peer address from [`ConnectInfo`], and
+/// the [`ClientIp`].
 ///
 /// For richer output, use [`RequestLogMiddleware::verbose()`] or pass a
 /// custom [`RequestLogConfig`] via [`RequestLogMiddleware::with_config()`].
@@ -157,12 +160,27 @@ impl Middleware for RequestLogMiddleware {
             None
         };
 
+        let remote_addr = req
+            .extensions()
+            .get::<ConnectInfo>()
+            .and_then(ConnectInfo::remote_addr);
+        let client_ip = req.extensions().get::<ClientIp>().copied();
+
         let span = info_span!(
             "request",
             method = %method,
             path = %path,
             trace_id = %trace_id,
+            version = ?req.version(),
+            remote_addr = tracing::field::Empty,
+            client_ip = tracing::field::Empty,
         );
+        if let Some(addr) = remote_addr {
+            span.record("remote_addr", tracing::field::display(addr));
+        }
+        if let Some(ip) = client_ip {
+            span.record("client_ip", tracing::field::display(*ip));
+        }
 
         Box::pin(
             async move {
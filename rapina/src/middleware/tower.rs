@@ -21,7 +21,7 @@ use tower_layer::Layer;
 use tower_service::Service;
 
 use crate::context::RequestContext;
-use crate::middleware::{BoxFuture, Middleware, MiddlewareStack, Next};
+use crate::middleware::{BoxFuture, Endpoint, Middleware, MiddlewareStack, Next};
 use crate::response::{BoxBody, IntoResponse};
 use crate::router::Router;
 use crate::state::AppState;
@@ -30,13 +30,22 @@ use crate::state::AppState;
 
 /// A [`tower::Service`] that delegates to the Rapina middleware chain.
 ///
-/// Owns all data needed to execute the chain, with no lifetime parameter.
-/// This makes it compatible with any Tower layer — including those requiring
+/// Holds no per-request data: the rest of the chain travels with each request
+/// in its extensions, so one service can sit in front of different chains
+/// (e.g. a layer shared by routes with different route middleware). This
+/// makes it compatible with any Tower layer — including those requiring
 /// `Clone` (e.g. tower-resilience, retry, circuit breaker).
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct NextService {
+    _private: (),
+}
+
+/// The part of the chain after a tower layer, handed to [`NextService`]
+/// through the request extensions.
+#[derive(Clone)]
+struct RemainingChain {
     middlewares: Arc<[Arc<dyn Middleware>]>,
-    router: Arc<Router>,
+    endpoint: Endpoint,
     state: Arc<AppState>,
 }
 
@@ -49,18 +58,20 @@ impl Service<Request<Incoming>> for NextService {
         Poll::Ready(Ok(()))
     }
 
-    fn call(&mut self, req: Request<Incoming>) -> Self::Future {
-        let middlewares = self.middlewares.clone();
-        let router = self.router.clone();
-        let state = self.state.clone();
+    fn call(&mut self, mut req: Request<Incoming>) -> Self::Future {
+        let chain = req.extensions_mut().remove::<RemainingChain>();
 
         Box::pin(async move {
+            let Some(chain) = chain else {
+                tracing::error!("NextService called outside of TowerLayerMiddleware");
+                return Ok(crate::error::Error::internal("internal server error").into_response());
+            };
             let ctx = req
                 .extensions()
                 .get::<RequestContext>()
                 .cloned()
                 .unwrap_or_default();
-            let next = Next::new(&middlewares, router, state, &ctx);
+            let next = Next::with_endpoint(&chain.middlewares, chain.endpoint, chain.state, &ctx);
             Ok(next.run(req).await)
         })
     }
@@ -72,7 +83,9 @@ impl Service<Request<Incoming>> for NextService {
 /// subsequent request clones the cached service — since tower layers store
 /// shared state behind `Arc` internally, all requests share the same state.
 /// This means stateful layers like rate limiters, circuit breakers, and
-/// bulkheads work correctly across concurrent requests.
+/// bulkheads work correctly across concurrent requests. The middleware that
+/// runs after the layer is passed along with each request, so sharing one
+/// layer between routes with different route middleware is fine.
 ///
 /// # Body type
 ///
@@ -133,14 +146,14 @@ where
     ) -> BoxFuture<'a, Response<BoxBody>> {
         Box::pin(async move {
             req.extensions_mut().insert(ctx.clone());
-            let template = self.service.get_or_init(|| {
-                let next_svc = NextService {
-                    middlewares: next.middlewares.iter().cloned().collect(),
-                    router: next.router.clone(),
-                    state: next.state.clone(),
-                };
-                self.layer.layer(next_svc)
+            req.extensions_mut().insert(RemainingChain {
+                middlewares: next.middlewares.iter().cloned().collect(),
+                endpoint: next.endpoint.clone(),
+                state: next.state.clone(),
             });
+            let template = self
+                .service
+                .get_or_init(|| self.layer.layer(NextService::default()));
             let mut svc = template.clone();
 
             if let Err(e) = std::future::poll_fn(|cx| svc.poll_ready(cx)).await {
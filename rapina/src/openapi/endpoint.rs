@@ -44,13 +44,13 @@ pub async fn openapi_spec(
             Response::builder()
                 .status(StatusCode::OK)
                 .header(CONTENT_TYPE, APPLICATION_JSON)
-                .body(http_body_util::Full::new(bytes::Bytes::from(json)))
+                .body(BoxBody::from(bytes::Bytes::from(json)))
                 .unwrap()
         }
         None => Response::builder()
             .status(StatusCode::NOT_FOUND)
             .header(CONTENT_TYPE, APPLICATION_JSON)
-            .body(http_body_util::Full::new(bytes::Bytes::from(
+            .body(BoxBody::from(bytes::Bytes::from(
                 r#"{"error": "OpenAPI spec not configured"}"#,
             )))
             .unwrap(),
@@ -93,13 +93,14 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_openapi_spec_returns_404_and_empty_body_when_openapi_is_disabled() {
+    async fn test_openapi_spec_returns_404_when_openapi_is_disabled() {
         let router = Router::new().route(Method::GET, "/hello", |_, _, _| async { "hello" });
         let app = Rapina::new().router(router);
         let client = TestClient::new(app).await;
         let response = client.get("/__rapina/openapi.json").send().await;
 
         assert_eq!(response.status(), StatusCode::NOT_FOUND);
-        assert!(response.text().is_empty());
+        let json = response.json::<Value>();
+        assert_eq!(json["error"]["code"], "NOT_FOUND");
     }
 }
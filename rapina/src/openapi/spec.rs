@@ -10,6 +10,9 @@ pub struct OpenApiSpec {
     pub paths: BTreeMap<String, PathItem>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub components: Option<Components>,
+    /// Security requirements for every operation, any one of which is enough
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub security: Vec<SecurityRequirement>,
 }
 
 impl OpenApiSpec {
@@ -23,6 +26,45 @@ impl OpenApiSpec {
             },
             paths: BTreeMap::new(),
             components: None,
+            security: Vec::new(),
+        }
+    }
+
+    /// Documents how clients authenticate.
+    ///
+    /// Each scheme is added to `components.securitySchemes` and, as an
+    /// alternative to the others, to the top-level `security`. Operations
+    /// for which `is_public` holds opt out with an empty `security`.
+    pub fn with_security(
+        &mut self,
+        schemes: impl IntoIterator<Item = (String, SecurityScheme)>,
+        routes: &[crate::introspection::RouteInfo],
+        is_public: impl Fn(&str, &str) -> bool,
+    ) {
+        let components = self.components.get_or_insert_with(Components::default);
+        for (name, scheme) in schemes {
+            if components.security_schemes.contains_key(&name) {
+                continue;
+            }
+            self.security
+                .push(BTreeMap::from([(name.clone(), Vec::new())]));
+            components.security_schemes.insert(name, scheme);
+        }
+        if self.security.is_empty() {
+            return;
+        }
+
+        for route in routes {
+            if !is_public(&route.method, &route.path) {
+                continue;
+            }
+            if let Some(operation) = self
+                .paths
+                .get_mut(&openapi_path(&route.path))
+                .and_then(|item| item.operation_mut(&route.method))
+            {
+                operation.security = Some(Vec::new());
+            }
         }
     }
 }
@@ -51,6 +93,20 @@ pub struct PathItem {
     pub patch: Option<Operation>,
 }
 
+impl PathItem {
+    /// The operation for `method`, if the path has one.
+    pub fn operation_mut(&mut self, method: &str) -> Option<&mut Operation> {
+        match method.to_uppercase().as_str() {
+            "GET" => self.get.as_mut(),
+            "POST" => self.post.as_mut(),
+            "PUT" => self.put.as_mut(),
+            "DELETE" => self.delete.as_mut(),
+            "PATCH" => self.patch.as_mut(),
+            _ => None,
+        }
+    }
+}
+
 /// A single API operation (endpoint)
 #[derive(Debug, Clone, Serialize)]
 pub struct Operation {
@@ -65,6 +121,15 @@ pub struct Operation {
     #[serde(rename = "requestBody", skip_serializing_if = "Option::is_none")]
     pub request_body: Option<RequestBody>,
     pub responses: BTreeMap<String, Response>,
+    /// Roles the authenticated user must all hold
+    #[serde(rename = "x-required-roles", skip_serializing_if = "Vec::is_empty")]
+    pub required_roles: Vec<String>,
+    /// Scopes the user's token must all grant
+    #[serde(rename = "x-required-scopes", skip_serializing_if = "Vec::is_empty")]
+    pub required_scopes: Vec<String>,
+    /// Overrides the spec's `security`; empty for public operations
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub security: Option<Vec<SecurityRequirement>>,
 }
 
 impl Default for Operation {
@@ -84,6 +149,9 @@ impl Default for Operation {
             parameters: Vec::new(),
             request_body: None,
             responses,
+            required_roles: Vec::new(),
+            required_scopes: Vec::new(),
+            security: None,
         }
     }
 }
@@ -107,6 +175,7 @@ pub enum ParameterLocation {
     Path,
     Query,
     Header,
+    Cookie,
 }
 
 /// Request body definition
@@ -148,8 +217,34 @@ pub enum Schema {
 pub struct Components {
     #[serde(skip_serializing_if = "BTreeMap::is_empty")]
     pub schemas: BTreeMap<String, serde_json::Value>,
+    #[serde(rename = "securitySchemes", skip_serializing_if = "BTreeMap::is_empty")]
+    pub security_schemes: BTreeMap<String, SecurityScheme>,
 }
 
+/// A way for clients to authenticate
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+pub enum SecurityScheme {
+    /// An `Authorization` header scheme, such as `Bearer`
+    #[serde(rename = "http")]
+    Http {
+        scheme: String,
+        #[serde(rename = "bearerFormat", skip_serializing_if = "Option::is_none")]
+        bearer_format: Option<String>,
+    },
+    /// A key in a header, query parameter or cookie
+    #[serde(rename = "apiKey")]
+    ApiKey {
+        name: String,
+        #[serde(rename = "in")]
+        location: ParameterLocation,
+    },
+}
+
+/// Schemes that together authenticate a request, by name, with the scopes
+/// each must grant
+pub type SecurityRequirement = BTreeMap<String, Vec<String>>;
+
 /// Generate a JSON Schema for type `T` using OpenAPI 3.0-compatible settings.
 ///
 /// This uses `SchemaSettings::openapi3()` which replaces boolean schemas
@@ -229,42 +324,56 @@ pub fn build_openapi_spec(
     let mut schemas = BTreeMap::new();
     schemas.insert("ErrorResponse".to_string(), error_response_schema());
 
-    spec.components = Some(Components { schemas });
+    spec.components = Some(Components {
+        schemas,
+        ..Default::default()
+    });
 
     for route in routes {
         // skip internal rapina routes
         if route.path.starts_with("/__rapina") {
             continue;
         }
-        // Extract path parameters (e.g., :id -> id)
+        // Extract path parameters (e.g., :id -> id, *path -> path)
         let params: Vec<Parameter> = route
             .path
             .split('/')
-            .filter(|s| s.starts_with(':'))
-            .map(|s| Parameter {
-                name: s.trim_start_matches(':').to_string(),
+            .filter_map(|s| {
+                if let Some(name) = s.strip_prefix(':') {
+                    Some((name, None))
+                } else {
+                    s.strip_prefix('*')
+                        .map(|name| (name, Some("Remainder of the path, may contain `/`")))
+                }
+            })
+            .map(|(name, description)| Parameter {
+                name: name.to_string(),
                 location: ParameterLocation::Path,
-                description: None,
+                description: description.map(str::to_string),
                 required: true,
                 schema: None,
             })
             .collect();
 
-        // Convert :param to {param} for OpenAPI format
-        let openapi_path = route
-            .path
-            .split('/')
-            .map(|s| {
-                if s.starts_with(':') {
-                    format!("{{{}}}", s.trim_start_matches(':'))
-                } else {
-                    s.to_string()
-                }
-            })
-            .collect::<Vec<_>>()
-            .join("/");
+        let openapi_path = openapi_path(&route.path);
 
-        let success_response = if let Some(schema) = &route.response_schema {
+        let success_response = if let Some(content_type) = &route.response_content_type {
+            let schema = route
+                .response_schema
+                .clone()
+                .unwrap_or_else(|| serde_json::json!({ "type": "string" }));
+            let mut content = BTreeMap::new();
+            content.insert(
+                content_type.clone(),
+                MediaType {
+                    schema: Schema::Inline(schema),
+                },
+            );
+            Response {
+                description: "Success".to_string(),
+                content: Some(content),
+            }
+        } else if let Some(schema) = &route.response_schema {
             let mut content = BTreeMap::new();
             content.insert(
                 "application/json".to_string(),
@@ -337,6 +446,20 @@ pub fn build_openapi_spec(
             });
         }
 
+        // Routes with role or scope requirements answer 403 to users without them
+        if !route.required_roles.is_empty() || !route.required_scopes.is_empty() {
+            operation.required_roles = route.required_roles.clone();
+            operation.required_scopes = route.required_scopes.clone();
+            operation
+                .responses
+                .entry("403".to_string())
+                .or_insert_with(|| {
+                    let mut response = error_response_ref();
+                    response.description = "Missing a required role or scope".to_string();
+                    response
+                });
+        }
+
         // Add default error response for undocumented errors
         operation
             .responses
@@ -357,6 +480,19 @@ pub fn build_openapi_spec(
     spec
 }
 
+/// Converts :param and *param to {param} for OpenAPI format
+fn openapi_path(path: &str) -> String {
+    path.split('/')
+        .map(
+            |s| match s.strip_prefix(':').or_else(|| s.strip_prefix('*')) {
+                Some(name) => format!("{{{}}}", name),
+                None => s.to_string(),
+            },
+        )
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -500,6 +636,153 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_build_openapi_spec_with_event_stream_response() {
+        let routes = vec![
+            RouteInfo::new(
+                "GET",
+                "/events",
+                "events",
+                None,
+                None,
+                None::<String>,
+                None,
+                Vec::new(),
+            )
+            .with_response_content_type("text/event-stream"),
+        ];
+        let spec = build_openapi_spec("Test API", "1.0.0", &routes);
+
+        let json = serde_json::to_value(&spec).unwrap();
+        let content = &json["paths"]["/events"]["get"]["responses"]["200"]["content"];
+        assert_eq!(content["text/event-stream"]["schema"]["type"], "string");
+        assert!(content.get("application/json").is_none());
+    }
+
+    #[test]
+    fn test_build_openapi_spec_with_catch_all_path() {
+        let routes = vec![RouteInfo::new(
+            "GET",
+            "/files/:bucket/*path",
+            "get_file",
+            None,
+            None,
+            None::<String>,
+            None,
+            Vec::new(),
+        )];
+        let spec = build_openapi_spec("Test API", "1.0.0", &routes);
+
+        let item = spec.paths.get("/files/{bucket}/{path}").unwrap();
+        let params = &item.get.as_ref().unwrap().parameters;
+        assert_eq!(params.len(), 2);
+        assert_eq!(params[0].name, "bucket");
+        assert!(params[0].description.is_none());
+        assert_eq!(params[1].name, "path");
+        assert!(params[1].description.is_some());
+        assert!(params[1].required);
+    }
+
+    #[test]
+    fn test_build_openapi_spec_with_requirements() {
+        let routes = vec![
+            RouteInfo::new(
+                "DELETE",
+                "/users/:id",
+                "delete_user",
+                None,
+                None,
+                None::<String>,
+                None,
+                Vec::new(),
+            )
+            .with_required_roles(vec!["admin".to_string()])
+            .with_required_scopes(vec!["users:write".to_string()]),
+        ];
+
+        let spec = build_openapi_spec("Test", "1.0.0", &routes);
+        let json = serde_json::to_value(&spec).unwrap();
+        let operation = &json["paths"]["/users/{id}"]["delete"];
+        assert_eq!(operation["x-required-roles"], serde_json::json!(["admin"]));
+        assert_eq!(
+            operation["x-required-scopes"],
+            serde_json::json!(["users:write"])
+        );
+        assert_eq!(
+            operation["responses"]["403"]["description"],
+            "Missing a required role or scope"
+        );
+    }
+
+    #[test]
+    fn test_openapi_spec_with_security() {
+        let routes = vec![
+            RouteInfo::new(
+                "GET",
+                "/users/:id",
+                "get_user",
+                None,
+                None,
+                None::<String>,
+                None,
+                Vec::new(),
+            ),
+            RouteInfo::new(
+                "POST",
+                "/login",
+                "login",
+                None,
+                None,
+                None::<String>,
+                None,
+                Vec::new(),
+            ),
+        ];
+        let mut spec = build_openapi_spec("Test", "1.0.0", &routes);
+        spec.with_security(
+            [
+                (
+                    "bearerAuth".to_string(),
+                    SecurityScheme::Http {
+                        scheme: "bearer".to_string(),
+                        bearer_format: Some("JWT".to_string()),
+                    },
+                ),
+                (
+                    "apiKeyAuth".to_string(),
+                    SecurityScheme::ApiKey {
+                        name: "X-API-Key".to_string(),
+                        location: ParameterLocation::Header,
+                    },
+                ),
+            ],
+            &routes,
+            |method, path| method == "POST" && path == "/login",
+        );
+
+        let json = serde_json::to_value(&spec).unwrap();
+        assert_eq!(
+            json["components"]["securitySchemes"],
+            serde_json::json!({
+                "bearerAuth": {"type": "http", "scheme": "bearer", "bearerFormat": "JWT"},
+                "apiKeyAuth": {"type": "apiKey", "name": "X-API-Key", "in": "header"}
+            })
+        );
+        assert_eq!(
+            json["security"],
+            serde_json::json!([{"bearerAuth": []}, {"apiKeyAuth": []}])
+        );
+        assert!(
+            json["paths"]["/users/{id}"]["get"]
+                .get("security")
+                .is_none()
+        );
+        assert_eq!(
+            json["paths"]["/login"]["post"]["security"],
+            serde_json::json!([])
+        );
+    }
+
     #[test]
     fn test_build_openapi_spec_skips_internal_routes() {
         let routes = vec![
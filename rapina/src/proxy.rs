@@ -0,0 +1,389 @@
+//! Client IP resolution behind reverse proxies.
+//!
+//! A reverse proxy opens its own connection to the app, so the socket peer
+//! address is the proxy's, not the client's. Proxies record the original
+//! client in `X-Forwarded-For` or the RFC 7239 `Forwarded` header, but a
+//! client can send those headers too. [`TrustedProxies`] says which peers may
+//! vouch for the hop before them and which of the two headers they write: that
+//! header is read from the right and the first address that is not a trusted
+//! proxy is the client.
+//!
+//! The resolved address is available to handlers as
+//! [`ClientIp`](crate::extract::ClientIp), and is used by rate limiting and
+//! request logging.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use rapina::prelude::*;
+//!
+//! Rapina::new()
+//!     .with_trusted_proxies(TrustedProxies::new(["10.0.0.0/8"]))
+//!     .discover()
+//!     .listen("0.0.0.0:3000")
+//!     .await
+//! ```
+
+use std::net::{IpAddr, SocketAddr};
+
+use http::HeaderMap;
+use ipnet::IpNet;
+
+/// The header a trusted proxy records the client in.
+///
+/// Only this header is read. A proxy that appends to `X-Forwarded-For` passes
+/// a client's own `Forwarded` header through untouched, so reading both would
+/// let the client choose its IP.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ForwardedHeader {
+    /// `X-Forwarded-For`, as set by Nginx, HAProxy and most load balancers.
+    #[default]
+    XForwardedFor,
+    /// The RFC 7239 `Forwarded` header.
+    Forwarded,
+}
+
+/// The proxies whose forwarding headers are believed.
+///
+/// The default trusts nobody, so the client IP is always the socket peer.
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies {
+    ranges: Vec<IpNet>,
+    header: ForwardedHeader,
+    unix_peers: bool,
+}
+
+impl TrustedProxies {
+    /// Trusts the given CIDR ranges, such as `"10.0.0.0/8"` or `"2001:db8::/32"`.
+    ///
+    /// A bare address trusts just that address.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a range is not a valid IP address or CIDR range.
+    pub fn new<I, S>(ranges: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        ranges.into_iter().fold(Self::default(), |proxies, range| {
+            proxies.trust(range.as_ref())
+        })
+    }
+
+    /// Trusts loopback and private network ranges.
+    ///
+    /// Fits a proxy on the same host or in the same private network. Do not
+    /// use it when untrusted clients can reach the app from those networks.
+    pub fn private_networks() -> Self {
+        Self::new([
+            "127.0.0.0/8",
+            "10.0.0.0/8",
+            "172.16.0.0/12",
+            "192.168.0.0/16",
+            "::1/128",
+            "fc00::/7",
+        ])
+    }
+
+    /// Adds a CIDR range or a single address to the trusted set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is not a valid IP address or CIDR range.
+    pub fn trust(mut self, range: &str) -> Self {
+        let net = range
+            .parse::<IpNet>()
+            .or_else(|_| range.parse::<IpAddr>().map(IpNet::from))
+            .unwrap_or_else(|_| panic!("invalid trusted proxy range: {:?}", range));
+        self.ranges.push(net);
+        self
+    }
+
+    /// Sets the header the trusted proxies write, `X-Forwarded-For` by default.
+    pub fn header(mut self, header: ForwardedHeader) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Trusts peers without an IP address, such as a proxy connecting over a
+    /// Unix domain socket.
+    ///
+    /// Only enable this if every process that can reach the socket is a proxy.
+    pub fn trust_unix_peers(mut self) -> Self {
+        self.unix_peers = true;
+        self
+    }
+
+    /// Returns `true` if `ip` is in one of the trusted ranges.
+    pub fn is_trusted(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        self.ranges.iter().any(|net| net.contains(&ip))
+    }
+
+    /// Resolves the client IP of a request that arrived from `peer`.
+    ///
+    /// Starting at the peer, walks the configured [`ForwardedHeader`] from the
+    /// right while the current hop is trusted, and returns the first untrusted
+    /// hop. If a trusted proxy forwarded a value that is not an IP address, the
+    /// walk stops at that proxy.
+    ///
+    /// A peer without an IP address, such as a Unix domain socket client, is
+    /// trusted only after [`trust_unix_peers`](Self::trust_unix_peers).
+    /// Returns `None` if no IP address is known at all.
+    pub fn resolve(&self, peer: Option<IpAddr>, headers: &HeaderMap) -> Option<IpAddr> {
+        let mut client = peer.map(|ip| ip.to_canonical());
+        let peer_trusted = match client {
+            Some(ip) => self.is_trusted(ip),
+            None => self.unix_peers,
+        };
+        if !peer_trusted {
+            return client;
+        }
+
+        for hop in forwarded_hops(self.header, headers).into_iter().rev() {
+            match hop {
+                Some(ip) => {
+                    client = Some(ip);
+                    if !self.is_trusted(ip) {
+                        break;
+                    }
+                }
+                None => break,
+            }
+        }
+        client
+    }
+}
+
+/// The forwarded-for chain in `header`, leftmost (furthest) hop first.
+///
+/// Hops that are not IP addresses, such as `unknown` or obfuscated
+/// identifiers, are `None`.
+fn forwarded_hops(header: ForwardedHeader, headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    let (name, parse): (_, fn(&str) -> Option<IpAddr>) = match header {
+        ForwardedHeader::XForwardedFor => ("x-forwarded-for", |hop| parse_node(hop.trim())),
+        ForwardedHeader::Forwarded => (http::header::FORWARDED.as_str(), forwarded_for),
+    };
+
+    headers
+        .get_all(name)
+        .iter()
+        .flat_map(|value| value.to_str().unwrap_or_default().split(','))
+        .map(parse)
+        .collect()
+}
+
+/// Extracts the `for=` node of one `Forwarded` element.
+fn forwarded_for(element: &str) -> Option<IpAddr> {
+    element.split(';').find_map(|pair| {
+        let (name, value) = pair.trim().split_once('=')?;
+        if !name.trim().eq_ignore_ascii_case("for") {
+            return None;
+        }
+        parse_node(value.trim().trim_matches('"'))
+    })
+}
+
+/// Parses a node such as `192.0.2.1`, `192.0.2.1:8080`, `[2001:db8::1]` or
+/// `[2001:db8::1]:8080`.
+fn parse_node(node: &str) -> Option<IpAddr> {
+    if let Ok(ip) = node.parse::<IpAddr>() {
+        return Some(ip.to_canonical());
+    }
+    if let Ok(addr) = node.parse::<SocketAddr>() {
+        return Some(addr.ip().to_canonical());
+    }
+    node.strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .and_then(|ip| ip.parse::<IpAddr>().ok())
+        .map(|ip| ip.to_canonical())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::HeaderValue;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn test_default_ignores_headers() {
+        let proxies = TrustedProxies::default();
+        let headers = headers(&[("x-forwarded-for", "203.0.113.9")]);
+
+        assert_eq!(
+            proxies.resolve(Some(ip("198.51.100.1")), &headers),
+            Some(ip("198.51.100.1"))
+        );
+    }
+
+    #[test]
+    fn test_untrusted_peer_is_client() {
+        let proxies = TrustedProxies::new(["10.0.0.0/8"]);
+        let headers = headers(&[("x-forwarded-for", "203.0.113.9")]);
+
+        assert_eq!(
+            proxies.resolve(Some(ip("198.51.100.1")), &headers),
+            Some(ip("198.51.100.1"))
+        );
+    }
+
+    #[test]
+    fn test_walks_x_forwarded_for_from_the_right() {
+        let proxies = TrustedProxies::new(["10.0.0.0/8"]);
+        // The client spoofed the first entry; 203.0.113.9 is what our edge
+        // proxy actually saw.
+        let headers = headers(&[("x-forwarded-for", "1.2.3.4, 203.0.113.9, 10.0.0.2")]);
+
+        assert_eq!(
+            proxies.resolve(Some(ip("10.0.0.1")), &headers),
+            Some(ip("203.0.113.9"))
+        );
+    }
+
+    #[test]
+    fn test_multiple_x_forwarded_for_headers_are_joined() {
+        let proxies = TrustedProxies::new(["10.0.0.0/8"]);
+        let headers = headers(&[
+            ("x-forwarded-for", "203.0.113.9"),
+            ("x-forwarded-for", "10.0.0.2"),
+        ]);
+
+        assert_eq!(
+            proxies.resolve(Some(ip("10.0.0.1")), &headers),
+            Some(ip("203.0.113.9"))
+        );
+    }
+
+    #[test]
+    fn test_all_trusted_returns_leftmost() {
+        let proxies = TrustedProxies::new(["10.0.0.0/8"]);
+        let headers = headers(&[("x-forwarded-for", "10.1.1.1, 10.0.0.2")]);
+
+        assert_eq!(
+            proxies.resolve(Some(ip("10.0.0.1")), &headers),
+            Some(ip("10.1.1.1"))
+        );
+    }
+
+    #[test]
+    fn test_trusted_peer_without_headers_is_client() {
+        let proxies = TrustedProxies::new(["10.0.0.0/8"]);
+
+        assert_eq!(
+            proxies.resolve(Some(ip("10.0.0.1")), &HeaderMap::new()),
+            Some(ip("10.0.0.1"))
+        );
+    }
+
+    #[test]
+    fn test_invalid_hop_stops_at_last_trusted_proxy() {
+        let proxies = TrustedProxies::new(["10.0.0.0/8"]);
+        let headers = headers(&[("x-forwarded-for", "203.0.113.9, garbage, 10.0.0.2")]);
+
+        assert_eq!(
+            proxies.resolve(Some(ip("10.0.0.1")), &headers),
+            Some(ip("10.0.0.2"))
+        );
+    }
+
+    #[test]
+    fn test_spoofed_forwarded_ignored_when_proxy_appends_x_forwarded_for() {
+        let proxies = TrustedProxies::new(["10.0.0.0/8"]);
+        // The client sent its own Forwarded header, which the proxy passed
+        // through while appending the real address to X-Forwarded-For.
+        let headers = headers(&[
+            ("forwarded", "for=1.2.3.4"),
+            ("x-forwarded-for", "203.0.113.9"),
+        ]);
+
+        assert_eq!(
+            proxies.resolve(Some(ip("10.0.0.1")), &headers),
+            Some(ip("203.0.113.9"))
+        );
+    }
+
+    #[test]
+    fn test_forwarded_header_when_configured() {
+        let proxies =
+            TrustedProxies::new(["10.0.0.0/8", "2001:db8::/32"]).header(ForwardedHeader::Forwarded);
+        let headers = headers(&[
+            ("x-forwarded-for", "1.2.3.4"),
+            (
+                "forwarded",
+                r#"for=192.0.2.60;proto=https, For="[2001:db8:cafe::17]:4711""#,
+            ),
+        ]);
+
+        assert_eq!(
+            proxies.resolve(Some(ip("10.0.0.1")), &headers),
+            Some(ip("192.0.2.60"))
+        );
+    }
+
+    #[test]
+    fn test_forwarded_unknown_node_stops_walk() {
+        let proxies = TrustedProxies::new(["10.0.0.0/8"]).header(ForwardedHeader::Forwarded);
+        let headers = headers(&[("forwarded", "for=unknown, for=10.0.0.2")]);
+
+        assert_eq!(
+            proxies.resolve(Some(ip("10.0.0.1")), &headers),
+            Some(ip("10.0.0.2"))
+        );
+    }
+
+    #[test]
+    fn test_ports_and_mapped_addresses() {
+        let proxies = TrustedProxies::new(["10.0.0.1"]);
+        let headers = headers(&[("x-forwarded-for", "203.0.113.9:51234")]);
+
+        assert_eq!(
+            proxies.resolve(Some(ip("::ffff:10.0.0.1")), &headers),
+            Some(ip("203.0.113.9"))
+        );
+    }
+
+    #[test]
+    fn test_unix_peer_trusted_only_when_opted_in() {
+        let headers = headers(&[("x-forwarded-for", "203.0.113.9")]);
+
+        assert_eq!(TrustedProxies::default().resolve(None, &headers), None);
+        assert_eq!(
+            TrustedProxies::new(["10.0.0.0/8"]).resolve(None, &headers),
+            None
+        );
+        assert_eq!(
+            TrustedProxies::default()
+                .trust_unix_peers()
+                .resolve(None, &headers),
+            Some(ip("203.0.113.9"))
+        );
+    }
+
+    #[test]
+    fn test_private_networks() {
+        let proxies = TrustedProxies::private_networks();
+
+        assert!(proxies.is_trusted(ip("127.0.0.1")));
+        assert!(proxies.is_trusted(ip("172.20.1.1")));
+        assert!(proxies.is_trusted(ip("fd00::1")));
+        assert!(!proxies.is_trusted(ip("8.8.8.8")));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid trusted proxy range")]
+    fn test_invalid_range_panics() {
+        TrustedProxies::new(["10.0.0.0/33"]);
+    }
+}
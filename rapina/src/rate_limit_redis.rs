@@ -0,0 +1,283 @@
+//! Redis-backed rate limit store.
+//!
+//! Requires the `rate-limit-redis` feature flag.
+//!
+//! ```toml
+//! [dependencies]
+//! rapina = { version = "0.11.0", features = ["rate-limit-redis"] }
+//! ```
+//!
+//! All instances of the app that point at the same Redis share their token
+//! buckets, so the configured limit holds across replicas. Each check runs a
+//! Lua script that refills and takes a token in one atomic step, using the
+//! Redis server clock so instances with skewed clocks agree.
+
+use redis::aio::MultiplexedConnection;
+use tokio::sync::OnceCell;
+
+use crate::middleware::{RateLimitDecision, RateLimitFuture, RateLimitStore};
+
+/// Refills the bucket in `KEYS[1]` at `ARGV[1]` tokens per second up to
+/// `ARGV[2]`, takes one token if it can, and returns
+/// `{allowed, remaining, retry_after, reset}`. Buckets expire once they would be
+/// full again, so idle clients don't leave keys behind.
+const TOKEN_BUCKET_SCRIPT: &str = r#"
+local rate = tonumber(ARGV[1])
+local burst = tonumber(ARGV[2])
+local time = redis.call('TIME')
+local now = tonumber(time[1]) + tonumber(time[2]) / 1000000
+
+local bucket = redis.call('HMGET', KEYS[1], 'tokens', 'ts')
+local tokens = tonumber(bucket[1])
+local ts = tonumber(bucket[2])
+if tokens == nil or ts == nil then
+  tokens = burst
+  ts = now
+end
+tokens = math.min(burst, tokens + math.max(0, now - ts) * rate)
+
+local allowed = 0
+local retry_after = 0
+if tokens >= 1 then
+  tokens = tokens - 1
+  allowed = 1
+else
+  retry_after = math.ceil((1 - tokens) / rate)
+end
+local reset = math.ceil((burst - tokens) / rate)
+
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'ts', tostring(now))
+redis.call('PEXPIRE', KEYS[1], math.ceil(burst / rate * 1000) + 1000)
+return {allowed, math.floor(tokens), retry_after, reset}
+"#;
+
+/// Redis rate limit store using a multiplexed async connection.
+///
+/// The connection is opened on the first request. If Redis can't be reached
+/// the request is allowed and a warning is logged, so an outage of the limiter
+/// doesn't take the app down with it.
+pub struct RedisRateLimitStore {
+    client: redis::Client,
+    conn: OnceCell<MultiplexedConnection>,
+    script: redis::Script,
+    prefix: String,
+}
+
+impl RedisRateLimitStore {
+    /// Creates a store for the Redis server at `url`.
+    ///
+    /// Only the URL is checked here; the connection is opened lazily.
+    pub fn new(url: &str) -> Result<Self, redis::RedisError> {
+        Ok(Self {
+            client: redis::Client::open(url)?,
+            conn: OnceCell::new(),
+            script: redis::Script::new(TOKEN_BUCKET_SCRIPT),
+            prefix: "rapina:ratelimit:".to_string(),
+        })
+    }
+
+    /// Sets a custom key prefix (default: "rapina:ratelimit:").
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    async fn try_take(
+        &self,
+        key: &str,
+        rate: f64,
+        burst: u32,
+    ) -> Result<RateLimitDecision, redis::RedisError> {
+        let conn = self
+            .conn
+            .get_or_try_init(|| self.client.get_multiplexed_async_connection())
+            .await?;
+        let (allowed, remaining, retry_after, reset): (i64, u32, u64, u64) = self
+            .script
+            .key(format!("{}{}", self.prefix, key))
+            .arg(rate)
+            .arg(burst)
+            .invoke_async(&mut conn.clone())
+            .await?;
+
+        Ok(RateLimitDecision {
+            allowed: allowed == 1,
+            remaining,
+            retry_after,
+            reset,
+        })
+    }
+}
+
+impl RateLimitStore for RedisRateLimitStore {
+    fn take(&self, key: &str, rate: f64, burst: u32) -> RateLimitFuture<'_, RateLimitDecision> {
+        let key = key.to_string();
+
+        Box::pin(async move {
+            self.try_take(&key, rate, burst).await.unwrap_or_else(|e| {
+                tracing::warn!(error = %e, "rate limit store unavailable, allowing request");
+                RateLimitDecision {
+                    allowed: true,
+                    remaining: burst.saturating_sub(1),
+                    retry_after: 0,
+                    reset: 0,
+                }
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    /// Reads one RESP command, an array of bulk strings.
+    async fn read_command<R: AsyncBufReadExt + Unpin>(reader: &mut R) -> Option<Vec<String>> {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await.ok()? == 0 {
+            return None;
+        }
+        let count: usize = line.trim_end().strip_prefix('*')?.parse().ok()?;
+        let mut args = Vec::with_capacity(count);
+        for _ in 0..count {
+            line.clear();
+            reader.read_line(&mut line).await.ok()?;
+            let len: usize = line.trim_end().strip_prefix('$')?.parse().ok()?;
+            let mut buf = vec![0; len + 2];
+            reader.read_exact(&mut buf).await.ok()?;
+            buf.truncate(len);
+            args.push(String::from_utf8(buf).ok()?);
+        }
+        Some(args)
+    }
+
+    /// A stand-in for redis-server that answers the token bucket script with
+    /// a fixed-window count per key, enough to exercise the client side.
+    async fn spawn_redis_stand_in() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let taken: Arc<Mutex<HashMap<String, u32>>> = Arc::default();
+
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let taken = Arc::clone(&taken);
+                tokio::spawn(async move {
+                    let (read, mut write) = stream.into_split();
+                    let mut reader = BufReader::new(read);
+                    while let Some(args) = read_command(&mut reader).await {
+                        let reply = if args[0].eq_ignore_ascii_case("EVALSHA") {
+                            // EVALSHA sha numkeys key rate burst
+                            let burst: u32 = args[5].parse().unwrap();
+                            let mut taken = taken.lock().unwrap();
+                            let count = taken.entry(args[3].clone()).or_default();
+                            if *count < burst {
+                                *count += 1;
+                                format!("*4\r\n:1\r\n:{}\r\n:0\r\n:{}\r\n", burst - *count, *count)
+                            } else {
+                                "*4\r\n:0\r\n:0\r\n:1\r\n:1\r\n".to_string()
+                            }
+                        } else {
+                            "+OK\r\n".to_string()
+                        };
+                        write.write_all(reply.as_bytes()).await.unwrap();
+                    }
+                });
+            }
+        });
+
+        format!("redis://{}", addr)
+    }
+
+    #[test]
+    fn test_invalid_url_is_rejected() {
+        assert!(RedisRateLimitStore::new("not a url").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_take_against_stand_in() {
+        let url = spawn_redis_stand_in().await;
+        let store = RedisRateLimitStore::new(&url).unwrap();
+
+        let first = store.take("client", 1.0, 2).await;
+        assert_eq!(
+            first,
+            RateLimitDecision {
+                allowed: true,
+                remaining: 1,
+                retry_after: 0,
+                reset: 1,
+            }
+        );
+        assert!(store.take("client", 1.0, 2).await.allowed);
+
+        let limited = store.take("client", 1.0, 2).await;
+        assert!(!limited.allowed);
+        assert_eq!(limited.retry_after, 1);
+
+        assert!(store.take("other", 1.0, 2).await.allowed);
+    }
+
+    #[tokio::test]
+    async fn test_take_prefixes_keys() {
+        let url = spawn_redis_stand_in().await;
+        let a = RedisRateLimitStore::new(&url).unwrap().with_prefix("a:");
+        let b = RedisRateLimitStore::new(&url).unwrap().with_prefix("b:");
+
+        assert!(a.take("client", 1.0, 1).await.allowed);
+        assert!(b.take("client", 1.0, 1).await.allowed);
+        assert!(!a.take("client", 1.0, 1).await.allowed);
+
+        let a_again = RedisRateLimitStore::new(&url).unwrap().with_prefix("a:");
+        assert!(!a_again.take("client", 1.0, 1).await.allowed);
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_redis_fails_open() {
+        // Nothing listens on port 1.
+        let store = RedisRateLimitStore::new("redis://127.0.0.1:1").unwrap();
+
+        let decision = store.take("client", 1.0, 5).await;
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 4);
+    }
+
+    // Integration tests require a running Redis instance.
+    // Run with: cargo test --features rate-limit-redis -- --ignored
+    #[ignore]
+    #[tokio::test]
+    async fn test_redis_store_enforces_burst() {
+        let store = RedisRateLimitStore::new("redis://127.0.0.1:6379")
+            .unwrap()
+            .with_prefix(&format!("rapina:test:{}:", std::process::id()));
+
+        for remaining in (0..3).rev() {
+            let decision = store.take("burst", 0.01, 3).await;
+            assert!(decision.allowed);
+            assert_eq!(decision.remaining, remaining);
+        }
+
+        let limited = store.take("burst", 0.01, 3).await;
+        assert!(!limited.allowed);
+        assert_eq!(limited.retry_after, 100);
+    }
+
+    #[ignore]
+    #[tokio::test]
+    async fn test_redis_store_is_shared_between_instances() {
+        let prefix = format!("rapina:test:{}:", std::process::id());
+        let a = RedisRateLimitStore::new("redis://127.0.0.1:6379")
+            .unwrap()
+            .with_prefix(&prefix);
+        let b = RedisRateLimitStore::new("redis://127.0.0.1:6379")
+            .unwrap()
+            .with_prefix(&prefix);
+
+        assert!(a.take("shared", 0.01, 1).await.allowed);
+        assert!(!b.take("shared", 0.01, 1).await.allowed);
+    }
+}
@@ -1,11 +1,18 @@
 //! Response types and conversion traits.
 //!
 //! This module defines the [`IntoResponse`] trait which allows various types
-//! to be converted into HTTP responses.
+//! to be converted into HTTP responses, and the [`BoxBody`] they carry.
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
 
 use bytes::Bytes;
+use futures_core::Stream;
 use http::{Response, StatusCode, header::CONTENT_TYPE};
-use http_body_util::Full;
+use http_body_util::combinators::UnsyncBoxBody;
+use http_body_util::{BodyExt, Empty, Full};
+use hyper::body::{Body, Frame, SizeHint};
+use tokio::io::{AsyncRead, ReadBuf};
 
 pub(crate) const APPLICATION_JSON: &str = "application/json";
 pub(crate) const APPLICATION_PROBLEM_JSON: &str = "application/problem+json";
@@ -13,9 +20,179 @@ pub(crate) const FORM_CONTENT_TYPE: &str = "application/x-www-form-urlencoded";
 #[cfg(feature = "metrics")]
 pub(crate) const PROMETHEUS_TEXT_FORMAT: &str = "text/plain; version=0.0.4; charset=utf-8";
 const TEXT_PLAIN_UTF8: &str = "text/plain; charset=utf-8";
+const APPLICATION_OCTET_STREAM: &str = "application/octet-stream";
+
+/// How much [`ReaderBody`] reads from its source per chunk.
+const READ_CHUNK_SIZE: usize = 8 * 1024;
+
+/// The error type of a [`BoxBody`].
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
 
 /// The body type used for HTTP responses.
-pub type BoxBody = Full<Bytes>;
+///
+/// Either a buffered chunk of bytes or a stream of chunks produced while the
+/// response is being sent. Build one from bytes or strings with `From`, from a
+/// stream with [`from_stream`](Self::from_stream), or from any other
+/// [`Body`] with [`new`](Self::new).
+///
+/// Middleware that needs the whole body, such as compression or caching, can
+/// buffer it with [`BodyExt::collect`].
+pub struct BoxBody(UnsyncBoxBody<Bytes, BoxError>);
+
+impl BoxBody {
+    /// Wraps any body whose chunks are [`Bytes`].
+    pub fn new<B>(body: B) -> Self
+    where
+        B: Body<Data = Bytes> + Send + 'static,
+        B::Error: Into<BoxError>,
+    {
+        Self(body.map_err(Into::into).boxed_unsync())
+    }
+
+    /// An empty body.
+    pub fn empty() -> Self {
+        Self::new(Empty::<Bytes>::new())
+    }
+
+    /// A body that sends each chunk of `stream` as it is produced.
+    ///
+    /// An error from the stream aborts the response.
+    pub fn from_stream<S, E>(stream: S) -> Self
+    where
+        S: Stream<Item = Result<Bytes, E>> + Send + 'static,
+        E: Into<BoxError>,
+    {
+        Self::new(StreamAdapter(Box::pin(stream)))
+    }
+
+    /// A body that reads `reader` to its end in chunks.
+    pub fn from_reader<R>(reader: R) -> Self
+    where
+        R: AsyncRead + Send + 'static,
+    {
+        Self::new(ReaderAdapter {
+            reader: Box::pin(reader),
+            buf: vec![0; READ_CHUNK_SIZE].into_boxed_slice(),
+        })
+    }
+}
+
+impl Default for BoxBody {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl std::fmt::Debug for BoxBody {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BoxBody").finish_non_exhaustive()
+    }
+}
+
+impl Body for BoxBody {
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, BoxError>>> {
+        Pin::new(&mut self.0).poll_frame(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.0.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.0.size_hint()
+    }
+}
+
+impl From<Bytes> for BoxBody {
+    fn from(bytes: Bytes) -> Self {
+        Self::new(Full::new(bytes))
+    }
+}
+
+impl From<Full<Bytes>> for BoxBody {
+    fn from(body: Full<Bytes>) -> Self {
+        Self::new(body)
+    }
+}
+
+impl From<String> for BoxBody {
+    fn from(s: String) -> Self {
+        Bytes::from(s).into()
+    }
+}
+
+impl From<&'static str> for BoxBody {
+    fn from(s: &'static str) -> Self {
+        Bytes::from_static(s.as_bytes()).into()
+    }
+}
+
+impl From<Vec<u8>> for BoxBody {
+    fn from(v: Vec<u8>) -> Self {
+        Bytes::from(v).into()
+    }
+}
+
+impl From<&'static [u8]> for BoxBody {
+    fn from(b: &'static [u8]) -> Self {
+        Bytes::from_static(b).into()
+    }
+}
+
+/// Turns a stream of byte chunks into body frames.
+struct StreamAdapter<S>(Pin<Box<S>>);
+
+impl<S, E> Body for StreamAdapter<S>
+where
+    S: Stream<Item = Result<Bytes, E>>,
+    E: Into<BoxError>,
+{
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, BoxError>>> {
+        self.0
+            .as_mut()
+            .poll_next(cx)
+            .map(|item| item.map(|chunk| chunk.map(Frame::data).map_err(Into::into)))
+    }
+}
+
+/// Reads an `AsyncRead` source into body frames.
+struct ReaderAdapter<R> {
+    reader: Pin<Box<R>>,
+    buf: Box<[u8]>,
+}
+
+impl<R: AsyncRead> Body for ReaderAdapter<R> {
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, BoxError>>> {
+        let this = &mut *self;
+        let mut buf = ReadBuf::new(&mut this.buf);
+        match this.reader.as_mut().poll_read(cx, &mut buf) {
+            Poll::Ready(Ok(())) if buf.filled().is_empty() => Poll::Ready(None),
+            Poll::Ready(Ok(())) => {
+                Poll::Ready(Some(Ok(Frame::data(Bytes::copy_from_slice(buf.filled())))))
+            }
+            Poll::Ready(Err(e)) => Poll::Ready(Some(Err(e.into()))),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
 
 /// Trait for types that can be converted into an HTTP response.
 ///
@@ -50,12 +227,18 @@ impl IntoResponse for Response<BoxBody> {
     }
 }
 
+impl IntoResponse for BoxBody {
+    fn into_response(self) -> Response<BoxBody> {
+        Response::new(self)
+    }
+}
+
 impl IntoResponse for &str {
     fn into_response(self) -> Response<BoxBody> {
         Response::builder()
             .status(StatusCode::OK)
             .header(CONTENT_TYPE, TEXT_PLAIN_UTF8)
-            .body(Full::new(Bytes::from(self.to_owned())))
+            .body(BoxBody::from(self.to_owned()))
             .unwrap()
     }
 }
@@ -65,7 +248,7 @@ impl IntoResponse for String {
         Response::builder()
             .status(StatusCode::OK)
             .header(CONTENT_TYPE, TEXT_PLAIN_UTF8)
-            .body(Full::new(Bytes::from(self)))
+            .body(BoxBody::from(self))
             .unwrap()
     }
 }
@@ -91,7 +274,7 @@ impl IntoResponse for StaticStr {
         Response::builder()
             .status(StatusCode::OK)
             .header(CONTENT_TYPE, TEXT_PLAIN_UTF8)
-            .body(Full::new(Bytes::from_static(self.0.as_bytes())))
+            .body(BoxBody::from(self.0))
             .unwrap()
     }
 }
@@ -100,7 +283,7 @@ impl IntoResponse for StatusCode {
     fn into_response(self) -> Response<BoxBody> {
         Response::builder()
             .status(self)
-            .body(Full::new(Bytes::new()))
+            .body(BoxBody::empty())
             .unwrap()
     }
 }
@@ -110,7 +293,65 @@ impl IntoResponse for (StatusCode, String) {
         Response::builder()
             .status(self.0)
             .header(CONTENT_TYPE, TEXT_PLAIN_UTF8)
-            .body(Full::new(Bytes::from(self.1)))
+            .body(BoxBody::from(self.1))
+            .unwrap()
+    }
+}
+
+/// Streams a response from a [`Stream`] of byte chunks.
+///
+/// Each chunk is sent as soon as the stream yields it, so large or slow
+/// responses don't have to fit in memory. The content type defaults to
+/// `application/octet-stream`.
+///
+/// # Example
+///
+/// ```ignore
+/// #[get("/export.csv")]
+/// async fn export() -> StreamBody<impl Stream<Item = Result<Bytes, std::io::Error>>> {
+///     StreamBody(rows_as_csv())
+/// }
+/// ```
+pub struct StreamBody<S>(pub S);
+
+impl<S, E> IntoResponse for StreamBody<S>
+where
+    S: Stream<Item = Result<Bytes, E>> + Send + 'static,
+    E: Into<BoxError>,
+{
+    fn into_response(self) -> Response<BoxBody> {
+        Response::builder()
+            .status(StatusCode::OK)
+            .header(CONTENT_TYPE, APPLICATION_OCTET_STREAM)
+            .body(BoxBody::from_stream(self.0))
+            .unwrap()
+    }
+}
+
+/// Streams a response from a [`tokio::io::AsyncRead`] source, such as a file
+/// or a child process's output.
+///
+/// The content type defaults to `application/octet-stream`.
+///
+/// # Example
+///
+/// ```ignore
+/// #[get("/report")]
+/// async fn report() -> Result<ReaderBody<tokio::fs::File>> {
+///     Ok(ReaderBody(tokio::fs::File::open("report.pdf").await?))
+/// }
+/// ```
+pub struct ReaderBody<R>(pub R);
+
+impl<R> IntoResponse for ReaderBody<R>
+where
+    R: AsyncRead + Send + 'static,
+{
+    fn into_response(self) -> Response<BoxBody> {
+        Response::builder()
+            .status(StatusCode::OK)
+            .header(CONTENT_TYPE, APPLICATION_OCTET_STREAM)
+            .body(BoxBody::from_reader(self.0))
             .unwrap()
     }
 }
@@ -127,7 +368,6 @@ impl<T: IntoResponse, E: IntoResponse> IntoResponse for std::result::Result<T, E
 #[cfg(test)]
 mod tests {
     use super::*;
-    use http_body_util::BodyExt;
 
     #[tokio::test]
     async fn test_str_into_response() {
@@ -205,7 +445,7 @@ mod tests {
     fn test_response_into_response_identity() {
         let original = Response::builder()
             .status(StatusCode::ACCEPTED)
-            .body(Full::new(Bytes::from("test")))
+            .body(BoxBody::from("test"))
             .unwrap();
 
         let response = original.into_response();
@@ -258,4 +498,71 @@ mod tests {
         // static data without allocating. Verify the content is correct.
         assert_eq!(&body[..], b"static content");
     }
+
+    #[tokio::test]
+    async fn test_box_body_size_hint_is_exact_for_buffered_bodies() {
+        assert_eq!(BoxBody::from("hello").size_hint().exact(), Some(5));
+        assert_eq!(BoxBody::empty().size_hint().exact(), Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_stream_body_into_response() {
+        let chunks: Vec<Result<Bytes, std::io::Error>> =
+            vec![Ok(Bytes::from("a,b\n")), Ok(Bytes::from("1,2\n"))];
+        let response = StreamBody(iter_stream(chunks)).into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get("content-type").unwrap(),
+            "application/octet-stream"
+        );
+
+        let mut body = response.into_body();
+        let first = body.frame().await.unwrap().unwrap().into_data().unwrap();
+        assert_eq!(&first[..], b"a,b\n");
+        let second = body.frame().await.unwrap().unwrap().into_data().unwrap();
+        assert_eq!(&second[..], b"1,2\n");
+        assert!(body.frame().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stream_body_error_aborts_body() {
+        let chunks: Vec<Result<Bytes, std::io::Error>> = vec![
+            Ok(Bytes::from("partial")),
+            Err(std::io::Error::other("upstream closed")),
+        ];
+        let response = StreamBody(iter_stream(chunks)).into_response();
+
+        let err = response.into_body().collect().await.unwrap_err();
+        assert_eq!(err.to_string(), "upstream closed");
+    }
+
+    #[tokio::test]
+    async fn test_reader_body_into_response() {
+        let data = vec![7u8; READ_CHUNK_SIZE * 2 + 10];
+        let response = ReaderBody(std::io::Cursor::new(data.clone())).into_response();
+        assert_eq!(
+            response.headers().get("content-type").unwrap(),
+            "application/octet-stream"
+        );
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(&body[..], &data[..]);
+    }
+
+    /// A minimal stream over a vector, to avoid pulling in a stream utility crate.
+    fn iter_stream<T: Unpin + Send + 'static>(
+        items: Vec<T>,
+    ) -> impl Stream<Item = T> + Send + 'static {
+        struct Iter<T>(std::vec::IntoIter<T>);
+
+        impl<T: Unpin> Stream for Iter<T> {
+            type Item = T;
+
+            fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<T>> {
+                Poll::Ready(self.0.next())
+            }
+        }
+
+        Iter(items.into_iter())
+    }
 }
@@ -10,19 +10,22 @@ use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
 
-use http::{Method, Request, Response, StatusCode};
-use hyper::body::Incoming;
+use http::header::{ALLOW, CONTENT_LENGTH};
+use http::{HeaderValue, Method, Request, Response, StatusCode};
+use hyper::body::{Body, Incoming};
 
-use crate::error::ErrorVariant;
+use crate::context::RequestContext;
+use crate::error::{Error, ErrorVariant};
 use crate::extract::PathParams;
 use crate::handler::Handler;
 use crate::introspection::RouteInfo;
+use crate::middleware::{Endpoint, Middleware, Next, RouteTarget};
 use crate::response::{BoxBody, IntoResponse};
 use crate::state::AppState;
 
 type BoxFuture = Pin<Box<dyn Future<Output = Response<BoxBody>> + Send>>;
-type HandlerFn =
-    Box<dyn Fn(Request<Incoming>, PathParams, Arc<AppState>) -> BoxFuture + Send + Sync>;
+pub(crate) type HandlerFn =
+    Arc<dyn Fn(Request<Incoming>, PathParams, Arc<AppState>) -> BoxFuture + Send + Sync>;
 
 /// Configuration for a route including metadata for introspection.
 pub struct RouteConfig {
@@ -30,6 +33,8 @@ pub struct RouteConfig {
     pub handler_name: String,
     /// JSON schema for the response body.
     pub response_schema: Option<serde_json::Value>,
+    /// Content type for the response body, when it isn't JSON.
+    pub response_content_type: Option<&'static str>,
     /// JSON schema for the request body.
     pub request_schema: Option<serde_json::Value>,
     /// Content type for the request body.
@@ -38,6 +43,10 @@ pub struct RouteConfig {
     pub request_body_required: Option<bool>,
     /// Error responses this handler may return.
     pub error_responses: Vec<ErrorVariant>,
+    /// Roles the authenticated user must all hold, or the route answers 403.
+    pub required_roles: Vec<String>,
+    /// Scopes the user's token must all grant, or the route answers 403.
+    pub required_scopes: Vec<String>,
 }
 
 impl Default for RouteConfig {
@@ -45,10 +54,30 @@ impl Default for RouteConfig {
         Self {
             handler_name: "handler".to_string(),
             response_schema: None,
+            response_content_type: None,
             request_schema: None,
             request_content_type: None,
             request_body_required: None,
             error_responses: Vec::new(),
+            required_roles: Vec::new(),
+            required_scopes: Vec::new(),
+        }
+    }
+}
+
+impl RouteConfig {
+    /// The configuration a [`Handler`] describes for itself.
+    fn for_handler<H: Handler>() -> Self {
+        Self {
+            handler_name: H::NAME.to_string(),
+            response_schema: H::response_schema(),
+            response_content_type: H::response_content_type(),
+            request_schema: H::request_schema(),
+            request_content_type: H::request_content_type(),
+            request_body_required: H::request_body_required(),
+            error_responses: H::error_responses(),
+            required_roles: H::required_roles().iter().map(|r| r.to_string()).collect(),
+            required_scopes: H::required_scopes().iter().map(|s| s.to_string()).collect(),
         }
     }
 }
@@ -57,11 +86,15 @@ pub(crate) struct Route {
     pub(crate) pattern: String,
     pub(crate) handler_name: String,
     pub(crate) response_schema: Option<serde_json::Value>,
+    pub(crate) response_content_type: Option<&'static str>,
     pub(crate) request_schema: Option<serde_json::Value>,
     pub(crate) request_content_type: Option<&'static str>,
     pub(crate) request_body_required: Option<bool>,
     pub(crate) error_responses: Vec<ErrorVariant>,
+    pub(crate) required_roles: Vec<String>,
+    pub(crate) required_scopes: Vec<String>,
     handler: HandlerFn,
+    middlewares: Vec<Arc<dyn Middleware>>,
 }
 
 /// The HTTP router for matching requests to handlers.
@@ -72,6 +105,10 @@ pub(crate) struct Route {
 /// param children at every node, so `/users/current` always wins
 /// over `/users/:id` regardless of registration order.
 ///
+/// A trailing `*name` segment captures the rest of the path, slashes
+/// included, so `/files/*path` matches `/files/a/b.txt` with
+/// `path = "a/b.txt"`. It only matches when nothing more specific does.
+///
 /// # Examples
 ///
 /// ```
@@ -93,6 +130,8 @@ pub(crate) struct Route {
 /// ```
 pub struct Router {
     pub(crate) routes: Vec<(Method, Route)>,
+    pub(crate) fallback: Option<HandlerFn>,
+    middlewares: Vec<Arc<dyn Middleware>>,
     static_map: Option<static_map::StaticMap>,
     trie: Option<trie::TrieRouter>,
 }
@@ -102,6 +141,8 @@ impl Router {
     pub fn new() -> Self {
         Self {
             routes: Vec::new(),
+            fallback: None,
+            middlewares: Vec::new(),
             static_map: None,
             trie: None,
         }
@@ -122,10 +163,18 @@ impl Router {
         Fut: Future<Output = Out> + Send + 'static,
         Out: IntoResponse + 'static,
     {
-        let handler = Box::new(
+        // Authorization runs before the handler so a forbidden request
+        // never reaches its extractors.
+        let roles = config.required_roles.clone();
+        let scopes = config.required_scopes.clone();
+        let handler = Arc::new(
             move |req: Request<Incoming>, params: PathParams, state: Arc<AppState>| {
+                let authorized = crate::auth::authorize(req.extensions(), &roles, &scopes);
                 let handler = handler.clone();
                 Box::pin(async move {
+                    if let Err(e) = authorized {
+                        return e.into_response();
+                    }
                     let output = handler(req, params, state).await;
                     output.into_response()
                 }) as BoxFuture
@@ -136,11 +185,15 @@ impl Router {
             pattern: pattern.to_string(),
             handler_name: config.handler_name,
             response_schema: config.response_schema,
+            response_content_type: config.response_content_type,
             request_schema: config.request_schema,
             request_content_type: config.request_content_type,
             request_body_required: config.request_body_required,
             error_responses: config.error_responses,
+            required_roles: config.required_roles,
+            required_scopes: config.required_scopes,
             handler,
+            middlewares: Vec::new(),
         };
 
         self.routes.push((method, route));
@@ -201,14 +254,7 @@ impl Router {
         self.route_named(
             Method::GET,
             pattern,
-            RouteConfig {
-                handler_name: H::NAME.to_string(),
-                response_schema: H::response_schema(),
-                request_schema: H::request_schema(),
-                request_content_type: H::request_content_type(),
-                request_body_required: H::request_body_required(),
-                error_responses: H::error_responses(),
-            },
+            RouteConfig::for_handler::<H>(),
             move |req, params, state| {
                 let h = handler.clone();
                 async move { h.call(req, params, state).await }
@@ -221,14 +267,7 @@ impl Router {
         self.route_named(
             Method::POST,
             pattern,
-            RouteConfig {
-                handler_name: H::NAME.to_string(),
-                response_schema: H::response_schema(),
-                request_schema: H::request_schema(),
-                request_content_type: H::request_content_type(),
-                request_body_required: H::request_body_required(),
-                error_responses: H::error_responses(),
-            },
+            RouteConfig::for_handler::<H>(),
             move |req, params, state| {
                 let h = handler.clone();
                 async move { h.call(req, params, state).await }
@@ -259,14 +298,7 @@ impl Router {
         self.route_named(
             Method::PUT,
             pattern,
-            RouteConfig {
-                handler_name: H::NAME.to_string(),
-                response_schema: H::response_schema(),
-                request_schema: H::request_schema(),
-                request_content_type: H::request_content_type(),
-                request_body_required: H::request_body_required(),
-                error_responses: H::error_responses(),
-            },
+            RouteConfig::for_handler::<H>(),
             move |req, params, state| {
                 let h = handler.clone();
                 async move { h.call(req, params, state).await }
@@ -297,14 +329,7 @@ impl Router {
         self.route_named(
             Method::PATCH,
             pattern,
-            RouteConfig {
-                handler_name: H::NAME.to_string(),
-                response_schema: H::response_schema(),
-                request_schema: H::request_schema(),
-                request_content_type: H::request_content_type(),
-                request_body_required: H::request_body_required(),
-                error_responses: H::error_responses(),
-            },
+            RouteConfig::for_handler::<H>(),
             move |req, params, state| {
                 let h = handler.clone();
                 async move { h.call(req, params, state).await }
@@ -335,14 +360,7 @@ impl Router {
         self.route_named(
             Method::DELETE,
             pattern,
-            RouteConfig {
-                handler_name: H::NAME.to_string(),
-                response_schema: H::response_schema(),
-                request_schema: H::request_schema(),
-                request_content_type: H::request_content_type(),
-                request_body_required: H::request_body_required(),
-                error_responses: H::error_responses(),
-            },
+            RouteConfig::for_handler::<H>(),
             move |req, params, state| {
                 let h = handler.clone();
                 async move { h.call(req, params, state).await }
@@ -350,6 +368,53 @@ impl Router {
         )
     }
 
+    /// Sets the handler that runs when no route matches the request path.
+    ///
+    /// Without a fallback, unmatched requests get an [`Error::not_found`]
+    /// response in the configured error format. Paths that exist under
+    /// another method still answer 405.
+    pub fn fallback<H: Handler>(mut self, handler: H) -> Self {
+        self.fallback = Some(Arc::new(
+            move |req: Request<Incoming>, params: PathParams, state: Arc<AppState>| {
+                handler.call(req, params, state)
+            },
+        ));
+        self
+    }
+
+    /// Adds middleware that runs only for requests matching this router's routes.
+    ///
+    /// Unlike [`Rapina::middleware`](crate::app::Rapina::middleware), which
+    /// runs for every request before routing, router middleware runs after a
+    /// route has matched and wraps just that route's handler. It applies to
+    /// every route of the router, including ones added later, and follows the
+    /// routes into [`group`](Self::group). Middleware runs in the order it was
+    /// added, outer groups before inner ones.
+    ///
+    /// To scope middleware to a single route, give the route its own group,
+    /// or use `#[middleware(...)]` on the handler.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rapina::prelude::*;
+    /// use rapina::middleware::TimeoutMiddleware;
+    /// use std::time::Duration;
+    ///
+    /// #[get("/")]
+    /// async fn monthly() -> &'static str { "report" }
+    ///
+    /// let reports = Router::new()
+    ///     .get("/monthly", monthly)
+    ///     .middleware(TimeoutMiddleware::new(Duration::from_secs(120)));
+    ///
+    /// let router = Router::new().group("/reports", reports);
+    /// ```
+    pub fn middleware<M: Middleware>(mut self, middleware: M) -> Self {
+        self.middlewares.push(Arc::new(middleware));
+        self
+    }
+
     /// Returns metadata about all registered routes.
     ///
     /// This is useful for introspection, documentation generation,
@@ -374,7 +439,7 @@ impl Router {
         self.routes
             .iter()
             .map(|(method, route)| {
-                RouteInfo::new(
+                let info = RouteInfo::new(
                     method.as_str(),
                     &route.pattern,
                     &route.handler_name,
@@ -384,12 +449,21 @@ impl Router {
                     route.request_body_required,
                     route.error_responses.clone(),
                 )
+                .with_required_roles(route.required_roles.clone())
+                .with_required_scopes(route.required_scopes.clone());
+                match route.response_content_type {
+                    Some(content_type) => info.with_response_content_type(content_type),
+                    None => info,
+                }
             })
             .collect()
     }
 
     /// Adds all routes from another router with a path prefix to compose a group of endpoints.
     ///
+    /// The group's [`fallback`](Self::fallback), if any, is ignored; only the
+    /// top-level router's fallback is used.
+    ///
     /// # Examples
     ///
     /// ```
@@ -411,6 +485,9 @@ impl Router {
         for (method, mut route) in router.routes {
             let joined_route_path = Self::join_group_route_pattern(prefix_pattern, &route.pattern);
             route.pattern = joined_route_path;
+            route
+                .middlewares
+                .splice(0..0, router.middlewares.iter().cloned());
             self.routes.push((method, route));
         }
 
@@ -438,6 +515,40 @@ impl Router {
         None
     }
 
+    /// Returns the methods that `path` answers to, in a stable order.
+    ///
+    /// An empty result means no route matches the path at all. A non-empty
+    /// result for a request that failed to [`resolve`](Self::resolve) means
+    /// the path exists but the method does not, i.e. a 405 rather than a 404.
+    ///
+    /// `HEAD` is included whenever `GET` is, and `OPTIONS` whenever the
+    /// path exists, since [`handle`](Self::handle) serves both implicitly.
+    #[doc(hidden)]
+    pub fn allowed_methods(&self, path: &str) -> Vec<Method> {
+        let mut methods: Vec<Method> = Vec::new();
+        if let Some(ref static_map) = self.static_map {
+            methods.extend(static_map.methods_for(path).cloned());
+        }
+        if let Some(ref trie) = self.trie {
+            for method in trie.methods_for(path) {
+                if !methods.contains(&method) {
+                    methods.push(method);
+                }
+            }
+        }
+        if methods.contains(&Method::GET) && !methods.contains(&Method::HEAD) {
+            methods.push(Method::HEAD);
+        }
+        if !methods.is_empty() && !methods.contains(&Method::OPTIONS) {
+            methods.push(Method::OPTIONS);
+        }
+        methods.sort_by(|a, b| {
+            let key = |m: &Method| (trie::method_index(m).unwrap_or(usize::MAX), m.to_string());
+            key(a).cmp(&key(b))
+        });
+        methods
+    }
+
     /// Resolves a route using the old linear scan (pre-trie) algorithm.
     ///
     /// Iterates over all registered routes checking each pattern against the
@@ -462,7 +573,7 @@ impl Router {
         if let Some(ref static_map) = self.static_map {
             if let Some(idx) = static_map.lookup(req.method(), req.uri().path()) {
                 let route = &self.routes[idx].1;
-                return (route.handler)(req, PathParams::new(), state.clone()).await;
+                return call_route(route, req, PathParams::new(), state).await;
             }
         }
 
@@ -471,11 +582,35 @@ impl Router {
             let mut params = PathParams::new();
             if let Some(idx) = trie.lookup(req.method(), req.uri().path(), &mut params) {
                 let route = &self.routes[idx].1;
-                return (route.handler)(req, params, state.clone()).await;
+                return call_route(route, req, params, state).await;
+            }
+        }
+
+        // Layer 3: HEAD without an explicit route runs the GET handler and
+        // drops the body, keeping the headers a GET would have sent.
+        if req.method() == Method::HEAD {
+            if let Some((idx, params)) = self.resolve(&Method::GET, req.uri().path()) {
+                let route = &self.routes[idx].1;
+                let response = call_route(route, req, params, state).await;
+                return strip_body(response);
             }
         }
 
-        StatusCode::NOT_FOUND.into_response()
+        // Layer 4: the path exists under another method — answer OPTIONS
+        // directly, everything else gets 405. Both carry an `Allow` header.
+        let allowed = self.allowed_methods(req.uri().path());
+        if !allowed.is_empty() {
+            if req.method() == Method::OPTIONS {
+                return options_response(&allowed);
+            }
+            return method_not_allowed(&req, &allowed);
+        }
+
+        // Layer 5: nothing matches — hand over to the fallback.
+        match self.fallback {
+            Some(ref fallback) => fallback(req, PathParams::new(), state.clone()).await,
+            None => not_found(&req),
+        }
     }
 
     /// Sorts routes and builds lookup structures for benchmarking.
@@ -504,12 +639,19 @@ impl Router {
     /// Builds the static route map and radix trie for fast route resolution.
     ///
     /// Called by `prepare()` after `sort_routes()`. After this, the router
-    /// is frozen — no more routes can be added. Idempotent: calling this
-    /// multiple times is safe and only builds the structures once.
+    /// is frozen — no more routes can be added. Router-level middleware is
+    /// folded into each route here. Idempotent: calling this multiple times
+    /// is safe and only builds the structures once.
     pub(crate) fn freeze(&mut self) {
         if self.static_map.is_some() {
             return;
         }
+        for (_, route) in &mut self.routes {
+            route
+                .middlewares
+                .splice(0..0, self.middlewares.iter().cloned());
+        }
+        self.middlewares.clear();
         self.static_map = Some(static_map::StaticMap::build(&self.routes));
         self.trie = Some(trie::TrieRouter::build(&self.routes));
     }
@@ -528,20 +670,119 @@ impl Router {
     }
 }
 
-/// Returns `true` if the pattern contains any `:param` segments.
+/// Runs a matched route's handler, through its own middleware if it has any.
+async fn call_route(
+    route: &Route,
+    mut req: Request<Incoming>,
+    params: PathParams,
+    state: &Arc<AppState>,
+) -> Response<BoxBody> {
+    if route.middlewares.is_empty() {
+        return (route.handler)(req, params, state.clone()).await;
+    }
+
+    let ctx = req
+        .extensions()
+        .get::<RequestContext>()
+        .cloned()
+        .unwrap_or_default();
+    req.extensions_mut().insert(RouteTarget {
+        handler: route.handler.clone(),
+        params,
+    });
+    // Boxed because the chain's `Next::run` is what called `Router::handle`.
+    let next = Next::with_endpoint(&route.middlewares, Endpoint::Route, state.clone(), &ctx);
+    Box::pin(next.run(req)).await
+}
+
+/// Formats `allowed` as an `Allow` header value.
+fn allow_header(allowed: &[Method]) -> Option<HeaderValue> {
+    let allow = allowed
+        .iter()
+        .map(Method::as_str)
+        .collect::<Vec<_>>()
+        .join(", ");
+    HeaderValue::from_str(&allow).ok()
+}
+
+/// Builds the 204 answer to an `OPTIONS` request with no explicit route.
+fn options_response(allowed: &[Method]) -> Response<BoxBody> {
+    let mut response = StatusCode::NO_CONTENT.into_response();
+    if let Some(value) = allow_header(allowed) {
+        response.headers_mut().insert(ALLOW, value);
+    }
+    response
+}
+
+/// Drops the body of a GET response so it can answer a HEAD request.
+///
+/// `Content-Length` is filled in from the original body when the handler
+/// did not set it, so clients see the size the GET would have returned.
+fn strip_body(response: Response<BoxBody>) -> Response<BoxBody> {
+    let (mut parts, body) = response.into_parts();
+    if !parts.headers.contains_key(CONTENT_LENGTH) {
+        if let Some(len) = body.size_hint().exact() {
+            parts.headers.insert(CONTENT_LENGTH, HeaderValue::from(len));
+        }
+    }
+    Response::from_parts(parts, BoxBody::empty())
+}
+
+/// The default fallback: a 404 in the configured error format.
+fn not_found(req: &Request<Incoming>) -> Response<BoxBody> {
+    let error = Error::not_found(format!("no route matches {}", req.uri().path()));
+    with_request_trace_id(error, req).into_response()
+}
+
+/// Tags `error` with the request's trace ID so the body matches the
+/// `x-trace-id` header set by [`TraceIdMiddleware`](crate::middleware::TraceIdMiddleware).
+fn with_request_trace_id(error: Error, req: &Request<Incoming>) -> Error {
+    match req.extensions().get::<RequestContext>() {
+        Some(ctx) => error.with_trace_id(ctx.trace_id()),
+        None => error,
+    }
+}
+
+/// Builds a 405 response with an `Allow` header listing `allowed`.
+fn method_not_allowed(req: &Request<Incoming>, allowed: &[Method]) -> Response<BoxBody> {
+    let error = Error::method_not_allowed(format!(
+        "method {} not allowed for {}",
+        req.method(),
+        req.uri().path()
+    ));
+
+    let mut response = with_request_trace_id(error, req).into_response();
+    if let Some(value) = allow_header(allowed) {
+        response.headers_mut().insert(ALLOW, value);
+    }
+    response
+}
+
+/// Returns `true` if the pattern contains any `:param` or `*catch_all` segments.
 pub(super) fn is_dynamic(pattern: &str) -> bool {
-    pattern.split('/').any(|seg| seg.starts_with(':'))
+    pattern
+        .split('/')
+        .any(|seg| seg.starts_with(':') || seg.starts_with('*'))
 }
 
 /// Returns a specificity key for a route pattern.
 ///
-/// Each segment maps to `0` (static) or `1` (`:param`). When sorted
-/// ascending, static segments win over parameterized ones at every position,
-/// so `/users/current` always comes before `/users/:id`.
+/// Each segment maps to `0` (static), `1` (`:param`) or `2` (`*catch_all`).
+/// When sorted ascending, static segments win over parameterized ones at
+/// every position, so `/users/current` always comes before `/users/:id`,
+/// which in turn comes before `/users/*rest`.
 fn route_specificity(pattern: &str) -> Vec<u8> {
     pattern
         .split('/')
-        .map(|seg| if seg.starts_with(':') { 1 } else { 0 })
+        .map(|seg| {
+            if seg.starts_with(':') {
+                1
+            } else if seg.starts_with('*') {
+                2
+            } else {
+                0
+            }
+        })
         .collect()
 }
 
@@ -805,6 +1046,7 @@ mod tests {
         assert!(!super::is_dynamic("/api/v1:latest"));
         assert!(super::is_dynamic("/users/:id"));
         assert!(super::is_dynamic("/users/:id/posts/:pid"));
+        assert!(super::is_dynamic("/files/*path"));
     }
 
     #[test]
@@ -819,6 +1061,31 @@ mod tests {
             super::route_specificity("/users/:id/posts"),
             vec![0, 0, 1, 0]
         );
+        assert_eq!(super::route_specificity("/users/*rest"), vec![0, 0, 2]);
+    }
+
+    #[test]
+    fn test_resolve_catch_all_after_freeze() {
+        let mut router = Router::new()
+            .route(Method::GET, "/files/*path", |_, _, _| async {
+                StatusCode::OK
+            })
+            .route(Method::GET, "/files/:id", |_, _, _| async {
+                StatusCode::OK
+            });
+        router.sort_routes();
+        router.freeze();
+
+        let (idx, params) = router.resolve(&Method::GET, "/files/a/b.txt").unwrap();
+        assert_eq!(router.routes[idx].1.pattern, "/files/*path");
+        assert_eq!(params.get("path").unwrap(), "a/b.txt");
+
+        let linear = router.resolve_linear(&Method::GET, "/files/a/b.txt");
+        assert_eq!(linear.map(|(i, _)| i), Some(idx));
+
+        let (idx, params) = router.resolve(&Method::GET, "/files/7").unwrap();
+        assert_eq!(router.routes[idx].1.pattern, "/files/:id");
+        assert_eq!(params.get("id").unwrap(), "7");
     }
 
     #[test]
@@ -918,6 +1185,51 @@ mod tests {
         assert!(router.resolve(&Method::POST, "/health").is_none());
     }
 
+    #[test]
+    fn test_allowed_methods_merges_static_and_dynamic() {
+        let mut router = Router::new()
+            .route(Method::POST, "/users/current", |_, _, _| async {
+                StatusCode::OK
+            })
+            .route(Method::DELETE, "/users/:id", |_, _, _| async {
+                StatusCode::NO_CONTENT
+            })
+            .route(Method::GET, "/users/:id", |_, _, _| async {
+                StatusCode::OK
+            });
+        router.sort_routes();
+        router.freeze();
+
+        assert_eq!(
+            router.allowed_methods("/users/current"),
+            vec![
+                Method::GET,
+                Method::POST,
+                Method::DELETE,
+                Method::HEAD,
+                Method::OPTIONS
+            ]
+        );
+        assert_eq!(
+            router.allowed_methods("/users/42"),
+            vec![Method::GET, Method::DELETE, Method::HEAD, Method::OPTIONS]
+        );
+        assert!(router.allowed_methods("/missing").is_empty());
+    }
+
+    #[test]
+    fn test_allowed_methods_without_get_has_no_head() {
+        let mut router =
+            Router::new().route(Method::POST, "/login", |_, _, _| async { StatusCode::OK });
+        router.sort_routes();
+        router.freeze();
+
+        assert_eq!(
+            router.allowed_methods("/login"),
+            vec![Method::POST, Method::OPTIONS]
+        );
+    }
+
     #[test]
     fn test_freeze_is_idempotent() {
         let mut router =
@@ -28,6 +28,17 @@ impl StaticMap {
         self.map.get(method)?.get(path).copied()
     }
 
+    /// Returns every method that has a static route registered for `path`.
+    ///
+    /// Only consulted on a lookup miss, to tell "wrong method" apart from
+    /// "no such path".
+    pub(super) fn methods_for(&self, path: &str) -> impl Iterator<Item = &Method> {
+        self.map
+            .iter()
+            .filter(move |(_, paths)| paths.contains_key(path))
+            .map(|(method, _)| method)
+    }
+
     #[cfg(test)]
     pub fn len(&self) -> usize {
         self.map.values().map(|m| m.len()).sum()
@@ -77,6 +88,27 @@ mod tests {
         assert!(static_map.lookup(&Method::POST, "/health").is_none());
     }
 
+    #[test]
+    fn test_methods_for_path() {
+        let router = crate::router::Router::new()
+            .route(Method::GET, "/users", |_, _, _| async {
+                http::StatusCode::OK
+            })
+            .route(Method::POST, "/users", |_, _, _| async {
+                http::StatusCode::CREATED
+            })
+            .route(Method::GET, "/health", |_, _, _| async {
+                http::StatusCode::OK
+            });
+
+        let static_map = StaticMap::build(&router.routes);
+
+        let mut methods: Vec<&Method> = static_map.methods_for("/users").collect();
+        methods.sort_by_key(|m| m.as_str());
+        assert_eq!(methods, vec![&Method::GET, &Method::POST]);
+        assert_eq!(static_map.methods_for("/missing").count(), 0);
+    }
+
     #[test]
     fn test_lookup_miss_wrong_path() {
         let router = crate::router::Router::new().route(Method::GET, "/health", |_, _, _| async {
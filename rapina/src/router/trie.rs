@@ -2,7 +2,8 @@
 //!
 //! Built once at `freeze()` time, then immutable for all lookups.
 //! One trie per HTTP method. Byte-level prefix compression with
-//! separate param children for `:name` segments.
+//! separate param children for `:name` segments and an optional
+//! trailing catch-all for `*name` segments.
 //!
 //! # Invariant: params consume full segments
 //!
@@ -19,6 +20,10 @@
 //! O(D) and only triggers when routes have overlapping static/param
 //! prefixes at the same depth.
 //!
+//! A `*name` catch-all is only allowed as the last segment of a pattern.
+//! It is tried after both the static and param children have failed, so
+//! `/files/readme` and `/files/:id` always win over `/files/*path`.
+//!
 //! **WARNING:** This backtracking relies on the full-segment invariant.
 //! If suffix patterns (e.g. `/:name.txt`) are ever added, the param
 //! child can no longer consume a clean segment boundary, and the
@@ -33,9 +38,22 @@ use crate::extract::PathParams;
 
 const NUM_STANDARD_METHODS: usize = 9;
 
+/// Standard HTTP methods in `method_index` order.
+const STANDARD_METHODS: [Method; NUM_STANDARD_METHODS] = [
+    Method::GET,
+    Method::POST,
+    Method::PUT,
+    Method::DELETE,
+    Method::HEAD,
+    Method::OPTIONS,
+    Method::PATCH,
+    Method::CONNECT,
+    Method::TRACE,
+];
+
 /// Map standard HTTP methods to array indices for zero-cost dispatch.
 /// Returns `None` for extension methods.
-fn method_index(m: &Method) -> Option<usize> {
+pub(super) fn method_index(m: &Method) -> Option<usize> {
     match m.as_str() {
         "GET" => Some(0),
         "POST" => Some(1),
@@ -66,6 +84,9 @@ struct Node {
     /// application lifetime so this is never reclaimed (and it's a handful of
     /// bytes total for any realistic route table).
     param_name: Option<&'static str>,
+    /// Trailing `*name` catch-all hanging off this node: the param name
+    /// (leaked like `param_name`) and the route index it resolves to.
+    catch_all: Option<(&'static str, usize)>,
     /// Route index if this is a terminal node.
     value: Option<usize>,
     /// Number of routes reachable through this subtree.
@@ -80,6 +101,7 @@ impl Node {
             children: Vec::new(),
             param_child: None,
             param_name: None,
+            catch_all: None,
             value: None,
             priority: 0,
         }
@@ -98,15 +120,23 @@ impl Node {
 enum Segment<'a> {
     Static(&'a [u8]),
     Param(&'a str),
+    CatchAll(&'a str),
+}
+
+/// Returns `true` if `bytes[i]` opens a `*name` catch-all segment.
+fn is_catch_all_start(bytes: &[u8], i: usize) -> bool {
+    bytes[i] == b'*' && i > 0 && bytes[i - 1] == b'/'
 }
 
 /// Split a route pattern into alternating static/param segments.
 ///
 /// `/users/:id/posts/:pid` → [Static("/users/"), Param("id"), Static("/posts/"), Param("pid")]
+/// `/files/*path` → [Static("/files/"), CatchAll("path")]
 ///
 /// # Panics
 ///
-/// Panics if a `:param` is not a full segment (e.g. `/files/:name.txt`).
+/// Panics if a `:param` is not a full segment (e.g. `/files/:name.txt`),
+/// or if a `*catch_all` is unnamed or not the last segment.
 fn split_pattern(pattern: &str) -> Vec<Segment<'_>> {
     let mut segments = Vec::new();
     let bytes = pattern.as_bytes();
@@ -136,13 +166,22 @@ fn split_pattern(pattern: &str) -> Vec<Segment<'_>> {
 
             segments.push(Segment::Param(&pattern[start..end]));
             i = end;
+        } else if is_catch_all_start(bytes, i) {
+            // Catch-all segment — always the rest of the pattern.
+            let name = &pattern[i + 1..];
+            assert!(
+                !name.is_empty() && !name.contains('/'),
+                "catch-all `*{}` in pattern `{}` must be named and be the last segment",
+                name,
+                pattern,
+            );
+            segments.push(Segment::CatchAll(name));
+            i = bytes.len();
         } else {
-            // Static segment — consume until next ':' or end.
+            // Static segment — consume until next ':', catch-all or end.
             let start = i;
-            let end = bytes[start..]
-                .iter()
-                .position(|&b| b == b':')
-                .map(|p| start + p)
+            let end = (start..bytes.len())
+                .find(|&j| bytes[j] == b':' || is_catch_all_start(bytes, j))
                 .unwrap_or(bytes.len());
             segments.push(Segment::Static(&bytes[start..end]));
             i = end;
@@ -195,6 +234,18 @@ impl RadixTrie {
                     }
                     current = self.arena[current].param_child.unwrap();
                 }
+                Segment::CatchAll(name) => {
+                    if let Some((existing, _)) = self.arena[current].catch_all {
+                        assert!(
+                            existing == *name,
+                            "conflicting catch-all names at the same position: \
+                             `*{existing}` and `*{name}` in pattern `{pattern}`",
+                        );
+                    }
+                    let leaked: &'static str = Box::leak(name.to_string().into_boxed_str());
+                    self.arena[current].catch_all = Some((leaked, route_index));
+                    return;
+                }
             }
         }
 
@@ -252,6 +303,7 @@ impl RadixTrie {
             children: std::mem::take(&mut self.arena[node].children),
             param_child: self.arena[node].param_child.take(),
             param_name: self.arena[node].param_name.take(),
+            catch_all: self.arena[node].catch_all.take(),
             value: self.arena[node].value.take(),
             priority: self.arena[node].priority,
         };
@@ -274,6 +326,9 @@ impl RadixTrie {
         } else {
             0
         };
+        if self.arena[node_id].catch_all.is_some() {
+            count += 1;
+        }
 
         // Recurse into static children (index-based to avoid cloning).
         let num_children = self.arena[node_id].children.len();
@@ -397,6 +452,12 @@ impl RadixTrie {
             }
         }
 
+        // Last resort: the catch-all swallows the rest of the path.
+        if let Some((name, route_index)) = node.catch_all {
+            params.push(name, remaining.to_string());
+            return Some(route_index);
+        }
+
         None
     }
 
@@ -458,6 +519,21 @@ impl TrieRouter {
         let idx = method_index(method)?;
         self.methods[idx].as_ref()?.lookup(path, params)
     }
+
+    /// Returns every method whose trie matches `path`, in `method_index` order.
+    ///
+    /// Only consulted on a lookup miss, so the per-method scratch
+    /// `PathParams` allocation stays off the hot path.
+    pub(super) fn methods_for(&self, path: &str) -> impl Iterator<Item = Method> {
+        self.methods
+            .iter()
+            .zip(STANDARD_METHODS.iter())
+            .filter_map(move |(slot, method)| {
+                let mut params = PathParams::new();
+                slot.as_ref()?.lookup(path, &mut params)?;
+                Some(method.clone())
+            })
+    }
 }
 
 // ── Tests ────────────────────────────────────────────────────────────
@@ -640,6 +716,115 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_trie_router_methods_for() {
+        let router = crate::router::Router::new()
+            .route(Method::DELETE, "/users/:id", |_, _, _| async {
+                http::StatusCode::NO_CONTENT
+            })
+            .route(Method::GET, "/users/:id", |_, _, _| async {
+                http::StatusCode::OK
+            })
+            .route(Method::GET, "/posts/:id", |_, _, _| async {
+                http::StatusCode::OK
+            });
+
+        let trie_router = TrieRouter::build(&router.routes);
+
+        let methods: Vec<Method> = trie_router.methods_for("/users/1").collect();
+        assert_eq!(methods, vec![Method::GET, Method::DELETE]);
+
+        let methods: Vec<Method> = trie_router.methods_for("/posts/1").collect();
+        assert_eq!(methods, vec![Method::GET]);
+
+        assert_eq!(trie_router.methods_for("/comments/1").count(), 0);
+    }
+
+    #[test]
+    fn test_catch_all_captures_rest_of_path() {
+        let mut trie = RadixTrie::new();
+        trie.insert("/files/*path", 0);
+
+        let (result, params) = lookup_params(&trie, "/files/docs/2024/report.pdf");
+        assert_eq!(result, Some(0));
+        assert_eq!(params.get("path").unwrap(), "docs/2024/report.pdf");
+
+        let (result, params) = lookup_params(&trie, "/files/a");
+        assert_eq!(result, Some(0));
+        assert_eq!(params.get("path").unwrap(), "a");
+
+        // The catch-all must capture at least one byte.
+        let (result, _) = lookup_params(&trie, "/files/");
+        assert_eq!(result, None);
+        let (result, _) = lookup_params(&trie, "/files");
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn test_catch_all_has_lowest_precedence() {
+        let mut trie = RadixTrie::new();
+        trie.insert("/files/*path", 0);
+        trie.insert("/files/:id/meta", 1);
+        trie.insert("/files/readme", 2);
+        trie.compute_priorities();
+        trie.reorder_children();
+
+        let (result, _) = lookup_params(&trie, "/files/readme");
+        assert_eq!(result, Some(2));
+
+        let (result, params) = lookup_params(&trie, "/files/7/meta");
+        assert_eq!(result, Some(1));
+        assert_eq!(params.get("id").unwrap(), "7");
+
+        // Param subtree fails — backtrack into the catch-all without
+        // leaking the :id capture.
+        let (result, params) = lookup_params(&trie, "/files/7/other");
+        assert_eq!(result, Some(0));
+        assert_eq!(params.len(), 1);
+        assert_eq!(params.get("path").unwrap(), "7/other");
+    }
+
+    #[test]
+    fn test_catch_all_survives_node_split() {
+        let mut trie = RadixTrie::new();
+        trie.insert("/files/*path", 0);
+        // Shares "/f" with "/files/", forcing the catch-all's node to split.
+        trie.insert("/fonts/:name", 1);
+
+        let (result, params) = lookup_params(&trie, "/files/x/y");
+        assert_eq!(result, Some(0));
+        assert_eq!(params.get("path").unwrap(), "x/y");
+
+        let (result, _) = lookup_params(&trie, "/fonts/mono");
+        assert_eq!(result, Some(1));
+    }
+
+    #[test]
+    fn test_split_pattern_catch_all() {
+        let segments = split_pattern("/docs/*rest");
+        assert_eq!(segments.len(), 2);
+        match &segments[1] {
+            Segment::CatchAll(name) => assert_eq!(*name, "rest"),
+            _ => panic!("expected a CatchAll segment"),
+        }
+
+        // A '*' that does not open a segment is a literal.
+        let segments = split_pattern("/a*b");
+        assert_eq!(segments.len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "must be named and be the last segment")]
+    fn test_catch_all_not_last_panics() {
+        split_pattern("/files/*path/meta");
+    }
+
+    #[test]
+    #[should_panic(expected = "must be named and be the last segment")]
+    fn test_unnamed_catch_all_panics() {
+        split_pattern("/files/*");
+    }
+
     #[test]
     fn test_empty_trie() {
         let trie = RadixTrie::new();
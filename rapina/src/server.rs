@@ -9,12 +9,15 @@ use hyper::body::Incoming;
 use hyper::service::service_fn;
 use hyper_util::rt::{TokioExecutor, TokioIo};
 use hyper_util::server::conn::auto;
-use hyper_util::server::graceful::GracefulShutdown;
+use hyper_util::server::graceful::{GracefulShutdown, Watcher};
 use tokio::net::TcpListener;
 
 use crate::context::RequestContext;
 use crate::date_cache::DateHeaderCache;
+use crate::extract::{ClientIp, ConnectInfo};
+use crate::listener::Listener;
 use crate::middleware::MiddlewareStack;
+use crate::proxy::TrustedProxies;
 use crate::router::Router;
 use crate::state::AppState;
 
@@ -28,23 +31,151 @@ pub(crate) type ShutdownHook = Box<dyn FnOnce() -> Pin<Box<dyn Future<Output = (
 /// in-flight requests to drain before forcing a close. After all connections
 /// are drained, each `shutdown_hooks` closure is awaited in registration order.
 pub(crate) async fn serve(
-    mut router: Router,
+    router: Router,
     state: AppState,
     middlewares: MiddlewareStack,
     addr: SocketAddr,
     shutdown_timeout: Duration,
     shutdown_hooks: Vec<ShutdownHook>,
 ) -> std::io::Result<()> {
-    router.freeze();
-    let router = Arc::new(router);
-    let state = Arc::new(state);
-    let middlewares = Arc::new(middlewares);
-    let date_cache = DateHeaderCache::start();
     let listener = TcpListener::bind(addr).await?;
+    serve_on(
+        router,
+        state,
+        middlewares,
+        listener,
+        shutdown_timeout,
+        shutdown_hooks,
+    )
+    .await
+}
+
+/// Same as [`serve`], accepting connections from an existing `listener`.
+pub(crate) async fn serve_on<L: Listener>(
+    router: Router,
+    state: AppState,
+    middlewares: MiddlewareStack,
+    listener: L,
+    shutdown_timeout: Duration,
+    shutdown_hooks: Vec<ShutdownHook>,
+) -> std::io::Result<()> {
+    serve_with(
+        ConnectionContext::new(router, state, middlewares),
+        listener,
+        Acceptor::Plain,
+        shutdown_timeout,
+        shutdown_hooks,
+    )
+    .await
+}
+
+/// Start the HTTPS server and block until a shutdown signal is received.
+///
+/// Same as [`serve`], with every accepted connection going through a TLS
+/// handshake first. Fails before binding if the certificate cannot be loaded.
+#[cfg(feature = "tls")]
+pub(crate) async fn serve_tls(
+    router: Router,
+    state: AppState,
+    middlewares: MiddlewareStack,
+    addr: SocketAddr,
+    tls: crate::tls::TlsConfig,
+    shutdown_timeout: Duration,
+    shutdown_hooks: Vec<ShutdownHook>,
+) -> std::io::Result<()> {
+    let acceptor = tls.build_acceptor()?;
+    let listener = TcpListener::bind(addr).await?;
+    serve_with(
+        ConnectionContext::new(router, state, middlewares),
+        listener,
+        Acceptor::Tls(acceptor),
+        shutdown_timeout,
+        shutdown_hooks,
+    )
+    .await
+}
+
+/// How accepted connections are turned into HTTP connections.
+pub(crate) enum Acceptor {
+    Plain,
+    #[cfg(feature = "tls")]
+    Tls(crate::tls::TlsAcceptor),
+}
+
+impl Acceptor {
+    fn scheme(&self) -> &'static str {
+        match self {
+            Acceptor::Plain => "http",
+            #[cfg(feature = "tls")]
+            Acceptor::Tls(_) => "https",
+        }
+    }
+}
+
+/// Everything a connection needs to serve requests, cheap to clone.
+#[derive(Clone)]
+pub(crate) struct ConnectionContext {
+    router: Arc<Router>,
+    state: Arc<AppState>,
+    middlewares: Arc<MiddlewareStack>,
+    trusted_proxies: Arc<TrustedProxies>,
+    date_cache: DateHeaderCache,
+}
+
+impl ConnectionContext {
+    /// Freezes `router` and starts the `Date` header cache.
+    ///
+    /// Client IPs are resolved with the [`TrustedProxies`] in `state`, if any.
+    pub(crate) fn new(mut router: Router, state: AppState, middlewares: MiddlewareStack) -> Self {
+        router.freeze();
+        let trusted_proxies = state.get::<TrustedProxies>().cloned().unwrap_or_default();
+        Self {
+            router: Arc::new(router),
+            state: Arc::new(state),
+            middlewares: Arc::new(middlewares),
+            trusted_proxies: Arc::new(trusted_proxies),
+            date_cache: DateHeaderCache::start(),
+        }
+    }
+}
+
+async fn serve_with<L: Listener>(
+    cx: ConnectionContext,
+    listener: L,
+    acceptor: Acceptor,
+    shutdown_timeout: Duration,
+    shutdown_hooks: Vec<ShutdownHook>,
+) -> std::io::Result<()> {
     let graceful = GracefulShutdown::new();
-    let mut ctrl_c = pin!(tokio::signal::ctrl_c());
 
-    let mut sigterm: Pin<Box<dyn Future<Output = ()> + Send>> = Box::pin(async {
+    match listener.local_addr() {
+        Ok(addr) => tracing::info!("Rapina listening on {}://{:?}", acceptor.scheme(), addr),
+        Err(_) => tracing::info!("Rapina listening"),
+    }
+
+    accept_loop(listener, &acceptor, &cx, &graceful, shutdown_signal()).await?;
+    tracing::info!("Shutdown signal received, waiting for connections to drain...");
+
+    tokio::select! {
+        _ = graceful.shutdown() => {
+            tracing::info!("All connections drained.");
+        }
+        _ = tokio::time::sleep(shutdown_timeout) => {
+            tracing::warn!("Shutdown timeout reached, forcing close.");
+        }
+    }
+
+    for hook in shutdown_hooks {
+        hook().await;
+    }
+
+    tracing::info!("Server stopped.");
+    Ok(())
+}
+
+/// Resolves on SIGINT, or SIGTERM on Unix.
+async fn shutdown_signal() {
+    let sigterm = async {
         #[cfg(unix)]
         {
             use tokio::signal::unix::SignalKind;
@@ -57,78 +188,102 @@ pub(crate) async fn serve(
         {
             std::future::pending::<()>().await;
         }
-    });
+    };
 
-    tracing::info!("Rapina listening on http://{}", addr);
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = sigterm => {}
+    }
+}
+
+/// Accepts connections from `listener` and serves each one on its own task
+/// until `shutdown` resolves, then drops the listener.
+///
+/// Connections are registered with `graceful` so the caller can drain them.
+/// Returns early with the error if accepting fails.
+pub(crate) async fn accept_loop<L: Listener>(
+    mut listener: L,
+    acceptor: &Acceptor,
+    cx: &ConnectionContext,
+    graceful: &GracefulShutdown,
+    shutdown: impl Future<Output = ()>,
+) -> std::io::Result<()> {
+    let mut shutdown = pin!(shutdown);
 
     loop {
         tokio::select! {
             result = listener.accept() => {
                 let (stream, _) = result?;
-                let io = TokioIo::new(stream);
-                let router = router.clone();
-                let state = state.clone();
-                let middlewares = middlewares.clone();
-
-                let date_cache = date_cache.clone();
-                let service = service_fn(move |mut req: Request<Incoming>| {
-                    let router = router.clone();
-                    let state = state.clone();
-                    let middlewares = middlewares.clone();
-                    let date_cache = date_cache.clone();
-
-                    let ctx = RequestContext::new();
-                    req.extensions_mut().insert(ctx.clone());
-
-                    async move {
-                        let mut response = middlewares.execute(req, router, state, &ctx).await;
-                        response
-                            .headers_mut()
-                            .insert(http::header::DATE, date_cache.header_value());
-                        Ok::<_, std::convert::Infallible>(response)
+                let (remote_addr, local_addr) = L::socket_addrs(&stream);
+                let cx = cx.clone();
+                let watcher = graceful.watcher();
+
+                match acceptor {
+                    Acceptor::Plain => {
+                        let info = ConnectInfo::new(remote_addr, local_addr, false);
+                        tokio::spawn(serve_connection(TokioIo::new(stream), info, cx, watcher));
                     }
-                });
-
-                let conn = auto::Builder::new(TokioExecutor::new())
-                    .serve_connection_with_upgrades(io, service)
-                    .into_owned();
-                let conn = graceful.watch(conn);
-
-                tokio::spawn(async move {
-                    if let Err(e) = conn.await {
-                        tracing::error!("connection error: {}", e);
+                    #[cfg(feature = "tls")]
+                    Acceptor::Tls(tls) => {
+                        let tls = tls.clone();
+                        let info = ConnectInfo::new(remote_addr, local_addr, true);
+                        tokio::spawn(async move {
+                            match tls.accept(stream).await {
+                                Ok(stream) => {
+                                    serve_connection(TokioIo::new(stream), info, cx, watcher)
+                                        .await
+                                }
+                                Err(e) => tracing::debug!("TLS handshake failed: {}", e),
+                            }
+                        });
                     }
-                });
+                }
             }
-            _ = ctrl_c.as_mut() => {
-                drop(listener);
-                tracing::info!("Shutdown signal received, waiting for connections to drain...");
-                break;
-            }
-
-            _ = sigterm.as_mut()  => {
-                drop(listener);
-                tracing::info!("Shutdown signal received, waiting for connections to drain...");
-                break;
+            _ = shutdown.as_mut() => {
+                return Ok(());
             }
         }
     }
+}
 
-    tokio::select! {
-        _ = graceful.shutdown() => {
-            tracing::info!("All connections drained.");
+/// Serves HTTP/1.1 or HTTP/2 on `io` until the client closes the connection
+/// or a graceful shutdown completes it.
+async fn serve_connection<I>(io: I, info: ConnectInfo, cx: ConnectionContext, watcher: Watcher)
+where
+    I: hyper::rt::Read + hyper::rt::Write + Unpin + Send + 'static,
+{
+    let service = service_fn(move |mut req: Request<Incoming>| {
+        let router = cx.router.clone();
+        let state = cx.state.clone();
+        let middlewares = cx.middlewares.clone();
+        let date_cache = cx.date_cache.clone();
+
+        let ctx = RequestContext::new();
+        req.extensions_mut().insert(ctx.clone());
+        let version = req.version();
+        req.extensions_mut().insert(info.with_version(version));
+
+        let peer = info.remote_addr().map(|addr| addr.ip());
+        if let Some(ip) = cx.trusted_proxies.resolve(peer, req.headers()) {
+            req.extensions_mut().insert(ClientIp(ip));
         }
-        _ = tokio::time::sleep(shutdown_timeout) => {
-            tracing::warn!("Shutdown timeout reached, forcing close.");
+
+        async move {
+            let mut response = middlewares.execute(req, router, state, &ctx).await;
+            response
+                .headers_mut()
+                .insert(http::header::DATE, date_cache.header_value());
+            Ok::<_, std::convert::Infallible>(response)
         }
-    }
+    });
 
-    for hook in shutdown_hooks {
-        hook().await;
-    }
+    let conn = auto::Builder::new(TokioExecutor::new())
+        .serve_connection_with_upgrades(io, service)
+        .into_owned();
 
-    tracing::info!("Server stopped.");
-    Ok(())
+    if let Err(e) = watcher.watch(conn).await {
+        tracing::error!("connection error: {}", e);
+    }
 }
 
 #[cfg(test)]
@@ -400,4 +555,307 @@ mod tests {
 
         let _ = tokio::time::timeout(Duration::from_secs(5), handle).await;
     }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_serve_on_prebound_listener() {
+        let std_listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = std_listener.local_addr().unwrap().port();
+        let listener = crate::listener::IntoListener::into_listener(std_listener).unwrap();
+        let hook_ran = Arc::new(Mutex::new(false));
+
+        let router = Router::new().route(http::Method::GET, "/", |_, _, _| async { "ok" });
+        let flag = hook_ran.clone();
+        let hooks: Vec<ShutdownHook> = vec![Box::new(move || {
+            Box::pin(async move {
+                *flag.lock().unwrap() = true;
+            })
+        })];
+
+        let handle = tokio::spawn(serve_on(
+            router,
+            AppState::new(),
+            MiddlewareStack::new(),
+            listener,
+            Duration::from_secs(5),
+            hooks,
+        ));
+
+        let response = http_get(port, "/").await;
+        assert!(response.contains("200"), "server should respond with 200");
+
+        #[cfg(unix)]
+        unix_tests::send_sigint();
+
+        #[cfg(windows)]
+        windows_tests::send_ctrl_break();
+
+        let result = tokio::time::timeout(Duration::from_secs(5), handle).await;
+        assert!(result.unwrap().unwrap().is_ok());
+        assert!(*hook_ran.lock().unwrap(), "shutdown hook should run");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    #[serial]
+    async fn test_serve_on_unix_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rapina.sock");
+        let listener = tokio::net::UnixListener::bind(&path).unwrap();
+
+        let router = Router::new().route(http::Method::GET, "/", |_, _, _| async { "over unix" });
+
+        let handle = tokio::spawn(serve_on(
+            router,
+            AppState::new(),
+            MiddlewareStack::new(),
+            listener,
+            Duration::from_secs(5),
+            vec![],
+        ));
+
+        let mut stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf).await.unwrap();
+        let response = String::from_utf8_lossy(&buf);
+        assert!(response.contains("200"), "got: {}", response);
+        assert!(response.contains("over unix"), "got: {}", response);
+
+        unix_tests::send_sigterm();
+
+        let result = tokio::time::timeout(Duration::from_secs(5), handle).await;
+        assert!(result.unwrap().unwrap().is_ok());
+    }
+
+    #[cfg(feature = "tls")]
+    mod tls_tests {
+        use super::*;
+        use crate::tls::TlsConfig;
+        use bytes::Bytes;
+        use http_body_util::{BodyExt, Full};
+        use rustls::pki_types::{CertificateDer, ServerName};
+        use std::path::Path;
+
+        fn write_cert(dir: &Path) -> CertificateDer<'static> {
+            let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+            std::fs::write(dir.join("server.crt"), cert.cert.pem()).unwrap();
+            std::fs::write(dir.join("server.key"), cert.key_pair.serialize_pem()).unwrap();
+            cert.cert.der().clone()
+        }
+
+        async fn connect(
+            port: u16,
+            trusted: &CertificateDer<'static>,
+            alpn: &[&[u8]],
+        ) -> tokio_rustls::client::TlsStream<TcpStream> {
+            let mut roots = rustls::RootCertStore::empty();
+            roots.add(trusted.clone()).unwrap();
+            let mut config = rustls::ClientConfig::builder_with_provider(Arc::new(
+                rustls::crypto::aws_lc_rs::default_provider(),
+            ))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+            config.alpn_protocols = alpn.iter().map(|p| p.to_vec()).collect();
+
+            let stream = TcpStream::connect(format!("127.0.0.1:{}", port))
+                .await
+                .unwrap();
+            tokio_rustls::TlsConnector::from(Arc::new(config))
+                .connect(ServerName::try_from("localhost").unwrap(), stream)
+                .await
+                .unwrap()
+        }
+
+        async fn get(
+            sender: &mut hyper::client::conn::http1::SendRequest<Full<Bytes>>,
+            path: &str,
+        ) -> String {
+            let req = Request::get(path)
+                .header("host", "localhost")
+                .body(Full::new(Bytes::new()))
+                .unwrap();
+            let response = sender.send_request(req).await.unwrap();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            String::from_utf8(body.to_vec()).unwrap()
+        }
+
+        fn spawn_tls_server(
+            port: u16,
+            tls: TlsConfig,
+            hooks: Vec<ShutdownHook>,
+        ) -> tokio::task::JoinHandle<std::io::Result<()>> {
+            let router = Router::new()
+                .route(http::Method::GET, "/", |_, _, _| async { "secure" })
+                .route(http::Method::GET, "/conn", |req, _, _| async move {
+                    let info = req.extensions().get::<ConnectInfo>().copied().unwrap();
+                    format!("{:?} {}", info.version(), info.is_secure())
+                });
+            tokio::spawn(serve_tls(
+                router,
+                AppState::new(),
+                MiddlewareStack::new(),
+                format!("127.0.0.1:{}", port).parse().unwrap(),
+                tls,
+                Duration::from_secs(5),
+                hooks,
+            ))
+        }
+
+        #[tokio::test]
+        #[serial]
+        async fn test_tls_serves_http1_and_runs_shutdown_hooks() {
+            let dir = tempfile::tempdir().unwrap();
+            let cert = write_cert(dir.path());
+            let port = free_port().await;
+            let log = Arc::new(Mutex::new(Vec::<String>::new()));
+            let log1 = log.clone();
+
+            let handle = spawn_tls_server(
+                port,
+                TlsConfig::new(dir.path().join("server.crt"), dir.path().join("server.key")),
+                vec![Box::new(move || {
+                    Box::pin(async move {
+                        log1.lock().unwrap().push("hook".to_string());
+                    }) as Pin<Box<dyn Future<Output = ()> + Send>>
+                })],
+            );
+            tokio::time::sleep(Duration::from_millis(100)).await;
+
+            let mut stream = connect(port, &cert, &[b"http/1.1"]).await;
+            assert_eq!(stream.get_ref().1.alpn_protocol(), Some(&b"http/1.1"[..]));
+            stream
+                .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+                .await
+                .unwrap();
+            let mut buf = Vec::new();
+            stream.read_to_end(&mut buf).await.unwrap();
+            let response = String::from_utf8_lossy(&buf);
+            assert!(response.starts_with("HTTP/1.1 200"), "got: {}", response);
+            assert!(response.ends_with("secure"));
+
+            #[cfg(unix)]
+            unix_tests::send_sigint();
+
+            #[cfg(windows)]
+            windows_tests::send_ctrl_break();
+
+            let result = tokio::time::timeout(Duration::from_secs(5), handle).await;
+            assert!(
+                result.unwrap().unwrap().is_ok(),
+                "server should exit cleanly"
+            );
+            assert_eq!(*log.lock().unwrap(), vec!["hook"]);
+        }
+
+        #[tokio::test]
+        #[serial]
+        async fn test_tls_negotiates_h2_via_alpn() {
+            let dir = tempfile::tempdir().unwrap();
+            let cert = write_cert(dir.path());
+            let port = free_port().await;
+            let handle = spawn_tls_server(
+                port,
+                TlsConfig::new(dir.path().join("server.crt"), dir.path().join("server.key")),
+                vec![],
+            );
+            tokio::time::sleep(Duration::from_millis(100)).await;
+
+            let stream = connect(port, &cert, &[b"h2", b"http/1.1"]).await;
+            assert_eq!(stream.get_ref().1.alpn_protocol(), Some(&b"h2"[..]));
+
+            let (mut sender, conn) =
+                hyper::client::conn::http2::handshake(TokioExecutor::new(), TokioIo::new(stream))
+                    .await
+                    .unwrap();
+            tokio::spawn(conn);
+
+            let req = Request::get("https://localhost/")
+                .body(Full::new(Bytes::new()))
+                .unwrap();
+            let response = sender.send_request(req).await.unwrap();
+            assert_eq!(response.version(), http::Version::HTTP_2);
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            assert_eq!(&body[..], b"secure");
+
+            let req = Request::get("https://localhost/conn")
+                .body(Full::new(Bytes::new()))
+                .unwrap();
+            let response = sender.send_request(req).await.unwrap();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            assert_eq!(&body[..], b"HTTP/2.0 true");
+
+            #[cfg(unix)]
+            unix_tests::send_sigint();
+
+            #[cfg(windows)]
+            windows_tests::send_ctrl_break();
+
+            let _ = tokio::time::timeout(Duration::from_secs(5), handle).await;
+        }
+
+        #[tokio::test]
+        #[serial]
+        async fn test_tls_reloads_certificate_without_dropping_connections() {
+            let dir = tempfile::tempdir().unwrap();
+            let old_cert = write_cert(dir.path());
+            let port = free_port().await;
+            let handle = spawn_tls_server(
+                port,
+                TlsConfig::new(dir.path().join("server.crt"), dir.path().join("server.key"))
+                    .reload_interval(Duration::from_millis(50)),
+                vec![],
+            );
+            tokio::time::sleep(Duration::from_millis(100)).await;
+
+            let stream = connect(port, &old_cert, &[b"http/1.1"]).await;
+            let (mut sender, conn) = hyper::client::conn::http1::handshake(TokioIo::new(stream))
+                .await
+                .unwrap();
+            tokio::spawn(conn);
+            assert_eq!(get(&mut sender, "/").await, "secure");
+
+            // Make sure the new files get a different mtime.
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            let new_cert = write_cert(dir.path());
+            tokio::time::sleep(Duration::from_millis(300)).await;
+
+            // The open connection keeps working on the old certificate.
+            assert_eq!(get(&mut sender, "/").await, "secure");
+
+            // New handshakes present the new certificate.
+            let stream = connect(port, &new_cert, &[b"http/1.1"]).await;
+            let presented = stream.get_ref().1.peer_certificates().unwrap();
+            assert_eq!(presented[0], new_cert);
+
+            drop(sender);
+
+            #[cfg(unix)]
+            unix_tests::send_sigint();
+
+            #[cfg(windows)]
+            windows_tests::send_ctrl_break();
+
+            let _ = tokio::time::timeout(Duration::from_secs(5), handle).await;
+        }
+
+        #[tokio::test]
+        async fn test_tls_fails_fast_on_missing_certificate() {
+            let dir = tempfile::tempdir().unwrap();
+            let port = free_port().await;
+            let handle = spawn_tls_server(
+                port,
+                TlsConfig::new(dir.path().join("server.crt"), dir.path().join("server.key")),
+                vec![],
+            );
+
+            let result = tokio::time::timeout(Duration::from_secs(5), handle).await;
+            assert!(result.unwrap().unwrap().is_err());
+        }
+    }
 }
@@ -0,0 +1,374 @@
+//! Session configuration.
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use cookie::{Cookie, CookieJar, Key};
+use http::{HeaderMap, HeaderValue};
+
+use crate::error::Error;
+
+use super::store::SessionStore;
+
+const DEFAULT_COOKIE_NAME: &str = "rapina_session";
+const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+const DEFAULT_CLEANUP_SCHEDULE: &str = "0 */10 * * * *"; // every 10 minutes
+
+/// The cookie's `SameSite` attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SameSite {
+    /// Sent only on requests from the app's own pages.
+    Strict,
+    /// Also sent when following a link to the app from another site.
+    #[default]
+    Lax,
+    /// Sent on cross-site requests too. Browsers require `Secure` with it.
+    None,
+}
+
+impl From<SameSite> for cookie::SameSite {
+    fn from(same_site: SameSite) -> Self {
+        match same_site {
+            SameSite::Strict => cookie::SameSite::Strict,
+            SameSite::Lax => cookie::SameSite::Lax,
+            SameSite::None => cookie::SameSite::None,
+        }
+    }
+}
+
+/// How the session cookie's value is protected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CookieProtection {
+    /// Authenticated with an HMAC: clients can read the value but not
+    /// change it.
+    Signed,
+    /// Encrypted with AES-GCM: clients can neither read nor change it.
+    #[default]
+    Encrypted,
+}
+
+/// Configuration for [`SessionMiddleware`](super::SessionMiddleware).
+///
+/// The secret derives the keys that sign or encrypt the session cookie, so
+/// every replica of the app needs the same one, and changing it signs
+/// everybody out.
+///
+/// # Example
+///
+/// ```ignore
+/// let sessions = SessionConfig::new(secret, InMemorySessionStore::new())
+///     .with_cookie_name("admin_session")
+///     .with_same_site(SameSite::Strict)
+///     .with_ttl(Duration::from_secs(8 * 60 * 60));
+/// ```
+#[derive(Clone)]
+pub struct SessionConfig {
+    key: Key,
+    store: Arc<dyn SessionStore>,
+    cookie_name: String,
+    protection: CookieProtection,
+    same_site: SameSite,
+    secure: bool,
+    http_only: bool,
+    path: String,
+    domain: Option<String>,
+    ttl: Duration,
+    cleanup_schedule: String,
+}
+
+impl SessionConfig {
+    /// Creates a configuration with the given secret and store.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `secret` is shorter than 32 bytes.
+    pub fn new(secret: impl AsRef<[u8]>, store: impl SessionStore) -> Self {
+        let secret = secret.as_ref();
+        assert!(
+            secret.len() >= 32,
+            "session secret must be at least 32 bytes, got {}",
+            secret.len()
+        );
+        Self {
+            key: Key::derive_from(secret),
+            store: Arc::new(store),
+            cookie_name: DEFAULT_COOKIE_NAME.to_string(),
+            protection: CookieProtection::default(),
+            same_site: SameSite::default(),
+            secure: true,
+            http_only: true,
+            path: "/".to_string(),
+            domain: None,
+            ttl: DEFAULT_TTL,
+            cleanup_schedule: DEFAULT_CLEANUP_SCHEDULE.to_string(),
+        }
+    }
+
+    /// Creates a configuration with the secret in the `SESSION_SECRET`
+    /// environment variable.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the secret is shorter than 32 bytes.
+    pub fn from_env(store: impl SessionStore) -> Result<Self, crate::config::ConfigError> {
+        let secret = crate::config::get_env("SESSION_SECRET")?;
+        Ok(Self::new(secret, store))
+    }
+
+    /// Sets the cookie's name (default: `rapina_session`).
+    pub fn with_cookie_name(mut self, name: impl Into<String>) -> Self {
+        self.cookie_name = name.into();
+        self
+    }
+
+    /// Sets how the cookie's value is protected (default: encrypted).
+    ///
+    /// Keep it encrypted with [`CookieSessionStore`](super::CookieSessionStore),
+    /// whose cookie holds the session data itself.
+    pub fn with_protection(mut self, protection: CookieProtection) -> Self {
+        self.protection = protection;
+        self
+    }
+
+    /// Sets the cookie's `SameSite` attribute (default: `Lax`).
+    pub fn with_same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = same_site;
+        self
+    }
+
+    /// Sets whether the cookie is only sent over HTTPS (default: `true`).
+    ///
+    /// Browsers accept `Secure` cookies from `http://localhost`, so this
+    /// only needs turning off for plain HTTP on other hosts.
+    pub fn with_secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// Sets whether the cookie is hidden from JavaScript (default: `true`).
+    pub fn with_http_only(mut self, http_only: bool) -> Self {
+        self.http_only = http_only;
+        self
+    }
+
+    /// Sets the cookie's `Path` (default: `/`).
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Sets the cookie's `Domain`, to share it with subdomains (default:
+    /// none, so only the host that set it receives it).
+    pub fn with_domain(mut self, domain: impl Into<String>) -> Self {
+        self.domain = Some(domain.into());
+        self
+    }
+
+    /// Sets how long a session lasts without requests (default: 24 hours).
+    ///
+    /// Each request in the second half of that time extends it again.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Sets the cron schedule on which expired sessions are deleted from
+    /// the store (default: every 10 minutes, `0 */10 * * * *`).
+    pub fn with_cleanup_schedule(mut self, schedule: impl Into<String>) -> Self {
+        self.cleanup_schedule = schedule.into();
+        self
+    }
+
+    pub(crate) fn store(&self) -> &Arc<dyn SessionStore> {
+        &self.store
+    }
+
+    pub(crate) fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    pub(crate) fn cleanup_schedule(&self) -> &str {
+        &self.cleanup_schedule
+    }
+
+    /// Whether the request carries a session cookie, and its value if the
+    /// signature or encryption checks out.
+    pub(crate) fn read_cookie(&self, headers: &HeaderMap) -> (bool, Option<String>) {
+        let mut jar = CookieJar::new();
+        let cookies = headers
+            .get_all(http::header::COOKIE)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| Cookie::split_parse_encoded(v.to_string()))
+            .flatten();
+        for cookie in cookies {
+            if cookie.name() == self.cookie_name {
+                jar.add_original(cookie);
+            }
+        }
+
+        let present = jar.get(&self.cookie_name).is_some();
+        let cookie = match self.protection {
+            CookieProtection::Signed => jar.signed(&self.key).get(&self.cookie_name),
+            CookieProtection::Encrypted => jar.private(&self.key).get(&self.cookie_name),
+        };
+        (present, cookie.map(|c| c.value().to_string()))
+    }
+
+    /// The `Set-Cookie` header that stores `value` in the session cookie.
+    pub(crate) fn session_cookie(&self, value: String) -> Result<HeaderValue, Error> {
+        let cookie = self.cookie(value);
+        let mut jar = CookieJar::new();
+        match self.protection {
+            CookieProtection::Signed => jar.signed_mut(&self.key).add(cookie),
+            CookieProtection::Encrypted => jar.private_mut(&self.key).add(cookie),
+        }
+        let cookie = jar
+            .get(&self.cookie_name)
+            .expect("the jar holds the cookie just added");
+        HeaderValue::from_str(&cookie.encoded().to_string())
+            .map_err(|e| Error::internal(format!("invalid session cookie: {}", e)))
+    }
+
+    /// The `Set-Cookie` header that deletes the session cookie.
+    pub(crate) fn removal_cookie(&self) -> Result<HeaderValue, Error> {
+        let mut cookie = self.cookie(String::new());
+        cookie.make_removal();
+        HeaderValue::from_str(&cookie.encoded().to_string())
+            .map_err(|e| Error::internal(format!("invalid session cookie: {}", e)))
+    }
+
+    fn cookie(&self, value: String) -> Cookie<'static> {
+        let mut cookie = Cookie::build((self.cookie_name.clone(), value))
+            .path(self.path.clone())
+            .same_site(self.same_site.into())
+            .secure(self.secure)
+            .http_only(self.http_only)
+            .max_age(cookie::time::Duration::seconds(self.ttl.as_secs() as i64));
+        if let Some(domain) = &self.domain {
+            cookie = cookie.domain(domain.clone());
+        }
+        cookie.build()
+    }
+}
+
+impl fmt::Debug for SessionConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionConfig")
+            .field("cookie_name", &self.cookie_name)
+            .field("protection", &self.protection)
+            .field("same_site", &self.same_site)
+            .field("secure", &self.secure)
+            .field("http_only", &self.http_only)
+            .field("path", &self.path)
+            .field("domain", &self.domain)
+            .field("ttl", &self.ttl)
+            .field("cleanup_schedule", &self.cleanup_schedule)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::InMemorySessionStore;
+
+    const SECRET: &str = "0123456789abcdef0123456789abcdef";
+
+    fn cookie_header(set_cookie: &HeaderValue) -> HeaderMap {
+        let pair = set_cookie.to_str().unwrap().split(';').next().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(http::header::COOKIE, pair.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    #[should_panic(expected = "at least 32 bytes")]
+    fn test_short_secret_panics() {
+        SessionConfig::new("too-short", InMemorySessionStore::new());
+    }
+
+    #[test]
+    fn test_cookie_attributes() {
+        let config = SessionConfig::new(SECRET, InMemorySessionStore::new())
+            .with_cookie_name("sid")
+            .with_same_site(SameSite::Strict)
+            .with_domain("example.com")
+            .with_ttl(Duration::from_secs(60));
+
+        let set_cookie = config.session_cookie("abc".to_string()).unwrap();
+        let set_cookie = set_cookie.to_str().unwrap();
+        assert!(set_cookie.starts_with("sid="));
+        for attribute in [
+            "HttpOnly",
+            "SameSite=Strict",
+            "Secure",
+            "Path=/",
+            "Domain=example.com",
+            "Max-Age=60",
+        ] {
+            assert!(set_cookie.contains(attribute), "{set_cookie}");
+        }
+
+        let config = config.with_secure(false).with_http_only(false);
+        let set_cookie = config.session_cookie("abc".to_string()).unwrap();
+        let set_cookie = set_cookie.to_str().unwrap();
+        assert!(!set_cookie.contains("Secure"));
+        assert!(!set_cookie.contains("HttpOnly"));
+    }
+
+    #[test]
+    fn test_encrypted_cookie_round_trip() {
+        let config = SessionConfig::new(SECRET, InMemorySessionStore::new());
+        let set_cookie = config.session_cookie("session-id".to_string()).unwrap();
+        assert!(!set_cookie.to_str().unwrap().contains("session-id"));
+
+        let (present, value) = config.read_cookie(&cookie_header(&set_cookie));
+        assert!(present);
+        assert_eq!(value.as_deref(), Some("session-id"));
+    }
+
+    #[test]
+    fn test_signed_cookie_round_trip() {
+        let config = SessionConfig::new(SECRET, InMemorySessionStore::new())
+            .with_protection(CookieProtection::Signed);
+        let set_cookie = config.session_cookie("session-id".to_string()).unwrap();
+        assert!(set_cookie.to_str().unwrap().contains("session-id"));
+
+        let (_, value) = config.read_cookie(&cookie_header(&set_cookie));
+        assert_eq!(value.as_deref(), Some("session-id"));
+    }
+
+    #[test]
+    fn test_tampered_cookie_is_rejected() {
+        let config = SessionConfig::new(SECRET, InMemorySessionStore::new())
+            .with_protection(CookieProtection::Signed);
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            http::header::COOKIE,
+            "rapina_session=forged-id".parse().unwrap(),
+        );
+        assert_eq!(config.read_cookie(&headers), (true, None));
+
+        // A different secret can't read the cookie either
+        let set_cookie = config.session_cookie("session-id".to_string()).unwrap();
+        let other = SessionConfig::new(
+            "fedcba9876543210fedcba9876543210",
+            InMemorySessionStore::new(),
+        )
+        .with_protection(CookieProtection::Signed);
+        assert_eq!(other.read_cookie(&cookie_header(&set_cookie)), (true, None));
+
+        assert_eq!(config.read_cookie(&HeaderMap::new()), (false, None));
+    }
+
+    #[test]
+    fn test_removal_cookie() {
+        let config = SessionConfig::new(SECRET, InMemorySessionStore::new());
+        let removal = config.removal_cookie().unwrap();
+        let removal = removal.to_str().unwrap();
+        assert!(removal.starts_with("rapina_session=;"));
+        assert!(removal.contains("Max-Age=0"));
+    }
+}
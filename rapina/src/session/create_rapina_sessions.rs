@@ -0,0 +1,103 @@
+//! SeaORM migration for the sessions table.
+//!
+//! This is a framework-provided migration for
+//! [`SeaOrmSessionStore`](super::SeaOrmSessionStore). Register it in the
+//! project's migration list so it runs alongside application migrations:
+//!
+//! ```rust,ignore
+//! use rapina::session::create_rapina_sessions;
+//!
+//! rapina::migrations! {
+//!     create_rapina_sessions,   // framework table — sorts first
+//!     m20260315_000001_create_users,
+//! }
+//! ```
+//!
+//! The table uses only portable column types, so the store works on
+//! PostgreSQL, MySQL and SQLite.
+
+use crate::migration::prelude::*;
+
+/// Migration that creates the `rapina_sessions` table.
+///
+/// Implements [`MigrationName`](sea_orm_migration::MigrationName) manually
+/// (instead of `DeriveMigrationName`) to use a zero-timestamp prefix that
+/// sorts before all user migrations.
+pub struct Migration;
+
+impl sea_orm_migration::MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m00000000_000002_create_rapina_sessions"
+    }
+}
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RapinaSessions::Table)
+                    .col(
+                        ColumnDef::new(RapinaSessions::Id)
+                            .string_len(255)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(RapinaSessions::Data).text().not_null())
+                    .col(
+                        ColumnDef::new(RapinaSessions::ExpiresAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_rapina_sessions_expires_at")
+                    .table(RapinaSessions::Table)
+                    .col(RapinaSessions::ExpiresAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RapinaSessions::Table).to_owned())
+            .await
+    }
+}
+
+/// Column identifiers for the `rapina_sessions` table.
+///
+/// Holds each session's data as JSON, with the Unix timestamp at which it
+/// expires.
+#[derive(DeriveIden)]
+pub(crate) enum RapinaSessions {
+    Table,
+    Id,
+    Data,
+    ExpiresAt,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migration_name_sorts_after_revocations_and_before_user_migrations() {
+        let name = sea_orm_migration::MigrationName::name(&Migration);
+        assert_eq!(name, "m00000000_000002_create_rapina_sessions");
+        assert!("m00000000_000001_create_rapina_revocations" < name);
+        assert!(name < "m20260315_000001_create_users");
+    }
+
+    #[test]
+    fn iden_table_name() {
+        assert_eq!(RapinaSessions::Table.to_string(), "rapina_sessions");
+    }
+}
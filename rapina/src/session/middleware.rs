@@ -0,0 +1,106 @@
+//! Session middleware.
+
+use http::header::SET_COOKIE;
+use http::{HeaderValue, Request, Response};
+use hyper::body::Incoming;
+
+use crate::auth::now;
+use crate::context::RequestContext;
+use crate::error::Error;
+use crate::middleware::{BoxFuture, Middleware, Next};
+use crate::response::{BoxBody, IntoResponse};
+
+use super::{Session, SessionConfig};
+
+/// Loads the session a request's cookie refers to before the handler runs
+/// and saves it afterwards.
+///
+/// A session is saved, and its cookie set, when the handler changed it, and
+/// also once half its TTL has passed, so sessions in use don't expire. One
+/// left empty is deleted along with its cookie.
+#[derive(Debug, Clone)]
+pub struct SessionMiddleware {
+    config: SessionConfig,
+}
+
+impl SessionMiddleware {
+    pub fn new(config: SessionConfig) -> Self {
+        Self { config }
+    }
+
+    /// Persists the session after the handler, returning the `Set-Cookie`
+    /// header to send, if any.
+    async fn commit(
+        &self,
+        session: &Session,
+        had_cookie: bool,
+    ) -> Result<Option<HeaderValue>, Error> {
+        let (mut record, loaded, changed) = {
+            let state = session.state();
+            (state.record.clone(), state.loaded.clone(), state.changed)
+        };
+        let store = self.config.store();
+
+        // Regenerated or destroyed: the old id must stop working
+        if let Some(old) = &loaded
+            && *old != record.id
+        {
+            store.delete(old).await?;
+        }
+
+        if record.data.is_empty() {
+            if loaded.as_deref() == Some(record.id.as_str()) {
+                store.delete(&record.id).await?;
+            }
+            return if had_cookie {
+                self.config.removal_cookie().map(Some)
+            } else {
+                Ok(None)
+            };
+        }
+
+        let now = now();
+        let ttl = self.config.ttl().as_secs();
+        let stale = record.expires_at.saturating_sub(now) < ttl / 2;
+        if !changed && !stale {
+            return Ok(None);
+        }
+
+        record.expires_at = now + ttl;
+        let value = store.save(&record).await?;
+        self.config.session_cookie(value).map(Some)
+    }
+}
+
+impl Middleware for SessionMiddleware {
+    fn handle<'a>(
+        &'a self,
+        mut req: Request<Incoming>,
+        _ctx: &'a RequestContext,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, Response<BoxBody>> {
+        Box::pin(async move {
+            let (had_cookie, cookie) = self.config.read_cookie(req.headers());
+            let loaded = match cookie {
+                Some(cookie) => match self.config.store().load(&cookie).await {
+                    Ok(record) => record,
+                    Err(e) => return e.into_response(),
+                },
+                None => None,
+            };
+
+            let session = Session::new(loaded);
+            req.extensions_mut().insert(session.clone());
+            let mut response = next.run(req).await;
+
+            match self.commit(&session, had_cookie).await {
+                Ok(Some(set_cookie)) => {
+                    response.headers_mut().append(SET_COOKIE, set_cookie);
+                    response
+                }
+                Ok(None) => response,
+                Err(e) => e.into_response(),
+            }
+        })
+    }
+}
@@ -0,0 +1,258 @@
+//! Cookie-based login sessions.
+//!
+//! [`SessionMiddleware`] gives each browser a session, identified by a
+//! signed or encrypted cookie, and handlers read and change it through the
+//! [`Session`] extractor. Where sessions live is up to a [`SessionStore`]:
+//! in memory ([`InMemorySessionStore`]), in the cookie itself
+//! ([`CookieSessionStore`]) or, with the `database` feature, in the app's
+//! database ([`SeaOrmSessionStore`]).
+//!
+//! ```rust,ignore
+//! use rapina::prelude::*;
+//! use rapina::session::{InMemorySessionStore, Session, SessionConfig};
+//!
+//! #[post("/admin/login")]
+//! async fn login(session: Session, form: Form<LoginForm>) -> Result<StatusCode> {
+//!     let user = verify_password(&form).await?;
+//!     session.regenerate(); // new id once the user is known
+//!     session.insert("user_id", user.id)?;
+//!     Ok(StatusCode::NO_CONTENT)
+//! }
+//!
+//! #[get("/admin/dashboard")]
+//! async fn dashboard(session: Session) -> Result<Json<Dashboard>> {
+//!     let user_id: i64 = session
+//!         .get("user_id")?
+//!         .ok_or_else(|| Error::unauthorized("not signed in"))?;
+//!     Ok(Json(Dashboard::load(user_id).await?))
+//! }
+//!
+//! #[post("/admin/logout")]
+//! async fn logout(session: Session) -> StatusCode {
+//!     session.destroy();
+//!     StatusCode::NO_CONTENT
+//! }
+//!
+//! Rapina::new()
+//!     .with_sessions(SessionConfig::from_env(InMemorySessionStore::new())?)
+//! ```
+//!
+//! Changes are saved once the handler returns, and a session only gets a
+//! cookie once something has been inserted into it. Expired sessions are
+//! deleted from the store on [`SessionConfig::with_cleanup_schedule`]'s
+//! schedule.
+
+mod config;
+#[cfg(feature = "database")]
+pub mod create_rapina_sessions;
+mod middleware;
+mod store;
+#[cfg(feature = "database")]
+mod store_db;
+
+pub use config::{CookieProtection, SameSite, SessionConfig};
+pub use middleware::SessionMiddleware;
+pub use store::{
+    CookieSessionStore, InMemorySessionStore, SessionFuture, SessionRecord, SessionStore,
+};
+#[cfg(feature = "database")]
+pub use store_db::SeaOrmSessionStore;
+
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::error::Error;
+use crate::extract::{FromRequestParts, PathParams};
+use crate::state::AppState;
+
+/// The current request's session.
+///
+/// Requires [`Rapina::with_sessions`](crate::app::Rapina::with_sessions).
+/// Clones share the same session.
+#[derive(Debug, Clone)]
+pub struct Session {
+    state: Arc<Mutex<SessionState>>,
+}
+
+#[derive(Debug)]
+pub(crate) struct SessionState {
+    pub(crate) record: SessionRecord,
+    /// The id of the session the request came with, if any
+    pub(crate) loaded: Option<String>,
+    /// Whether the handler changed the session
+    pub(crate) changed: bool,
+}
+
+impl Session {
+    /// The session loaded for a request, or a new one.
+    pub(crate) fn new(loaded: Option<SessionRecord>) -> Self {
+        let state = match loaded {
+            Some(record) => SessionState {
+                loaded: Some(record.id.clone()),
+                record,
+                changed: false,
+            },
+            None => SessionState {
+                record: SessionRecord {
+                    id: new_id(),
+                    ..Default::default()
+                },
+                loaded: None,
+                changed: false,
+            },
+        };
+        Self {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    pub(crate) fn state(&self) -> MutexGuard<'_, SessionState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The session's id.
+    pub fn id(&self) -> String {
+        self.state().record.id.clone()
+    }
+
+    /// The value stored under `key`, if any.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, Error> {
+        let state = self.state();
+        let Some(value) = state.record.data.get(key) else {
+            return Ok(None);
+        };
+        T::deserialize(value)
+            .map(Some)
+            .map_err(|e| Error::internal(format!("invalid session value for `{}`: {}", key, e)))
+    }
+
+    /// Stores `value` under `key`.
+    pub fn insert<T: Serialize>(&self, key: impl Into<String>, value: T) -> Result<(), Error> {
+        let value = serde_json::to_value(value)
+            .map_err(|e| Error::internal(format!("failed to serialize session value: {}", e)))?;
+        let mut state = self.state();
+        state.record.data.insert(key.into(), value);
+        state.changed = true;
+        Ok(())
+    }
+
+    /// Removes the value stored under `key`, returning whether there was
+    /// one.
+    pub fn remove(&self, key: &str) -> bool {
+        let mut state = self.state();
+        let removed = state.record.data.remove(key).is_some();
+        state.changed |= removed;
+        removed
+    }
+
+    /// Moves the session to a new id, keeping its data.
+    ///
+    /// Call it when a user signs in or their privileges change, so an id an
+    /// attacker planted or saw beforehand is useless afterwards.
+    pub fn regenerate(&self) {
+        let mut state = self.state();
+        state.record.id = new_id();
+        state.changed = true;
+    }
+
+    /// Deletes the session and its cookie.
+    ///
+    /// Anything inserted afterwards starts a new session.
+    pub fn destroy(&self) {
+        let mut state = self.state();
+        state.record.id = new_id();
+        state.record.data.clear();
+        state.changed = true;
+    }
+}
+
+impl FromRequestParts for Session {
+    async fn from_request_parts(
+        parts: &http::request::Parts,
+        _params: &PathParams,
+        _state: &Arc<AppState>,
+    ) -> Result<Self, Error> {
+        parts.extensions.get::<Session>().cloned().ok_or_else(|| {
+            Error::internal("sessions are not configured; enable them with Rapina::with_sessions")
+        })
+    }
+}
+
+fn new_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_get_remove() {
+        let session = Session::new(None);
+        assert_eq!(session.get::<i64>("user_id").unwrap(), None);
+        assert!(!session.state().changed);
+
+        session.insert("user_id", 42).unwrap();
+        assert_eq!(session.get::<i64>("user_id").unwrap(), Some(42));
+        assert!(session.state().changed);
+
+        let err = session.get::<String>("user_id").unwrap_err();
+        assert_eq!(err.status(), 500);
+
+        assert!(session.remove("user_id"));
+        assert!(!session.remove("user_id"));
+        assert_eq!(session.get::<i64>("user_id").unwrap(), None);
+    }
+
+    #[test]
+    fn test_regenerate_keeps_data() {
+        let record = SessionRecord {
+            id: "old".to_string(),
+            data: [("user_id".to_string(), serde_json::json!(42))].into(),
+            expires_at: u64::MAX,
+        };
+        let session = Session::new(Some(record));
+        assert_eq!(session.id(), "old");
+
+        session.regenerate();
+        assert_ne!(session.id(), "old");
+        assert_eq!(session.get::<i64>("user_id").unwrap(), Some(42));
+        assert_eq!(session.state().loaded.as_deref(), Some("old"));
+    }
+
+    #[test]
+    fn test_destroy_clears_data() {
+        let record = SessionRecord {
+            id: "old".to_string(),
+            data: [("user_id".to_string(), serde_json::json!(42))].into(),
+            expires_at: u64::MAX,
+        };
+        let session = Session::new(Some(record));
+
+        session.destroy();
+        assert_ne!(session.id(), "old");
+        assert_eq!(session.get::<i64>("user_id").unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_extractor_requires_middleware() {
+        let (parts, _) = http::Request::new(()).into_parts();
+        let err =
+            Session::from_request_parts(&parts, &PathParams::new(), &Arc::new(AppState::new()))
+                .await
+                .unwrap_err();
+        assert_eq!(err.status(), 500);
+
+        let session = Session::new(None);
+        let mut req = http::Request::new(());
+        req.extensions_mut().insert(session.clone());
+        let (parts, _) = req.into_parts();
+        let extracted =
+            Session::from_request_parts(&parts, &PathParams::new(), &Arc::new(AppState::new()))
+                .await
+                .unwrap();
+        assert_eq!(extracted.id(), session.id());
+    }
+}
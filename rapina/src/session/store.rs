@@ -0,0 +1,229 @@
+//! Session storage backends.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::now;
+use crate::error::Error;
+
+/// A boxed future for trait object compatibility.
+pub type SessionFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Largest session, as JSON, that [`CookieSessionStore`] accepts. Encrypted
+/// and base64-encoded, it still fits the 4096 bytes browsers allow a cookie.
+const MAX_COOKIE_DATA: usize = 2800;
+
+/// A session as it is stored.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionRecord {
+    /// The session's id, a random UUID
+    pub id: String,
+    /// The values set with [`Session::insert`](super::Session::insert)
+    pub data: HashMap<String, serde_json::Value>,
+    /// When the session expires, as a Unix timestamp
+    pub expires_at: u64,
+}
+
+impl SessionRecord {
+    /// Whether the session has expired.
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= now()
+    }
+}
+
+/// Trait for session storage backends.
+///
+/// Uses `BoxFuture` returns for `dyn SessionStore` compatibility, like
+/// [`RevocationStore`](crate::auth::RevocationStore). What the session
+/// cookie holds is up to the store: server-side stores put the session id
+/// in it, [`CookieSessionStore`] the whole session. Either way the
+/// middleware signs or encrypts it.
+pub trait SessionStore: Send + Sync + 'static {
+    /// Loads the session a cookie refers to, given the cookie's verified
+    /// value. Expired sessions are not returned.
+    fn load(&self, cookie: &str) -> SessionFuture<'_, Result<Option<SessionRecord>, Error>>;
+
+    /// Saves `record`, replacing any session with the same id, and returns
+    /// the value for the session cookie.
+    fn save(&self, record: &SessionRecord) -> SessionFuture<'_, Result<String, Error>>;
+
+    /// Deletes the session with the given id.
+    fn delete(&self, id: &str) -> SessionFuture<'_, Result<(), Error>>;
+
+    /// Deletes expired sessions, returning how many were removed.
+    ///
+    /// [`Rapina::with_sessions`](crate::app::Rapina::with_sessions) calls
+    /// this on the configured cleanup schedule.
+    fn cleanup_expired(&self) -> SessionFuture<'_, Result<u64, Error>>;
+}
+
+/// In-process sessions in a `DashMap`.
+///
+/// Sessions are lost on restart and each instance of the app keeps its
+/// own, so behind a load balancer use a shared store, such as the SeaORM
+/// one, or sticky sessions.
+///
+/// # Example
+///
+/// ```ignore
+/// Rapina::new()
+///     .with_sessions(SessionConfig::from_env(InMemorySessionStore::new())?)
+/// ```
+#[derive(Debug, Default)]
+pub struct InMemorySessionStore {
+    sessions: DashMap<String, SessionRecord>,
+}
+
+impl InMemorySessionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SessionStore for InMemorySessionStore {
+    fn load(&self, cookie: &str) -> SessionFuture<'_, Result<Option<SessionRecord>, Error>> {
+        let record = self
+            .sessions
+            .get(cookie)
+            .map(|record| record.clone())
+            .filter(|record| !record.is_expired());
+        Box::pin(async move { Ok(record) })
+    }
+
+    fn save(&self, record: &SessionRecord) -> SessionFuture<'_, Result<String, Error>> {
+        self.sessions.insert(record.id.clone(), record.clone());
+        let id = record.id.clone();
+        Box::pin(async move { Ok(id) })
+    }
+
+    fn delete(&self, id: &str) -> SessionFuture<'_, Result<(), Error>> {
+        self.sessions.remove(id);
+        Box::pin(async { Ok(()) })
+    }
+
+    fn cleanup_expired(&self) -> SessionFuture<'_, Result<u64, Error>> {
+        let now = now();
+        let before = self.sessions.len();
+        self.sessions.retain(|_, record| record.expires_at > now);
+        let removed = before.saturating_sub(self.sessions.len()) as u64;
+        Box::pin(async move { Ok(removed) })
+    }
+}
+
+/// Sessions kept entirely in the session cookie.
+///
+/// Nothing is stored on the server, so it scales without shared state, but
+/// a session can't be revoked before it expires: [`Session::destroy`]
+/// deletes the cookie from the browser, not copies of it taken elsewhere.
+/// Sessions are limited to about 2.8 KB of JSON.
+///
+/// [`Session::destroy`]: super::Session::destroy
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CookieSessionStore;
+
+impl CookieSessionStore {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl SessionStore for CookieSessionStore {
+    fn load(&self, cookie: &str) -> SessionFuture<'_, Result<Option<SessionRecord>, Error>> {
+        let record = serde_json::from_str::<SessionRecord>(cookie)
+            .ok()
+            .filter(|record| !record.is_expired());
+        Box::pin(async move { Ok(record) })
+    }
+
+    fn save(&self, record: &SessionRecord) -> SessionFuture<'_, Result<String, Error>> {
+        let cookie = serde_json::to_string(record)
+            .map_err(|e| Error::internal(format!("failed to serialize session: {}", e)))
+            .and_then(|cookie| {
+                if cookie.len() > MAX_COOKIE_DATA {
+                    return Err(Error::internal(format!(
+                        "session is too large for a cookie ({} bytes, at most {})",
+                        cookie.len(),
+                        MAX_COOKIE_DATA
+                    )));
+                }
+                Ok(cookie)
+            });
+        Box::pin(async move { cookie })
+    }
+
+    fn delete(&self, _id: &str) -> SessionFuture<'_, Result<(), Error>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn cleanup_expired(&self) -> SessionFuture<'_, Result<u64, Error>> {
+        Box::pin(async { Ok(0) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(id: &str, expires_at: u64) -> SessionRecord {
+        SessionRecord {
+            id: id.to_string(),
+            data: HashMap::from([("user_id".to_string(), serde_json::json!(42))]),
+            expires_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store() {
+        let store = InMemorySessionStore::new();
+        let session = record("s1", now() + 60);
+
+        let cookie = store.save(&session).await.unwrap();
+        assert_eq!(cookie, "s1");
+        assert_eq!(store.load("s1").await.unwrap(), Some(session));
+
+        store.delete("s1").await.unwrap();
+        assert_eq!(store.load("s1").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_cleanup_expired() {
+        let store = InMemorySessionStore::new();
+        store.save(&record("live", now() + 60)).await.unwrap();
+        store.save(&record("expired", now() - 1)).await.unwrap();
+
+        assert_eq!(store.load("expired").await.unwrap(), None);
+        assert_eq!(store.cleanup_expired().await.unwrap(), 1);
+        assert_eq!(store.sessions.len(), 1);
+        assert!(store.load("live").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_cookie_store_round_trip() {
+        let store = CookieSessionStore::new();
+        let session = record("s1", now() + 60);
+
+        let cookie = store.save(&session).await.unwrap();
+        assert_eq!(store.load(&cookie).await.unwrap(), Some(session));
+
+        let expired = store.save(&record("s2", now() - 1)).await.unwrap();
+        assert_eq!(store.load(&expired).await.unwrap(), None);
+        assert_eq!(store.load("not json").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_cookie_store_rejects_large_sessions() {
+        let mut session = record("s1", now() + 60);
+        session.data.insert(
+            "blob".to_string(),
+            serde_json::json!("x".repeat(MAX_COOKIE_DATA)),
+        );
+
+        let err = CookieSessionStore::new().save(&session).await.unwrap_err();
+        assert_eq!(err.status(), 500);
+        assert!(err.message().contains("too large"));
+    }
+}
@@ -0,0 +1,135 @@
+//! SeaORM-backed session store.
+
+use std::collections::HashMap;
+
+use sea_orm::sea_query::{Expr, OnConflict, Query};
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr};
+
+use crate::auth::now;
+use crate::database::DbError;
+use crate::error::{Error, IntoApiError};
+
+use super::create_rapina_sessions::RapinaSessions;
+use super::store::{SessionFuture, SessionRecord, SessionStore};
+
+/// Sessions kept in the app's database, shared by every replica.
+///
+/// The table comes from the
+/// [`create_rapina_sessions`](super::create_rapina_sessions) migration, and
+/// the session cookie holds only the session's id.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let conn = DatabaseConfig::from_env()?.connect().await?;
+/// Rapina::new()
+///     .with_sessions(SessionConfig::from_env(SeaOrmSessionStore::new(conn))?)
+/// ```
+#[derive(Debug, Clone)]
+pub struct SeaOrmSessionStore {
+    conn: DatabaseConnection,
+}
+
+impl SeaOrmSessionStore {
+    /// Creates a store on the given connection pool.
+    pub fn new(conn: DatabaseConnection) -> Self {
+        Self { conn }
+    }
+
+    async fn select(&self, id: String) -> Result<Option<SessionRecord>, DbErr> {
+        let select = Query::select()
+            .columns([RapinaSessions::Data, RapinaSessions::ExpiresAt])
+            .from(RapinaSessions::Table)
+            .and_where(Expr::col(RapinaSessions::Id).eq(id.clone()))
+            .and_where(Expr::col(RapinaSessions::ExpiresAt).gt(now() as i64))
+            .to_owned();
+        let backend = self.conn.get_database_backend();
+        let Some(row) = self.conn.query_one(backend.build(&select)).await? else {
+            return Ok(None);
+        };
+
+        let data: String = row.try_get("", "data")?;
+        let expires_at: i64 = row.try_get("", "expires_at")?;
+        let data: HashMap<String, serde_json::Value> =
+            serde_json::from_str(&data).map_err(|e| DbErr::Json(e.to_string()))?;
+        Ok(Some(SessionRecord {
+            id,
+            data,
+            expires_at: expires_at as u64,
+        }))
+    }
+
+    async fn upsert(&self, record: SessionRecord) -> Result<String, DbErr> {
+        let data = serde_json::to_string(&record.data).map_err(|e| DbErr::Json(e.to_string()))?;
+        let upsert = Query::insert()
+            .into_table(RapinaSessions::Table)
+            .columns([
+                RapinaSessions::Id,
+                RapinaSessions::Data,
+                RapinaSessions::ExpiresAt,
+            ])
+            .values_panic([
+                record.id.clone().into(),
+                data.into(),
+                (record.expires_at as i64).into(),
+            ])
+            .on_conflict(
+                OnConflict::column(RapinaSessions::Id)
+                    .update_columns([RapinaSessions::Data, RapinaSessions::ExpiresAt])
+                    .to_owned(),
+            )
+            .to_owned();
+        self.conn
+            .execute(self.conn.get_database_backend().build(&upsert))
+            .await?;
+        Ok(record.id)
+    }
+
+    async fn remove(&self, id: String) -> Result<(), DbErr> {
+        let delete = Query::delete()
+            .from_table(RapinaSessions::Table)
+            .and_where(Expr::col(RapinaSessions::Id).eq(id))
+            .to_owned();
+        self.conn
+            .execute(self.conn.get_database_backend().build(&delete))
+            .await?;
+        Ok(())
+    }
+
+    async fn purge_expired(&self) -> Result<u64, DbErr> {
+        let delete = Query::delete()
+            .from_table(RapinaSessions::Table)
+            .and_where(Expr::col(RapinaSessions::ExpiresAt).lte(now() as i64))
+            .to_owned();
+        let result = self
+            .conn
+            .execute(self.conn.get_database_backend().build(&delete))
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+fn store_error(e: DbErr) -> Error {
+    DbError(e).into_api_error()
+}
+
+impl SessionStore for SeaOrmSessionStore {
+    fn load(&self, cookie: &str) -> SessionFuture<'_, Result<Option<SessionRecord>, Error>> {
+        let id = cookie.to_string();
+        Box::pin(async move { self.select(id).await.map_err(store_error) })
+    }
+
+    fn save(&self, record: &SessionRecord) -> SessionFuture<'_, Result<String, Error>> {
+        let record = record.clone();
+        Box::pin(async move { self.upsert(record).await.map_err(store_error) })
+    }
+
+    fn delete(&self, id: &str) -> SessionFuture<'_, Result<(), Error>> {
+        let id = id.to_string();
+        Box::pin(async move { self.remove(id).await.map_err(store_error) })
+    }
+
+    fn cleanup_expired(&self) -> SessionFuture<'_, Result<u64, Error>> {
+        Box::pin(async move { self.purge_expired().await.map_err(store_error) })
+    }
+}
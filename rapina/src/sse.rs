@@ -0,0 +1,539 @@
+//! Server-Sent Events.
+//!
+//! Return an [`Sse`] from a handler to push a stream of [`Event`]s to the
+//! client over a single long-lived response. Browsers consume it with
+//! `EventSource`, which reconnects on its own and sends back the id of the
+//! last event it saw; read it with the [`LastEventId`] extractor to resume
+//! where the client left off.
+//!
+//! ```rust,ignore
+//! use rapina::prelude::*;
+//! use rapina::sse::{Event, LastEventId, Sse};
+//!
+//! #[get("/ticks")]
+//! async fn ticks(last: LastEventId) -> Sse<TickStream> {
+//!     let start = last.0.and_then(|id| id.parse().ok()).unwrap_or(0);
+//!     Sse::new(TickStream::starting_at(start))
+//! }
+//! ```
+//!
+//! Routes registered with the route macros are documented as
+//! `text/event-stream` in the OpenAPI spec.
+
+use std::borrow::Cow;
+use std::fmt::Write as _;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures_core::Stream;
+use http::{HeaderValue, Response, header};
+use hyper::body::{Body, Frame};
+use serde::Serialize;
+use tokio::time::{Instant, Sleep};
+
+use crate::error::Error;
+use crate::extract::{FromRequestParts, PathParams};
+use crate::response::{BoxBody, BoxError, IntoResponse};
+use crate::state::AppState;
+
+/// Content type of a Server-Sent Events response.
+pub const TEXT_EVENT_STREAM: &str = "text/event-stream";
+
+const DEFAULT_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// A single Server-Sent Event.
+///
+/// Built up field by field; every field is optional. Multi-line data is sent
+/// as one `data:` line per line and joined back together by the client.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use rapina::sse::Event;
+///
+/// let event = Event::default()
+///     .id("42")
+///     .event("order")
+///     .data("shipped")
+///     .retry(Duration::from_secs(5));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Event {
+    id: Option<String>,
+    event: Option<String>,
+    data: Option<String>,
+    retry: Option<Duration>,
+    comment: Option<String>,
+}
+
+impl Event {
+    /// Sets the event id, which the client sends back in `Last-Event-ID`
+    /// when it reconnects.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` contains a newline, carriage return or NUL character.
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        let id = id.into();
+        assert_single_line("id", &id);
+        assert!(!id.contains('\0'), "SSE event id must not contain NUL");
+        self.id = Some(id);
+        self
+    }
+
+    /// Sets the event type, which selects the `EventSource` listener that
+    /// receives it. Events without a type are dispatched as `message`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `event` contains a newline or carriage return.
+    pub fn event(mut self, event: impl Into<String>) -> Self {
+        let event = event.into();
+        assert_single_line("event", &event);
+        self.event = Some(event);
+        self
+    }
+
+    /// Sets the event data.
+    pub fn data(mut self, data: impl Into<String>) -> Self {
+        self.data = Some(data.into());
+        self
+    }
+
+    /// Sets the event data to `value` serialized as JSON.
+    pub fn json_data<T: Serialize>(self, value: &T) -> Result<Self, serde_json::Error> {
+        Ok(self.data(serde_json::to_string(value)?))
+    }
+
+    /// Sets how long the client waits before reconnecting after the
+    /// connection drops.
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Adds a comment line, which clients ignore.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `comment` contains a newline or carriage return.
+    pub fn comment(mut self, comment: impl Into<String>) -> Self {
+        let comment = comment.into();
+        assert_single_line("comment", &comment);
+        self.comment = Some(comment);
+        self
+    }
+
+    /// Encodes the event in the `text/event-stream` wire format.
+    fn encode(&self) -> Bytes {
+        let mut buf = String::new();
+        if let Some(comment) = &self.comment {
+            let _ = writeln!(buf, ": {}", comment);
+        }
+        if let Some(event) = &self.event {
+            let _ = writeln!(buf, "event: {}", event);
+        }
+        if let Some(data) = &self.data {
+            for line in data.split("\r\n").flat_map(|l| l.split(['\n', '\r'])) {
+                let _ = writeln!(buf, "data: {}", line);
+            }
+        }
+        if let Some(id) = &self.id {
+            let _ = writeln!(buf, "id: {}", id);
+        }
+        if let Some(retry) = self.retry {
+            let _ = writeln!(buf, "retry: {}", retry.as_millis());
+        }
+        buf.push('\n');
+        Bytes::from(buf)
+    }
+}
+
+fn assert_single_line(field: &str, value: &str) {
+    assert!(
+        !value.contains(['\n', '\r']),
+        "SSE event {} must not contain newlines",
+        field
+    );
+}
+
+/// Keep-alive comments sent while a [`Sse`] stream is idle.
+///
+/// Proxies and load balancers tend to close connections that stay quiet for
+/// too long. A comment line every `interval` keeps the connection open
+/// without the client seeing an event. The timer restarts after every event.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use rapina::sse::KeepAlive;
+///
+/// let keep_alive = KeepAlive::new()
+///     .interval(Duration::from_secs(30))
+///     .text("ping");
+/// ```
+#[derive(Debug, Clone)]
+pub struct KeepAlive {
+    interval: Duration,
+    text: Cow<'static, str>,
+}
+
+impl KeepAlive {
+    /// Creates a keep-alive that sends an empty comment every 15 seconds.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how long the stream may stay idle before a comment is sent.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sets the text of the comment.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `text` contains a newline or carriage return.
+    pub fn text(mut self, text: impl Into<Cow<'static, str>>) -> Self {
+        let text = text.into();
+        assert_single_line("keep-alive text", &text);
+        self.text = text;
+        self
+    }
+
+    fn comment(&self) -> Bytes {
+        if self.text.is_empty() {
+            Bytes::from_static(b":\n\n")
+        } else {
+            Bytes::from(format!(": {}\n\n", self.text))
+        }
+    }
+}
+
+impl Default for KeepAlive {
+    fn default() -> Self {
+        Self {
+            interval: DEFAULT_KEEP_ALIVE_INTERVAL,
+            text: Cow::Borrowed(""),
+        }
+    }
+}
+
+/// A Server-Sent Events response.
+///
+/// Wraps a stream of `Result<Event, E>` and sends each event as soon as the
+/// stream yields it. An error from the stream ends the response. Keep-alive
+/// comments are sent every 15 seconds by default; change that with
+/// [`keep_alive`](Self::keep_alive) or turn it off with
+/// [`no_keep_alive`](Self::no_keep_alive).
+///
+/// The response is sent with `Cache-Control: no-cache` and
+/// `X-Accel-Buffering: no`, so nginx passes events through instead of
+/// buffering them, and is never compressed by `CompressionMiddleware`.
+pub struct Sse<S> {
+    stream: S,
+    keep_alive: Option<KeepAlive>,
+}
+
+impl<S> Sse<S> {
+    /// Creates an SSE response from a stream of events.
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream,
+            keep_alive: Some(KeepAlive::default()),
+        }
+    }
+
+    /// Configures the keep-alive comments.
+    pub fn keep_alive(mut self, keep_alive: KeepAlive) -> Self {
+        self.keep_alive = Some(keep_alive);
+        self
+    }
+
+    /// Disables keep-alive comments.
+    pub fn no_keep_alive(mut self) -> Self {
+        self.keep_alive = None;
+        self
+    }
+}
+
+impl<S> std::fmt::Debug for Sse<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Sse")
+            .field("keep_alive", &self.keep_alive)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S, E> IntoResponse for Sse<S>
+where
+    S: Stream<Item = Result<Event, E>> + Send + 'static,
+    E: Into<BoxError>,
+{
+    fn into_response(self) -> Response<BoxBody> {
+        let keep_alive = self.keep_alive.map(|keep_alive| KeepAliveTimer {
+            comment: keep_alive.comment(),
+            interval: keep_alive.interval,
+            sleep: Box::pin(tokio::time::sleep(keep_alive.interval)),
+        });
+        let body = SseBody {
+            stream: Box::pin(self.stream),
+            keep_alive,
+        };
+
+        let mut response = Response::new(BoxBody::new(body));
+        let headers = response.headers_mut();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static(TEXT_EVENT_STREAM),
+        );
+        headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+        headers.insert("x-accel-buffering", HeaderValue::from_static("no"));
+        response
+    }
+}
+
+struct KeepAliveTimer {
+    comment: Bytes,
+    interval: Duration,
+    sleep: Pin<Box<Sleep>>,
+}
+
+impl KeepAliveTimer {
+    fn reset(&mut self) {
+        self.sleep.as_mut().reset(Instant::now() + self.interval);
+    }
+}
+
+struct SseBody<S> {
+    stream: Pin<Box<S>>,
+    keep_alive: Option<KeepAliveTimer>,
+}
+
+impl<S, E> Body for SseBody<S>
+where
+    S: Stream<Item = Result<Event, E>>,
+    E: Into<BoxError>,
+{
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, BoxError>>> {
+        let this = self.get_mut();
+
+        match this.stream.as_mut().poll_next(cx) {
+            Poll::Ready(Some(Ok(event))) => {
+                if let Some(keep_alive) = &mut this.keep_alive {
+                    keep_alive.reset();
+                }
+                return Poll::Ready(Some(Ok(Frame::data(event.encode()))));
+            }
+            Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e.into()))),
+            Poll::Ready(None) => return Poll::Ready(None),
+            Poll::Pending => {}
+        }
+
+        if let Some(keep_alive) = &mut this.keep_alive {
+            if keep_alive.sleep.as_mut().poll(cx).is_ready() {
+                keep_alive.reset();
+                return Poll::Ready(Some(Ok(Frame::data(keep_alive.comment.clone()))));
+            }
+        }
+
+        Poll::Pending
+    }
+}
+
+/// The `Last-Event-ID` header a reconnecting `EventSource` sends.
+///
+/// Holds the id of the last event the client received, or `None` on the
+/// first connection. Never fails.
+///
+/// # Examples
+///
+/// ```ignore
+/// use rapina::prelude::*;
+/// use rapina::sse::LastEventId;
+///
+/// #[get("/events")]
+/// async fn events(last: LastEventId) -> String {
+///     format!("resuming after {:?}", last.0)
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LastEventId(pub Option<String>);
+
+impl FromRequestParts for LastEventId {
+    async fn from_request_parts(
+        parts: &http::request::Parts,
+        _params: &PathParams,
+        _state: &Arc<AppState>,
+    ) -> Result<Self, Error> {
+        Ok(LastEventId(
+            parts
+                .headers
+                .get("last-event-id")
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+    use std::future::poll_fn;
+
+    struct Events(Vec<Event>);
+
+    impl Stream for Events {
+        type Item = Result<Event, Infallible>;
+
+        fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            if self.0.is_empty() {
+                Poll::Ready(None)
+            } else {
+                Poll::Ready(Some(Ok(self.0.remove(0))))
+            }
+        }
+    }
+
+    /// Never yields, so only keep-alive comments come through.
+    struct Idle;
+
+    impl Stream for Idle {
+        type Item = Result<Event, Infallible>;
+
+        fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            Poll::Pending
+        }
+    }
+
+    async fn next_chunk(body: &mut BoxBody) -> Option<Bytes> {
+        let frame = poll_fn(|cx| Pin::new(&mut *body).poll_frame(cx)).await?;
+        Some(frame.unwrap().into_data().unwrap())
+    }
+
+    #[test]
+    fn test_encode_all_fields() {
+        let event = Event::default()
+            .comment("hi")
+            .id("7")
+            .event("update")
+            .data("payload")
+            .retry(Duration::from_millis(2500));
+        assert_eq!(
+            event.encode(),
+            ": hi\nevent: update\ndata: payload\nid: 7\nretry: 2500\n\n"
+        );
+    }
+
+    #[test]
+    fn test_encode_multiline_data() {
+        let event = Event::default().data("one\ntwo\r\nthree\rfour");
+        assert_eq!(
+            event.encode(),
+            "data: one\ndata: two\ndata: three\ndata: four\n\n"
+        );
+    }
+
+    #[test]
+    fn test_encode_json_data() {
+        let event = Event::default()
+            .json_data(&serde_json::json!({"id": 1}))
+            .unwrap();
+        assert_eq!(event.encode(), "data: {\"id\":1}\n\n");
+    }
+
+    #[test]
+    #[should_panic(expected = "must not contain newlines")]
+    fn test_event_type_with_newline_panics() {
+        let _ = Event::default().event("a\nb");
+    }
+
+    #[test]
+    #[should_panic(expected = "must not contain newlines")]
+    fn test_id_with_newline_panics() {
+        let _ = Event::default().id("1\r2");
+    }
+
+    #[test]
+    fn test_keep_alive_comment() {
+        assert_eq!(KeepAlive::new().comment(), ":\n\n");
+        assert_eq!(KeepAlive::new().text("ping").comment(), ": ping\n\n");
+    }
+
+    #[tokio::test]
+    async fn test_response_headers() {
+        let response = Sse::new(Events(Vec::new())).into_response();
+        assert_eq!(response.headers()["content-type"], TEXT_EVENT_STREAM);
+        assert_eq!(response.headers()["cache-control"], "no-cache");
+        assert_eq!(response.headers()["x-accel-buffering"], "no");
+        assert!(response.body().size_hint().exact().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_body_yields_events_then_ends() {
+        let stream = Events(vec![Event::default().data("a"), Event::default().data("b")]);
+        let mut body = Sse::new(stream).into_response().into_body();
+
+        assert_eq!(next_chunk(&mut body).await.unwrap(), "data: a\n\n");
+        assert_eq!(next_chunk(&mut body).await.unwrap(), "data: b\n\n");
+        assert!(next_chunk(&mut body).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_keep_alive_sent_while_idle() {
+        let sse = Sse::new(Idle).keep_alive(
+            KeepAlive::new()
+                .interval(Duration::from_millis(50))
+                .text("ping"),
+        );
+        let mut body = sse.into_response().into_body();
+
+        let started = Instant::now();
+        assert_eq!(next_chunk(&mut body).await.unwrap(), ": ping\n\n");
+        assert!(started.elapsed() >= Duration::from_millis(50));
+        assert_eq!(next_chunk(&mut body).await.unwrap(), ": ping\n\n");
+        assert!(started.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn test_no_keep_alive_stays_silent() {
+        let mut body = Sse::new(Idle).no_keep_alive().into_response().into_body();
+
+        let chunk = tokio::time::timeout(Duration::from_millis(100), next_chunk(&mut body)).await;
+        assert!(chunk.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_last_event_id() {
+        let state = Arc::new(AppState::new());
+        let (parts, _) = http::Request::builder()
+            .header("Last-Event-ID", "41")
+            .body(())
+            .unwrap()
+            .into_parts();
+        let last = LastEventId::from_request_parts(&parts, &PathParams::new(), &state)
+            .await
+            .unwrap();
+        assert_eq!(last, LastEventId(Some("41".to_string())));
+
+        let (parts, _) = http::Request::new(()).into_parts();
+        let last = LastEventId::from_request_parts(&parts, &PathParams::new(), &state)
+            .await
+            .unwrap();
+        assert_eq!(last, LastEventId(None));
+    }
+}
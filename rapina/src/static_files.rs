@@ -0,0 +1,506 @@
+//! Serving files from disk.
+//!
+//! [`ServeDir`] serves a directory tree and [`ServeFile`] a single file.
+//! Both are [`Handler`]s, so they mount like any other route: on a
+//! catch-all route, where the captured path picks the file, or as a whole
+//! router under a prefix with [`ServeDir::into_router`].
+//!
+//! ```rust,ignore
+//! use rapina::prelude::*;
+//! use rapina::static_files::{ServeDir, ServeFile};
+//!
+//! let router = Router::new()
+//!     .get("/assets/*path", ServeDir::new("assets").precompressed_br())
+//!     .get("/favicon.ico", ServeFile::new("assets/favicon.ico"))
+//!     .group("/admin", ServeDir::new("admin/dist").spa_fallback().into_router());
+//! ```
+//!
+//! Responses carry `Content-Type` guessed from the file extension, plus
+//! `ETag` and `Last-Modified` validators. `If-None-Match` and
+//! `If-Modified-Since` are answered with `304 Not Modified`, and a single
+//! `Range` with `206 Partial Content`.
+
+use std::future::Future;
+use std::io::SeekFrom;
+use std::path::{Component, Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use http::header::{
+    ACCEPT_ENCODING, ACCEPT_RANGES, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE,
+    ETAG, IF_RANGE, LAST_MODIFIED, RANGE, VARY,
+};
+use http::{HeaderMap, HeaderValue, Request, Response, StatusCode};
+use httpdate::HttpDate;
+use hyper::body::Incoming;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use crate::conditional::{ETag, is_not_modified, not_modified};
+use crate::context::RequestContext;
+use crate::error::Error;
+use crate::extract::PathParams;
+use crate::handler::Handler;
+use crate::middleware::accept_encoding::AcceptEncoding;
+use crate::response::{BoxBody, IntoResponse};
+use crate::router::Router;
+use crate::state::AppState;
+
+type BoxFuture = Pin<Box<dyn Future<Output = Response<BoxBody>> + Send>>;
+
+const INDEX_FILE: &str = "index.html";
+
+/// Which precompressed siblings to look for, in order of preference.
+#[derive(Debug, Clone, Copy, Default)]
+struct Precompressed {
+    br: bool,
+    gzip: bool,
+}
+
+impl Precompressed {
+    fn any(&self) -> bool {
+        self.br || self.gzip
+    }
+
+    /// The encodings to try for a request, best first.
+    fn candidates(&self, accept_encoding: &str) -> Vec<(&'static str, &'static str)> {
+        let accept = AcceptEncoding::parse(accept_encoding);
+        let mut candidates = Vec::new();
+        if self.br && accept.accepts("br") {
+            candidates.push(("br", "br"));
+        }
+        if self.gzip && accept.accepts("gzip") {
+            candidates.push(("gzip", "gz"));
+        }
+        candidates
+    }
+}
+
+/// Serves files from a directory.
+///
+/// The file is picked by the route's last path parameter, normally a
+/// trailing `*path` catch-all, resolved against the root directory. Paths
+/// with `..` segments or other components that would leave the root are
+/// rejected with 404. A request for a directory serves its `index.html`.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use rapina::prelude::*;
+/// use rapina::static_files::ServeDir;
+///
+/// // GET /reports/2024/q1.pdf → ./reports/2024/q1.pdf
+/// let router = Router::new().get("/reports/*path", ServeDir::new("reports"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ServeDir {
+    root: PathBuf,
+    precompressed: Precompressed,
+    spa_fallback: bool,
+}
+
+impl ServeDir {
+    /// Creates a handler that serves files below `root`.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            precompressed: Precompressed::default(),
+            spa_fallback: false,
+        }
+    }
+
+    /// Serves `file.gz` in place of `file` when it exists and the client
+    /// accepts gzip.
+    pub fn precompressed_gzip(mut self) -> Self {
+        self.precompressed.gzip = true;
+        self
+    }
+
+    /// Serves `file.br` in place of `file` when it exists and the client
+    /// accepts brotli. Brotli is preferred over gzip when both are enabled.
+    pub fn precompressed_br(mut self) -> Self {
+        self.precompressed.br = true;
+        self
+    }
+
+    /// Serves the root `index.html` for paths that don't match a file, so a
+    /// single-page app can handle its own client-side routes.
+    pub fn spa_fallback(mut self) -> Self {
+        self.spa_fallback = true;
+        self
+    }
+
+    /// Wraps the handler in a router that answers both the mount point and
+    /// every path below it, for use with [`Router::group`].
+    ///
+    /// ```rust,ignore
+    /// let router = Router::new().group("/admin", ServeDir::new("dist").into_router());
+    /// // GET /admin          → dist/index.html
+    /// // GET /admin/app.js   → dist/app.js
+    /// ```
+    pub fn into_router(self) -> Router {
+        Router::new().get("/", self.clone()).get("/*path", self)
+    }
+
+    /// Maps the requested path onto a file below the root.
+    async fn resolve(&self, requested: &str) -> Option<PathBuf> {
+        let mut path = self.root.clone();
+        for segment in requested.split('/') {
+            let segment = percent_encoding::percent_decode_str(segment)
+                .decode_utf8()
+                .ok()?;
+            if segment.is_empty() || segment == "." {
+                continue;
+            }
+            if segment.contains(['/', '\\', '\0']) {
+                return None;
+            }
+            let mut components = Path::new(segment.as_ref()).components();
+            match (components.next(), components.next()) {
+                (Some(Component::Normal(name)), None) => path.push(name),
+                _ => return None,
+            }
+        }
+
+        match tokio::fs::metadata(&path).await {
+            Ok(meta) if meta.is_dir() => Some(path.join(INDEX_FILE)),
+            _ => Some(path),
+        }
+    }
+}
+
+impl Handler for ServeDir {
+    const NAME: &'static str = "serve_dir";
+
+    fn call(&self, req: Request<Incoming>, params: PathParams, _state: Arc<AppState>) -> BoxFuture {
+        let this = self.clone();
+        Box::pin(async move {
+            // The catch-all is always the route's last parameter.
+            let requested = params.iter().last().map(|(_, v)| v.as_str()).unwrap_or("");
+
+            if let Some(path) = this.resolve(requested).await {
+                if let Some(response) = serve_file(&req, &path, this.precompressed).await {
+                    return response;
+                }
+            }
+            if this.spa_fallback {
+                let index = this.root.join(INDEX_FILE);
+                if let Some(response) = serve_file(&req, &index, this.precompressed).await {
+                    return response;
+                }
+            }
+            not_found(&req)
+        })
+    }
+}
+
+/// Serves a single file, whatever the request path.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use rapina::prelude::*;
+/// use rapina::static_files::ServeFile;
+///
+/// let router = Router::new().get("/robots.txt", ServeFile::new("static/robots.txt"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ServeFile {
+    path: PathBuf,
+    precompressed: Precompressed,
+}
+
+impl ServeFile {
+    /// Creates a handler that serves the file at `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            precompressed: Precompressed::default(),
+        }
+    }
+
+    /// Serves `file.gz` in place of the file when it exists and the client
+    /// accepts gzip.
+    pub fn precompressed_gzip(mut self) -> Self {
+        self.precompressed.gzip = true;
+        self
+    }
+
+    /// Serves `file.br` in place of the file when it exists and the client
+    /// accepts brotli.
+    pub fn precompressed_br(mut self) -> Self {
+        self.precompressed.br = true;
+        self
+    }
+}
+
+impl Handler for ServeFile {
+    const NAME: &'static str = "serve_file";
+
+    fn call(
+        &self,
+        req: Request<Incoming>,
+        _params: PathParams,
+        _state: Arc<AppState>,
+    ) -> BoxFuture {
+        let this = self.clone();
+        Box::pin(async move {
+            match serve_file(&req, &this.path, this.precompressed).await {
+                Some(response) => response,
+                None => not_found(&req),
+            }
+        })
+    }
+}
+
+fn not_found(req: &Request<Incoming>) -> Response<BoxBody> {
+    let mut error = Error::not_found(format!("no file at {}", req.uri().path()));
+    if let Some(ctx) = req.extensions().get::<RequestContext>() {
+        error = error.with_trace_id(ctx.trace_id());
+    }
+    error.into_response()
+}
+
+/// A file opened for serving, possibly a precompressed sibling.
+struct OpenFile {
+    file: tokio::fs::File,
+    len: u64,
+    modified: Option<SystemTime>,
+    encoding: Option<&'static str>,
+}
+
+async fn open(path: &Path, encoding: Option<&'static str>) -> Option<OpenFile> {
+    let file = tokio::fs::File::open(path).await.ok()?;
+    let meta = file.metadata().await.ok()?;
+    if !meta.is_file() {
+        return None;
+    }
+    Some(OpenFile {
+        file,
+        len: meta.len(),
+        modified: meta.modified().ok(),
+        encoding,
+    })
+}
+
+/// Opens the best precompressed variant of `path` the client accepts,
+/// falling back to `path` itself.
+async fn open_variant(
+    path: &Path,
+    precompressed: Precompressed,
+    headers: &HeaderMap,
+) -> Option<OpenFile> {
+    let accept_encoding = headers
+        .get(ACCEPT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    for (encoding, extension) in precompressed.candidates(accept_encoding) {
+        let mut sibling = path.as_os_str().to_owned();
+        sibling.push(".");
+        sibling.push(extension);
+        if let Some(file) = open(Path::new(&sibling), Some(encoding)).await {
+            return Some(file);
+        }
+    }
+    open(path, None).await
+}
+
+/// Serves the file at `path`, or returns `None` if there is no such file.
+async fn serve_file(
+    req: &Request<Incoming>,
+    path: &Path,
+    precompressed: Precompressed,
+) -> Option<Response<BoxBody>> {
+    let headers = req.headers();
+    let OpenFile {
+        mut file,
+        len,
+        modified,
+        encoding,
+    } = open_variant(path, precompressed, headers).await?;
+
+    let etag = modified.map(|modified| {
+        let nanos = modified
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        ETag::strong(format!("{:x}-{:x}", len, nanos))
+    });
+    let last_modified = modified.map(HttpDate::from);
+
+    let mut response = Response::new(BoxBody::empty());
+    let response_headers = response.headers_mut();
+    let content_type = mime_guess::from_path(path).first_or_octet_stream();
+    if let Ok(value) = HeaderValue::from_str(content_type.as_ref()) {
+        response_headers.insert(CONTENT_TYPE, value);
+    }
+    response_headers.insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    if let Some(etag) = &etag {
+        if let Ok(value) = HeaderValue::from_str(&etag.to_string()) {
+            response_headers.insert(ETAG, value);
+        }
+    }
+    if let Some(last_modified) = last_modified {
+        if let Ok(value) = HeaderValue::from_str(&last_modified.to_string()) {
+            response_headers.insert(LAST_MODIFIED, value);
+        }
+    }
+    if precompressed.any() {
+        response_headers.insert(VARY, HeaderValue::from_static("Accept-Encoding"));
+    }
+    if let Some(encoding) = encoding {
+        response_headers.insert(CONTENT_ENCODING, HeaderValue::from_static(encoding));
+    }
+
+    if is_not_modified(headers, etag.as_ref(), last_modified) {
+        return Some(not_modified(response));
+    }
+
+    let range = headers
+        .get(RANGE)
+        .and_then(|v| v.to_str().ok())
+        .filter(|_| if_range_matches(headers, etag.as_ref(), last_modified))
+        .and_then(|range| parse_range(range, len));
+
+    let (start, end) = match range {
+        None => (0, len),
+        Some(Ok((start, end))) => {
+            *response.status_mut() = StatusCode::PARTIAL_CONTENT;
+            let content_range = format!("bytes {}-{}/{}", start, end - 1, len);
+            if let Ok(value) = HeaderValue::from_str(&content_range) {
+                response.headers_mut().insert(CONTENT_RANGE, value);
+            }
+            (start, end)
+        }
+        Some(Err(())) => {
+            let mut error = Error::new(
+                416,
+                "RANGE_NOT_SATISFIABLE",
+                format!("range is outside the {} byte file", len),
+            );
+            if let Some(ctx) = req.extensions().get::<RequestContext>() {
+                error = error.with_trace_id(ctx.trace_id());
+            }
+            let mut response = error.into_response();
+            if let Ok(value) = HeaderValue::from_str(&format!("bytes */{}", len)) {
+                response.headers_mut().insert(CONTENT_RANGE, value);
+            }
+            return Some(response);
+        }
+    };
+
+    if start > 0 && file.seek(SeekFrom::Start(start)).await.is_err() {
+        return None;
+    }
+    response
+        .headers_mut()
+        .insert(CONTENT_LENGTH, HeaderValue::from(end - start));
+    *response.body_mut() = BoxBody::from_reader(file.take(end - start));
+    Some(response)
+}
+
+/// Whether a `Range` may be honored given the request's `If-Range`.
+///
+/// An `If-Range` that doesn't match the current file means the client's
+/// partial copy is stale, so the full file is sent instead.
+fn if_range_matches(
+    headers: &HeaderMap,
+    etag: Option<&ETag>,
+    last_modified: Option<HttpDate>,
+) -> bool {
+    let Some(if_range) = headers.get(IF_RANGE).and_then(|v| v.to_str().ok()) else {
+        return true;
+    };
+    let if_range = if_range.trim();
+    if if_range.starts_with('"') || if_range.starts_with("W/") {
+        // Strong comparison: a weak tag never matches.
+        match (ETag::parse(if_range), etag) {
+            (Some(if_range), Some(etag)) => if_range.strong_eq(etag),
+            _ => false,
+        }
+    } else {
+        match (if_range.parse::<HttpDate>(), last_modified) {
+            (Ok(date), Some(modified)) => date == modified,
+            _ => false,
+        }
+    }
+}
+
+/// Parses a single-range `Range: bytes=...` header against a file of `len`
+/// bytes into a half-open `[start, end)` span.
+///
+/// Returns `None` when the header should be ignored (malformed, another
+/// unit, or several ranges) and `Some(Err(()))` when it can't be satisfied.
+fn parse_range(header: &str, len: u64) -> Option<Result<(u64, u64), ()>> {
+    let spec = header.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') {
+        return None;
+    }
+    let (first, last) = spec.trim().split_once('-')?;
+
+    if first.is_empty() {
+        let suffix: u64 = last.parse().ok()?;
+        if suffix == 0 || len == 0 {
+            return Some(Err(()));
+        }
+        return Some(Ok((len.saturating_sub(suffix), len)));
+    }
+
+    let start: u64 = first.parse().ok()?;
+    let end = if last.is_empty() {
+        len
+    } else {
+        let last: u64 = last.parse().ok()?;
+        if last < start {
+            return None;
+        }
+        last.saturating_add(1).min(len)
+    };
+    if start >= len {
+        return Some(Err(()));
+    }
+    Some(Ok((start, end)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("bytes=0-4", 10), Some(Ok((0, 5))));
+        assert_eq!(parse_range("bytes=5-", 10), Some(Ok((5, 10))));
+        assert_eq!(parse_range("bytes=-3", 10), Some(Ok((7, 10))));
+        assert_eq!(parse_range("bytes=8-100", 10), Some(Ok((8, 10))));
+        assert_eq!(parse_range("bytes=-100", 10), Some(Ok((0, 10))));
+    }
+
+    #[test]
+    fn test_parse_range_unsatisfiable() {
+        assert_eq!(parse_range("bytes=10-", 10), Some(Err(())));
+        assert_eq!(parse_range("bytes=-0", 10), Some(Err(())));
+        assert_eq!(parse_range("bytes=0-1", 0), Some(Err(())));
+    }
+
+    #[test]
+    fn test_parse_range_ignored() {
+        assert_eq!(parse_range("items=0-4", 10), None);
+        assert_eq!(parse_range("bytes=0-1,4-5", 10), None);
+        assert_eq!(parse_range("bytes=5-2", 10), None);
+        assert_eq!(parse_range("bytes=a-b", 10), None);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_blocks_traversal() {
+        let dir = ServeDir::new("/srv/www");
+        assert_eq!(
+            dir.resolve("css/site.css").await,
+            Some(PathBuf::from("/srv/www/css/site.css"))
+        );
+        assert_eq!(dir.resolve("../etc/passwd").await, None);
+        assert_eq!(dir.resolve("css/../../etc/passwd").await, None);
+        assert_eq!(dir.resolve("%2e%2e/etc/passwd").await, None);
+        assert_eq!(dir.resolve("css%2F..%2F..%2Fetc").await, None);
+        assert_eq!(dir.resolve("..%5Cetc").await, None);
+    }
+}
@@ -1,31 +1,33 @@
 //! Test client for integration testing Rapina applications.
 
 use std::net::SocketAddr;
-use std::sync::Arc;
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
 
 use bytes::Bytes;
 use http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode};
 use http_body_util::{BodyExt, Full};
 use hyper::Request;
-use hyper::body::Incoming;
-use hyper::service::service_fn;
 use hyper_util::client::legacy::Client;
-use hyper_util::rt::{TokioExecutor, TokioIo};
-use hyper_util::server::conn::auto;
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::server::graceful::GracefulShutdown;
 use serde::{Serialize, de::DeserializeOwned};
 use tokio::net::TcpListener;
 use tokio::sync::oneshot;
 
-use crate::context::RequestContext;
+use crate::listener::Listener;
 use crate::middleware::MiddlewareStack;
 use crate::response::{APPLICATION_JSON, FORM_CONTENT_TYPE};
 use crate::router::Router;
+use crate::server::{Acceptor, ConnectionContext, accept_loop};
 use crate::state::AppState;
 
 /// A test client for making HTTP requests to a Rapina application.
 ///
 /// The test client spawns a lightweight HTTP server on a random port
 /// and provides a convenient API for making requests and asserting responses.
+/// The server uses the same connection handling as
+/// [`Rapina::listen`](crate::app::Rapina::listen).
 ///
 /// # Examples
 ///
@@ -46,11 +48,17 @@ use crate::state::AppState;
 /// }
 /// ```
 pub struct TestClient {
-    addr: SocketAddr,
-    client: Client<hyper_util::client::legacy::connect::HttpConnector, Full<Bytes>>,
+    transport: Transport,
+    client: Client<HttpConnector, Full<Bytes>>,
     _shutdown: oneshot::Sender<()>,
 }
 
+enum Transport {
+    Tcp(SocketAddr),
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
 impl TestClient {
     /// Creates a new test client from a Rapina application.
     ///
@@ -62,66 +70,37 @@ impl TestClient {
 
     /// Creates a test client from router, state, and middlewares.
     pub async fn from_parts(router: Router, state: AppState, middlewares: MiddlewareStack) -> Self {
-        let router = Arc::new(router);
-        let state = Arc::new(state);
-        let middlewares = Arc::new(middlewares);
-
         // Bind to a random available port
         let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
         let addr = listener.local_addr().unwrap();
 
-        // Create shutdown channel
-        let (shutdown_tx, mut shutdown_rx) = oneshot::channel();
-
-        // Spawn the server
-        tokio::spawn(async move {
-            loop {
-                tokio::select! {
-                    result = listener.accept() => {
-                        match result {
-                            Ok((stream, _)) => {
-                                let io = TokioIo::new(stream);
-                                let router = router.clone();
-                                let state = state.clone();
-                                let middlewares = middlewares.clone();
-
-                                tokio::spawn(async move {
-                                    let service = service_fn(move |mut req: Request<Incoming>| {
-                                        let router = router.clone();
-                                        let state = state.clone();
-                                        let middlewares = middlewares.clone();
-
-                                        let ctx = RequestContext::new();
-                                        req.extensions_mut().insert(ctx.clone());
-
-                                        async move {
-                                            let response = middlewares.execute(req, router, state, &ctx).await;
-                                            Ok::<_, std::convert::Infallible>(response)
-                                        }
-                                    });
-
-                                    let _ = auto::Builder::new(TokioExecutor::new())
-                                        .serve_connection_with_upgrades(io, service)
-                                        .into_owned()
-                                        .await;
-                                });
-                            }
-                            Err(_) => break,
-                        }
-                    }
-                    _ = &mut shutdown_rx => {
-                        break;
-                    }
-                }
-            }
-        });
-
+        let shutdown = spawn_server(listener, router, state, middlewares);
         let client = Client::builder(hyper_util::rt::TokioExecutor::new()).build_http();
 
         Self {
-            addr,
+            transport: Transport::Tcp(addr),
             client,
-            _shutdown: shutdown_tx,
+            _shutdown: shutdown,
+        }
+    }
+
+    /// Creates a test client that serves the application on a Unix domain
+    /// socket at `path`.
+    ///
+    /// Requests are sent over the socket, which is useful for testing apps
+    /// deployed with [`Rapina::listen_unix`](crate::app::Rapina::listen_unix).
+    #[cfg(unix)]
+    pub async fn new_unix(app: crate::app::Rapina, path: impl AsRef<Path>) -> Self {
+        let app = app.prepare();
+        let path = path.as_ref().to_path_buf();
+        let listener = tokio::net::UnixListener::bind(&path).unwrap();
+
+        let shutdown = spawn_server(listener, app.router, app.state, app.middlewares);
+
+        Self {
+            transport: Transport::Unix(path),
+            client: Client::builder(hyper_util::rt::TokioExecutor::new()).build_http(),
+            _shutdown: shutdown,
         }
     }
 
@@ -156,11 +135,57 @@ impl TestClient {
     }
 
     /// Returns the address the test server is listening on.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the client was created with [`new_unix`](Self::new_unix).
     pub fn addr(&self) -> SocketAddr {
-        self.addr
+        match &self.transport {
+            Transport::Tcp(addr) => *addr,
+            #[cfg(unix)]
+            Transport::Unix(_) => panic!("TestClient::addr called on a Unix socket client"),
+        }
+    }
+
+    async fn send(&self, request: Request<Full<Bytes>>) -> http::Response<hyper::body::Incoming> {
+        match &self.transport {
+            Transport::Tcp(_) => self.client.request(request).await.unwrap(),
+            #[cfg(unix)]
+            Transport::Unix(path) => {
+                let stream = tokio::net::UnixStream::connect(path).await.unwrap();
+                let (mut sender, conn) =
+                    hyper::client::conn::http1::handshake(hyper_util::rt::TokioIo::new(stream))
+                        .await
+                        .unwrap();
+                tokio::spawn(conn);
+                sender.send_request(request).await.unwrap()
+            }
+        }
     }
 }
 
+/// Serves the app on `listener` in the background until the returned sender
+/// is dropped.
+fn spawn_server<L: Listener>(
+    listener: L,
+    router: Router,
+    state: AppState,
+    middlewares: MiddlewareStack,
+) -> oneshot::Sender<()> {
+    let cx = ConnectionContext::new(router, state, middlewares);
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+
+    tokio::spawn(async move {
+        let graceful = GracefulShutdown::new();
+        let shutdown = async {
+            let _ = shutdown_rx.await;
+        };
+        let _ = accept_loop(listener, &Acceptor::Plain, &cx, &graceful, shutdown).await;
+    });
+
+    shutdown_tx
+}
+
 /// Builder for constructing test requests.
 pub struct TestRequestBuilder<'a> {
     client: &'a TestClient,
@@ -218,9 +243,17 @@ impl<'a> TestRequestBuilder<'a> {
 
     /// Sends the request and returns the response.
     pub async fn send(self) -> TestResponse {
-        let uri = format!("http://{}{}", self.client.addr, self.path);
+        let uri = match &self.client.transport {
+            Transport::Tcp(addr) => format!("http://{}{}", addr, self.path),
+            #[cfg(unix)]
+            Transport::Unix(_) => self.path.clone(),
+        };
 
         let mut builder = Request::builder().method(self.method).uri(&uri);
+        #[cfg(unix)]
+        if matches!(self.client.transport, Transport::Unix(_)) {
+            builder = builder.header(http::header::HOST, "localhost");
+        }
 
         for (key, value) in self.headers.iter() {
             builder = builder.header(key, value);
@@ -228,7 +261,7 @@ impl<'a> TestRequestBuilder<'a> {
 
         let request = builder.body(Full::new(self.body)).unwrap();
 
-        let response = self.client.client.request(request).await.unwrap();
+        let response = self.client.send(request).await;
 
         let status = response.status();
         let headers = response.headers().clone();
@@ -391,9 +424,7 @@ mod tests {
                     http::Response::builder()
                         .status(StatusCode::OK)
                         .header(http::header::CONTENT_TYPE, APPLICATION_JSON)
-                        .body(http_body_util::Full::new(bytes::Bytes::from(
-                            r#"{"id":1,"name":"test"}"#,
-                        )))
+                        .body(crate::response::BoxBody::from(r#"{"id":1,"name":"test"}"#))
                         .unwrap()
                 }),
             );
@@ -502,4 +533,32 @@ mod tests {
         assert!(addr.port() > 0);
         assert_eq!(addr.ip().to_string(), "127.0.0.1");
     }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_client_over_unix_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = Rapina::new()
+            .with_introspection(false)
+            .router(
+                Router::new().route(http::Method::POST, "/echo", |req, _, _| async move {
+                    let host = req.headers()[http::header::HOST]
+                        .to_str()
+                        .unwrap()
+                        .to_owned();
+                    let body = req.into_body().collect().await.unwrap().to_bytes();
+                    format!("{} {}", host, String::from_utf8_lossy(&body))
+                }),
+            );
+
+        let client = TestClient::new_unix(app, dir.path().join("app.sock")).await;
+        let response = client.post("/echo").body("hello").send().await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.text(), "localhost hello");
+        assert!(response.headers().contains_key(http::header::DATE));
+
+        let response = client.get("/missing").send().await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
 }
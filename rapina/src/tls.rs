@@ -0,0 +1,403 @@
+//! Native HTTPS support built on rustls.
+//!
+//! Requires the `tls` feature. Pass a [`TlsConfig`] to
+//! [`Rapina::listen_tls`](crate::app::Rapina::listen_tls) to serve HTTPS
+//! without a TLS-terminating proxy in front of the application.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use rapina::prelude::*;
+//! use rapina::tls::TlsConfig;
+//!
+//! #[tokio::main]
+//! async fn main() -> std::io::Result<()> {
+//!     let tls = TlsConfig::new("certs/server.crt", "certs/server.key");
+//!
+//!     Rapina::new()
+//!         .discover()
+//!         .listen_tls("0.0.0.0:8443", tls)
+//!         .await
+//! }
+//! ```
+//!
+//! Both HTTP/2 and HTTP/1.1 are offered through ALPN. The certificate and
+//! key files are watched for changes and reloaded in place: new handshakes
+//! pick up the new certificate while open connections keep running.
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::CertifiedKey;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::task::JoinHandle;
+use tokio_rustls::server::TlsStream;
+
+use crate::config::{ConfigError, get_env, get_env_parsed_or};
+
+/// How often certificate files are checked for changes by default.
+const DEFAULT_RELOAD_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How long a client gets to complete the TLS handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Configuration for the HTTPS listener.
+///
+/// Use environment variables to configure:
+/// - `TLS_CERT_PATH` - PEM file with the certificate chain, leaf first (required)
+/// - `TLS_KEY_PATH` - PEM file with the private key (required)
+/// - `TLS_RELOAD_INTERVAL` - Seconds between checks for changed files, `0` disables reloading (default: 10)
+///
+/// # Example
+///
+/// ```ignore
+/// let tls = TlsConfig::from_env().expect("Missing TLS_CERT_PATH or TLS_KEY_PATH");
+/// // or with explicit values:
+/// let tls = TlsConfig::new("server.crt", "server.key")
+///     .reload_interval(Duration::from_secs(60));
+/// ```
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    cert_path: PathBuf,
+    key_path: PathBuf,
+    reload_interval: Option<Duration>,
+}
+
+impl TlsConfig {
+    /// Creates a configuration from a PEM certificate chain and a PEM private key.
+    pub fn new(cert_path: impl Into<PathBuf>, key_path: impl Into<PathBuf>) -> Self {
+        Self {
+            cert_path: cert_path.into(),
+            key_path: key_path.into(),
+            reload_interval: Some(DEFAULT_RELOAD_INTERVAL),
+        }
+    }
+
+    /// Loads configuration from environment variables.
+    ///
+    /// Required: `TLS_CERT_PATH`, `TLS_KEY_PATH`
+    /// Optional: `TLS_RELOAD_INTERVAL` (default: 10 seconds, `0` disables reloading)
+    pub fn from_env() -> Result<Self, ConfigError> {
+        let cert_path = get_env("TLS_CERT_PATH");
+        let key_path = get_env("TLS_KEY_PATH");
+        let (cert_path, key_path) = match (cert_path, key_path) {
+            (Ok(cert), Ok(key)) => (cert, key),
+            (Err(_), Err(_)) => {
+                return Err(ConfigError::MissingMultiple(vec![
+                    "TLS_CERT_PATH".to_string(),
+                    "TLS_KEY_PATH".to_string(),
+                ]));
+            }
+            (Err(e), _) | (_, Err(e)) => return Err(e),
+        };
+
+        let interval = get_env_parsed_or("TLS_RELOAD_INTERVAL", DEFAULT_RELOAD_INTERVAL.as_secs());
+        let config = Self::new(cert_path, key_path);
+        Ok(match interval {
+            0 => config.without_reload(),
+            secs => config.reload_interval(Duration::from_secs(secs)),
+        })
+    }
+
+    /// Sets how often the certificate and key files are checked for changes.
+    pub fn reload_interval(mut self, interval: Duration) -> Self {
+        self.reload_interval = Some(interval);
+        self
+    }
+
+    /// Disables certificate hot-reloading.
+    pub fn without_reload(mut self) -> Self {
+        self.reload_interval = None;
+        self
+    }
+
+    /// Returns the path of the certificate chain.
+    pub fn cert_path(&self) -> &Path {
+        &self.cert_path
+    }
+
+    /// Returns the path of the private key.
+    pub fn key_path(&self) -> &Path {
+        &self.key_path
+    }
+
+    /// Loads the certificate and builds the acceptor used by the server.
+    ///
+    /// Fails if the files cannot be read, hold no certificate or key, or the
+    /// key does not belong to the certificate. Must be called from within a
+    /// Tokio runtime when reloading is enabled.
+    pub(crate) fn build_acceptor(&self) -> io::Result<TlsAcceptor> {
+        let provider = Arc::new(rustls::crypto::aws_lc_rs::default_provider());
+        let resolver = Arc::new(ReloadingCertResolver::load(
+            provider.clone(),
+            self.cert_path.clone(),
+            self.key_path.clone(),
+        )?);
+
+        let mut config = rustls::ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(io::Error::other)?
+            .with_no_client_auth()
+            .with_cert_resolver(resolver.clone());
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+        let reload_task = self.reload_interval.map(|interval| {
+            let resolver = resolver.clone();
+            tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(interval);
+                ticker.tick().await;
+                loop {
+                    ticker.tick().await;
+                    match resolver.reload_if_changed() {
+                        Ok(true) => tracing::info!(
+                            "Reloaded TLS certificate from {}",
+                            resolver.cert_path.display()
+                        ),
+                        Ok(false) => {}
+                        Err(e) => tracing::warn!(
+                            "Failed to reload TLS certificate, keeping the current one: {}",
+                            e
+                        ),
+                    }
+                }
+            })
+        });
+
+        Ok(TlsAcceptor {
+            inner: tokio_rustls::TlsAcceptor::from(Arc::new(config)),
+            _reload_task: reload_task.map(|task| Arc::new(AbortOnDrop(task))),
+        })
+    }
+}
+
+/// Performs TLS handshakes for accepted connections.
+///
+/// Stops the certificate reload task once the last clone is dropped.
+#[derive(Clone)]
+pub(crate) struct TlsAcceptor {
+    inner: tokio_rustls::TlsAcceptor,
+    _reload_task: Option<Arc<AbortOnDrop>>,
+}
+
+impl TlsAcceptor {
+    /// Runs the server side of the handshake, giving up after [`HANDSHAKE_TIMEOUT`].
+    pub(crate) async fn accept<IO>(&self, stream: IO) -> io::Result<TlsStream<IO>>
+    where
+        IO: AsyncRead + AsyncWrite + Unpin,
+    {
+        tokio::time::timeout(HANDSHAKE_TIMEOUT, self.inner.accept(stream))
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "TLS handshake timed out"))?
+    }
+}
+
+/// Aborts the wrapped task when dropped.
+struct AbortOnDrop(JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Serves the most recently loaded certificate to every handshake.
+struct ReloadingCertResolver {
+    provider: Arc<CryptoProvider>,
+    cert_path: PathBuf,
+    key_path: PathBuf,
+    current: RwLock<Arc<CertifiedKey>>,
+    modified: Mutex<(Option<SystemTime>, Option<SystemTime>)>,
+}
+
+impl ReloadingCertResolver {
+    fn load(
+        provider: Arc<CryptoProvider>,
+        cert_path: PathBuf,
+        key_path: PathBuf,
+    ) -> io::Result<Self> {
+        let modified = (modified_at(&cert_path), modified_at(&key_path));
+        let key = load_certified_key(&provider, &cert_path, &key_path)?;
+        Ok(Self {
+            provider,
+            cert_path,
+            key_path,
+            current: RwLock::new(Arc::new(key)),
+            modified: Mutex::new(modified),
+        })
+    }
+
+    /// Reloads the certificate if either file changed since the last load.
+    ///
+    /// On failure the current certificate stays in place and the change is
+    /// retried on the next call, which covers files caught mid-write.
+    fn reload_if_changed(&self) -> io::Result<bool> {
+        let modified = (modified_at(&self.cert_path), modified_at(&self.key_path));
+        let mut last = self.modified.lock().unwrap();
+        if *last == modified {
+            return Ok(false);
+        }
+
+        let key = load_certified_key(&self.provider, &self.cert_path, &self.key_path)?;
+        *self.current.write().unwrap() = Arc::new(key);
+        *last = modified;
+        Ok(true)
+    }
+}
+
+impl fmt::Debug for ReloadingCertResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReloadingCertResolver")
+            .field("cert_path", &self.cert_path)
+            .field("key_path", &self.key_path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ResolvesServerCert for ReloadingCertResolver {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.current.read().unwrap().clone())
+    }
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn load_certified_key(
+    provider: &CryptoProvider,
+    cert_path: &Path,
+    key_path: &Path,
+) -> io::Result<CertifiedKey> {
+    let invalid = |path: &Path, e: &dyn fmt::Display| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: {}", path.display(), e),
+        )
+    };
+
+    let certs = CertificateDer::pem_file_iter(cert_path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| invalid(cert_path, &e))?;
+    if certs.is_empty() {
+        return Err(invalid(cert_path, &"no certificate found"));
+    }
+
+    let key = PrivateKeyDer::from_pem_file(key_path).map_err(|e| invalid(key_path, &e))?;
+    let signing_key = provider
+        .key_provider
+        .load_private_key(key)
+        .map_err(|e| invalid(key_path, &e))?;
+
+    let certified = CertifiedKey::new(certs, signing_key);
+    certified.keys_match().map_err(|e| invalid(key_path, &e))?;
+    Ok(certified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_self_signed(dir: &Path, name: &str) -> (PathBuf, PathBuf) {
+        let cert = rcgen::generate_simple_self_signed(vec![name.to_string()]).unwrap();
+        let cert_path = dir.join("server.crt");
+        let key_path = dir.join("server.key");
+        std::fs::write(&cert_path, cert.cert.pem()).unwrap();
+        std::fs::write(&key_path, cert.key_pair.serialize_pem()).unwrap();
+        (cert_path, key_path)
+    }
+
+    #[test]
+    fn test_tls_config_new_defaults() {
+        let config = TlsConfig::new("a.crt", "a.key");
+        assert_eq!(config.cert_path(), Path::new("a.crt"));
+        assert_eq!(config.key_path(), Path::new("a.key"));
+        assert_eq!(config.reload_interval, Some(DEFAULT_RELOAD_INTERVAL));
+    }
+
+    #[test]
+    fn test_tls_config_without_reload() {
+        let config = TlsConfig::new("a.crt", "a.key").without_reload();
+        assert_eq!(config.reload_interval, None);
+    }
+
+    #[tokio::test]
+    async fn test_build_acceptor_from_pem_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let (cert, key) = write_self_signed(dir.path(), "localhost");
+
+        let acceptor = TlsConfig::new(cert, key).build_acceptor();
+        assert!(acceptor.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_build_acceptor_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let result = TlsConfig::new(dir.path().join("nope.crt"), dir.path().join("nope.key"))
+            .build_acceptor();
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_build_acceptor_rejects_mismatched_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let (cert, _) = write_self_signed(dir.path(), "localhost");
+        let other = tempfile::tempdir().unwrap();
+        let (_, other_key) = write_self_signed(other.path(), "localhost");
+
+        let err = TlsConfig::new(cert, other_key)
+            .build_acceptor()
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_resolver_reloads_changed_files_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let (cert, key) = write_self_signed(dir.path(), "first.test");
+        let provider = Arc::new(rustls::crypto::aws_lc_rs::default_provider());
+        let resolver = ReloadingCertResolver::load(provider, cert, key).unwrap();
+        let first = resolver.current.read().unwrap().clone();
+
+        assert!(!resolver.reload_if_changed().unwrap());
+
+        // Make sure the new files get a different mtime.
+        std::thread::sleep(Duration::from_millis(20));
+        write_self_signed(dir.path(), "second.test");
+        let file = std::fs::File::options()
+            .append(true)
+            .open(&resolver.cert_path)
+            .unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(1))
+            .unwrap();
+
+        assert!(resolver.reload_if_changed().unwrap());
+        let second = resolver.current.read().unwrap().clone();
+        assert_ne!(first.end_entity_cert(), second.end_entity_cert());
+    }
+
+    #[test]
+    fn test_resolver_keeps_certificate_on_broken_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let (cert, key) = write_self_signed(dir.path(), "localhost");
+        let provider = Arc::new(rustls::crypto::aws_lc_rs::default_provider());
+        let resolver = ReloadingCertResolver::load(provider, cert.clone(), key).unwrap();
+        let before = resolver.current.read().unwrap().clone();
+
+        std::fs::write(&cert, "not a certificate").unwrap();
+        let file = std::fs::File::options().append(true).open(&cert).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(1))
+            .unwrap();
+
+        assert!(resolver.reload_if_changed().is_err());
+        let after = resolver.current.read().unwrap().clone();
+        assert_eq!(before.end_entity_cert(), after.end_entity_cert());
+    }
+}
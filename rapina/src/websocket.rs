@@ -65,7 +65,7 @@ impl FromRequest for WebSocketUpgrade {
         let (response, websocket) = hyper_tungstenite::upgrade(&mut req, None)
             .map_err(|e| Error::internal(format!("WebSocket upgrade failed: {e}")))?;
         Ok(Self {
-            response,
+            response: response.map(BoxBody::from),
             websocket,
         })
     }
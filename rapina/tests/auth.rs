@@ -0,0 +1,557 @@
+//! Integration tests for custom claims, role/scope authorization,
+//! asymmetric signing keys, refresh tokens, revocation and API keys.
+
+use http::StatusCode;
+use rapina::auth::hash_api_key;
+use rapina::prelude::*;
+use rapina::testing::{TestClient, TestResponse};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize)]
+struct TenantClaims {
+    sub: String,
+    exp: u64,
+    tenant: String,
+    #[serde(default)]
+    roles: Vec<String>,
+    /// Space-separated OAuth scopes
+    #[serde(default)]
+    scope: String,
+}
+
+impl AuthClaims for TenantClaims {
+    fn subject(&self) -> &str {
+        &self.sub
+    }
+
+    fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+
+    fn has_scope(&self, scope: &str) -> bool {
+        self.scope.split_whitespace().any(|s| s == scope)
+    }
+}
+
+#[get("/tenant")]
+async fn tenant(user: CurrentUser<TenantClaims>) -> String {
+    format!("{}@{}", user.id, user.claims.tenant)
+}
+
+#[get("/admin")]
+#[requires_role("admin")]
+async fn admin() -> &'static str {
+    "admin area"
+}
+
+#[post("/orders")]
+#[requires_role("staff")]
+#[requires_scope("orders:write")]
+async fn create_order() -> StatusCode {
+    StatusCode::CREATED
+}
+
+fn auth() -> AuthConfig<TenantClaims> {
+    AuthConfig::new("test-secret-authz", 3600).with_claims::<TenantClaims>()
+}
+
+fn token(auth: &AuthConfig<TenantClaims>, roles: &[&str], scope: &str) -> String {
+    auth.encode(&TenantClaims {
+        sub: "user-1".to_string(),
+        exp: auth.expires_at(),
+        tenant: "acme".to_string(),
+        roles: roles.iter().map(|r| r.to_string()).collect(),
+        scope: scope.to_string(),
+    })
+    .unwrap()
+}
+
+async fn client() -> TestClient {
+    let app = Rapina::new()
+        .with_introspection(false)
+        .with_auth(auth())
+        .router(
+            Router::new()
+                .get("/tenant", tenant)
+                .get("/admin", admin)
+                .post("/orders", create_order),
+        );
+    TestClient::new(app).await
+}
+
+#[tokio::test]
+async fn test_custom_claims_reach_current_user() {
+    let client = client().await;
+    let token = token(&auth(), &[], "");
+
+    let response = client
+        .get("/tenant")
+        .header("authorization", &format!("Bearer {}", token))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.text(), "user-1@acme");
+}
+
+#[tokio::test]
+async fn test_requires_role() {
+    let client = client().await;
+
+    let response = client.get("/admin").send().await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = client
+        .get("/admin")
+        .header(
+            "authorization",
+            &format!("Bearer {}", token(&auth(), &["staff"], "")),
+        )
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let json: serde_json::Value = response.json();
+    assert_eq!(json["error"]["code"], "FORBIDDEN");
+    assert_eq!(json["error"]["message"], "requires role `admin`");
+
+    let response = client
+        .get("/admin")
+        .header(
+            "authorization",
+            &format!("Bearer {}", token(&auth(), &["admin"], "")),
+        )
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.text(), "admin area");
+}
+
+#[tokio::test]
+async fn test_requires_role_and_scope() {
+    let client = client().await;
+
+    let response = client
+        .post("/orders")
+        .header(
+            "authorization",
+            &format!("Bearer {}", token(&auth(), &["staff"], "orders:read")),
+        )
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let json: serde_json::Value = response.json();
+    assert_eq!(json["error"]["message"], "requires scope `orders:write`");
+
+    let response = client
+        .post("/orders")
+        .header(
+            "authorization",
+            &format!(
+                "Bearer {}",
+                token(&auth(), &["staff"], "orders:read orders:write")
+            ),
+        )
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn test_default_claims_grant_no_roles() {
+    let auth = AuthConfig::new("test-secret-authz", 3600);
+    let token = auth.create_token("user-2").unwrap();
+    let app = Rapina::new()
+        .with_introspection(false)
+        .with_auth(auth)
+        .router(Router::new().get("/admin", admin));
+    let client = TestClient::new(app).await;
+
+    let response = client
+        .get("/admin")
+        .header("authorization", &format!("Bearer {}", token))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_requirements_in_introspection_and_openapi() {
+    let app = Rapina::new()
+        .with_introspection(true)
+        .openapi("Test", "1.0.0")
+        .router(
+            Router::new()
+                .get("/admin", admin)
+                .post("/orders", create_order),
+        );
+    let client = TestClient::new(app).await;
+
+    let routes: serde_json::Value = client.get("/__rapina/routes").send().await.json();
+    let orders = routes
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|route| route["path"] == "/orders")
+        .unwrap();
+    assert_eq!(orders["required_roles"], serde_json::json!(["staff"]));
+    assert_eq!(
+        orders["required_scopes"],
+        serde_json::json!(["orders:write"])
+    );
+
+    let spec: serde_json::Value = client.get("/__rapina/openapi.json").send().await.json();
+    let operation = &spec["paths"]["/admin"]["get"];
+    assert_eq!(operation["x-required-roles"], serde_json::json!(["admin"]));
+    assert!(operation.get("x-required-scopes").is_none());
+    assert!(operation["responses"]["403"].is_object());
+}
+
+const RSA_PRIVATE: &str = include_str!("fixtures/jwt/rsa_private.pem");
+const RSA_PUBLIC: &str = include_str!("fixtures/jwt/rsa_public.pem");
+const ED_PRIVATE: &str = include_str!("fixtures/jwt/ed_private.pem");
+const ED_PUBLIC: &str = include_str!("fixtures/jwt/ed_public.pem");
+
+#[get("/me")]
+async fn me(user: CurrentUser) -> String {
+    user.id
+}
+
+#[tokio::test]
+async fn test_asymmetric_keys_and_jwks_endpoint() {
+    let previous = AuthConfig::from_key(
+        SigningKey::rs256("rsa-2026-07", RSA_PRIVATE, RSA_PUBLIC).unwrap(),
+        3600,
+    );
+    let auth = AuthConfig::from_key(
+        SigningKey::eddsa("ed-2026-10", ED_PRIVATE, ED_PUBLIC).unwrap(),
+        3600,
+    )
+    .verify_with(VerifyingKey::rs256("rsa-2026-07", RSA_PUBLIC).unwrap());
+    let old_token = previous.create_token("user-1").unwrap();
+    let new_token = auth.create_token("user-2").unwrap();
+
+    let app = Rapina::new()
+        .with_introspection(false)
+        .with_auth(auth)
+        .router(Router::new().get("/me", me));
+    let client = TestClient::new(app).await;
+
+    for (token, id) in [(old_token, "user-1"), (new_token, "user-2")] {
+        let response = client
+            .get("/me")
+            .header("authorization", &format!("Bearer {}", token))
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.text(), id);
+    }
+
+    // Served without a token
+    let response = client.get("/.well-known/jwks.json").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers().get("cache-control").unwrap(),
+        "public, max-age=300"
+    );
+    let jwks: serde_json::Value = response.json();
+    let keys = jwks["keys"].as_array().unwrap();
+    assert_eq!(keys.len(), 2);
+    assert_eq!(keys[0]["kid"], "ed-2026-10");
+    assert_eq!(keys[0]["kty"], "OKP");
+    assert_eq!(keys[1]["kid"], "rsa-2026-07");
+    assert_eq!(keys[1]["kty"], "RSA");
+    assert!(keys.iter().all(|key| key.get("d").is_none()));
+}
+
+#[tokio::test]
+async fn test_hmac_secret_is_not_published() {
+    let app = Rapina::new()
+        .with_introspection(false)
+        .with_auth(AuthConfig::new("test-secret", 3600))
+        .router(Router::new().get("/me", me));
+    let client = TestClient::new(app).await;
+
+    let response = client.get("/.well-known/jwks.json").send().await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct RefreshRequest {
+    refresh_token: String,
+}
+
+#[public]
+#[post("/login")]
+async fn login(auth: State<AuthConfig>) -> Result<Json<TokenPair>> {
+    Ok(Json(auth.create_token_pair("user-1")?))
+}
+
+#[public]
+#[post("/refresh")]
+async fn refresh(auth: State<AuthConfig>, body: Json<RefreshRequest>) -> Result<Json<TokenPair>> {
+    Ok(Json(auth.refresh(&body.refresh_token).await?))
+}
+
+#[post("/logout")]
+async fn logout(auth: State<AuthConfig>, user: CurrentUser) -> Result<StatusCode> {
+    auth.logout(&user).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[post("/logout/all")]
+async fn logout_all(auth: State<AuthConfig>, user: CurrentUser) -> Result<StatusCode> {
+    auth.logout_all(&user.id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn session_client() -> TestClient {
+    let auth = AuthConfig::new("test-secret-refresh", 900)
+        .with_revocation_store(InMemoryRevocationStore::new());
+    let app = Rapina::new()
+        .with_introspection(false)
+        .with_auth(auth.clone())
+        .public_route("POST", "/login")
+        .public_route("POST", "/refresh")
+        .state(auth)
+        .router(
+            Router::new()
+                .post("/login", login)
+                .post("/refresh", refresh)
+                .post("/logout", logout)
+                .post("/logout/all", logout_all)
+                .get("/me", me),
+        );
+    TestClient::new(app).await
+}
+
+async fn get_me(client: &TestClient, token: &str) -> TestResponse {
+    client
+        .get("/me")
+        .header("authorization", &format!("Bearer {}", token))
+        .send()
+        .await
+}
+
+async fn post_with(client: &TestClient, path: &str, token: &str) -> StatusCode {
+    client
+        .post(path)
+        .header("authorization", &format!("Bearer {}", token))
+        .send()
+        .await
+        .status()
+}
+
+async fn refresh_with(client: &TestClient, refresh_token: &str) -> TestResponse {
+    client
+        .post("/refresh")
+        .json(&serde_json::json!({ "refresh_token": refresh_token }))
+        .send()
+        .await
+}
+
+#[tokio::test]
+async fn test_refresh_and_logout() {
+    let client = session_client().await;
+    let first: TokenPair = client.post("/login").send().await.json();
+    assert_eq!(get_me(&client, &first.token).await.status(), StatusCode::OK);
+
+    let response = refresh_with(&client, &first.refresh_token).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let second: TokenPair = response.json();
+    assert_eq!(get_me(&client, &second.token).await.text(), "user-1");
+
+    assert_eq!(
+        post_with(&client, "/logout", &second.token).await,
+        StatusCode::NO_CONTENT
+    );
+
+    // Every token of the session is gone, not just the one used to log out
+    for token in [&first.token, &second.token] {
+        let response = get_me(&client, token).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            response.json::<serde_json::Value>()["error"]["message"],
+            "token revoked"
+        );
+    }
+    let response = refresh_with(&client, &second.refresh_token).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_refresh_token_reuse_revokes_session() {
+    let client = session_client().await;
+    let first: TokenPair = client.post("/login").send().await.json();
+    let second: TokenPair = refresh_with(&client, &first.refresh_token).await.json();
+
+    let response = refresh_with(&client, &first.refresh_token).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        response.json::<serde_json::Value>()["error"]["message"],
+        "refresh token reused"
+    );
+    assert_eq!(
+        get_me(&client, &second.token).await.status(),
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[tokio::test]
+async fn test_logout_all_sessions() {
+    let client = session_client().await;
+    let phone: TokenPair = client.post("/login").send().await.json();
+    let laptop: TokenPair = client.post("/login").send().await.json();
+
+    // Tokens issued in the same second as the cutoff stay valid, so wait
+    // for the clock to move on before signing out everywhere
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    assert_eq!(
+        post_with(&client, "/logout/all", &laptop.token).await,
+        StatusCode::NO_CONTENT
+    );
+
+    for pair in [&phone, &laptop] {
+        assert_eq!(
+            get_me(&client, &pair.token).await.status(),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            refresh_with(&client, &pair.refresh_token).await.status(),
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    // Logging back in works straight away
+    let again: TokenPair = client.post("/login").send().await.json();
+    assert_eq!(get_me(&client, &again.token).await.status(), StatusCode::OK);
+}
+
+struct ServiceKeys;
+
+impl ApiKeyStore<TenantClaims> for ServiceKeys {
+    fn lookup(&self, key_hash: &str) -> ApiKeyFuture<'_, Result<Option<TenantClaims>>> {
+        let claims = (key_hash == hash_api_key("sk_test_billing")).then(|| TenantClaims {
+            sub: "billing-service".to_string(),
+            exp: 0,
+            tenant: "acme".to_string(),
+            roles: vec!["staff".to_string()],
+            scope: "orders:write".to_string(),
+        });
+        Box::pin(async move { Ok(claims) })
+    }
+}
+
+#[public]
+#[get("/status")]
+async fn status() -> &'static str {
+    "ok"
+}
+
+async fn api_key_client() -> TestClient {
+    let app = Rapina::new()
+        .with_introspection(false)
+        .openapi("Test", "1.0.0")
+        .with_auth(auth())
+        .with_auth_scheme(ApiKeyAuth::header("X-API-Key", ServiceKeys))
+        .with_auth_scheme(ApiKeyAuth::query("api_key", ServiceKeys).with_name("apiKeyQuery"))
+        .public_route("GET", "/status")
+        .router(
+            Router::new()
+                .get("/tenant", tenant)
+                .get("/status", status)
+                .post("/orders", create_order),
+        );
+    TestClient::new(app).await
+}
+
+#[tokio::test]
+async fn test_api_key_and_jwt_side_by_side() {
+    let client = api_key_client().await;
+
+    let response = client
+        .get("/tenant")
+        .header("x-api-key", "sk_test_billing")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.text(), "billing-service@acme");
+
+    let response = client.get("/tenant?api_key=sk_test_billing").send().await;
+    assert_eq!(response.text(), "billing-service@acme");
+
+    // Roles and scopes come from the key's claims
+    let response = client
+        .post("/orders")
+        .header("x-api-key", "sk_test_billing")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let token = token(&auth(), &[], "");
+    let response = client
+        .get("/tenant")
+        .header("authorization", &format!("Bearer {}", token))
+        .send()
+        .await;
+    assert_eq!(response.text(), "user-1@acme");
+}
+
+#[tokio::test]
+async fn test_api_key_rejections() {
+    let client = api_key_client().await;
+
+    let response = client
+        .get("/tenant")
+        .header("x-api-key", "sk_test_unknown")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let json: serde_json::Value = response.json();
+    assert_eq!(json["error"]["message"], "invalid API key");
+
+    let response = client.get("/tenant").send().await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let json: serde_json::Value = response.json();
+    assert_eq!(json["error"]["message"], "missing credentials");
+
+    // A bad token is reported even when a later scheme finds no key
+    let response = client
+        .get("/tenant")
+        .header("authorization", "Bearer not-a-token")
+        .send()
+        .await;
+    let json: serde_json::Value = response.json();
+    assert_eq!(json["error"]["message"], "invalid token");
+
+    // A valid key still authenticates alongside a bad token
+    let response = client
+        .get("/tenant")
+        .header("authorization", "Bearer not-a-token")
+        .header("x-api-key", "sk_test_billing")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_security_schemes_in_openapi() {
+    let client = api_key_client().await;
+    let spec: serde_json::Value = client.get("/__rapina/openapi.json").send().await.json();
+
+    assert_eq!(
+        spec["components"]["securitySchemes"],
+        serde_json::json!({
+            "bearerAuth": {"type": "http", "scheme": "bearer", "bearerFormat": "JWT"},
+            "apiKeyAuth": {"type": "apiKey", "name": "X-API-Key", "in": "header"},
+            "apiKeyQuery": {"type": "apiKey", "name": "api_key", "in": "query"}
+        })
+    );
+    assert_eq!(
+        spec["security"],
+        serde_json::json!([{"bearerAuth": []}, {"apiKeyAuth": []}, {"apiKeyQuery": []}])
+    );
+    assert_eq!(
+        spec["paths"]["/status"]["get"]["security"],
+        serde_json::json!([])
+    );
+    assert!(spec["paths"]["/tenant"]["get"].get("security").is_none());
+}
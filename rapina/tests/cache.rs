@@ -1,8 +1,13 @@
 //! Integration tests for the caching layer.
 
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
 use http::StatusCode;
-use rapina::cache::{CACHE_STATUS_HEADER, CacheConfig};
+use rapina::cache::{CACHE_STATUS_HEADER, Cache, CacheConfig};
 use rapina::prelude::*;
+use rapina::response::BoxBody;
 use rapina::testing::TestClient;
 
 #[tokio::test]
@@ -17,9 +22,7 @@ async fn test_cache_miss_then_hit() {
                 let mut response = http::Response::builder()
                     .status(StatusCode::OK)
                     .header("content-type", "application/json")
-                    .body(http_body_util::Full::new(bytes::Bytes::from(
-                        r#"{"value":42}"#,
-                    )))
+                    .body(BoxBody::from(bytes::Bytes::from(r#"{"value":42}"#)))
                     .unwrap();
                 response
                     .headers_mut()
@@ -54,7 +57,7 @@ async fn test_cache_strips_internal_ttl_header() {
             Router::new().route(http::Method::GET, "/data", |_, _, _| async {
                 let mut response = http::Response::builder()
                     .status(StatusCode::OK)
-                    .body(http_body_util::Full::new(bytes::Bytes::from("ok")))
+                    .body(BoxBody::from(bytes::Bytes::from("ok")))
                     .unwrap();
                 response
                     .headers_mut()
@@ -116,7 +119,7 @@ async fn test_mutation_invalidates_cache() {
                 .route(http::Method::GET, "/items", |_, _, _| async {
                     let mut response = http::Response::builder()
                         .status(StatusCode::OK)
-                        .body(http_body_util::Full::new(bytes::Bytes::from("items")))
+                        .body(BoxBody::from(bytes::Bytes::from("items")))
                         .unwrap();
                     response
                         .headers_mut()
@@ -160,7 +163,7 @@ async fn test_cache_preserves_response_headers() {
                     .status(StatusCode::OK)
                     .header("content-type", "application/json")
                     .header("x-custom", "preserved")
-                    .body(http_body_util::Full::new(bytes::Bytes::from("{}")))
+                    .body(BoxBody::from(bytes::Bytes::from("{}")))
                     .unwrap();
                 response
                     .headers_mut()
@@ -195,7 +198,7 @@ async fn test_cache_only_caches_get() {
             Router::new().route(http::Method::POST, "/data", |_, _, _| async {
                 let mut response = http::Response::builder()
                     .status(StatusCode::CREATED)
-                    .body(http_body_util::Full::new(bytes::Bytes::from("created")))
+                    .body(BoxBody::from(bytes::Bytes::from("created")))
                     .unwrap();
                 response
                     .headers_mut()
@@ -224,7 +227,7 @@ async fn test_cache_query_params_affect_key() {
                 let query = req.uri().query().unwrap_or("none").to_string();
                 let mut response = http::Response::builder()
                     .status(StatusCode::OK)
-                    .body(http_body_util::Full::new(bytes::Bytes::from(query)))
+                    .body(BoxBody::from(bytes::Bytes::from(query)))
                     .unwrap();
                 response
                     .headers_mut()
@@ -247,3 +250,357 @@ async fn test_cache_query_params_affect_key() {
     assert_eq!(r3.headers().get(CACHE_STATUS_HEADER).unwrap(), "HIT");
     assert_eq!(r3.text(), "q=rust");
 }
+
+/// A response marked cacheable for 60 seconds, with optional vary headers.
+fn cacheable(body: String, vary: Option<&'static str>, vary_user: bool) -> http::Response<BoxBody> {
+    let mut response = http::Response::builder()
+        .status(StatusCode::OK)
+        .body(BoxBody::from(bytes::Bytes::from(body)))
+        .unwrap();
+    let headers = response.headers_mut();
+    headers.insert("x-rapina-cache-ttl", http::HeaderValue::from_static("60"));
+    if let Some(vary) = vary {
+        headers.insert("x-rapina-cache-vary", http::HeaderValue::from_static(vary));
+    }
+    if vary_user {
+        headers.insert(
+            "x-rapina-cache-vary-user",
+            http::HeaderValue::from_static("1"),
+        );
+    }
+    response
+}
+
+#[tokio::test]
+async fn test_cache_varies_by_header() {
+    let app = Rapina::new()
+        .with_introspection(false)
+        .with_cache(CacheConfig::in_memory(100))
+        .await
+        .unwrap()
+        .router(
+            Router::new().route(http::Method::GET, "/greeting", |req, _, _| async move {
+                let language = req
+                    .headers()
+                    .get("accept-language")
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or("en")
+                    .to_string();
+                cacheable(language, Some("accept-language"), false)
+            }),
+        );
+
+    let client = TestClient::new(app).await;
+
+    let r1 = client
+        .get("/greeting")
+        .header("accept-language", "pt")
+        .send()
+        .await;
+    assert_eq!(r1.headers().get(CACHE_STATUS_HEADER).unwrap(), "MISS");
+    assert_eq!(r1.headers().get("vary").unwrap(), "accept-language");
+    assert!(r1.headers().get("x-rapina-cache-vary").is_none());
+
+    let r2 = client
+        .get("/greeting")
+        .header("accept-language", "de")
+        .send()
+        .await;
+    assert_eq!(r2.headers().get(CACHE_STATUS_HEADER).unwrap(), "MISS");
+    assert_eq!(r2.text(), "de");
+
+    let r3 = client
+        .get("/greeting")
+        .header("accept-language", "pt")
+        .send()
+        .await;
+    assert_eq!(r3.headers().get(CACHE_STATUS_HEADER).unwrap(), "HIT");
+    assert_eq!(r3.headers().get("vary").unwrap(), "accept-language");
+    assert_eq!(r3.text(), "pt");
+}
+
+/// An authenticated app whose `/me` route echoes the user id.
+async fn personalised_app(vary_user: bool) -> (TestClient, AuthConfig) {
+    let auth = AuthConfig::new("test-secret-cache", 3600);
+    let app = Rapina::new()
+        .with_introspection(false)
+        .with_cache(CacheConfig::in_memory(100))
+        .await
+        .unwrap()
+        .with_auth(auth.clone())
+        .router(
+            Router::new().route(http::Method::GET, "/me", move |req, _, _| async move {
+                let id = req
+                    .extensions()
+                    .get::<CurrentUser>()
+                    .map(|user| user.id.clone())
+                    .unwrap_or_default();
+                cacheable(id, None, vary_user)
+            }),
+        );
+
+    (TestClient::new(app).await, auth)
+}
+
+#[tokio::test]
+async fn test_cache_varies_by_user() {
+    let (client, auth) = personalised_app(true).await;
+    let alice = format!("Bearer {}", auth.create_token("alice").unwrap());
+    let bob = format!("Bearer {}", auth.create_token("bob").unwrap());
+
+    let r1 = client
+        .get("/me")
+        .header("authorization", &alice)
+        .send()
+        .await;
+    assert_eq!(r1.headers().get(CACHE_STATUS_HEADER).unwrap(), "MISS");
+    assert_eq!(r1.headers().get("vary").unwrap(), "authorization");
+    assert_eq!(r1.text(), "alice");
+
+    let r2 = client.get("/me").header("authorization", &bob).send().await;
+    assert_eq!(r2.headers().get(CACHE_STATUS_HEADER).unwrap(), "MISS");
+    assert_eq!(r2.text(), "bob");
+
+    let r3 = client
+        .get("/me")
+        .header("authorization", &alice)
+        .send()
+        .await;
+    assert_eq!(r3.headers().get(CACHE_STATUS_HEADER).unwrap(), "HIT");
+    assert_eq!(r3.text(), "alice");
+}
+
+#[tokio::test]
+async fn test_cache_refuses_personalised_response_without_vary() {
+    let (client, auth) = personalised_app(false).await;
+    let alice = format!("Bearer {}", auth.create_token("alice").unwrap());
+    let bob = format!("Bearer {}", auth.create_token("bob").unwrap());
+
+    let r1 = client
+        .get("/me")
+        .header("authorization", &alice)
+        .send()
+        .await;
+    assert!(r1.headers().get(CACHE_STATUS_HEADER).is_none());
+    assert!(r1.headers().get("x-rapina-cache-ttl").is_none());
+    assert_eq!(r1.text(), "alice");
+
+    // Bob must never see Alice's response
+    let r2 = client.get("/me").header("authorization", &bob).send().await;
+    assert!(r2.headers().get(CACHE_STATUS_HEADER).is_none());
+    assert_eq!(r2.text(), "bob");
+}
+
+#[tokio::test]
+async fn test_cache_refuses_private_response() {
+    let app = Rapina::new()
+        .with_introspection(false)
+        .with_cache(CacheConfig::in_memory(100))
+        .await
+        .unwrap()
+        .router(
+            Router::new().route(http::Method::GET, "/cart", |_, _, _| async {
+                let mut response = cacheable("cart".to_string(), None, false);
+                response
+                    .headers_mut()
+                    .insert("cache-control", http::HeaderValue::from_static("private"));
+                response
+            }),
+        );
+
+    let client = TestClient::new(app).await;
+
+    let r1 = client.get("/cart").send().await;
+    assert!(r1.headers().get(CACHE_STATUS_HEADER).is_none());
+    let r2 = client.get("/cart").send().await;
+    assert!(r2.headers().get(CACHE_STATUS_HEADER).is_none());
+}
+
+/// An app whose `/slow` route counts its calls and answers with the count
+/// after `delay`, cached for `ttl` seconds with an optional stale window.
+async fn counting_app(
+    delay: Duration,
+    ttl: &'static str,
+    stale: Option<&'static str>,
+) -> (TestClient, Arc<AtomicUsize>) {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let app = Rapina::new()
+        .with_introspection(false)
+        .with_cache(CacheConfig::in_memory(100))
+        .await
+        .unwrap()
+        .router(
+            Router::new().route(http::Method::GET, "/slow", move |_, _, _| {
+                let counter = counter.clone();
+                async move {
+                    let call = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                    tokio::time::sleep(delay).await;
+                    let mut response = http::Response::builder()
+                        .status(StatusCode::OK)
+                        .body(BoxBody::from(call.to_string()))
+                        .unwrap();
+                    let headers = response.headers_mut();
+                    headers.insert("x-rapina-cache-ttl", http::HeaderValue::from_static(ttl));
+                    if let Some(stale) = stale {
+                        headers.insert(
+                            "x-rapina-cache-stale",
+                            http::HeaderValue::from_static(stale),
+                        );
+                    }
+                    response
+                }
+            }),
+        );
+
+    (TestClient::new(app).await, calls)
+}
+
+#[tokio::test]
+async fn test_concurrent_misses_are_coalesced() {
+    let (client, calls) = counting_app(Duration::from_millis(100), "60", None).await;
+
+    let (r1, r2, r3, r4) = tokio::join!(
+        client.get("/slow").send(),
+        client.get("/slow").send(),
+        client.get("/slow").send(),
+        client.get("/slow").send(),
+    );
+
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    let responses = [r1, r2, r3, r4];
+    let misses = responses
+        .iter()
+        .filter(|r| r.headers().get(CACHE_STATUS_HEADER).unwrap() == "MISS")
+        .count();
+    assert_eq!(misses, 1);
+    for response in &responses {
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.text(), "1");
+    }
+}
+
+#[tokio::test]
+async fn test_stale_entry_served_while_refreshing() {
+    let (client, calls) = counting_app(Duration::ZERO, "1", Some("30")).await;
+
+    let response = client.get("/slow").send().await;
+    assert_eq!(response.headers().get(CACHE_STATUS_HEADER).unwrap(), "MISS");
+    assert!(response.headers().get("x-rapina-cache-stale").is_none());
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+
+    // Past the TTL: the old entry is served and refreshed in the background
+    let response = client.get("/slow").send().await;
+    assert_eq!(
+        response.headers().get(CACHE_STATUS_HEADER).unwrap(),
+        "STALE"
+    );
+    assert_eq!(response.text(), "1");
+    assert!(
+        response
+            .headers()
+            .get("x-rapina-cache-fresh-until")
+            .is_none()
+    );
+
+    for _ in 0..50 {
+        if calls.load(Ordering::SeqCst) == 2 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    let response = client.get("/slow").send().await;
+    assert_eq!(response.headers().get(CACHE_STATUS_HEADER).unwrap(), "HIT");
+    assert_eq!(response.text(), "2");
+}
+
+#[tokio::test]
+async fn test_expired_entry_without_stale_window_is_not_served() {
+    let (client, calls) = counting_app(Duration::ZERO, "1", None).await;
+
+    client.get("/slow").send().await;
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+
+    let response = client.get("/slow").send().await;
+    assert_eq!(response.headers().get(CACHE_STATUS_HEADER).unwrap(), "MISS");
+    assert_eq!(response.text(), "2");
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[get("/inv-orders")]
+#[cache(ttl = 60, tags = ["orders"])]
+async fn inv_orders() -> &'static str {
+    "all orders"
+}
+
+#[get("/inv-customers/:id/orders")]
+#[cache(ttl = 60)]
+async fn inv_customer_orders() -> &'static str {
+    "customer orders"
+}
+
+#[get("/inv-products")]
+#[cache(ttl = 60)]
+async fn inv_products() -> &'static str {
+    "products"
+}
+
+#[post("/inv-purge")]
+async fn inv_purge(cache: Cache) -> StatusCode {
+    cache.invalidate("/inv-customers/3/orders").await;
+    cache.invalidate_tag("orders").await;
+    StatusCode::NO_CONTENT
+}
+
+fn invalidation_router() -> Router {
+    Router::new()
+        .get("/inv-orders", inv_orders)
+        .get("/inv-customers/:id/orders", inv_customer_orders)
+        .get("/inv-products", inv_products)
+        .post("/inv-purge", inv_purge)
+}
+
+#[tokio::test]
+async fn test_cache_extractor_invalidates_keys_and_tags() {
+    let app = Rapina::new()
+        .with_introspection(false)
+        .with_cache(CacheConfig::in_memory(100))
+        .await
+        .unwrap()
+        .router(invalidation_router());
+    let client = TestClient::new(app).await;
+
+    for path in ["/inv-orders", "/inv-customers/3/orders", "/inv-products"] {
+        let response = client.get(path).send().await;
+        assert_eq!(response.headers().get(CACHE_STATUS_HEADER).unwrap(), "MISS");
+        assert!(response.headers().get("x-rapina-cache-tags").is_none());
+        let response = client.get(path).send().await;
+        assert_eq!(response.headers().get(CACHE_STATUS_HEADER).unwrap(), "HIT");
+    }
+
+    let response = client.post("/inv-purge").send().await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = client.get("/inv-orders").send().await;
+    assert_eq!(response.headers().get(CACHE_STATUS_HEADER).unwrap(), "MISS");
+    let response = client.get("/inv-customers/3/orders").send().await;
+    assert_eq!(response.headers().get(CACHE_STATUS_HEADER).unwrap(), "MISS");
+    // Neither the key nor the tag covers products
+    let response = client.get("/inv-products").send().await;
+    assert_eq!(response.headers().get(CACHE_STATUS_HEADER).unwrap(), "HIT");
+}
+
+#[tokio::test]
+async fn test_cache_extractor_without_cache_is_an_error() {
+    let app = Rapina::new()
+        .with_introspection(false)
+        .router(invalidation_router());
+    let client = TestClient::new(app).await;
+
+    let response = client.post("/inv-purge").send().await;
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
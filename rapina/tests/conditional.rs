@@ -0,0 +1,152 @@
+//! Integration tests for conditional requests.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+
+use http::StatusCode;
+use rapina::conditional::{Conditional, ConditionalConfig, ETag, IfMatch};
+use rapina::prelude::*;
+use rapina::response::BoxBody;
+use rapina::testing::TestClient;
+
+static VERSION: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Serialize, JsonSchema)]
+struct Doc {
+    version: u64,
+}
+
+fn current_etag() -> ETag {
+    ETag::strong(format!("v{}", VERSION.load(Ordering::SeqCst)))
+}
+
+#[get("/cond-doc")]
+async fn get_doc() -> Conditional<Json<Doc>> {
+    let version = VERSION.load(Ordering::SeqCst);
+    Conditional::new(Json(Doc { version }))
+        .etag(current_etag())
+        .last_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(1_445_412_480))
+}
+
+#[put("/cond-doc")]
+async fn put_doc(if_match: IfMatch) -> Result<Json<Doc>> {
+    if_match.check(&current_etag())?;
+    let version = VERSION.fetch_add(1, Ordering::SeqCst) + 1;
+    Ok(Json(Doc { version }))
+}
+
+#[get("/cond-plain")]
+async fn get_plain() -> &'static str {
+    "unchanged"
+}
+
+fn conditional_app() -> Rapina {
+    Rapina::new()
+        .with_introspection(false)
+        .with_conditional(ConditionalConfig::default())
+        .router(
+            Router::new()
+                .get("/cond-doc", get_doc)
+                .put("/cond-doc", put_doc)
+                .get("/cond-plain", get_plain)
+                .route(http::Method::GET, "/cond-stream", |_, _, _| async {
+                    http::Response::new(BoxBody::from_reader(&b"chunk"[..]))
+                }),
+        )
+}
+
+#[tokio::test]
+async fn test_body_hash_etag_and_304() {
+    let client = TestClient::new(conditional_app()).await;
+
+    let response = client.get("/cond-plain").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let etag = response
+        .headers()
+        .get("etag")
+        .expect("etag header")
+        .to_str()
+        .unwrap()
+        .to_string();
+    assert!(etag.starts_with('"'));
+
+    let response = client
+        .get("/cond-plain")
+        .header("if-none-match", &etag)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(response.headers().get("etag").unwrap(), etag.as_str());
+    assert!(response.headers().get("content-type").is_none());
+    assert_eq!(response.text(), "");
+
+    let response = client
+        .get("/cond-plain")
+        .header("if-none-match", "\"stale\"")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.text(), "unchanged");
+}
+
+#[tokio::test]
+async fn test_streaming_body_is_not_tagged() {
+    let client = TestClient::new(conditional_app()).await;
+
+    let response = client.get("/cond-stream").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get("etag").is_none());
+    assert_eq!(response.text(), "chunk");
+}
+
+#[tokio::test]
+async fn test_explicit_validators_and_if_match() {
+    let client = TestClient::new(conditional_app()).await;
+
+    let response = client.get("/cond-doc").send().await;
+    let etag = response
+        .headers()
+        .get("etag")
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string();
+    assert_eq!(etag, current_etag().to_string());
+    assert_eq!(
+        response.headers().get("last-modified").unwrap(),
+        "Wed, 21 Oct 2015 07:28:00 GMT"
+    );
+
+    let response = client
+        .get("/cond-doc")
+        .header("if-modified-since", "Wed, 21 Oct 2015 07:28:00 GMT")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+    // The first writer with the current tag wins...
+    let response = client
+        .put("/cond-doc")
+        .header("if-match", &etag)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // ...and a second writer holding the same, now stale, tag is refused.
+    let response = client
+        .put("/cond-doc")
+        .header("if-match", &etag)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["error"]["code"], "PRECONDITION_FAILED");
+
+    // The old tag no longer short-circuits reads either.
+    let response = client
+        .get("/cond-doc")
+        .header("if-none-match", &etag)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
@@ -5,8 +5,10 @@
 //! Use unique `/disc-*` path prefixes to avoid collisions.
 
 use http::StatusCode;
+use rapina::middleware::{TRACE_ID_HEADER, TimeoutMiddleware, TraceIdMiddleware};
 use rapina::prelude::*;
 use rapina::testing::TestClient;
+use std::time::Duration;
 
 // ── Discovered handlers ─────────────────────────────────────────────────────
 
@@ -97,6 +99,36 @@ async fn grp_public_below() -> &'static str {
     "grouped public below"
 }
 
+// ── Route middleware ─────────────────────────────────────────────────────────
+
+#[get("/disc-mw-slow")]
+#[middleware(TimeoutMiddleware::new(Duration::from_millis(50)))]
+async fn disc_mw_slow() -> &'static str {
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    "should not reach"
+}
+
+#[get("/grp-mw/:id", group = "/api")]
+#[public]
+#[middleware(TraceIdMiddleware::new())]
+async fn grp_mw_item(id: Path<u64>) -> String {
+    format!("grouped item {}", *id)
+}
+
+// ── Rate limit policies (both orderings) ────────────────────────────────────
+
+#[post("/disc-login")]
+#[rate_limit("disc-login")]
+async fn disc_login() -> &'static str {
+    "logged in"
+}
+
+#[rate_limit("disc-login-above")]
+#[post("/disc-login-above")]
+async fn disc_login_above() -> &'static str {
+    "logged in above"
+}
+
 // ── Tests ───────────────────────────────────────────────────────────────────
 
 #[tokio::test]
@@ -362,13 +394,13 @@ async fn test_grouped_public_below_route_macro_bypasses_auth() {
 }
 
 #[tokio::test]
-async fn test_grouped_route_wrong_method_returns_404() {
+async fn test_grouped_route_wrong_method_returns_405() {
     let app = Rapina::new().with_introspection(false).discover();
     let client = TestClient::new(app).await;
 
     // grp_list_users is GET only, POST is a different handler
     let resp = client.delete("/api/grp-users").send().await;
-    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
 }
 
 #[tokio::test]
@@ -400,3 +432,62 @@ async fn test_grouped_introspection_shows_method_correctly() {
         "/grp-users should not appear in introspection"
     );
 }
+
+#[tokio::test]
+async fn test_discovered_route_middleware_attribute() {
+    let app = Rapina::new().with_introspection(false).discover();
+    let client = TestClient::new(app).await;
+
+    let resp = client.get("/disc-mw-slow").send().await;
+    assert_eq!(resp.status(), StatusCode::REQUEST_TIMEOUT);
+
+    // Other discovered routes don't pick up the middleware.
+    let resp = client.get("/disc-hello").send().await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(resp.headers().get(TRACE_ID_HEADER).is_none());
+}
+
+#[tokio::test]
+async fn test_grouped_route_middleware_attribute_keeps_params_and_public() {
+    let auth_config = AuthConfig::new("disc-test-secret", 3600);
+    let app = Rapina::new()
+        .with_introspection(false)
+        .with_auth(auth_config)
+        .discover();
+    let client = TestClient::new(app).await;
+
+    let resp = client.get("/api/grp-mw/9").send().await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.text(), "grouped item 9");
+    assert!(resp.headers().get(TRACE_ID_HEADER).is_some());
+}
+
+#[cfg(feature = "rate-limit")]
+#[tokio::test]
+async fn test_discovered_rate_limit_attribute() {
+    use rapina::middleware::{RateLimitConfig, RateLimitPolicy};
+
+    let config = RateLimitConfig::new(100.0, 100)
+        .route_policy("disc-login", RateLimitPolicy::per_minute(1))
+        .route_policy("disc-login-above", RateLimitPolicy::per_minute(1));
+    let app = Rapina::new()
+        .with_introspection(false)
+        .with_rate_limit(config)
+        .discover();
+    let client = TestClient::new(app).await;
+
+    for path in ["/disc-login", "/disc-login-above"] {
+        let resp = client.post(path).send().await;
+        assert_eq!(resp.status(), StatusCode::OK, "{path}");
+        assert_eq!(resp.headers()["ratelimit-limit"], "1", "{path}");
+        assert_eq!(resp.headers()["ratelimit-policy"], "100;w=1, 1;w=60");
+
+        let resp = client.post(path).send().await;
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS, "{path}");
+    }
+
+    // Routes without the attribute only get the default policy.
+    let resp = client.get("/disc-hello").send().await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers()["ratelimit-policy"], "100;w=1");
+}
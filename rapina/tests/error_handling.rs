@@ -416,8 +416,58 @@ async fn test_router_404_response() {
     let client = TestClient::new(app).await;
     let response = client.get("/not-exists").send().await;
 
-    // Router returns plain 404, not JSON error
     assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let json: serde_json::Value = response.json();
+    assert_eq!(json["error"]["code"], "NOT_FOUND");
+    assert_eq!(json["error"]["message"], "no route matches /not-exists");
+    assert_eq!(json["trace_id"].as_str().unwrap().len(), 36);
+}
+
+#[tokio::test]
+async fn test_router_404_response_uses_rfc7807() {
+    let app = Rapina::new()
+        .with_introspection(false)
+        .enable_rfc7807_errors()
+        .router(Router::new().route(http::Method::GET, "/exists", |_, _, _| async { "found" }));
+
+    let client = TestClient::new(app).await;
+    let response = client.get("/not-exists").send().await;
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(
+        response.headers().get("content-type").unwrap(),
+        "application/problem+json"
+    );
+
+    let json: serde_json::Value = response.json();
+    assert_eq!(json["status"], 404);
+    assert_eq!(json["detail"], "no route matches /not-exists");
+}
+
+#[tokio::test]
+async fn test_router_405_response_uses_rfc7807() {
+    let app = Rapina::new()
+        .with_introspection(false)
+        .enable_rfc7807_errors()
+        .router(Router::new().route(http::Method::GET, "/exists", |_, _, _| async { "found" }));
+
+    let client = TestClient::new(app).await;
+    let response = client.post("/exists").send().await;
+
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(
+        response.headers().get("allow").unwrap(),
+        "GET, HEAD, OPTIONS"
+    );
+    assert_eq!(
+        response.headers().get("content-type").unwrap(),
+        "application/problem+json"
+    );
+
+    let json: serde_json::Value = response.json();
+    assert_eq!(json["status"], 405);
+    assert_eq!(json["title"], "Method Not Allowed");
 }
 
 #[tokio::test]
@@ -1,6 +1,7 @@
 //! Integration tests for request extractors.
 
 use http::StatusCode;
+use rapina::extract::FromRequestParts;
 use rapina::prelude::*;
 use rapina::testing::TestClient;
 use serde::{Deserialize, Serialize};
@@ -437,6 +438,103 @@ async fn test_context_trace_id() {
     assert_eq!(response.text(), "Trace ID length: 36");
 }
 
+// ConnectInfo Extractor Tests
+
+#[tokio::test]
+async fn test_connect_info_reports_peer_and_version() {
+    let app = Rapina::new()
+        .with_introspection(false)
+        .router(Router::new().route(
+            http::Method::GET,
+            "/conn",
+            |req, params, state: Arc<rapina::state::AppState>| async move {
+                let (parts, _) = req.into_parts();
+                let conn = ConnectInfo::from_request_parts(&parts, &params, &state)
+                    .await
+                    .unwrap();
+                format!(
+                    "{} {} {:?} {}",
+                    conn.remote_addr().unwrap().ip(),
+                    conn.local_addr().unwrap().port(),
+                    conn.version(),
+                    conn.is_secure()
+                )
+            },
+        ));
+
+    let client = TestClient::new(app).await;
+    let response = client
+        .get("/conn")
+        .header("x-forwarded-for", "203.0.113.7")
+        .send()
+        .await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.text(),
+        format!("127.0.0.1 {} HTTP/1.1 false", client.addr().port())
+    );
+}
+
+#[tokio::test]
+async fn test_client_ip_ignores_headers_from_untrusted_peer() {
+    let app = Rapina::new()
+        .with_introspection(false)
+        .router(Router::new().route(
+            http::Method::GET,
+            "/ip",
+            |req, params, state: Arc<rapina::state::AppState>| async move {
+                let (parts, _) = req.into_parts();
+                let ip = ClientIp::from_request_parts(&parts, &params, &state)
+                    .await
+                    .unwrap();
+                ip.to_string()
+            },
+        ));
+
+    let client = TestClient::new(app).await;
+    let response = client
+        .get("/ip")
+        .header("x-forwarded-for", "203.0.113.7")
+        .send()
+        .await;
+
+    assert_eq!(response.text(), "127.0.0.1");
+}
+
+#[tokio::test]
+async fn test_client_ip_from_trusted_proxy_headers() {
+    let app = Rapina::new()
+        .with_introspection(false)
+        .with_trusted_proxies(
+            TrustedProxies::new(["127.0.0.0/8"]).header(ForwardedHeader::Forwarded),
+        )
+        .router(Router::new().route(
+            http::Method::GET,
+            "/ip",
+            |req, params, state: Arc<rapina::state::AppState>| async move {
+                let (parts, _) = req.into_parts();
+                let ip = ClientIp::from_request_parts(&parts, &params, &state)
+                    .await
+                    .unwrap();
+                ip.to_string()
+            },
+        ));
+
+    let client = TestClient::new(app).await;
+
+    let response = client
+        .get("/ip")
+        .header("forwarded", "for=\"[2001:db8::1]:4711\"")
+        .header("x-forwarded-for", "203.0.113.7")
+        .send()
+        .await;
+    assert_eq!(response.text(), "2001:db8::1");
+
+    let response = client.get("/ip").send().await;
+    assert_eq!(response.text(), "127.0.0.1");
+}
+
 // Validated Extractor Tests
 
 #[derive(Debug, Deserialize, Validate)]
@@ -395,6 +395,92 @@ async fn test_rate_limit_returns_429_when_exceeded() {
     assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
 }
 
+#[cfg(feature = "rate-limit")]
+#[tokio::test]
+async fn test_rate_limit_headers_on_every_response() {
+    let app = Rapina::new()
+        .with_introspection(false)
+        .with_rate_limit(RateLimitConfig::per_minute(3))
+        .router(Router::new().route(http::Method::GET, "/", |_, _, _| async { "ok" }));
+
+    let client = TestClient::new(app).await;
+
+    let response = client.get("/").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["ratelimit-limit"], "3");
+    assert_eq!(response.headers()["ratelimit-remaining"], "2");
+    assert_eq!(response.headers()["ratelimit-reset"], "20");
+    assert_eq!(response.headers()["ratelimit-policy"], "3;w=60");
+    assert!(response.headers().get("retry-after").is_none());
+
+    client.get("/").send().await;
+    client.get("/").send().await;
+
+    let response = client.get("/").send().await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()["ratelimit-remaining"], "0");
+    assert_eq!(response.headers()["ratelimit-policy"], "3;w=60");
+    assert!(response.headers().contains_key("retry-after"));
+}
+
+#[cfg(feature = "rate-limit")]
+#[tokio::test]
+async fn test_rate_limit_policies_evaluated_together() {
+    use rapina::middleware::RateLimitPolicy;
+
+    let config = RateLimitConfig::from_policy(RateLimitPolicy::per_second(10))
+        .with_policy("daily", RateLimitPolicy::per_day(2));
+    let app = Rapina::new()
+        .with_introspection(false)
+        .with_rate_limit(config)
+        .router(Router::new().route(http::Method::GET, "/", |_, _, _| async { "ok" }));
+
+    let client = TestClient::new(app).await;
+
+    let response = client.get("/").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    // The daily quota is closest to running out, so it is the one reported.
+    assert_eq!(response.headers()["ratelimit-limit"], "2");
+    assert_eq!(response.headers()["ratelimit-remaining"], "1");
+    assert_eq!(response.headers()["ratelimit-policy"], "10;w=1, 2;w=86400");
+
+    assert_eq!(client.get("/").send().await.status(), StatusCode::OK);
+
+    // The burst limit has room left, but the daily quota is spent.
+    let response = client.get("/").send().await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()["ratelimit-limit"], "2");
+}
+
+#[cfg(feature = "rate-limit")]
+#[tokio::test]
+async fn test_rate_limit_route_policy() {
+    use rapina::middleware::RateLimitPolicy;
+
+    let config =
+        RateLimitConfig::new(100.0, 100).route_policy("login", RateLimitPolicy::per_minute(1));
+    let app = Rapina::new()
+        .with_introspection(false)
+        .with_rate_limit(config)
+        .rate_limit_route("POST", "/login", "login")
+        .router(
+            Router::new()
+                .route(http::Method::POST, "/login", |_, _, _| async { "ok" })
+                .route(http::Method::GET, "/", |_, _, _| async { "ok" }),
+        );
+
+    let client = TestClient::new(app).await;
+
+    assert_eq!(client.post("/login").send().await.status(), StatusCode::OK);
+    assert_eq!(
+        client.post("/login").send().await.status(),
+        StatusCode::TOO_MANY_REQUESTS
+    );
+
+    // Other routes aren't held to the login policy.
+    assert_eq!(client.get("/").send().await.status(), StatusCode::OK);
+}
+
 #[cfg(feature = "rate-limit")]
 #[tokio::test]
 async fn test_rate_limit_includes_retry_after_header() {
@@ -472,6 +558,58 @@ async fn test_rate_limit_per_minute_convenience() {
     assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
 }
 
+#[cfg(feature = "rate-limit")]
+#[tokio::test]
+async fn test_rate_limit_ignores_spoofed_forwarded_for() {
+    let app = Rapina::new()
+        .with_introspection(false)
+        .with_rate_limit(RateLimitConfig::new(1.0, 1))
+        .router(Router::new().route(http::Method::GET, "/", |_, _, _| async { "ok" }));
+
+    let client = TestClient::new(app).await;
+
+    let response = client
+        .get("/")
+        .header("x-forwarded-for", "203.0.113.1")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // A new header value does not buy a new bucket: the key is the peer address.
+    let response = client
+        .get("/")
+        .header("x-forwarded-for", "203.0.113.2")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[cfg(feature = "rate-limit")]
+#[tokio::test]
+async fn test_rate_limit_keys_on_client_behind_trusted_proxy() {
+    // The test client connects from loopback, so it plays the proxy.
+    let app = Rapina::new()
+        .with_introspection(false)
+        .with_trusted_proxies(TrustedProxies::new(["127.0.0.1", "10.0.0.0/8"]))
+        .with_rate_limit(RateLimitConfig::new(1.0, 1))
+        .router(Router::new().route(http::Method::GET, "/", |_, _, _| async { "ok" }));
+
+    let client = TestClient::new(app).await;
+
+    for ip in ["203.0.113.1", "203.0.113.2"] {
+        let response = client.get("/").header("x-forwarded-for", ip).send().await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    // A spoofed leftmost entry is skipped: the rightmost untrusted hop counts.
+    let response = client
+        .get("/")
+        .header("x-forwarded-for", "198.51.100.7, 203.0.113.1, 10.0.0.1")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+}
+
 #[cfg(feature = "compression")]
 #[tokio::test]
 async fn test_compression_gzip() {
@@ -538,6 +676,232 @@ async fn test_compression_skips_without_accept_encoding() {
     assert!(response.headers().get("content-encoding").is_none());
 }
 
+/// A compressible route with a body well above the default `min_size`.
+#[cfg(feature = "compression")]
+fn compressible_app(config: CompressionConfig) -> Rapina {
+    let body = "hello from rapina ".repeat(100);
+    Rapina::new()
+        .with_introspection(false)
+        .with_compression(config)
+        .router(Router::new().route(http::Method::GET, "/", move |_, _, _| {
+            let body = body.clone();
+            async move { body }
+        }))
+}
+
+#[cfg(feature = "compression")]
+#[tokio::test]
+async fn test_compression_respects_zero_q_value() {
+    let client = TestClient::new(compressible_app(CompressionConfig::default())).await;
+
+    let response = client
+        .get("/")
+        .header("Accept-Encoding", "gzip;q=0")
+        .send()
+        .await;
+    assert!(response.headers().get("content-encoding").is_none());
+    assert_eq!(response.text(), "hello from rapina ".repeat(100));
+
+    let response = client
+        .get("/")
+        .header("Accept-Encoding", "gzip;q=0, deflate")
+        .send()
+        .await;
+    assert_eq!(
+        response.headers().get("content-encoding").unwrap(),
+        "deflate"
+    );
+}
+
+#[cfg(feature = "compression")]
+#[tokio::test]
+async fn test_compression_uses_configured_preference() {
+    use rapina::middleware::Encoding;
+
+    let config = CompressionConfig::default().preference([Encoding::Deflate, Encoding::Gzip]);
+    let client = TestClient::new(compressible_app(config)).await;
+
+    let response = client
+        .get("/")
+        .header("Accept-Encoding", "gzip, deflate")
+        .send()
+        .await;
+    assert_eq!(
+        response.headers().get("content-encoding").unwrap(),
+        "deflate"
+    );
+
+    // The client's weights still come first.
+    let response = client
+        .get("/")
+        .header("Accept-Encoding", "gzip, deflate;q=0.5")
+        .send()
+        .await;
+    assert_eq!(response.headers().get("content-encoding").unwrap(), "gzip");
+}
+
+#[cfg(feature = "compression-br")]
+#[tokio::test]
+async fn test_compression_brotli() {
+    let client = TestClient::new(compressible_app(CompressionConfig::default())).await;
+
+    let response = client
+        .get("/")
+        .header("Accept-Encoding", "gzip, deflate, br")
+        .send()
+        .await;
+    assert_eq!(response.headers().get("content-encoding").unwrap(), "br");
+
+    let mut decompressed = Vec::new();
+    brotli::BrotliDecompress(&mut response.bytes().as_ref(), &mut decompressed).unwrap();
+    assert_eq!(decompressed, "hello from rapina ".repeat(100).as_bytes());
+}
+
+#[cfg(feature = "compression-zstd")]
+#[tokio::test]
+async fn test_compression_zstd() {
+    let client = TestClient::new(compressible_app(CompressionConfig::default())).await;
+
+    let response = client
+        .get("/")
+        .header("Accept-Encoding", "gzip, zstd")
+        .send()
+        .await;
+    assert_eq!(response.headers().get("content-encoding").unwrap(), "zstd");
+    assert_eq!(
+        zstd::decode_all(response.bytes().as_ref()).unwrap(),
+        "hello from rapina ".repeat(100).as_bytes()
+    );
+}
+
+/// Echoes a JSON body back, reading it through the `Json` extractor.
+#[cfg(feature = "compression")]
+fn decompressing_app(config: rapina::middleware::DecompressionConfig) -> Rapina {
+    use rapina::extract::FromRequest;
+
+    Rapina::new()
+        .with_introspection(false)
+        .with_decompression(config)
+        .router(Router::new().route(
+            http::Method::POST,
+            "/echo",
+            |req, params, state| async move {
+                match Json::<serde_json::Value>::from_request(req, &params, &state).await {
+                    Ok(body) => body.into_response(),
+                    Err(e) => e.into_response(),
+                }
+            },
+        ))
+}
+
+#[cfg(feature = "compression")]
+fn gzip(data: &[u8]) -> Vec<u8> {
+    use std::io::Write;
+
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[cfg(feature = "compression")]
+#[tokio::test]
+async fn test_decompression_gzip_json_body() {
+    use rapina::middleware::DecompressionConfig;
+
+    let client = TestClient::new(decompressing_app(DecompressionConfig::default())).await;
+
+    let response = client
+        .post("/echo")
+        .header("content-type", "application/json")
+        .header("content-encoding", "gzip")
+        .body(gzip(br#"{"name":"rapina"}"#))
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.json::<serde_json::Value>(),
+        serde_json::json!({"name": "rapina"})
+    );
+
+    // Uncompressed bodies pass through untouched.
+    let response = client
+        .post("/echo")
+        .header("content-type", "application/json")
+        .body(r#"{"name":"plain"}"#)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[cfg(feature = "compression-zstd")]
+#[tokio::test]
+async fn test_decompression_zstd_json_body() {
+    use rapina::middleware::DecompressionConfig;
+
+    let client = TestClient::new(decompressing_app(DecompressionConfig::default())).await;
+
+    let response = client
+        .post("/echo")
+        .header("content-type", "application/json")
+        .header("content-encoding", "zstd")
+        .body(zstd::encode_all(&br#"{"id":7}"#[..], 3).unwrap())
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.json::<serde_json::Value>(),
+        serde_json::json!({"id": 7})
+    );
+}
+
+#[cfg(feature = "compression")]
+#[tokio::test]
+async fn test_decompression_rejects_unsupported_encoding() {
+    use rapina::middleware::DecompressionConfig;
+
+    let client = TestClient::new(decompressing_app(DecompressionConfig::default())).await;
+
+    let response = client
+        .post("/echo")
+        .header("content-type", "application/json")
+        .header("content-encoding", "compress")
+        .body("{}")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert!(
+        response.headers()["accept-encoding"]
+            .to_str()
+            .unwrap()
+            .contains("gzip")
+    );
+}
+
+#[cfg(feature = "compression")]
+#[tokio::test]
+async fn test_decompression_rejects_bomb() {
+    use rapina::middleware::{BodyLimitMiddleware, DecompressionConfig};
+
+    // 4 MB of whitespace compresses to a few KB, well under the body limit.
+    let mut payload = vec![b' '; 4 * 1024 * 1024];
+    payload.extend_from_slice(b"{}");
+    let bomb = gzip(&payload);
+    assert!(bomb.len() < 64 * 1024);
+
+    let app = decompressing_app(DecompressionConfig::new(64 * 1024))
+        .middleware(BodyLimitMiddleware::new(64 * 1024));
+    let client = TestClient::new(app).await;
+
+    let response = client
+        .post("/echo")
+        .header("content-type", "application/json")
+        .header("content-encoding", "gzip")
+        .body(bomb)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
+
 #[tokio::test]
 async fn test_trace_id_middleware_preserves_incoming_trace_id() {
     let app = Rapina::new()
@@ -704,3 +1068,204 @@ async fn test_tower_concurrency_limit_integration() {
     assert_eq!(r2.status(), StatusCode::OK);
     assert_eq!(max_concurrent.load(Ordering::SeqCst), 1);
 }
+
+/// Appends its tag to an `x-scope` response header, to observe which
+/// router middleware ran and in what order.
+struct ScopeTag(&'static str);
+
+impl Middleware for ScopeTag {
+    fn handle<'a>(
+        &'a self,
+        req: hyper::Request<hyper::body::Incoming>,
+        _ctx: &'a RequestContext,
+        next: Next<'a>,
+    ) -> rapina::middleware::BoxFuture<'a, hyper::Response<rapina::response::BoxBody>> {
+        Box::pin(async move {
+            let mut response = next.run(req).await;
+            let scope = match response.headers().get("x-scope") {
+                Some(inner) => format!("{},{}", self.0, inner.to_str().unwrap()),
+                None => self.0.to_string(),
+            };
+            response
+                .headers_mut()
+                .insert("x-scope", scope.parse().unwrap());
+            response
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_router_middleware_only_runs_for_its_routes() {
+    let admin = Router::new()
+        .route(http::Method::GET, "/stats", |_, _, _| async { "stats" })
+        .middleware(ScopeTag("admin"));
+
+    let app = Rapina::new().with_introspection(false).router(
+        Router::new()
+            .route(http::Method::GET, "/public", |_, _, _| async { "public" })
+            .group("/admin", admin),
+    );
+
+    let client = TestClient::new(app).await;
+
+    let response = client.get("/admin/stats").send().await;
+    assert_eq!(response.text(), "stats");
+    assert_eq!(response.headers().get("x-scope").unwrap(), "admin");
+
+    let response = client.get("/public").send().await;
+    assert_eq!(response.text(), "public");
+    assert!(response.headers().get("x-scope").is_none());
+
+    // Unmatched paths under the group prefix never reach router middleware.
+    let response = client.get("/admin/missing").send().await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert!(response.headers().get("x-scope").is_none());
+}
+
+#[tokio::test]
+async fn test_router_middleware_runs_outer_group_first() {
+    let reports = Router::new()
+        .route(http::Method::GET, "/:id", |_, params, _| async move {
+            format!("report {}", params.get("id").unwrap())
+        })
+        .middleware(ScopeTag("reports"));
+
+    let api = Router::new()
+        .group("/reports", reports)
+        .middleware(ScopeTag("api"));
+
+    let app = Rapina::new().with_introspection(false).router(
+        Router::new()
+            .group("/api", api)
+            .middleware(ScopeTag("root")),
+    );
+
+    let client = TestClient::new(app).await;
+    let response = client.get("/api/reports/7").send().await;
+
+    assert_eq!(response.text(), "report 7");
+    assert_eq!(
+        response.headers().get("x-scope").unwrap(),
+        "root,api,reports"
+    );
+}
+
+#[tokio::test]
+async fn test_router_middleware_timeout_is_scoped() {
+    let reports = Router::new()
+        .route(http::Method::GET, "/slow", |_, _, _| async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            "should not reach"
+        })
+        .middleware(TimeoutMiddleware::new(Duration::from_millis(50)));
+
+    let app = Rapina::new().with_introspection(false).router(
+        Router::new()
+            .route(http::Method::GET, "/slow", |_, _, _| async {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                "done"
+            })
+            .group("/reports", reports),
+    );
+
+    let client = TestClient::new(app).await;
+
+    let response = client.get("/reports/slow").send().await;
+    assert_eq!(response.status(), StatusCode::REQUEST_TIMEOUT);
+
+    let response = client.get("/slow").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_router_middleware_applies_to_head_fallback() {
+    let app = Rapina::new().with_introspection(false).router(
+        Router::new()
+            .route(http::Method::GET, "/page", |_, _, _| async { "page" })
+            .middleware(ScopeTag("page")),
+    );
+
+    let client = TestClient::new(app).await;
+    let response = client.request(http::Method::HEAD, "/page").send().await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers().get("x-scope").unwrap(), "page");
+}
+
+#[cfg(feature = "rate-limit")]
+#[tokio::test]
+async fn test_router_middleware_rate_limit_single_route() {
+    let login = Router::new()
+        .route(http::Method::POST, "/", |_, _, _| async { "token" })
+        .middleware(RateLimitMiddleware::new(RateLimitConfig::new(1.0, 1)));
+
+    let app = Rapina::new().with_introspection(false).router(
+        Router::new()
+            .route(http::Method::GET, "/health", |_, _, _| async { "ok" })
+            .group("/auth/login", login),
+    );
+
+    let client = TestClient::new(app).await;
+
+    assert_eq!(
+        client.post("/auth/login").send().await.status(),
+        StatusCode::OK
+    );
+    assert_eq!(
+        client.post("/auth/login").send().await.status(),
+        StatusCode::TOO_MANY_REQUESTS
+    );
+    for _ in 0..3 {
+        assert_eq!(client.get("/health").send().await.status(), StatusCode::OK);
+    }
+}
+
+#[cfg(feature = "tower")]
+#[tokio::test]
+async fn test_tower_layer_as_router_middleware() {
+    let app = Rapina::new().with_introspection(false).router(
+        Router::new()
+            .route(http::Method::GET, "/a", |_, _, _| async { "a" })
+            .route(http::Method::GET, "/b/:id", |_, params, _| async move {
+                format!("b{}", params.get("id").unwrap())
+            })
+            .middleware(TowerLayerMiddleware::new(tower_layer::Identity::new())),
+    );
+
+    let client = TestClient::new(app).await;
+
+    // The layer is shared by both routes; each must still reach its own handler.
+    assert_eq!(client.get("/a").send().await.text(), "a");
+    assert_eq!(client.get("/b/2").send().await.text(), "b2");
+    assert_eq!(client.get("/a").send().await.text(), "a");
+}
+
+#[cfg(feature = "tower")]
+#[tokio::test]
+async fn test_tower_layer_shared_by_routes_with_different_inner_middleware() {
+    let reports = Router::new()
+        .route(http::Method::GET, "/reports", |_, _, _| async { "reports" })
+        .middleware(ScopeTag("reports"));
+    let api = Router::new()
+        .route(http::Method::GET, "/users", |_, _, _| async { "users" })
+        .group("/", reports)
+        .middleware(TowerLayerMiddleware::new(tower_layer::Identity::new()))
+        .middleware(ScopeTag("api"));
+
+    let app = Rapina::new()
+        .with_introspection(false)
+        .router(Router::new().group("/api", api));
+    let client = TestClient::new(app).await;
+
+    // Whichever route comes first must not decide the chain for the other.
+    let response = client.get("/api/reports").send().await;
+    assert_eq!(response.text(), "reports");
+    assert_eq!(response.headers()["x-scope"], "api,reports");
+
+    let response = client.get("/api/users").send().await;
+    assert_eq!(response.text(), "users");
+    assert_eq!(response.headers()["x-scope"], "api");
+
+    let response = client.get("/api/reports").send().await;
+    assert_eq!(response.headers()["x-scope"], "api,reports");
+}
@@ -141,3 +141,50 @@ async fn test_multipart_invalid_request() {
 
     assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 }
+
+#[cfg(feature = "compression")]
+#[tokio::test]
+async fn test_multipart_gzip_body() {
+    use rapina::middleware::DecompressionConfig;
+    use std::io::Write;
+
+    let app = Rapina::new()
+        .with_introspection(false)
+        .with_decompression(DecompressionConfig::default())
+        .router(Router::new().route(
+            http::Method::POST,
+            "/upload",
+            |req: http::Request<Incoming>, params: PathParams, state: Arc<AppState>| async move {
+                let mut multipart = Multipart::from_request(req, &params, &state).await.unwrap();
+                let field = multipart.next_field().await.unwrap().unwrap();
+                field.text().await.unwrap()
+            },
+        ));
+
+    let client = TestClient::new(app).await;
+
+    let boundary = "boundary";
+    let body = format!(
+        "--{boundary}\r\n\
+         Content-Disposition: form-data; name=\"note\"\r\n\
+         \r\n\
+         compressed upload\r\n\
+         --{boundary}--\r\n"
+    );
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(body.as_bytes()).unwrap();
+
+    let response = client
+        .post("/upload")
+        .header(
+            "content-type",
+            &format!("multipart/form-data; boundary={boundary}"),
+        )
+        .header("content-encoding", "gzip")
+        .body(encoder.finish().unwrap())
+        .send()
+        .await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.text(), "compressed upload");
+}
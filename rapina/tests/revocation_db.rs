@@ -0,0 +1,101 @@
+#![cfg(feature = "sqlite")]
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rapina::auth::create_rapina_revocations;
+use rapina::auth::{RevocationCheck, RevocationStore, SeaOrmRevocationStore};
+use rapina::sea_orm::Database;
+
+rapina::migrations! {
+    create_rapina_revocations,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+async fn store() -> SeaOrmRevocationStore {
+    let conn = Database::connect("sqlite::memory:").await.unwrap();
+    rapina::migration::run_pending::<Migrator>(&conn)
+        .await
+        .unwrap();
+    SeaOrmRevocationStore::new(conn)
+}
+
+fn check(token_id: &str, session_id: &str, issued_at: u64) -> RevocationCheck {
+    RevocationCheck {
+        token_id: Some(token_id.to_string()),
+        session_id: Some(session_id.to_string()),
+        subject: "user-1".to_string(),
+        issued_at: Some(issued_at),
+    }
+}
+
+#[tokio::test]
+async fn test_revoke_ids() {
+    let store = store().await;
+    let now = now();
+
+    assert!(store.revoke("jti-1", now + 60).await.unwrap());
+    assert!(!store.revoke("jti-1", now + 60).await.unwrap());
+
+    assert!(
+        store
+            .is_revoked(&check("jti-1", "sid-1", now))
+            .await
+            .unwrap()
+    );
+    assert!(
+        !store
+            .is_revoked(&check("jti-2", "sid-1", now))
+            .await
+            .unwrap()
+    );
+
+    store.revoke("sid-1", now + 60).await.unwrap();
+    assert!(
+        store
+            .is_revoked(&check("jti-2", "sid-1", now))
+            .await
+            .unwrap()
+    );
+}
+
+#[tokio::test]
+async fn test_revoke_subject() {
+    let store = store().await;
+    let now = now();
+
+    store
+        .revoke_subject("user-1", now - 5, now + 60)
+        .await
+        .unwrap();
+    assert!(store.is_revoked(&check("a", "b", now - 10)).await.unwrap());
+    assert!(!store.is_revoked(&check("a", "b", now - 5)).await.unwrap());
+
+    // A later cutoff replaces the earlier one
+    store.revoke_subject("user-1", now, now + 60).await.unwrap();
+    assert!(store.is_revoked(&check("a", "b", now - 5)).await.unwrap());
+    assert!(!store.is_revoked(&check("a", "b", now)).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_purge_expired() {
+    let store = store().await;
+    let now = now();
+
+    store.revoke("old", now - 1).await.unwrap();
+    store.revoke("new", now + 60).await.unwrap();
+    store.revoke_subject("user-2", now, now - 1).await.unwrap();
+
+    // Expired rows are ignored before they are purged
+    assert!(!store.is_revoked(&check("old", "sid", now)).await.unwrap());
+    assert_eq!(store.purge_expired().await.unwrap(), 2);
+    assert!(store.is_revoked(&check("new", "sid", now)).await.unwrap());
+
+    // An expired id can be revoked again after the purge
+    assert!(store.revoke("old", now + 60).await.unwrap());
+}
@@ -93,9 +93,47 @@ async fn test_method_not_matching() {
     let response = client.get("/resource").send().await;
     assert_eq!(response.status(), StatusCode::OK);
 
-    // POST should return 404 (method doesn't match)
+    // POST should return 405 (path exists, method doesn't match)
     let response = client.post("/resource").send().await;
-    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(
+        response.headers().get("allow").unwrap(),
+        "GET, HEAD, OPTIONS"
+    );
+}
+
+#[tokio::test]
+async fn test_method_not_allowed_lists_all_methods() {
+    let app = Rapina::new().with_introspection(false).router(
+        Router::new()
+            .route(http::Method::GET, "/users/:id", |_, _, _| async { "get" })
+            .route(http::Method::DELETE, "/users/:id", |_, _, _| async {
+                StatusCode::NO_CONTENT
+            })
+            .route(http::Method::POST, "/users/current", |_, _, _| async {
+                "post"
+            }),
+    );
+
+    let client = TestClient::new(app).await;
+
+    let response = client.put("/users/42").send().await;
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(
+        response.headers().get("allow").unwrap(),
+        "GET, DELETE, HEAD, OPTIONS"
+    );
+
+    let response = client.put("/users/current").send().await;
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(
+        response.headers().get("allow").unwrap(),
+        "GET, POST, DELETE, HEAD, OPTIONS"
+    );
+
+    let json: serde_json::Value = response.json();
+    assert_eq!(json["error"]["code"], "METHOD_NOT_ALLOWED");
+    assert!(json["trace_id"].is_string());
 }
 
 #[tokio::test]
@@ -369,8 +407,186 @@ async fn test_static_map_and_dynamic_fallback() {
         client.get("/nope").send().await.status(),
         StatusCode::NOT_FOUND
     );
+
+    // 405 — path exists but the method is not registered
     assert_eq!(
         client.delete("/users").send().await.status(),
+        StatusCode::METHOD_NOT_ALLOWED
+    );
+}
+
+#[tokio::test]
+async fn test_head_served_by_get_handler() {
+    let app = Rapina::new()
+        .with_introspection(false)
+        .router(Router::new().route(http::Method::GET, "/users/:id", |_, _, _| async { "hello" }));
+
+    let client = TestClient::new(app).await;
+    let response = client.request(Method::HEAD, "/users/1").send().await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers().get("content-type").unwrap(),
+        "text/plain; charset=utf-8"
+    );
+    assert_eq!(response.headers().get("content-length").unwrap(), "5");
+    assert!(response.bytes().is_empty());
+}
+
+#[tokio::test]
+async fn test_explicit_head_route_takes_precedence() {
+    let app = Rapina::new().with_introspection(false).router(
+        Router::new()
+            .route(http::Method::GET, "/probe", |_, _, _| async { "get" })
+            .route(http::Method::HEAD, "/probe", |_, _, _| async {
+                StatusCode::ACCEPTED
+            }),
+    );
+
+    let client = TestClient::new(app).await;
+    let response = client.request(Method::HEAD, "/probe").send().await;
+
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+}
+
+#[tokio::test]
+async fn test_head_without_get_route_is_405() {
+    let app = Rapina::new()
+        .with_introspection(false)
+        .router(Router::new().route(http::Method::POST, "/login", |_, _, _| async { "ok" }));
+
+    let client = TestClient::new(app).await;
+    let response = client.request(Method::HEAD, "/login").send().await;
+
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(response.headers().get("allow").unwrap(), "POST, OPTIONS");
+}
+
+#[tokio::test]
+async fn test_options_answered_from_route_table() {
+    let app = Rapina::new().with_introspection(false).router(
+        Router::new()
+            .route(http::Method::GET, "/users", |_, _, _| async { "list" })
+            .route(http::Method::POST, "/users", |_, _, _| async {
+                StatusCode::CREATED
+            }),
+    );
+
+    let client = TestClient::new(app).await;
+
+    let response = client.request(Method::OPTIONS, "/users").send().await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(
+        response.headers().get("allow").unwrap(),
+        "GET, POST, HEAD, OPTIONS"
+    );
+
+    let response = client.request(Method::OPTIONS, "/missing").send().await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[get("/files/*path")]
+async fn serve_file(path: Path<String>) -> String {
+    format!("file:{}", path.into_inner())
+}
+
+#[tokio::test]
+async fn test_catch_all_route_extracts_remaining_path() {
+    let app = Rapina::new().with_introspection(false).router(
+        Router::new()
+            .get("/files/*path", serve_file)
+            .route(http::Method::GET, "/files/readme", |_, _, _| async {
+                "static"
+            })
+            .route(http::Method::GET, "/files/:id/meta", |_, _, _| async {
+                "meta"
+            }),
+    );
+
+    let client = TestClient::new(app).await;
+
+    let response = client.get("/files/docs/2024/report.pdf").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.text(), "file:docs/2024/report.pdf");
+
+    // Static and param routes keep precedence over the catch-all.
+    assert_eq!(client.get("/files/readme").send().await.text(), "static");
+    assert_eq!(client.get("/files/7/meta").send().await.text(), "meta");
+    assert_eq!(
+        client.get("/files/7/other").send().await.text(),
+        "file:7/other"
+    );
+
+    // Nothing left to capture.
+    assert_eq!(
+        client.get("/files/").send().await.status(),
         StatusCode::NOT_FOUND
     );
 }
+
+#[tokio::test]
+async fn test_catch_all_in_introspection() {
+    let app = Rapina::new()
+        .with_introspection(true)
+        .router(Router::new().get("/docs/*rest", serve_file));
+
+    let client = TestClient::new(app).await;
+    let response = client.get("/__rapina/routes").send().await;
+
+    let json: serde_json::Value = response.json();
+    let route = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|r| r["path"] == "/docs/*rest")
+        .unwrap();
+    assert_eq!(route["wildcard"], "rest");
+}
+
+#[get("/")]
+async fn not_found_page(headers: Headers) -> (StatusCode, String) {
+    let agent = headers
+        .get("user-agent")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("unknown");
+    (StatusCode::NOT_FOUND, format!("nothing here for {}", agent))
+}
+
+#[tokio::test]
+async fn test_custom_fallback_handler() {
+    let app = Rapina::new()
+        .with_introspection(false)
+        .router(Router::new().route(http::Method::GET, "/users", |_, _, _| async { "users" }))
+        .fallback(not_found_page);
+
+    let client = TestClient::new(app).await;
+
+    let response = client
+        .get("/missing")
+        .header("user-agent", "tester")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(response.text(), "nothing here for tester");
+
+    // Matched routes and method mismatches are unaffected.
+    assert_eq!(client.get("/users").send().await.text(), "users");
+    assert_eq!(
+        client.post("/users").send().await.status(),
+        StatusCode::METHOD_NOT_ALLOWED
+    );
+}
+
+#[tokio::test]
+async fn test_router_fallback_kept_by_app() {
+    let app = Rapina::new()
+        .with_introspection(false)
+        .fallback(not_found_page)
+        .router(Router::new().route(http::Method::GET, "/users", |_, _, _| async { "users" }));
+
+    let client = TestClient::new(app).await;
+    let response = client.get("/missing").send().await;
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert!(response.text().starts_with("nothing here for"));
+}
@@ -0,0 +1,150 @@
+//! Integration tests for cookie sessions.
+#![cfg(feature = "session")]
+
+use http::StatusCode;
+use rapina::prelude::*;
+use rapina::session::{CookieSessionStore, InMemorySessionStore, SessionStore};
+use rapina::testing::{TestClient, TestResponse};
+
+const SECRET: &str = "0123456789abcdef0123456789abcdef";
+
+#[post("/login")]
+async fn login(session: Session) -> Result<StatusCode> {
+    session.regenerate();
+    session.insert("user_id", 42)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[get("/me")]
+async fn me(session: Session) -> Result<String> {
+    let user_id: i64 = session
+        .get("user_id")?
+        .ok_or_else(|| Error::unauthorized("not signed in"))?;
+    Ok(user_id.to_string())
+}
+
+#[post("/logout")]
+async fn logout(session: Session) -> StatusCode {
+    session.destroy();
+    StatusCode::NO_CONTENT
+}
+
+fn router() -> Router {
+    Router::new()
+        .post("/login", login)
+        .get("/me", me)
+        .post("/logout", logout)
+}
+
+async fn client(store: impl SessionStore) -> TestClient {
+    let app = Rapina::new()
+        .with_introspection(false)
+        .with_sessions(SessionConfig::new(SECRET, store))
+        .router(router());
+    TestClient::new(app).await
+}
+
+/// The `name=value` pair of the response's `Set-Cookie` header.
+fn cookie(res: &TestResponse) -> String {
+    let set_cookie = res
+        .headers()
+        .get(http::header::SET_COOKIE)
+        .expect("response sets a cookie")
+        .to_str()
+        .unwrap();
+    set_cookie.split(';').next().unwrap().to_string()
+}
+
+async fn me_status(client: &TestClient, cookie: &str) -> StatusCode {
+    client
+        .get("/me")
+        .header("cookie", cookie)
+        .send()
+        .await
+        .status()
+}
+
+#[tokio::test]
+async fn test_login_sets_session_cookie() {
+    let client = client(InMemorySessionStore::new()).await;
+
+    let res = client.get("/me").send().await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    assert!(res.headers().get(http::header::SET_COOKIE).is_none());
+
+    let res = client.post("/login").send().await;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+    let set_cookie = res.headers().get(http::header::SET_COOKIE).unwrap();
+    let set_cookie = set_cookie.to_str().unwrap();
+    assert!(set_cookie.starts_with("rapina_session="));
+    assert!(set_cookie.contains("HttpOnly"));
+    assert!(set_cookie.contains("SameSite=Lax"));
+
+    let res = client
+        .get("/me")
+        .header("cookie", &cookie(&res))
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.text(), "42");
+    // Nothing changed, so the cookie isn't sent again
+    assert!(res.headers().get(http::header::SET_COOKIE).is_none());
+}
+
+#[tokio::test]
+async fn test_regenerate_invalidates_old_id() {
+    let client = client(InMemorySessionStore::new()).await;
+
+    let first = cookie(&client.post("/login").send().await);
+    let res = client.post("/login").header("cookie", &first).send().await;
+    let second = cookie(&res);
+    assert_ne!(first, second);
+
+    assert_eq!(me_status(&client, &first).await, StatusCode::UNAUTHORIZED);
+    assert_eq!(me_status(&client, &second).await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_logout_destroys_session() {
+    let client = client(InMemorySessionStore::new()).await;
+    let session = cookie(&client.post("/login").send().await);
+
+    let res = client
+        .post("/logout")
+        .header("cookie", &session)
+        .send()
+        .await;
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+    let removal = res.headers().get(http::header::SET_COOKIE).unwrap();
+    assert!(removal.to_str().unwrap().contains("Max-Age=0"));
+
+    assert_eq!(me_status(&client, &session).await, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_tampered_cookie_is_ignored() {
+    let client = client(InMemorySessionStore::new()).await;
+    let session = cookie(&client.post("/login").send().await);
+
+    let mut tampered = session.clone();
+    let last = tampered.pop().unwrap();
+    tampered.push(if last == 'A' { 'B' } else { 'A' });
+
+    let res = client.get("/me").header("cookie", &tampered).send().await;
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    // The unreadable cookie is cleared
+    let removal = res.headers().get(http::header::SET_COOKIE).unwrap();
+    assert!(removal.to_str().unwrap().contains("Max-Age=0"));
+}
+
+#[tokio::test]
+async fn test_cookie_store_needs_no_server_state() {
+    let session = {
+        let client = client(CookieSessionStore::new()).await;
+        cookie(&client.post("/login").send().await)
+    };
+
+    // A fresh instance with the same secret reads the session
+    let client = client(CookieSessionStore::new()).await;
+    assert_eq!(me_status(&client, &session).await, StatusCode::OK);
+}
@@ -0,0 +1,66 @@
+#![cfg(all(feature = "session", feature = "sqlite"))]
+
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rapina::sea_orm::Database;
+use rapina::session::create_rapina_sessions;
+use rapina::session::{SeaOrmSessionStore, SessionRecord, SessionStore};
+
+rapina::migrations! {
+    create_rapina_sessions,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+async fn store() -> SeaOrmSessionStore {
+    let conn = Database::connect("sqlite::memory:").await.unwrap();
+    rapina::migration::run_pending::<Migrator>(&conn)
+        .await
+        .unwrap();
+    SeaOrmSessionStore::new(conn)
+}
+
+fn record(id: &str, expires_at: u64) -> SessionRecord {
+    SessionRecord {
+        id: id.to_string(),
+        data: HashMap::from([("user_id".to_string(), serde_json::json!(42))]),
+        expires_at,
+    }
+}
+
+#[tokio::test]
+async fn test_save_load_delete() {
+    let store = store().await;
+    let mut session = record("s1", now() + 60);
+
+    assert_eq!(store.save(&session).await.unwrap(), "s1");
+    assert_eq!(store.load("s1").await.unwrap(), Some(session.clone()));
+
+    // Saving again replaces the row
+    session
+        .data
+        .insert("theme".to_string(), serde_json::json!("dark"));
+    store.save(&session).await.unwrap();
+    assert_eq!(store.load("s1").await.unwrap(), Some(session));
+
+    store.delete("s1").await.unwrap();
+    assert_eq!(store.load("s1").await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_cleanup_expired() {
+    let store = store().await;
+    store.save(&record("live", now() + 60)).await.unwrap();
+    store.save(&record("expired", now() - 1)).await.unwrap();
+
+    assert_eq!(store.load("expired").await.unwrap(), None);
+    assert_eq!(store.cleanup_expired().await.unwrap(), 1);
+    assert_eq!(store.cleanup_expired().await.unwrap(), 0);
+    assert!(store.load("live").await.unwrap().is_some());
+}
@@ -0,0 +1,130 @@
+//! Integration tests for Server-Sent Events responses.
+
+use std::convert::Infallible;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_core::Stream;
+use http::StatusCode;
+use rapina::prelude::*;
+use rapina::sse::{Event, KeepAlive, LastEventId, Sse};
+use rapina::testing::TestClient;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Yields the events in order, then ends.
+struct Events(std::vec::IntoIter<Event>);
+
+impl Stream for Events {
+    type Item = std::result::Result<Event, Infallible>;
+
+    fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Ready(self.0.next().map(Ok))
+    }
+}
+
+/// Never yields an event.
+struct Idle;
+
+impl Stream for Idle {
+    type Item = std::result::Result<Event, Infallible>;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Pending
+    }
+}
+
+#[get("/sse-ticks")]
+async fn sse_ticks(last: LastEventId) -> Sse<Events> {
+    let start: u32 = last
+        .0
+        .and_then(|id| id.parse::<u32>().ok())
+        .map_or(1, |id| id + 1);
+    let events: Vec<Event> = (start..start + 2)
+        .map(|n| Event::default().id(n.to_string()).data(format!("tick {n}")))
+        .collect();
+    Sse::new(Events(events.into_iter()))
+}
+
+fn ticks_app() -> Rapina {
+    Rapina::new()
+        .with_introspection(false)
+        .openapi("sse-test", "1.0")
+        .router(Router::new().get("/sse-ticks", sse_ticks))
+}
+
+#[tokio::test]
+async fn test_sse_sends_events() {
+    let client = TestClient::new(ticks_app()).await;
+    let response = client.get("/sse-ticks").send().await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers().get("content-type").unwrap(),
+        "text/event-stream"
+    );
+    assert_eq!(response.headers().get("cache-control").unwrap(), "no-cache");
+    assert_eq!(
+        response.text(),
+        "data: tick 1\nid: 1\n\ndata: tick 2\nid: 2\n\n"
+    );
+}
+
+#[tokio::test]
+async fn test_sse_resumes_after_last_event_id() {
+    let client = TestClient::new(ticks_app()).await;
+    let response = client
+        .get("/sse-ticks")
+        .header("Last-Event-ID", "5")
+        .send()
+        .await;
+
+    assert_eq!(
+        response.text(),
+        "data: tick 6\nid: 6\n\ndata: tick 7\nid: 7\n\n"
+    );
+}
+
+#[tokio::test]
+async fn test_sse_route_documented_as_event_stream() {
+    let client = TestClient::new(ticks_app()).await;
+    let spec = client
+        .get("/__rapina/openapi.json")
+        .send()
+        .await
+        .json::<serde_json::Value>();
+
+    let content = &spec["paths"]["/sse-ticks"]["get"]["responses"]["200"]["content"];
+    assert!(content.get("text/event-stream").is_some());
+    assert!(content.get("application/json").is_none());
+}
+
+#[tokio::test]
+async fn test_sse_keep_alive_reaches_client() {
+    let app = Rapina::new()
+        .with_introspection(false)
+        .router(
+            Router::new().route(http::Method::GET, "/", |_, _, _| async {
+                Sse::new(Idle).keep_alive(
+                    KeepAlive::new()
+                        .interval(Duration::from_millis(50))
+                        .text("ping"),
+                )
+            }),
+        );
+    let client = TestClient::new(app).await;
+
+    let mut stream = tokio::net::TcpStream::connect(client.addr()).await.unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .await
+        .unwrap();
+
+    let mut received = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !String::from_utf8_lossy(&received).contains(": ping\n\n") {
+        let n = stream.read(&mut buf).await.unwrap();
+        assert!(n > 0, "connection closed before the keep-alive");
+        received.extend_from_slice(&buf[..n]);
+    }
+}
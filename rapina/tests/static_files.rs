@@ -0,0 +1,259 @@
+//! Integration tests for serving files from disk.
+
+use http::StatusCode;
+use rapina::prelude::*;
+use rapina::static_files::{ServeDir, ServeFile};
+use rapina::testing::TestClient;
+use tempfile::TempDir;
+
+/// A site with an index page, a stylesheet and a compressible script.
+fn site() -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("index.html"), "<h1>home</h1>").unwrap();
+    std::fs::create_dir(dir.path().join("css")).unwrap();
+    std::fs::write(dir.path().join("css/site.css"), "body { margin: 0 }").unwrap();
+    std::fs::write(dir.path().join("app.js"), "console.log('plain')").unwrap();
+    std::fs::write(dir.path().join("app.js.gz"), "gzip bytes").unwrap();
+    std::fs::write(dir.path().join("app.js.br"), "brotli bytes").unwrap();
+    std::fs::write(dir.path().join("digits.txt"), "0123456789").unwrap();
+    dir
+}
+
+async fn client(router: Router) -> TestClient {
+    TestClient::new(Rapina::new().with_introspection(false).router(router)).await
+}
+
+#[tokio::test]
+async fn test_serves_files_on_catch_all_route() {
+    let dir = site();
+    let client = client(Router::new().get("/static/*path", ServeDir::new(dir.path()))).await;
+
+    let response = client.get("/static/css/site.css").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/css");
+    assert_eq!(response.headers()["content-length"], "18");
+    assert_eq!(response.headers()["accept-ranges"], "bytes");
+    assert!(response.headers().contains_key("etag"));
+    assert!(response.headers().contains_key("last-modified"));
+    assert_eq!(response.text(), "body { margin: 0 }");
+
+    let response = client.get("/static/missing.css").send().await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_serves_directory_mounted_with_group() {
+    let dir = site();
+    let client =
+        client(Router::new().group("/admin", ServeDir::new(dir.path()).into_router())).await;
+
+    let response = client.get("/admin").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/html");
+    assert_eq!(response.text(), "<h1>home</h1>");
+
+    let response = client.get("/admin/css/site.css").send().await;
+    assert_eq!(response.text(), "body { margin: 0 }");
+}
+
+#[tokio::test]
+async fn test_head_has_length_but_no_body() {
+    let dir = site();
+    let client = client(Router::new().get("/*path", ServeDir::new(dir.path()))).await;
+
+    let response = client
+        .request(http::Method::HEAD, "/digits.txt")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-length"], "10");
+    assert!(response.bytes().is_empty());
+}
+
+#[tokio::test]
+async fn test_path_traversal_is_rejected() {
+    let root = tempfile::tempdir().unwrap();
+    std::fs::write(root.path().join("secret.txt"), "secret").unwrap();
+    let public = root.path().join("public");
+    std::fs::create_dir(&public).unwrap();
+    let client = client(Router::new().get("/files/*path", ServeDir::new(&public))).await;
+
+    for path in [
+        "/files/../secret.txt",
+        "/files/%2e%2e/secret.txt",
+        "/files/a/%2E%2E/%2e%2e/secret.txt",
+        "/files/..%2fsecret.txt",
+        "/files/..%5csecret.txt",
+    ] {
+        let response = client.get(path).send().await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND, "{path}");
+        assert_ne!(response.text(), "secret", "{path}");
+    }
+}
+
+#[tokio::test]
+async fn test_if_none_match_answers_not_modified() {
+    let dir = site();
+    let client = client(Router::new().get("/*path", ServeDir::new(dir.path()))).await;
+
+    let first = client.get("/digits.txt").send().await;
+    let etag = first.headers()["etag"].to_str().unwrap().to_string();
+
+    let response = client
+        .get("/digits.txt")
+        .header("If-None-Match", &etag)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(response.headers()["etag"], etag.as_str());
+    assert!(response.bytes().is_empty());
+
+    let response = client
+        .get("/digits.txt")
+        .header("If-None-Match", "\"stale\"")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_if_modified_since_answers_not_modified() {
+    let dir = site();
+    let client = client(Router::new().get("/*path", ServeDir::new(dir.path()))).await;
+
+    let first = client.get("/digits.txt").send().await;
+    let last_modified = first.headers()["last-modified"]
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    let response = client
+        .get("/digits.txt")
+        .header("If-Modified-Since", &last_modified)
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+    let response = client
+        .get("/digits.txt")
+        .header("If-Modified-Since", "Thu, 01 Jan 1970 00:00:00 GMT")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_range_answers_partial_content() {
+    let dir = site();
+    let client = client(Router::new().get("/*path", ServeDir::new(dir.path()))).await;
+
+    let response = client
+        .get("/digits.txt")
+        .header("Range", "bytes=2-5")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(response.headers()["content-range"], "bytes 2-5/10");
+    assert_eq!(response.headers()["content-length"], "4");
+    assert_eq!(response.text(), "2345");
+
+    let response = client
+        .get("/digits.txt")
+        .header("Range", "bytes=-3")
+        .send()
+        .await;
+    assert_eq!(response.text(), "789");
+
+    let response = client
+        .get("/digits.txt")
+        .header("Range", "bytes=20-")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+    assert_eq!(response.headers()["content-range"], "bytes */10");
+}
+
+#[tokio::test]
+async fn test_stale_if_range_sends_full_file() {
+    let dir = site();
+    let client = client(Router::new().get("/*path", ServeDir::new(dir.path()))).await;
+
+    let response = client
+        .get("/digits.txt")
+        .header("Range", "bytes=2-5")
+        .header("If-Range", "\"stale\"")
+        .send()
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.text(), "0123456789");
+}
+
+#[tokio::test]
+async fn test_precompressed_siblings() {
+    let dir = site();
+    let serve = ServeDir::new(dir.path())
+        .precompressed_gzip()
+        .precompressed_br();
+    let client = client(Router::new().get("/*path", serve)).await;
+
+    let response = client
+        .get("/app.js")
+        .header("Accept-Encoding", "gzip, br")
+        .send()
+        .await;
+    assert_eq!(response.headers()["content-encoding"], "br");
+    assert_eq!(response.headers()["content-type"], "text/javascript");
+    assert_eq!(response.headers()["vary"], "Accept-Encoding");
+    assert_eq!(response.text(), "brotli bytes");
+
+    let response = client
+        .get("/app.js")
+        .header("Accept-Encoding", "gzip, br;q=0")
+        .send()
+        .await;
+    assert_eq!(response.headers()["content-encoding"], "gzip");
+    assert_eq!(response.text(), "gzip bytes");
+
+    let response = client.get("/app.js").send().await;
+    assert!(response.headers().get("content-encoding").is_none());
+    assert_eq!(response.text(), "console.log('plain')");
+
+    // No sibling on disk: the plain file is sent.
+    let response = client
+        .get("/digits.txt")
+        .header("Accept-Encoding", "gzip, br")
+        .send()
+        .await;
+    assert!(response.headers().get("content-encoding").is_none());
+    assert_eq!(response.text(), "0123456789");
+}
+
+#[tokio::test]
+async fn test_spa_fallback_serves_index() {
+    let dir = site();
+    let client = client(Router::new().group(
+        "/app",
+        ServeDir::new(dir.path()).spa_fallback().into_router(),
+    ))
+    .await;
+
+    let response = client.get("/app/settings/profile").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.text(), "<h1>home</h1>");
+
+    let response = client.get("/app/css/site.css").send().await;
+    assert_eq!(response.text(), "body { margin: 0 }");
+}
+
+#[tokio::test]
+async fn test_serve_file() {
+    let dir = site();
+    let client =
+        client(Router::new().get("/robots.txt", ServeFile::new(dir.path().join("digits.txt"))))
+            .await;
+
+    let response = client.get("/robots.txt").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/plain");
+    assert_eq!(response.text(), "0123456789");
+}
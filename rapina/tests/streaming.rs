@@ -0,0 +1,158 @@
+//! Integration tests for streaming response bodies.
+
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use futures_core::Stream;
+use http::StatusCode;
+use rapina::prelude::*;
+use rapina::testing::TestClient;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::mpsc;
+
+/// Yields whatever the test sends through the channel.
+struct ChannelStream(mpsc::UnboundedReceiver<std::io::Result<Bytes>>);
+
+impl Stream for ChannelStream {
+    type Item = std::io::Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.0.poll_recv(cx)
+    }
+}
+
+/// A route that streams a fixed list of chunks.
+fn chunks_app(chunks: &'static [&'static str]) -> Rapina {
+    Rapina::new()
+        .with_introspection(false)
+        .router(
+            Router::new().route(http::Method::GET, "/", move |_, _, _| async move {
+                let (tx, rx) = mpsc::unbounded_channel();
+                for chunk in chunks {
+                    tx.send(Ok(Bytes::from_static(chunk.as_bytes()))).unwrap();
+                }
+                StreamBody(ChannelStream(rx))
+            }),
+        )
+}
+
+#[tokio::test]
+async fn test_stream_body_is_sent_chunked() {
+    let client = TestClient::new(chunks_app(&["id,name\n", "1,alice\n", "2,bob\n"])).await;
+    let response = client.get("/").send().await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers().get("content-type").unwrap(),
+        "application/octet-stream"
+    );
+    assert_eq!(
+        response.headers().get("transfer-encoding").unwrap(),
+        "chunked"
+    );
+    assert!(response.headers().get("content-length").is_none());
+    assert_eq!(response.text(), "id,name\n1,alice\n2,bob\n");
+}
+
+#[tokio::test]
+async fn test_stream_chunks_reach_client_before_stream_ends() {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let rx = Arc::new(Mutex::new(Some(rx)));
+
+    let app = Rapina::new()
+        .with_introspection(false)
+        .router(Router::new().route(http::Method::GET, "/", move |_, _, _| {
+            let rx = rx.lock().unwrap().take().unwrap();
+            async move { StreamBody(ChannelStream(rx)) }
+        }));
+    let client = TestClient::new(app).await;
+
+    let mut stream = tokio::net::TcpStream::connect(client.addr()).await.unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .await
+        .unwrap();
+
+    tx.send(Ok(Bytes::from("first"))).unwrap();
+    let mut received = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !String::from_utf8_lossy(&received).contains("first") {
+        let n = stream.read(&mut buf).await.unwrap();
+        assert!(n > 0, "connection closed before the first chunk");
+        received.extend_from_slice(&buf[..n]);
+    }
+    assert!(!String::from_utf8_lossy(&received).contains("second"));
+
+    tx.send(Ok(Bytes::from("second"))).unwrap();
+    drop(tx);
+    while !String::from_utf8_lossy(&received).ends_with("0\r\n\r\n") {
+        let n = stream.read(&mut buf).await.unwrap();
+        assert!(n > 0, "connection closed before the last chunk");
+        received.extend_from_slice(&buf[..n]);
+    }
+    assert!(String::from_utf8_lossy(&received).contains("second"));
+}
+
+#[tokio::test]
+async fn test_reader_body_streams_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("report.csv");
+    let contents = "row\n".repeat(10_000);
+    std::fs::write(&path, &contents).unwrap();
+
+    let app = Rapina::new()
+        .with_introspection(false)
+        .router(
+            Router::new().route(http::Method::GET, "/report", move |_, _, _| {
+                let path = path.clone();
+                async move { ReaderBody(tokio::fs::File::open(path).await.unwrap()) }
+            }),
+        );
+    let client = TestClient::new(app).await;
+
+    let response = client.get("/report").send().await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.text(), contents);
+}
+
+#[tokio::test]
+async fn test_head_on_streaming_route_has_no_body() {
+    let client = TestClient::new(chunks_app(&["never ", "sent"])).await;
+    let response = client.request(http::Method::HEAD, "/").send().await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.bytes().is_empty());
+}
+
+#[cfg(feature = "compression")]
+#[tokio::test]
+async fn test_compression_passes_streams_through() {
+    use rapina::middleware::CompressionConfig;
+
+    let app = Rapina::new()
+        .with_introspection(false)
+        .with_compression(CompressionConfig::new(0, 6))
+        .router(
+            Router::new().route(http::Method::GET, "/", |_, _, _| async {
+                let (tx, rx) = mpsc::unbounded_channel();
+                tx.send(Ok(Bytes::from("data: one\n\n".repeat(200))))
+                    .unwrap();
+                http::Response::builder()
+                    .header("content-type", "text/plain")
+                    .body(rapina::response::BoxBody::from_stream(ChannelStream(rx)))
+                    .unwrap()
+            }),
+        );
+    let client = TestClient::new(app).await;
+
+    let response = client
+        .get("/")
+        .header("Accept-Encoding", "gzip")
+        .send()
+        .await;
+
+    assert!(response.headers().get("content-encoding").is_none());
+    assert_eq!(response.text(), "data: one\n\n".repeat(200));
+}